@@ -2,7 +2,7 @@ use std::fmt::Write;
 use wit_bindgen_core::{
     abi::{AbiVariant, WasmType},
     make_external_symbol, uwriteln,
-    wit_parser::{self, Function, Resolve, TypeOwner, WorldId, WorldKey},
+    wit_parser::{self, Function, FunctionKind, Resolve, TypeOwner, WorldId, WorldItem, WorldKey},
     Source, WorldGenerator,
 };
 
@@ -14,6 +14,11 @@ struct Bridge {
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, rename_all = "kebab-case")
+)]
 pub struct Opts {
     /// Output bridge code for webassembly micro runtime
     #[cfg_attr(feature = "clap", arg(long))]
@@ -160,6 +165,7 @@ impl WorldGenerator for Bridge {
         world: wit_parser::WorldId,
         files: &mut wit_bindgen_core::Files,
     ) -> anyhow::Result<()> {
+        check_sync(resolve, world)?;
         let world = &resolve.worlds[world];
         files.push(&format!("{}_bridge.c", world.name), self.src.as_bytes());
         Ok(())
@@ -199,6 +205,32 @@ impl Bridge {
     }
 }
 
+/// The bridge only forwards synchronous calls, so worlds with async functions
+/// are rejected instead of being bridged with a mismatched ABI. Supporting them
+/// is a follow-up, see "Async host functions" in the C++ generator's
+/// `DESIGN.md`.
+fn check_sync(resolve: &Resolve, world: WorldId) -> anyhow::Result<()> {
+    let world = &resolve.worlds[world];
+    for item in world.imports.values().chain(world.exports.values()) {
+        let funcs = match item {
+            WorldItem::Function(func) => vec![func],
+            WorldItem::Interface { id, .. } => resolve.interfaces[*id].functions.values().collect(),
+            WorldItem::Type(_) => Vec::new(),
+        };
+        for func in funcs {
+            if matches!(
+                func.kind,
+                FunctionKind::AsyncFreestanding
+                    | FunctionKind::AsyncMethod(_)
+                    | FunctionKind::AsyncStatic(_)
+            ) {
+                anyhow::bail!("bridge does not support async functions (`{}`)", func.name);
+            }
+        }
+    }
+    Ok(())
+}
+
 struct BridgeInterfaceGenerator<'a> {
     gen: &'a mut Bridge,
     resolve: &'a Resolve,
@@ -6,9 +6,11 @@ use indexmap::IndexSet;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::mem;
+use std::sync::Arc;
 use wit_bindgen_core::abi::{
     self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmSignature, WasmType,
 };
+use wit_bindgen_core::layout::Layouts;
 use wit_bindgen_core::{
     dealias, uwrite, uwriteln, wit_parser::*, AnonymousTypeGenerator, AsyncFilterSet, Direction,
     Files, InterfaceGenerator as _, Ns, WorldGenerator,
@@ -30,16 +32,24 @@ struct C {
     needs_union_int64_double: bool,
     needs_union_double_int64: bool,
     needs_async: bool,
+    needs_error_context: bool,
     prim_names: HashSet<String>,
     world: String,
-    sizes: SizeAlign,
+    sizes: Arc<SizeAlign>,
+    layouts: Option<Arc<Layouts>>,
     renamed_interfaces: HashMap<WorldKey, String>,
 
     world_id: Option<WorldId>,
     dtor_funcs: HashMap<TypeId, String>,
+    /// Destructors which are no-ops due to `--arena` and needn't be called.
+    noop_dtors: HashSet<TypeId>,
     type_names: HashMap<TypeId, String>,
     resources: HashMap<TypeId, ResourceInfo>,
     futures: IndexSet<TypeId>,
+    /// `*_blocking` wrappers which would have the same name as an import.
+    blocking_conflicts: Vec<String>,
+    /// Async exports, which `--arena` doesn't support.
+    arena_async_exports: Vec<String>,
 }
 
 #[derive(Default)]
@@ -52,6 +62,11 @@ pub struct ResourceInfo {
 
 #[derive(Default, Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum Enabled {
     #[default]
     No,
@@ -69,6 +84,11 @@ impl std::fmt::Display for Enabled {
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, rename_all = "kebab-case")
+)]
 pub struct Opts {
     /// Skip emitting component allocation helper functions
     #[cfg_attr(feature = "clap", arg(long))]
@@ -83,6 +103,10 @@ pub struct Opts {
             value_name = "ENCODING",
         ),
     )]
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "wit_bindgen_core::deserialize_from_str")
+    )]
     pub string_encoding: StringEncoding,
 
     /// Skip optional null pointer and boolean result argument signature
@@ -119,7 +143,19 @@ pub struct Opts {
     )]
     pub autodrop_borrows: Enabled,
 
+    /// Allocate lifted values from an arena which is reset after each
+    /// exported function, turning the generated `*_free` functions into
+    /// no-ops. This can't be used with async exports.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
+    pub arena: bool,
+
+    /// Generate a `*_blocking` variant of each async import which waits for
+    /// the import to return.
+    #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
+    pub blocking_imports: bool,
+
     #[cfg_attr(feature = "clap", clap(flatten))]
+    #[cfg_attr(feature = "serde", serde(rename = "async"))]
     pub async_: AsyncFilterSet,
 }
 
@@ -165,13 +201,19 @@ enum Scalar {
 }
 
 impl WorldGenerator for C {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         self.world = self
             .opts
             .rename_world
             .clone()
             .unwrap_or_else(|| resolve.worlds[world].name.clone());
-        self.sizes.fill(resolve);
+        let layouts = Layouts::reuse(self.layouts.take());
+        self.sizes = layouts.sizes(resolve, false);
+        self.layouts = Some(layouts);
         self.world_id = Some(world);
 
         let mut interfaces = HashMap::new();
@@ -191,6 +233,10 @@ impl WorldGenerator for C {
                 }
             }
         }
+
+        if self.opts.blocking_imports {
+            self.find_blocking_conflicts(resolve, world);
+        }
     }
 
     fn import_interface(
@@ -304,7 +350,35 @@ impl WorldGenerator for C {
         r#gen.r#gen.src.append(&r#gen.src);
     }
 
+    fn export_types(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) -> Result<()> {
+        let mut r#gen = self.interface(resolve, false, None);
+        let mut live = LiveTypes::default();
+        for (_, id) in types {
+            live.add_type_id(resolve, *id);
+        }
+        r#gen.define_live_types(live);
+        r#gen.r#gen.src.append(&r#gen.src);
+        Ok(())
+    }
+
     fn finish(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
+        if let Some(name) = self.blocking_conflicts.first() {
+            anyhow::bail!(
+                "`--blocking-imports` can't generate `{name}` as an import already has that name"
+            );
+        }
+        if let Some(name) = self.arena_async_exports.first() {
+            // The arena is global and reset at the end of each export, which
+            // would free the memory of async exports still running.
+            anyhow::bail!("`--arena` can't be used with async exports such as `{name}`");
+        }
+
         let linking_symbol = component_type_object::linking_symbol(&self.world);
         self.c_include("<stdlib.h>");
         let snake = self.world.to_snake_case();
@@ -327,75 +401,76 @@ impl WorldGenerator for C {
 
         if self.needs_string {
             self.c_include("<string.h>");
-            let (strlen, size) = match self.opts.string_encoding {
-                StringEncoding::UTF8 => (format!("strlen(s)"), 1),
+            if self.opts.string_encoding != StringEncoding::UTF8 {
+                self.h_include("<uchar.h>");
+                uwrite!(
+                    self.src.h_helpers,
+                    "
+                        // Returns the length of the UTF-16 string `s` in code units
+                        size_t {snake}_string_len(const char16_t* s);
+                    ",
+                );
+                uwrite!(
+                    self.src.c_helpers,
+                    "
+                        size_t {snake}_string_len(const char16_t* s) {{
+                            char16_t* c = (char16_t*)s;
+                            for (; *c; ++c);
+                            return c-s;
+                        }}
+                    ",
+                );
+            }
+            let utf16_len = format!("{snake}_string_len(s)");
+            match self.opts.string_encoding {
+                StringEncoding::UTF8 => self.print_string_helpers("", "char", "strlen(s)", 1, None),
                 StringEncoding::UTF16 => {
-                    self.h_include("<uchar.h>");
-                    uwrite!(
-                        self.src.h_helpers,
-                        "
-                            // Returns the length of the UTF-16 string `s` in code units
-                            size_t {snake}_string_len(const char16_t* s);
-                        ",
-                    );
-                    uwrite!(
-                        self.src.c_helpers,
-                        "
-                            size_t {snake}_string_len(const char16_t* s) {{
-                                char16_t* c = (char16_t*)s;
-                                for (; *c; ++c);
-                                return c-s;
-                            }}
-                        ",
-                    );
-                    (format!("{snake}_string_len(s)"), 2)
+                    self.print_string_helpers("", "char16_t", &utf16_len, 2, None)
                 }
-                StringEncoding::CompactUTF16 => unimplemented!(),
-            };
-            let ty = self.char_type();
-            let c_string_ty = match self.opts.string_encoding {
-                StringEncoding::UTF8 => "char",
-                StringEncoding::UTF16 => "char16_t",
-                StringEncoding::CompactUTF16 => panic!("Compact UTF16 unsupported"),
-            };
+                // Compact strings are either latin1 or UTF-16, so there's a
+                // pair of helpers for each.
+                StringEncoding::CompactUTF16 => {
+                    self.print_string_helpers("", "char", "strlen(s)", 1, Some(false));
+                    self.print_string_helpers("_utf16", "char16_t", &utf16_len, 2, Some(true));
+                }
+            }
             uwrite!(
                 self.src.h_helpers,
                 "
-                   // Sets the string `ret` to reference the input string `s` without copying it
-                   void {snake}_string_set({snake}_string_t *ret, const {c_string_ty} *s);
-
-                   // Creates a copy of the input nul-terminated string `s` and
-                   // stores it into the component model string `ret`.
-                   void {snake}_string_dup({snake}_string_t *ret, const {c_string_ty} *s);
-
                    // Deallocates the string pointed to by `ret`, deallocating
                    // the memory behind the string.
                    void {snake}_string_free({snake}_string_t *ret);\
                ",
             );
-            uwrite!(
-                self.src.c_helpers,
-                "
-                   void {snake}_string_set({snake}_string_t *ret, const {c_string_ty} *s) {{
-                       ret->ptr = ({ty}*) s;
-                       ret->len = {strlen};
-                   }}
-
-                   void {snake}_string_dup({snake}_string_t *ret, const {c_string_ty} *s) {{
-                       ret->len = {strlen};
-                       ret->ptr = ({ty}*) cabi_realloc(NULL, 0, {size}, ret->len * {size});
-                       memcpy(ret->ptr, s, ret->len * {size});
-                   }}
-
-                   void {snake}_string_free({snake}_string_t *ret) {{
-                       if (ret->len > 0) {{
-                           free(ret->ptr);
+            let reset_tag = match self.opts.string_encoding {
+                StringEncoding::CompactUTF16 => "ret->utf16 = false;",
+                StringEncoding::UTF8 | StringEncoding::UTF16 => "",
+            };
+            if self.opts.arena {
+                uwrite!(
+                    self.src.c_helpers,
+                    "
+                       void {snake}_string_free({snake}_string_t *ret) {{
+                           // the string is freed along with the arena
+                           (void) ret;
                        }}
-                       ret->ptr = NULL;
-                       ret->len = 0;
-                   }}
-               ",
-            );
+                   ",
+                );
+            } else {
+                uwrite!(
+                    self.src.c_helpers,
+                    "
+                       void {snake}_string_free({snake}_string_t *ret) {{
+                           if (ret->len > 0) {{
+                               free(ret->ptr);
+                           }}
+                           ret->ptr = NULL;
+                           ret->len = 0;
+                           {reset_tag}
+                       }}
+                   ",
+                );
+            }
         }
         if self.needs_union_int32_float {
             uwriteln!(
@@ -424,6 +499,9 @@ impl WorldGenerator for C {
         if self.needs_async || self.futures.len() > 0 {
             self.generate_async_helpers();
         }
+        if self.needs_error_context {
+            self.generate_error_context_helpers();
+        }
         let version = env!("CARGO_PKG_VERSION");
         let mut h_str = wit_bindgen_core::Source::default();
 
@@ -477,15 +555,32 @@ impl WorldGenerator for C {
         }
 
         if self.needs_string {
-            uwriteln!(
-                h_str,
-                "
-                typedef struct {snake}_string_t {{\n\
-                  {ty} *ptr;\n\
-                  size_t len;\n\
-                }} {snake}_string_t;",
-                ty = self.char_type(),
-            );
+            match self.opts.string_encoding {
+                StringEncoding::UTF8 | StringEncoding::UTF16 => uwriteln!(
+                    h_str,
+                    "
+                    typedef struct {snake}_string_t {{\n\
+                      {ty} *ptr;\n\
+                      size_t len;\n\
+                    }} {snake}_string_t;",
+                    ty = self.char_type(),
+                ),
+                StringEncoding::CompactUTF16 => uwriteln!(
+                    h_str,
+                    "
+                    typedef struct {snake}_string_t {{\n\
+                      // latin1 bytes, or UTF-16 code units if `utf16` is set\n\
+                      uint8_t *ptr;\n\
+                      // length in bytes for latin1 or in code units for UTF-16\n\
+                      size_t len;\n\
+                      bool utf16;\n\
+                    }} {snake}_string_t;",
+                ),
+            }
+        }
+
+        if self.needs_error_context {
+            uwriteln!(h_str, "\ntypedef uint32_t {snake}_error_context_t;");
         }
 
         if self.src.h_async.len() > 0 {
@@ -583,12 +678,72 @@ impl C {
 
     fn char_type(&self) -> &'static str {
         match self.opts.string_encoding {
-            StringEncoding::UTF8 => "uint8_t",
+            // Compact strings are latin1 or UTF-16 depending on their tag, so
+            // they're handed out as bytes.
+            StringEncoding::UTF8 | StringEncoding::CompactUTF16 => "uint8_t",
             StringEncoding::UTF16 => "uint16_t",
-            StringEncoding::CompactUTF16 => panic!("Compact UTF16 unsupported"),
         }
     }
 
+    /// Prints the `_set` and `_dup` helpers creating strings from
+    /// nul-terminated `c_string_ty` strings.
+    ///
+    /// `utf16` is the tag such strings have when using the compact encoding.
+    fn print_string_helpers(
+        &mut self,
+        suffix: &str,
+        c_string_ty: &str,
+        strlen: &str,
+        size: usize,
+        utf16: Option<bool>,
+    ) {
+        let snake = self.world.to_snake_case();
+        let ty = self.char_type();
+        let set_tag = match utf16 {
+            Some(utf16) => format!("ret->utf16 = {utf16};"),
+            None => String::new(),
+        };
+        // The canonical ABI requires compact strings to be 2-byte aligned,
+        // latin1 ones included.
+        let (align, check_align, align_doc) = match utf16 {
+            Some(false) => (
+                2,
+                "if ((uintptr_t) s & 1) abort();",
+                ".\n// Compact strings must be 2-byte aligned, so this aborts if `s` isn't.",
+            ),
+            _ => (size, "", ""),
+        };
+        uwrite!(
+            self.src.h_helpers,
+            "
+               // Sets the string `ret` to reference the input string `s` without copying it{align_doc}
+               void {snake}_string_set{suffix}({snake}_string_t *ret, const {c_string_ty} *s);
+
+               // Creates a copy of the input nul-terminated string `s` and
+               // stores it into the component model string `ret`.
+               void {snake}_string_dup{suffix}({snake}_string_t *ret, const {c_string_ty} *s);
+           ",
+        );
+        uwrite!(
+            self.src.c_helpers,
+            "
+               void {snake}_string_set{suffix}({snake}_string_t *ret, const {c_string_ty} *s) {{
+                   {check_align}
+                   ret->ptr = ({ty}*) s;
+                   ret->len = {strlen};
+                   {set_tag}
+               }}
+
+               void {snake}_string_dup{suffix}({snake}_string_t *ret, const {c_string_ty} *s) {{
+                   ret->len = {strlen};
+                   ret->ptr = ({ty}*) cabi_realloc(NULL, 0, {align}, ret->len * {size});
+                   memcpy(ret->ptr, s, ret->len * {size});
+                   {set_tag}
+               }}
+           ",
+        );
+    }
+
     fn type_name(&mut self, ty: &Type) -> String {
         let mut name = String::new();
         self.push_type_name(ty, &mut name);
@@ -615,7 +770,13 @@ impl C {
                 dst.push_str("string_t");
                 self.needs_string = true;
             }
-            Type::ErrorContext => dst.push_str("error_context"),
+            Type::ErrorContext => {
+                dst.push_str(&self.world.to_snake_case());
+                dst.push_str("_error_context_t");
+                self.needs_error_context = true;
+                // Debug messages are strings.
+                self.needs_string = true;
+            }
             Type::Id(id) => {
                 if let Some(name) = self.type_names.get(id) {
                     dst.push_str(name);
@@ -841,6 +1002,172 @@ void {snake}_context_set(void *val) {{
 }}
             "#
         );
+        if self.opts.blocking_imports {
+            self.generate_subtask_wait();
+        }
+    }
+
+    /// Generates `{snake}_subtask_wait`, which the `*_blocking` variants of
+    /// async imports use to wait for the import to return.
+    fn generate_subtask_wait(&mut self) {
+        let snake = self.world.to_snake_case();
+        let shouty = self.world.to_shouty_snake_case();
+        uwriteln!(
+            self.src.h_async,
+            "
+// Called by `*_blocking` functions while the import hasn't returned yet, with
+// the `poll_ctx` passed to them. Returning `false` cancels the import, e.g.
+// once a timeout has expired.
+typedef bool (*{snake}_poll_hook_t)(void *ctx);
+
+// Waits for the subtask started with `status` to return, dropping it
+// afterwards. With a NULL `poll` this blocks until the subtask returns,
+// otherwise the subtask is polled and `poll` is called each time it hasn't
+// returned yet. Returns `false` if the subtask was cancelled before
+// returning.
+bool {snake}_subtask_wait({snake}_subtask_status_t status, {snake}_poll_hook_t poll, void *ctx);"
+        );
+        uwriteln!(
+            self.src.c_async,
+            r#"
+bool {snake}_subtask_wait({snake}_subtask_status_t status, {snake}_poll_hook_t poll, void *ctx) {{
+    {snake}_subtask_state_t state = {shouty}_SUBTASK_STATE(status);
+    if (state == {shouty}_SUBTASK_RETURNED) {{
+        return true;
+    }}
+    {snake}_subtask_t subtask = {shouty}_SUBTASK_HANDLE(status);
+    {snake}_waitable_set_t set = {snake}_waitable_set_new();
+    {snake}_waitable_join(subtask, set);
+    while (state != {shouty}_SUBTASK_RETURNED) {{
+        {snake}_event_t event;
+        if (poll == NULL) {{
+            {snake}_waitable_set_wait(set, &event);
+        }} else {{
+            {snake}_waitable_set_poll(set, &event);
+        }}
+        if (event.event == {shouty}_EVENT_SUBTASK && event.waitable == subtask) {{
+            state = ({snake}_subtask_state_t) event.code;
+        }} else if (poll != NULL && event.event == {shouty}_EVENT_NONE && !poll(ctx)) {{
+            state = {shouty}_SUBTASK_STATE({snake}_subtask_cancel(subtask));
+            break;
+        }}
+    }}
+    {snake}_waitable_join(subtask, 0);
+    {snake}_waitable_set_drop(set);
+    {snake}_subtask_drop(subtask);
+    return state == {shouty}_SUBTASK_RETURNED;
+}}"#
+        );
+    }
+
+    /// Records the `*_blocking` wrapper of each async import in `world` whose
+    /// name is already taken by another import, e.g. an async `foo` next to a
+    /// `foo-blocking` function.
+    fn find_blocking_conflicts(&mut self, resolve: &Resolve, world: &World) {
+        let mut funcs = Vec::new();
+        for (key, item) in world.imports.iter() {
+            match item {
+                WorldItem::Function(func) => funcs.push((None, func)),
+                WorldItem::Interface { id, .. } => funcs.extend(
+                    resolve.interfaces[*id]
+                        .functions
+                        .values()
+                        .map(|func| (Some(key), func)),
+                ),
+                WorldItem::Type(_) => {}
+            }
+        }
+        let names = funcs
+            .iter()
+            .map(|(key, func)| {
+                c_func_name(
+                    true,
+                    resolve,
+                    &self.world,
+                    *key,
+                    func,
+                    &self.renamed_interfaces,
+                )
+            })
+            .collect::<Vec<_>>();
+        let taken = names.iter().collect::<HashSet<_>>();
+        for ((key, func), name) in funcs.iter().zip(&names) {
+            let blocking = format!("{name}_blocking");
+            if taken.contains(&blocking) && self.opts.async_.is_async(resolve, *key, func, true) {
+                self.blocking_conflicts.push(blocking);
+            }
+        }
+    }
+
+    /// Generates wrappers over the `error-context` canonical built-ins, which
+    /// are used with the component's string encoding.
+    fn generate_error_context_helpers(&mut self) {
+        let snake = self.world.to_snake_case();
+        let plain = |encoding| {
+            (
+                encoding,
+                "ret.len".to_string(),
+                "debug_message->len".to_string(),
+                String::new(),
+            )
+        };
+        let (encoding, debug_message_len, message_len, utf16) = match self.opts.string_encoding {
+            StringEncoding::UTF8 => plain("utf8"),
+            StringEncoding::UTF16 => plain("utf16"),
+            StringEncoding::CompactUTF16 => (
+                "latin1+utf16",
+                format!("ret.len & ~{UTF16_TAG}"),
+                format!(
+                    "(debug_message->utf16 ? debug_message->len | {UTF16_TAG} : debug_message->len)"
+                ),
+                format!("ret_string->utf16 = (ret.len & {UTF16_TAG}) != 0;"),
+            ),
+        };
+        let ty = self.char_type();
+        uwriteln!(
+            self.src.h_helpers,
+            "
+// Creates a new `error-context` with the debug message `debug_message`.
+{snake}_error_context_t {snake}_error_context_new(const {snake}_string_t *debug_message);
+
+// Stores the debug message of `err` into `ret`, which must be freed with
+// `{snake}_string_free`.
+void {snake}_error_context_debug_message({snake}_error_context_t err, {snake}_string_t *ret);
+
+// Drops the `error-context` handle `err`.
+void {snake}_error_context_drop({snake}_error_context_t err);"
+        );
+        uwriteln!(
+            self.src.c_helpers,
+            r#"
+__attribute__((__import_module__("$root"), __import_name__("[error-context-new-{encoding}]")))
+extern uint32_t __error_context_new(uint8_t *, size_t);
+
+{snake}_error_context_t {snake}_error_context_new(const {snake}_string_t *debug_message) {{
+    return __error_context_new((uint8_t *) debug_message->ptr, {message_len});
+}}
+
+__attribute__((__import_module__("$root"), __import_name__("[error-context-debug-message-{encoding}]")))
+extern void __error_context_debug_message(uint32_t, uint8_t *);
+
+void {snake}_error_context_debug_message({snake}_error_context_t err, {snake}_string_t *ret_string) {{
+    struct {{
+        uint8_t *ptr;
+        size_t len;
+    }} ret;
+    __error_context_debug_message(err, (uint8_t *) &ret);
+    ret_string->ptr = ({ty} *) ret.ptr;
+    ret_string->len = {debug_message_len};
+    {utf16}
+}}
+
+__attribute__((__import_module__("$root"), __import_name__("[error-context-drop]")))
+extern void __error_context_drop(uint32_t);
+
+void {snake}_error_context_drop({snake}_error_context_t err) {{
+    __error_context_drop(err);
+}}"#
+        );
     }
 }
 
@@ -859,7 +1186,7 @@ pub fn imported_types_used_by_exported_interfaces(
                 exported_interfaces.insert(*id);
                 live_export_types.add_interface(resolve, *id)
             }
-            WorldItem::Type(_) => unreachable!(),
+            WorldItem::Type(_) => {}
         }
     }
 
@@ -897,7 +1224,9 @@ fn is_prim_type(resolve: &Resolve, ty: &Type) -> bool {
 
 fn is_prim_type_id(resolve: &Resolve, id: TypeId) -> bool {
     match &resolve.types[id].kind {
-        TypeDefKind::List(elem) => is_prim_type(resolve, elem),
+        TypeDefKind::List(elem) | TypeDefKind::FixedSizeList(elem, _) => {
+            is_prim_type(resolve, elem)
+        }
 
         TypeDefKind::Option(ty) => is_prim_type(resolve, ty),
 
@@ -915,7 +1244,6 @@ fn is_prim_type_id(resolve: &Resolve, id: TypeId) -> bool {
         | TypeDefKind::Future(_)
         | TypeDefKind::Stream(_)
         | TypeDefKind::Unknown => false,
-        TypeDefKind::FixedSizeList(..) => todo!(),
     }
 }
 
@@ -934,7 +1262,7 @@ pub fn push_ty_name(resolve: &Resolve, ty: &Type, src: &mut String) {
         Type::F32 => src.push_str("f32"),
         Type::F64 => src.push_str("f64"),
         Type::String => src.push_str("string"),
-        Type::ErrorContext => todo!(),
+        Type::ErrorContext => src.push_str("error_context"),
         Type::Id(id) => {
             let ty = &resolve.types[*id];
             if let Some(name) = &ty.name {
@@ -999,8 +1327,13 @@ pub fn push_ty_name(resolve: &Resolve, ty: &Type, src: &mut String) {
                     src.push_str("borrow_");
                     push_ty_name(resolve, &Type::Id(*resource), src);
                 }
+                TypeDefKind::FixedSizeList(ty, size) => {
+                    src.push_str("array");
+                    src.push_str(&size.to_string());
+                    src.push_str("_");
+                    push_ty_name(resolve, ty, src);
+                }
                 TypeDefKind::Unknown => unreachable!(),
-                TypeDefKind::FixedSizeList(..) => todo!(),
             }
         }
     }
@@ -1131,6 +1464,10 @@ impl C {
         // overridden from some other symbol.
         self.src.c_fns("\n// Canonical ABI intrinsics");
         self.src.c_fns("\n");
+        if self.opts.arena {
+            self.print_arena_intrinsics();
+            return;
+        }
         self.src.c_fns(
             r#"
                 __attribute__((__weak__, __export_name__("cabi_realloc")))
@@ -1144,6 +1481,56 @@ impl C {
             "#,
         );
     }
+
+    /// Prints `cabi_realloc` allocating from the arena, along with a default
+    /// arena bump allocating from a static buffer.
+    fn print_arena_intrinsics(&mut self) {
+        self.c_include("<string.h>");
+        uwrite!(
+            self.src.h_helpers,
+            "
+               // Allocates `size` bytes aligned to `align` from the arena which
+               // lifted arguments and results are allocated from.
+               void *cabi_arena_alloc(size_t size, size_t align);
+
+               // Frees everything allocated from the arena. This is done after
+               // each exported function returns.
+               void cabi_arena_reset(void);
+           ",
+        );
+        self.src.c_fns(
+            r#"
+                #ifndef CABI_ARENA_SIZE
+                #define CABI_ARENA_SIZE 65536
+                #endif
+
+                __attribute__((__aligned__(8)))
+                static uint8_t CABI_ARENA[CABI_ARENA_SIZE];
+                static size_t cabi_arena_used;
+
+                __attribute__((__weak__))
+                void *cabi_arena_alloc(size_t size, size_t align) {
+                    size_t start = (cabi_arena_used + align - 1) & ~(align - 1);
+                    if (start > CABI_ARENA_SIZE || size > CABI_ARENA_SIZE - start) abort();
+                    cabi_arena_used = start + size;
+                    return &CABI_ARENA[start];
+                }
+
+                __attribute__((__weak__))
+                void cabi_arena_reset(void) {
+                    cabi_arena_used = 0;
+                }
+
+                __attribute__((__weak__, __export_name__("cabi_realloc")))
+                void *cabi_realloc(void *ptr, size_t old_size, size_t align, size_t new_size) {
+                    if (new_size == 0) return (void*) align;
+                    void *ret = cabi_arena_alloc(new_size, align);
+                    if (old_size > 0) memcpy(ret, ptr, old_size < new_size ? old_size : new_size);
+                    return ret;
+                }
+            "#,
+        );
+    }
 }
 
 impl Return {
@@ -1160,7 +1547,6 @@ impl Return {
                 self.retptrs.push(*orig_ty);
                 return;
             }
-            Type::ErrorContext => todo!("return_single for error-context"),
             _ => {
                 self.scalar = Some(Scalar::Type(*orig_ty));
                 return;
@@ -1209,11 +1595,11 @@ impl Return {
             TypeDefKind::Tuple(_)
             | TypeDefKind::Record(_)
             | TypeDefKind::List(_)
+            | TypeDefKind::FixedSizeList(..)
             | TypeDefKind::Variant(_) => {}
 
             TypeDefKind::Resource => todo!("return_single for resource"),
             TypeDefKind::Unknown => unreachable!(),
-            TypeDefKind::FixedSizeList(..) => todo!(),
         }
 
         self.retptrs.push(*orig_ty);
@@ -1265,7 +1651,7 @@ extern void {ns}_{snake}_drop_own({own} handle);
         } else {
             let module = match self.interface {
                 Some((_, key)) => self.resolve.name_world_key(key),
-                None => unimplemented!("resource exports from worlds"),
+                None => "$root".to_string(),
             };
             format!("[export]{module}")
         };
@@ -1341,8 +1727,16 @@ extern {borrow} {ns}_borrow_{snake}({own} handle);
             self.src.h_defs(&ty_name);
             self.src.h_defs(" ");
             self.print_typedef_target(id);
-            let (_, key) = self.interface.unwrap();
-            let module = self.resolve.name_world_key(key);
+            let (module, dtor_name) = match self.interface {
+                Some((_, key)) => {
+                    let module = self.resolve.name_world_key(key);
+                    let dtor_name = format!("{module}#[dtor]{snake}");
+                    (module, dtor_name)
+                }
+                // World-level resources have no interface to prefix their
+                // destructor with, like world-level functions.
+                None => ("$root".to_string(), format!("[dtor]{name}")),
+            };
 
             // Exported resources use a different representation than imports
             // for borrows which is a raw pointer to the struct declared just
@@ -1382,7 +1776,7 @@ extern int32_t __wasm_import_{ns}_{snake}_rep(int32_t);
     return ({ns}_{snake}_t*) __wasm_import_{ns}_{snake}_rep(handle.__handle);
 }}
 
-__attribute__((__export_name__("{module}#[dtor]{snake}")))
+__attribute__((__export_name__("{dtor_name}")))
 void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
     {ns}_{snake}_destructor(arg);
 }}
@@ -1560,6 +1954,15 @@ void __wasm_export_{ns}_{snake}_dtor({ns}_{snake}_t* arg) {{
         self.finish_typedef_struct(id);
     }
 
+    fn type_fixed_size_list(&mut self, id: TypeId, _name: &str, ty: &Type, size: u32, docs: &Docs) {
+        self.src.h_defs("\n");
+        self.docs(docs, SourceType::HDefs);
+        self.start_typedef_struct(id);
+        self.print_ty(SourceType::HDefs, ty);
+        uwriteln!(self.src.h_defs, " data[{size}];");
+        self.finish_typedef_struct(id);
+    }
+
     fn type_future(&mut self, id: TypeId, _name: &str, _ty: &Option<Type>, docs: &Docs) {
         self.src.h_defs("\n");
         self.docs(docs, SourceType::HDefs);
@@ -1672,14 +2075,14 @@ impl<'a> wit_bindgen_core::AnonymousTypeGenerator<'a> for InterfaceGenerator<'a>
         todo!("print_anonymous_type for type");
     }
 
-    fn anonymous_type_fixed_size_list(
-        &mut self,
-        _id: TypeId,
-        _ty: &Type,
-        _size: u32,
-        _docs: &Docs,
-    ) {
-        todo!("print_anonymous_type for fixed size list");
+    fn anonymous_type_fixed_size_list(&mut self, id: TypeId, ty: &Type, size: u32, _docs: &Docs) {
+        self.src.h_defs("\ntypedef ");
+        self.src.h_defs("struct {\n");
+        let ty = self.r#gen.type_name(ty);
+        uwriteln!(self.src.h_defs, "{ty} data[{size}];");
+        self.src.h_defs("}");
+        self.src.h_defs(" ");
+        self.print_typedef_target(id);
     }
 }
 
@@ -1786,25 +2189,29 @@ impl InterfaceGenerator<'_> {
         self.src
             .c_helpers(&format!("\nvoid {prefix}_free({name} *ptr) {{\n"));
         let c_helpers_body_start = self.src.c_helpers.len();
+        // Whether anything needs freeing without `--arena`, in which case the
+        // destructor is still defined when it's a no-op with `--arena`.
+        let mut owns = false;
         match &self.resolve.types[id].kind {
-            TypeDefKind::Type(t) => self.free(t, "ptr"),
+            TypeDefKind::Type(t) => owns |= self.free(t, "ptr"),
 
             TypeDefKind::Flags(_) => {}
             TypeDefKind::Enum(_) => {}
 
             TypeDefKind::Record(r) => {
                 for field in r.fields.iter() {
-                    self.free(&field.ty, &format!("&ptr->{}", to_c_ident(&field.name)));
+                    owns |= self.free(&field.ty, &format!("&ptr->{}", to_c_ident(&field.name)));
                 }
             }
 
             TypeDefKind::Tuple(t) => {
                 for (i, ty) in t.types.iter().enumerate() {
-                    self.free(ty, &format!("&ptr->f{i}"));
+                    owns |= self.free(ty, &format!("&ptr->f{i}"));
                 }
             }
 
             TypeDefKind::List(t) => {
+                owns = true;
                 self.src.c_helpers("size_t list_len = ptr->len;\n");
                 uwriteln!(self.src.c_helpers, "if (list_len > 0) {{");
                 let mut t_name = String::new();
@@ -1813,10 +2220,21 @@ impl InterfaceGenerator<'_> {
                     .c_helpers(&format!("{t_name} *list_ptr = ptr->ptr;\n"));
                 self.src
                     .c_helpers("for (size_t i = 0; i < list_len; i++) {\n");
+                let body_start = self.src.c_helpers.len();
                 self.free(t, "&list_ptr[i]");
-                self.src.c_helpers("}\n");
-                uwriteln!(self.src.c_helpers, "free(list_ptr);");
-                uwriteln!(self.src.c_helpers, "}}");
+                if !self.r#gen.opts.arena {
+                    self.src.c_helpers("}\n");
+                    uwriteln!(self.src.c_helpers, "free(list_ptr);");
+                    uwriteln!(self.src.c_helpers, "}}");
+                } else if body_start == self.src.c_helpers.len() {
+                    self.src
+                        .c_helpers
+                        .as_mut_string()
+                        .truncate(c_helpers_body_start);
+                } else {
+                    self.src.c_helpers("}\n");
+                    uwriteln!(self.src.c_helpers, "}}");
+                }
             }
 
             TypeDefKind::Variant(v) => {
@@ -1825,7 +2243,7 @@ impl InterfaceGenerator<'_> {
                     if let Some(ty) = &case.ty {
                         uwriteln!(self.src.c_helpers, "case {}: {{", i);
                         let expr = format!("&ptr->val.{}", to_c_ident(&case.name));
-                        self.free(ty, &expr);
+                        owns |= self.free(ty, &expr);
                         self.src.c_helpers("break;\n");
                         self.src.c_helpers("}\n");
                     }
@@ -1835,51 +2253,75 @@ impl InterfaceGenerator<'_> {
 
             TypeDefKind::Option(t) => {
                 self.src.c_helpers("if (ptr->is_some) {\n");
-                self.free(t, "&ptr->val");
+                owns |= self.free(t, "&ptr->val");
                 self.src.c_helpers("}\n");
             }
 
             TypeDefKind::Result(r) => {
                 self.src.c_helpers("if (!ptr->is_err) {\n");
                 if let Some(ok) = &r.ok {
-                    self.free(ok, "&ptr->val.ok");
+                    owns |= self.free(ok, "&ptr->val.ok");
                 }
                 if let Some(err) = &r.err {
                     self.src.c_helpers("} else {\n");
-                    self.free(err, "&ptr->val.err");
+                    owns |= self.free(err, "&ptr->val.err");
                 }
                 self.src.c_helpers("}\n");
             }
             TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {
-                self.free(&Type::Id(id), "*ptr");
+                owns |= self.free(&Type::Id(id), "*ptr");
             }
             TypeDefKind::Resource => {}
             TypeDefKind::Handle(Handle::Borrow(id) | Handle::Own(id)) => {
-                self.free(&Type::Id(*id), "*ptr");
+                owns |= self.free(&Type::Id(*id), "*ptr");
+            }
+            TypeDefKind::FixedSizeList(t, size) => {
+                let loop_start = self.src.c_helpers.len();
+                uwriteln!(self.src.c_helpers, "for (size_t i = 0; i < {size}; i++) {{");
+                let body_start = self.src.c_helpers.len();
+                owns |= self.free(t, "&ptr->data[i]");
+                if body_start == self.src.c_helpers.len() {
+                    self.src.c_helpers.as_mut_string().truncate(loop_start);
+                } else {
+                    self.src.c_helpers("}\n");
+                }
             }
             TypeDefKind::Unknown => unreachable!(),
-            TypeDefKind::FixedSizeList(..) => todo!(),
         }
-        if c_helpers_body_start == self.src.c_helpers.len() {
+        let noop = c_helpers_body_start == self.src.c_helpers.len();
+        if noop && !owns {
             self.src.c_helpers.as_mut_string().truncate(c_helpers_start);
             self.src.h_helpers.as_mut_string().truncate(h_helpers_start);
             return;
         }
+        if noop {
+            self.src.c_helpers("(void) ptr;\n");
+            self.r#gen.noop_dtors.insert(id);
+        }
         self.src.c_helpers("}\n");
         self.r#gen.dtor_funcs.insert(id, format!("{prefix}_free"));
     }
 
-    fn free(&mut self, ty: &Type, expr: &str) {
+    /// Prints freeing `expr` of type `ty`, returning whether it needs freeing
+    /// at all without `--arena`.
+    fn free(&mut self, ty: &Type, expr: &str) -> bool {
         match ty {
-            Type::Id(id) => {
-                if let Some(dtor) = self.r#gen.dtor_funcs.get(&id) {
-                    self.src.c_helpers(&format!("{dtor}({expr});\n"));
+            Type::Id(id) => match self.r#gen.dtor_funcs.get(&id) {
+                Some(dtor) => {
+                    if !self.r#gen.noop_dtors.contains(id) {
+                        self.src.c_helpers(&format!("{dtor}({expr});\n"));
+                    }
+                    true
                 }
-            }
+                None => false,
+            },
             Type::String => {
-                let snake = self.r#gen.world.to_snake_case();
-                self.src
-                    .c_helpers(&format!("{snake}_string_free({expr});\n"));
+                if !self.r#gen.opts.arena {
+                    let snake = self.r#gen.world.to_snake_case();
+                    self.src
+                        .c_helpers(&format!("{snake}_string_free({expr});\n"));
+                }
+                true
             }
             Type::Bool
             | Type::U8
@@ -1892,8 +2334,12 @@ impl InterfaceGenerator<'_> {
             | Type::S64
             | Type::F32
             | Type::F64
-            | Type::Char => {}
-            Type::ErrorContext => todo!("error context free"),
+            | Type::Char => false,
+            Type::ErrorContext => {
+                let snake = self.r#gen.world.to_snake_case();
+                uwriteln!(self.src.c_helpers, "{snake}_error_context_drop(*{expr});");
+                true
+            }
         }
     }
 
@@ -1928,6 +2374,10 @@ impl InterfaceGenerator<'_> {
 
         self.src.c_fns("\n");
 
+        let name = self.c_func_name(interface_name, func);
+        let import_name = self.r#gen.names.tmp(&format!("__wasm_import_{name}",));
+        let results_ty = self.wasm_results_type(&sig.results, &import_name);
+
         // In the private C file, print a function declaration which is the
         // actual wasm import that we'll be calling, and this has the raw wasm
         // signature.
@@ -1940,14 +2390,8 @@ impl InterfaceGenerator<'_> {
             },
             func.name
         );
-        let name = self.c_func_name(interface_name, func);
-        let import_name = self.r#gen.names.tmp(&format!("__wasm_import_{name}",));
         self.src.c_fns("extern ");
-        match sig.results.len() {
-            0 => self.src.c_fns("void"),
-            1 => self.src.c_fns(wasm_type(sig.results[0])),
-            _ => unimplemented!("multi-value return not supported"),
-        }
+        self.src.c_fns(&results_ty);
         self.src.c_fns(" ");
         self.src.c_fns(&import_name);
         self.src.c_fns("(");
@@ -1971,13 +2415,17 @@ impl InterfaceGenerator<'_> {
         self.src.c_adapters(" {\n");
 
         if async_ {
-            self.import_body_async(func, c_sig, &sig, &import_name);
+            self.import_body_async(func, c_sig.clone(), &sig, &import_name);
         } else {
-            self.import_body_sync(func, c_sig, &import_name);
+            self.import_body_sync(func, c_sig.clone(), &import_name);
         }
 
         self.src.c_adapters("}\n");
 
+        if async_ && self.r#gen.opts.blocking_imports {
+            self.import_blocking(func, &sig, &c_sig);
+        }
+
         self.generate_async_futures_and_streams("", func, interface_name);
     }
 
@@ -2083,6 +2531,57 @@ impl InterfaceGenerator<'_> {
         );
     }
 
+    /// Prints `{name}_blocking` calling the async import `c_sig` and waiting
+    /// for it to return.
+    fn import_blocking(&mut self, func: &Function, sig: &WasmSignature, c_sig: &CSig) {
+        let snake = self.r#gen.world.to_snake_case();
+        let name = format!("{}_blocking", c_sig.name);
+        if self.r#gen.blocking_conflicts.contains(&name) {
+            // Reported as an error by `finish`.
+            return;
+        }
+        self.r#gen.names.insert(&name).expect("duplicate symbols");
+
+        // The types of the async import's parameters, which are laid out as
+        // in `print_sig_async_import_params`.
+        let mut tys = if sig.indirect_params {
+            match &func.params[..] {
+                [] => Vec::new(),
+                [(_, ty)] => vec![self.r#gen.type_name(ty)],
+                _ => vec![format!("{}_args_t", c_sig.name)],
+            }
+        } else {
+            func.params
+                .iter()
+                .map(|(_, ty)| self.r#gen.type_name(ty))
+                .collect()
+        };
+        tys.extend(func.result.as_ref().map(|ty| self.r#gen.type_name(ty)));
+
+        let mut params = Vec::new();
+        let mut args = Vec::new();
+        for ((pointer, param), ty) in c_sig.params.iter().zip(tys) {
+            let pointer = if *pointer { "*" } else { "" };
+            params.push(format!("{ty} {pointer}{param}"));
+            args.push(param.as_str());
+        }
+        params.push(format!("{snake}_poll_hook_t poll"));
+        params.push("void *poll_ctx".to_string());
+
+        let sig = format!("bool {name}({})", params.join(", "));
+        uwriteln!(self.src.h_fns, "extern {sig};");
+        self.src.c_adapters("\n");
+        self.src.c_adapters(&sig);
+        self.src.c_adapters(" {\n");
+        uwriteln!(
+            self.src.c_adapters,
+            "return {snake}_subtask_wait({}({}), poll, poll_ctx);",
+            c_sig.name,
+            args.join(", "),
+        );
+        self.src.c_adapters("}\n");
+    }
+
     fn export(&mut self, func: &Function, interface_name: Option<&WorldKey>) {
         let async_ = self
             .r#gen
@@ -2116,13 +2615,16 @@ impl InterfaceGenerator<'_> {
         );
         let name = self.c_func_name(interface_name, func);
         let import_name = self.r#gen.names.tmp(&format!("__wasm_export_{name}"));
+        let results_ty = self.wasm_results_type(&sig.results, &import_name);
 
-        let mut f = FunctionBindgen::new(self, h_sig, &import_name);
-        match sig.results.len() {
-            0 => f.r#gen.src.c_adapters("void"),
-            1 => f.r#gen.src.c_adapters(wasm_type(sig.results[0])),
-            _ => unimplemented!("multi-value return not supported"),
+        let needs_post_return = abi::guest_export_needs_post_return(self.resolve, func);
+        if async_ && self.r#gen.opts.arena {
+            self.r#gen.arena_async_exports.push(name.clone());
         }
+
+        let mut f = FunctionBindgen::new(self, h_sig, &import_name);
+        f.reset_arena = f.r#gen.r#gen.opts.arena && !async_ && !needs_post_return;
+        f.r#gen.src.c_adapters(&results_ty);
         f.r#gen.src.c_adapters(" ");
         f.r#gen.src.c_adapters(&import_name);
         f.r#gen.src.c_adapters("(");
@@ -2211,7 +2713,7 @@ void {name}_return({return_ty}) {{
 }}
                 "#
             );
-        } else if abi::guest_export_needs_post_return(self.resolve, func) {
+        } else if needs_post_return {
             uwriteln!(
                 self.src.c_fns,
                 "__attribute__((__weak__, __export_name__(\"cabi_post_{export_name}\")))"
@@ -2234,17 +2736,54 @@ void {name}_return({return_ty}) {{
             }
             self.src.c_fns.push_str(") {\n");
 
-            let mut f = FunctionBindgen::new(self, c_sig, &import_name);
-            f.params = params;
-            abi::post_return(f.r#gen.resolve, func, &mut f);
-            let FunctionBindgen { src, .. } = f;
-            self.src.c_fns(&src);
+            if self.r#gen.opts.arena {
+                // The results were allocated from the arena as well.
+                for param in params.iter() {
+                    uwriteln!(self.src.c_fns, "(void) {param};");
+                }
+                uwriteln!(self.src.c_fns, "cabi_arena_reset();");
+            } else {
+                let mut f = FunctionBindgen::new(self, c_sig, &import_name);
+                f.params = params;
+                abi::post_return(f.r#gen.resolve, func, &mut f);
+                let FunctionBindgen { src, .. } = f;
+                self.src.c_fns(&src);
+            }
             self.src.c_fns("}\n");
         }
 
         self.generate_async_futures_and_streams("[export]", func, interface_name);
     }
 
+    /// Returns the C return type of the core wasm function `func_name` with
+    /// the given `results`.
+    ///
+    /// Multiple results are returned as a struct which is defined here. Clang
+    /// only lowers returning it to multiple results with the `experimental-mv`
+    /// target ABI, so compiling for wasm without the `multivalue` feature is
+    /// an error.
+    fn wasm_results_type(&mut self, results: &[WasmType], func_name: &str) -> String {
+        match results {
+            [] => "void".to_string(),
+            [ty] => wasm_type(*ty).to_string(),
+            _ => {
+                let name = wasm_results_struct(func_name);
+                uwriteln!(
+                    self.src.c_fns,
+                    "#if defined(__wasm__) && !defined(__wasm_multivalue__)
+#error \"`{func_name}` has multiple results, compile with `-mmultivalue -Xclang -target-abi -Xclang experimental-mv`\"
+#endif"
+                );
+                uwriteln!(self.src.c_fns, "typedef struct {name} {{");
+                for (i, ty) in results.iter().enumerate() {
+                    uwriteln!(self.src.c_fns, "  {} f{i};", wasm_type(*ty));
+                }
+                uwriteln!(self.src.c_fns, "}} {name};");
+                name
+            }
+        }
+    }
+
     fn print_sig(
         &mut self,
         interface_name: Option<&WorldKey>,
@@ -2550,14 +3089,15 @@ void {name}_return({return_ty}) {{
                             .map_or(false, |ty| self.contains_droppable_borrow(ty))
                 }
 
-                TypeDefKind::List(ty) => self.contains_droppable_borrow(ty),
+                TypeDefKind::List(ty) | TypeDefKind::FixedSizeList(ty, _) => {
+                    self.contains_droppable_borrow(ty)
+                }
 
                 TypeDefKind::Future(_) | TypeDefKind::Stream(_) => false,
 
                 TypeDefKind::Type(ty) => self.contains_droppable_borrow(ty),
 
                 TypeDefKind::Unknown => false,
-                TypeDefKind::FixedSizeList(..) => todo!(),
             }
         } else {
             false
@@ -2770,6 +3310,10 @@ struct FunctionBindgen<'a, 'b> {
 
     /// Forward declarations for temporary storage of borrow copies.
     borrow_decls: wit_bindgen_core::Source,
+
+    /// Whether to reset the arena before returning, set for synchronous
+    /// exports without a post-return function when using `--arena`.
+    reset_arena: bool,
 }
 
 /// State associated with the generation of the `task.return` intrinsic function
@@ -2845,6 +3389,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             borrow_decls: Default::default(),
             borrows: Vec::new(),
             deferred_task_return: DeferredTaskReturn::None,
+            reset_arena: false,
         }
     }
 
@@ -2927,6 +3472,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         &self.r#gen.r#gen.sizes
     }
 
+    fn layouts(&self) -> Option<&Layouts> {
+        self.r#gen.r#gen.layouts.as_deref()
+    }
+
     fn push_block(&mut self) {
         let prev = mem::take(&mut self.src);
         self.block_storage.push(prev);
@@ -3400,10 +3949,23 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             Instruction::EnumLower { .. } => results.push(format!("(int32_t) {}", operands[0])),
             Instruction::EnumLift { .. } => results.push(operands.pop().unwrap()),
 
-            Instruction::ListCanonLower { .. } | Instruction::StringLower { .. } => {
+            Instruction::ListCanonLower { .. } => {
                 results.push(format!("(uint8_t *) ({}).ptr", operands[0]));
                 results.push(format!("({}).len", operands[0]));
             }
+            Instruction::StringLower { .. } => {
+                results.push(format!("(uint8_t *) ({}).ptr", operands[0]));
+                match self.r#gen.r#gen.opts.string_encoding {
+                    StringEncoding::UTF8 | StringEncoding::UTF16 => {
+                        results.push(format!("({}).len", operands[0]));
+                    }
+                    // UTF-16 compact strings are tagged in the length's high bit
+                    StringEncoding::CompactUTF16 => results.push(format!(
+                        "(({op}).utf16 ? ({op}).len | {UTF16_TAG} : ({op}).len)",
+                        op = operands[0]
+                    )),
+                }
+            }
             Instruction::ListCanonLift { element, ty, .. } => {
                 self.assert_no_droppable_borrows("list", &Type::Id(*ty));
 
@@ -3416,13 +3978,20 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             }
             Instruction::StringLift { .. } => {
                 let list_name = self.r#gen.r#gen.type_name(&Type::String);
-                results.push(format!(
-                    "({}) {{ ({}*)({}), ({}) }}",
-                    list_name,
-                    self.r#gen.r#gen.char_type(),
-                    operands[0],
-                    operands[1]
-                ));
+                match self.r#gen.r#gen.opts.string_encoding {
+                    StringEncoding::UTF8 | StringEncoding::UTF16 => results.push(format!(
+                        "({}) {{ ({}*)({}), ({}) }}",
+                        list_name,
+                        self.r#gen.r#gen.char_type(),
+                        operands[0],
+                        operands[1]
+                    )),
+                    StringEncoding::CompactUTF16 => results.push(format!(
+                        "({list_name}) {{ (uint8_t*)({ptr}), ({len}) & ~{UTF16_TAG}, (({len}) & {UTF16_TAG}) != 0 }}",
+                        ptr = operands[0],
+                        len = operands[1],
+                    )),
+                }
             }
 
             Instruction::ListLower { .. } => {
@@ -3442,6 +4011,49 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     list_name, elem_name, operands[0], operands[1]
                 ));
             }
+            Instruction::FixedSizeListLower { size, .. } => {
+                let op = &operands[0];
+                for i in 0..*size {
+                    results.push(format!("({op}).data[{i}]"));
+                }
+            }
+            Instruction::FixedSizeListLift { id, element, .. } => {
+                let name = self.r#gen.r#gen.type_name(&Type::Id(*id));
+                let elem_name = self.r#gen.r#gen.type_name(element);
+                let mut result = format!("({name}) {{ {{\n");
+                for op in operands.iter() {
+                    uwriteln!(result, "({elem_name}) {op},");
+                }
+                result.push_str("} }");
+                results.push(result);
+            }
+            Instruction::FixedSizeListLowerMemory { element, size, .. } => {
+                let (body, results) = self.blocks.pop().unwrap();
+                assert!(results.is_empty());
+                let elem_name = self.r#gen.r#gen.type_name(element);
+                let elem_size = self.r#gen.r#gen.sizes.size(element);
+                // Hoist the source array and destination address into fresh
+                // locals so nested lists don't see the inner `e`/`base`.
+                let array = self.locals.tmp("array");
+                let target = self.locals.tmp("target");
+                let index = self.locals.tmp("i");
+                uwriteln!(self.src, "{elem_name} *{array} = ({}).data;", operands[0]);
+                uwriteln!(self.src, "uint8_t *{target} = {};", operands[1]);
+                uwriteln!(
+                    self.src,
+                    "for (size_t {index} = 0; {index} < {size}; {index}++) {{"
+                );
+                uwriteln!(self.src, "{elem_name} e = {array}[{index}];");
+                uwriteln!(
+                    self.src,
+                    "uint8_t *base = {target} + {index} * {};",
+                    elem_size.format(POINTER_SIZE_EXPRESSION)
+                );
+                uwriteln!(self.src, "(void) base;");
+                uwrite!(self.src, "{body}");
+                uwriteln!(self.src, "}}");
+            }
+
             Instruction::IterElem { .. } => results.push("e".to_string()),
             Instruction::IterBasePointer => results.push("base".to_string()),
 
@@ -3455,7 +4067,16 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                         uwrite!(self.src, " {} = ", ret);
                         results.push(ret);
                     }
-                    _ => unimplemented!(),
+                    n => {
+                        let ret = self.locals.tmp("ret");
+                        self.wasm_return = Some(ret.clone());
+                        uwrite!(
+                            self.src,
+                            "{} {ret} = ",
+                            wasm_results_struct(self.func_to_call)
+                        );
+                        results.extend((0..n).map(|i| format!("{ret}.f{i}")));
+                    }
                 }
                 self.src.push_str(self.func_to_call);
                 self.src.push_str("(");
@@ -3696,9 +4317,19 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                     uwriteln!(self.src, "}}");
                 }
 
-                assert!(*amt <= 1);
-                if *amt == 1 {
-                    uwriteln!(self.src, "return {};", operands[0]);
+                if self.reset_arena {
+                    uwriteln!(self.src, "cabi_arena_reset();");
+                }
+
+                match amt {
+                    0 => {}
+                    1 => uwriteln!(self.src, "return {};", operands[0]),
+                    _ => uwriteln!(
+                        self.src,
+                        "return ({}) {{ {} }};",
+                        wasm_results_struct(self.func_to_call),
+                        operands.join(", ")
+                    ),
                 }
             }
 
@@ -3802,6 +4433,13 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 };
             }
 
+            Instruction::ErrorContextLift { .. } => {
+                results.push(format!("((uint32_t) {})", operands[0]));
+            }
+            Instruction::ErrorContextLower { .. } => {
+                results.push(format!("((int32_t) {})", operands[0]));
+            }
+
             Instruction::FutureLift { .. } => {
                 results.push(format!("((uint32_t) {})", operands[0]));
             }
@@ -3895,6 +4533,12 @@ pub fn wasm_type(ty: WasmType) -> &'static str {
     }
 }
 
+/// Name of the struct holding the results of the core wasm function
+/// `func_name` when it has more than one.
+fn wasm_results_struct(func_name: &str) -> String {
+    format!("{func_name}_results_t")
+}
+
 pub fn int_repr(ty: Int) -> &'static str {
     match ty {
         Int::U8 => "uint8_t",
@@ -3924,12 +4568,14 @@ pub fn is_arg_by_pointer(resolve: &Resolve, ty: &Type) -> bool {
             TypeDefKind::Enum(_) => false,
             TypeDefKind::Flags(_) => false,
             TypeDefKind::Handle(_) => false,
-            TypeDefKind::Tuple(_) | TypeDefKind::Record(_) | TypeDefKind::List(_) => true,
+            TypeDefKind::Tuple(_)
+            | TypeDefKind::Record(_)
+            | TypeDefKind::List(_)
+            | TypeDefKind::FixedSizeList(..) => true,
             TypeDefKind::Future(_) => false,
             TypeDefKind::Stream(_) => false,
             TypeDefKind::Resource => todo!("is_arg_by_pointer for resource"),
             TypeDefKind::Unknown => unreachable!(),
-            TypeDefKind::FixedSizeList(..) => todo!(),
         },
         Type::String => true,
         _ => false,
@@ -4047,3 +4693,177 @@ pub fn to_c_ident(name: &str) -> String {
 }
 
 pub const POINTER_SIZE_EXPRESSION: &str = "sizeof(void*)";
+
+/// High bit of a compact UTF-16 string's length, set when the string is
+/// UTF-16 rather than latin1.
+const UTF16_TAG: &str = "0x80000000";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn c_sig() -> CSig {
+        CSig {
+            name: "f".to_string(),
+            sig: String::new(),
+            params: Vec::new(),
+            ret: Return {
+                scalar: None,
+                retptrs: Vec::new(),
+            },
+            retptrs: Vec::new(),
+        }
+    }
+
+    // The canonical ABI never flattens to more than one result, so this drives
+    // `FunctionBindgen` with a multi-value core signature directly.
+    #[test]
+    fn multiple_wasm_results() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str("test.wit", "package a:b; world w { export f: func(); }")
+            .unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let func = match &resolve.worlds[world].exports[0] {
+            WorldItem::Function(func) => func.clone(),
+            _ => unreachable!(),
+        };
+        let sig = WasmSignature {
+            params: vec![WasmType::I32],
+            results: vec![WasmType::I32, WasmType::I64],
+            indirect_params: false,
+            retptr: false,
+        };
+        let mut c = C::default();
+
+        // Imports call the core function and unpack the struct it returns.
+        let mut r#gen = c.interface(&resolve, true, None);
+        let ty = r#gen.wasm_results_type(&sig.results, "__wasm_import_f");
+        assert_eq!(ty, "__wasm_import_f_results_t");
+        let defs = r#gen.src.c_fns.as_str();
+        assert!(defs.contains("#if defined(__wasm__) && !defined(__wasm_multivalue__)"));
+        assert!(defs.contains(
+            "typedef struct __wasm_import_f_results_t {\n    int32_t f0;\n    int64_t f1;\n}"
+        ));
+        let mut f = FunctionBindgen::new(&mut r#gen, c_sig(), "__wasm_import_f");
+        let mut results = Vec::new();
+        f.emit(
+            &resolve,
+            &Instruction::CallWasm {
+                name: "f",
+                sig: &sig,
+                module_prefix: "",
+            },
+            &mut vec!["x".to_string()],
+            &mut results,
+        );
+        assert_eq!(results, ["ret.f0", "ret.f1"]);
+        assert_eq!(
+            f.src.as_str(),
+            "__wasm_import_f_results_t ret = __wasm_import_f(x);\n"
+        );
+
+        // Exports pack their results into the struct they return.
+        let mut r#gen = c.interface(&resolve, false, None);
+        let mut f = FunctionBindgen::new(&mut r#gen, c_sig(), "__wasm_export_f");
+        f.emit(
+            &resolve,
+            &Instruction::Return {
+                amt: 2,
+                func: &func,
+            },
+            &mut vec!["a".to_string(), "b".to_string()],
+            &mut Vec::new(),
+        );
+        assert_eq!(
+            f.src.as_str(),
+            "return (__wasm_export_f_results_t) { a, b };\n"
+        );
+    }
+
+    fn generate_blocking(wit: &str) -> Result<Files> {
+        let mut resolve = Resolve::default();
+        let pkg = resolve.push_str("test.wit", wit).unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let mut opts = Opts {
+            blocking_imports: true,
+            ..Opts::default()
+        };
+        opts.async_.push("all");
+        let mut files = Files::default();
+        opts.build().generate(&resolve, world, &mut files)?;
+        Ok(files)
+    }
+
+    #[test]
+    fn blocking_imports() {
+        let files = generate_blocking(
+            "package a:b;
+            world w {
+                record r { a: u32, b: u32, c: u32, d: u32, e: u32 }
+                import f: func(a: u32, b: string) -> string;
+                import g: func();
+                import h: func(r: r);
+                import i: func(a: u32, b: u32, c: u32, d: u32, e: u32);
+            }",
+        )
+        .unwrap();
+        let (_, header) = files.iter().find(|(name, _)| *name == "w.h").unwrap();
+        let header = std::str::from_utf8(header).unwrap();
+        for sig in [
+            "bool w_f_blocking(uint32_t a, w_string_t b, w_string_t *result, w_poll_hook_t poll, void *poll_ctx);",
+            "bool w_g_blocking(w_poll_hook_t poll, void *poll_ctx);",
+            "bool w_h_blocking(w_r_t *arg, w_poll_hook_t poll, void *poll_ctx);",
+            "bool w_i_blocking(w_i_args_t *args, w_poll_hook_t poll, void *poll_ctx);",
+        ] {
+            assert!(header.contains(sig), "missing `{sig}` in:\n{header}");
+        }
+    }
+
+    #[test]
+    fn blocking_import_conflict() {
+        let Err(err) = generate_blocking(
+            "package a:b;
+            world w {
+                import f: func();
+                import f-blocking: func();
+            }",
+        ) else {
+            panic!("expected a conflict");
+        };
+        assert_eq!(
+            err.to_string(),
+            "`--blocking-imports` can't generate `w_f_blocking` as an import already has that name"
+        );
+    }
+
+    #[test]
+    fn arena_async_exports() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package a:b;
+                world w {
+                    export f: func() -> string;
+                    export g: async func() -> string;
+                }",
+            )
+            .unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let opts = Opts {
+            arena: true,
+            ..Opts::default()
+        };
+        let Err(err) = opts
+            .build()
+            .generate(&resolve, world, &mut Files::default())
+        else {
+            panic!("expected async exports to be rejected");
+        };
+        assert_eq!(
+            err.to_string(),
+            "`--arena` can't be used with async exports such as `exports_w_async_g`"
+        );
+    }
+}
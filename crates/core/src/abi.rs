@@ -1,3 +1,4 @@
+use crate::layout::Layouts;
 use std::fmt;
 pub use wit_parser::abi::{AbiVariant, FlatTypes, WasmSignature, WasmType};
 use wit_parser::{
@@ -5,6 +6,10 @@ use wit_parser::{
     Handle, Int, Record, Resolve, Result_, SizeAlign, Tuple, Type, TypeDefKind, TypeId, Variant,
 };
 
+mod recorder;
+use recorder::ToValue;
+pub use recorder::{record, type_name, Block, Recorder, Step, Trace, Value};
+
 // Helper macro for defining instructions without having to have tons of
 // exhaustive `match` statements to update
 macro_rules! def_instruction {
@@ -61,6 +66,31 @@ macro_rules! def_instruction {
                     )*
                 }
             }
+
+            /// Name of this instruction, e.g. `"I32Load"`.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(
+                        Self::$variant { .. } => stringify!($variant),
+                    )*
+                }
+            }
+
+            /// Names and values of the immediate fields of this instruction.
+            #[allow(unused_variables)]
+            pub fn fields(&self, resolve: &Resolve) -> Vec<(&'static str, Value)> {
+                match self {
+                    $(
+                        Self::$variant $( {
+                            $(
+                                $field,
+                            )*
+                        } )? => vec![$( $(
+                            (stringify!($field), $field.to_value(resolve)),
+                        )* )?],
+                    )*
+                }
+            }
         }
     };
 }
@@ -589,6 +619,46 @@ def_instruction! {
         /// lowered representation of a function's results to `task.return`.
         AsyncTaskReturn { name: &'a str, params: &'a [WasmType] } : [params.len()] => [0],
 
+        /// Used by hosts calling an async-lifted wasm export after `CallWasm`.
+        ///
+        /// Pops a block from the block stack and, for exports lifted with a
+        /// `callback`, the status code returned by `CallWasm`. The callback is
+        /// to be invoked with events from the waitable set named by the status
+        /// code until the status reports that the task exited. Exports lifted
+        /// without a callback (stackful) have exited once `CallWasm` returns.
+        /// The block lifts the function's results from the operands which the
+        /// guest passes to the host's `task_return` import, which has `params`
+        /// as its parameters. Pushes the lifted result, if any.
+        AsyncCallbackLoop {
+            func: &'a Function,
+            callback: Option<&'a str>,
+            task_return: &'a str,
+            params: &'a [WasmType],
+        } : [usize::from(callback.is_some())] => [usize::from(func.result.is_some())],
+
+        /// Pushes the `nth` core parameter, of type `ty`, which the guest
+        /// passed to `task.return`.
+        ///
+        /// This is only used inside the block of `AsyncCallbackLoop`.
+        AsyncTaskReturnArg { nth: usize, ty: WasmType } : [0] => [1],
+
+        /// Used by hosts implementing an import which the guest calls with
+        /// `[async-lower]`. Pops the pending result of the preceding
+        /// `CallInterface` (if the function has one) and a block from the
+        /// block stack, then pushes the `i32` status code returned to the
+        /// guest.
+        ///
+        /// The block is to be run once the call completes and writes the
+        /// result into the guest-provided return pointer; it must finish
+        /// before the subtask is reported as returned.
+        AsyncSubtaskStart { func: &'a Function } : [usize::from(func.result.is_some())] => [1],
+
+        /// Pushes an operand representing the value that an async host call
+        /// completed with.
+        ///
+        /// This is only used inside the block of `AsyncSubtaskStart`.
+        AsyncSubtaskResult : [0] => [1],
+
         /// Force the evaluation of the specified number of expressions and push
         /// the results to the stack.
         ///
@@ -735,6 +805,12 @@ pub trait Bindgen {
     /// Returns size information that was previously calculated for all types.
     fn sizes(&self) -> &SizeAlign;
 
+    /// Returns the layouts shared with other generators, if any, from which
+    /// the flat representation of types is then looked up.
+    fn layouts(&self) -> Option<&Layouts> {
+        None
+    }
+
     /// Returns whether or not the specified element type is represented in a
     /// "canonical" form for lists. This dictates whether the `ListCanonLower`
     /// and `ListCanonLift` instructions are used or not.
@@ -841,7 +917,9 @@ pub fn guest_export_needs_post_return(resolve: &Resolve, func: &Function) -> boo
 fn needs_deallocate(resolve: &Resolve, ty: &Type, what: Deallocate) -> bool {
     match ty {
         Type::String => true,
-        Type::ErrorContext => true,
+        // Like `own<T>`, futures and streams, an `error-context` is a handle
+        // rather than memory, so on its own it doesn't need a post-return.
+        Type::ErrorContext => what.handles(),
         Type::Id(id) => match &resolve.types[*id].kind {
             TypeDefKind::List(_) => true,
             TypeDefKind::Type(t) => needs_deallocate(resolve, t, what),
@@ -970,6 +1048,13 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         }
     }
 
+    fn flat_types(&self, ty: &Type) -> Option<Vec<WasmType>> {
+        match self.bindgen.layouts() {
+            Some(layouts) => layouts.flat(self.resolve, ty),
+            None => flat_types(self.resolve, ty),
+        }
+    }
+
     fn call(&mut self, func: &Function, variant: AbiVariant, lift_lower: LiftLower, async_: bool) {
         let sig = self.resolve.wasm_signature(variant, func);
         self.call_with_signature(func, sig, variant, lift_lower, async_);
@@ -1005,13 +1090,19 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             || (matches!(lift_lower, LiftLower::Symmetric)
                 && matches!(variant, AbiVariant::GuestImport));
         match language_to_abi {
+            true if async_ => {
+                assert!(
+                    lift_lower == LiftLower::LowerArgsLiftResults
+                        && !matches!(
+                            variant,
+                            AbiVariant::GuestImport | AbiVariant::GuestImportAsync
+                        ),
+                    "generators should not be using this for async"
+                );
+                self.host_call_async_export(func, variant);
+            }
             true => {
-                assert!(!async_, "generators should not be using this for async");
-
                 self.realloc = Some(realloc);
-                if let (AbiVariant::GuestExport, true) = (variant, async_) {
-                    unimplemented!("host-side code generation for async lift/lower not supported");
-                }
 
                 let lower_to_memory = |self_: &mut Self, ptr: B::Operand| {
                     let mut offset = ArchitectureSize::default();
@@ -1138,11 +1229,16 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     amt: usize::from(func.result.is_some()),
                 });
             }
+            false
+                if async_
+                    && matches!(
+                        variant,
+                        AbiVariant::GuestImport | AbiVariant::GuestImportAsync
+                    ) =>
+            {
+                self.host_async_import(func);
+            }
             false => {
-                if let (AbiVariant::GuestImport, true) = (variant, async_) {
-                    todo!("implement host-side support for async lift/lower");
-                }
-
                 let read_from_memory = |self_: &mut Self| {
                     let mut offset = ArchitectureSize::default();
                     let ptr = self_.stack.pop().unwrap();
@@ -1159,7 +1255,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     // make-up the type.
                     let mut offset = 0;
                     for (_, ty) in func.params.iter() {
-                        let types = flat_types(self.resolve, ty).unwrap();
+                        let types = self.flat_types(ty).unwrap();
                         for _ in 0..types.len() {
                             self.emit(&Instruction::GetArg { nth: offset });
                             offset += 1;
@@ -1183,7 +1279,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                 // intrinsic.
                 let (lower_to_memory, async_flat_results) = if async_ {
                     let results = match &func.result {
-                        Some(ty) => flat_types(self.resolve, ty),
+                        Some(ty) => self.flat_types(ty),
                         None => Some(Vec::new()),
                     };
                     (results.is_none(), Some(results))
@@ -1289,6 +1385,146 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         );
     }
 
+    /// Host calling an async-lifted guest export: lowers the arguments, calls
+    /// the `[async-lift]` export and drives its `[callback]` until the results
+    /// have been delivered through `task.return`. Stackful exports are called
+    /// through `[async-lift-stackful]` and have no callback to drive.
+    fn host_call_async_export(&mut self, func: &Function, variant: AbiVariant) {
+        let sig = self.resolve.wasm_signature(variant, func);
+        let (module_prefix, callback) = match variant {
+            AbiVariant::GuestExportAsyncStackful => ("[async-lift-stackful]", None),
+            _ => (
+                "[async-lift]",
+                Some(format!("[callback][async-lift]{}", func.name)),
+            ),
+        };
+
+        // Ownership of the arguments is always passed to the guest.
+        self.realloc = Some(Realloc::Export("cabi_realloc"));
+        if !sig.indirect_params {
+            for (nth, (_, ty)) in func.params.iter().enumerate() {
+                self.emit(&Instruction::GetArg { nth });
+                self.lower(ty);
+            }
+        } else {
+            let ElementInfo { size, align } = self
+                .bindgen
+                .sizes()
+                .record(func.params.iter().map(|t| &t.1));
+            self.emit(&Instruction::Malloc {
+                realloc: "cabi_realloc",
+                size,
+                align,
+            });
+            let ptr = self.stack.pop().unwrap();
+            for nth in 0..func.params.len() {
+                self.emit(&Instruction::GetArg { nth });
+            }
+            self.write_params_to_memory(
+                func.params.iter().map(|(_, ty)| ty),
+                ptr.clone(),
+                Default::default(),
+            );
+            self.stack.push(ptr);
+        }
+        self.realloc = None;
+
+        assert_eq!(self.stack.len(), sig.params.len());
+        self.emit(&Instruction::CallWasm {
+            name: &func.name,
+            sig: &sig,
+            module_prefix,
+        });
+
+        // The export itself only returns a status code, the results are passed
+        // to the host's `task.return` import instead. They're passed flat if
+        // possible, otherwise as a pointer into guest memory.
+        let flat = func.result.as_ref().map(|ty| self.flat_types(ty));
+        let params = match &flat {
+            Some(Some(flat)) => flat.clone(),
+            Some(None) => vec![WasmType::Pointer],
+            None => Vec::new(),
+        };
+        self.push_block();
+        for (nth, ty) in params.iter().enumerate() {
+            self.emit(&Instruction::AsyncTaskReturnArg { nth, ty: *ty });
+        }
+        match (&func.result, flat) {
+            (Some(ty), Some(Some(_))) => self.lift(ty),
+            (Some(_), Some(None)) => {
+                let ptr = self.stack.pop().unwrap();
+                self.read_results_from_memory(&func.result, ptr, Default::default());
+            }
+            _ => {}
+        }
+        self.finish_block(usize::from(func.result.is_some()));
+
+        self.emit(&Instruction::AsyncCallbackLoop {
+            func,
+            callback: callback.as_deref(),
+            task_return: &format!("[task-return]{}", func.name),
+            params: &params,
+        });
+        self.emit(&Instruction::Return {
+            func,
+            amt: usize::from(func.result.is_some()),
+        });
+    }
+
+    /// Host implementing an import which the guest calls with
+    /// `[async-lower]`: lifts the arguments, starts the call and arranges for
+    /// the result to be written to the guest's return pointer on completion.
+    fn host_async_import(&mut self, func: &Function) {
+        let sig = self
+            .resolve
+            .wasm_signature(AbiVariant::GuestImportAsync, func);
+
+        if !sig.indirect_params {
+            let mut offset = 0;
+            for (_, ty) in func.params.iter() {
+                let types = self.flat_types(ty).unwrap();
+                for _ in 0..types.len() {
+                    self.emit(&Instruction::GetArg { nth: offset });
+                    offset += 1;
+                }
+                self.lift(ty);
+            }
+        } else {
+            // Parameters live in guest memory which stays valid until the
+            // subtask is reported as started, so they're read eagerly.
+            self.emit(&Instruction::GetArg { nth: 0 });
+            let ptr = self.stack.pop().unwrap();
+            self.read_fields_from_memory(
+                func.params.iter().map(|(_, ty)| ty),
+                ptr,
+                Default::default(),
+            );
+        }
+
+        self.emit(&Instruction::CallInterface { func, async_: true });
+
+        // The guest receives ownership of the result, so lists are allocated
+        // with its `cabi_realloc`.
+        self.realloc = Some(Realloc::Export("cabi_realloc"));
+        self.push_block();
+        if func.result.is_some() {
+            self.emit(&Instruction::AsyncSubtaskResult);
+            self.emit(&Instruction::GetArg {
+                nth: sig.params.len() - 1,
+            });
+            let ptr = self.stack.pop().unwrap();
+            self.write_params_to_memory(&func.result, ptr, Default::default());
+        }
+        self.finish_block(0);
+        self.realloc = None;
+
+        self.emit(&Instruction::AsyncSubtaskStart { func });
+        self.emit(&Instruction::Return {
+            func,
+            amt: sig.results.len(),
+        });
+    }
+
     fn post_return(&mut self, func: &Function) {
         let sig = self.resolve.wasm_signature(AbiVariant::GuestExport, func);
 
@@ -1329,7 +1565,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             let mut operands = operands;
             let mut operands_for_ty;
             for ty in types {
-                let types = flat_types(self.resolve, ty).unwrap();
+                let types = self.flat_types(ty).unwrap();
                 (operands_for_ty, operands) = operands.split_at(types.len());
                 self.stack.extend_from_slice(operands_for_ty);
                 self.deallocate(ty, what);
@@ -1541,7 +1777,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         cases: impl IntoIterator<Item = Option<&'b Type>>,
     ) -> Vec<WasmType> {
         use Instruction::*;
-        let results = flat_types(self.resolve, ty).unwrap();
+        let results = self.flat_types(ty).unwrap();
         let mut casts = Vec::new();
         for (i, ty) in cases.into_iter().enumerate() {
             self.push_block();
@@ -1558,7 +1794,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                 // Determine the types of all the wasm values we just
                 // pushed, and record how many. If we pushed too few
                 // then we'll need to push some zeros after this.
-                let temp = flat_types(self.resolve, ty).unwrap();
+                let temp = self.flat_types(ty).unwrap();
                 pushed += temp.len();
 
                 // For all the types pushed we may need to insert some
@@ -1716,7 +1952,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                 }
                 TypeDefKind::Unknown => unreachable!(),
                 TypeDefKind::FixedSizeList(ty, size) => {
-                    let temp = flat_types(self.resolve, ty).unwrap();
+                    let temp = self.flat_types(ty).unwrap();
                     let flat_per_elem = temp.to_vec().len();
                     let flatsize = flat_per_elem * (*size as usize);
                     let mut lowered_args = self
@@ -1743,13 +1979,13 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         types: impl Iterator<Item = &'b Type>,
         mut iter: impl FnMut(&mut Self, &Type),
     ) {
-        let temp = flat_types(self.resolve, container).unwrap();
+        let temp = self.flat_types(container).unwrap();
         let mut args = self
             .stack
             .drain(self.stack.len() - temp.len()..)
             .collect::<Vec<_>>();
         for ty in types {
-            let temp = flat_types(self.resolve, ty).unwrap();
+            let temp = self.flat_types(ty).unwrap();
             self.stack.extend(args.drain(..temp.len()));
             iter(self, ty);
         }
@@ -1762,7 +1998,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
         cases: impl IntoIterator<Item = Option<&'b Type>>,
         mut iter: impl FnMut(&mut Self, &Type),
     ) {
-        let params = flat_types(self.resolve, ty).unwrap();
+        let params = self.flat_types(ty).unwrap();
         let mut casts = Vec::new();
         let block_inputs = self
             .stack
@@ -1773,7 +2009,7 @@ impl<'a, B: Bindgen> Generator<'a, B> {
             if let Some(ty) = ty {
                 // Push only the values we need for this variant onto
                 // the stack.
-                let temp = flat_types(self.resolve, ty).unwrap();
+                let temp = self.flat_types(ty).unwrap();
                 self.stack
                     .extend(block_inputs[..temp.len()].iter().cloned());
 
@@ -2318,10 +2554,16 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                     self.stack.pop().unwrap();
                 }
 
+                TypeDefKind::FixedSizeList(element, size) => {
+                    self.flat_for_each_record_type(
+                        ty,
+                        std::iter::repeat_n(element, *size as usize),
+                        |me, ty| me.deallocate(ty, what),
+                    );
+                }
+
                 TypeDefKind::Resource => unreachable!(),
                 TypeDefKind::Unknown => unreachable!(),
-
-                TypeDefKind::FixedSizeList(..) => todo!(),
             },
         }
     }
@@ -2440,7 +2682,14 @@ impl<'a, B: Bindgen> Generator<'a, B> {
                 TypeDefKind::Future(_) => unreachable!(),
                 TypeDefKind::Stream(_) => unreachable!(),
                 TypeDefKind::Unknown => unreachable!(),
-                TypeDefKind::FixedSizeList(_, _) => {}
+                TypeDefKind::FixedSizeList(element, size) => {
+                    let increment = self.bindgen.sizes().size(element);
+                    let mut position = offset;
+                    for _ in 0..*size {
+                        self.deallocate_indirect(element, addr.clone(), position, what);
+                        position += increment;
+                    }
+                }
             },
         }
     }
@@ -2543,7 +2792,7 @@ pub fn wasm_signature_symmetric(
     resolve.wasm_signature_symmetric(variant, func, symmetric)
 }
 
-fn flat_types(resolve: &Resolve, ty: &Type) -> Option<Vec<WasmType>> {
+pub(crate) fn flat_types(resolve: &Resolve, ty: &Type) -> Option<Vec<WasmType>> {
     let mut storage = [WasmType::I32; MAX_FLAT_PARAMS];
     let mut flat = FlatTypes::new(&mut storage);
     if resolve.push_flat(ty, &mut flat) {
@@ -2552,3 +2801,44 @@ fn flat_types(resolve: &Resolve, ty: &Type) -> Option<Vec<WasmType>> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn post_return_for_handles() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    package a:b;
+                    interface i {
+                        resource r;
+                        error: func() -> error-context;
+                        errors: func() -> tuple<error-context, option<error-context>>;
+                        error-list: func() -> list<error-context>;
+                        error-and-string: func() -> result<string, error-context>;
+                        %own: func() -> r;
+                        %future: func() -> future<u32>;
+                        %string: func() -> string;
+                    }
+                "#,
+            )
+            .unwrap();
+        let iface = resolve.packages[pkg].interfaces["i"];
+        let needs_post_return = |name: &str| {
+            let func = &resolve.interfaces[iface].functions[name];
+            guest_export_needs_post_return(&resolve, func)
+        };
+
+        assert!(!needs_post_return("error"));
+        assert!(!needs_post_return("errors"));
+        assert!(!needs_post_return("own"));
+        assert!(!needs_post_return("future"));
+        assert!(needs_post_return("error-list"));
+        assert!(needs_post_return("error-and-string"));
+        assert!(needs_post_return("string"));
+    }
+}
@@ -0,0 +1,569 @@
+//! A [`Bindgen`] which records the instructions it receives.
+//!
+//! Rather than generating code, [`Recorder`] keeps every instruction emitted
+//! by [`call`] as a [`Step`], naming the values each step consumes and
+//! produces by number. Blocks, as used for variant cases and list elements,
+//! are nested under the instruction which consumes them. With the `serde`
+//! feature the result can be serialized, which is what
+//! `wit-bindgen abi --instructions` prints.
+
+use super::{
+    call, wasm_signature_symmetric, AbiVariant, Bindgen, Bitcast, Generator, Instruction,
+    LiftLower, Realloc, WasmSignature, WasmType,
+};
+use std::collections::BTreeMap;
+use wit_parser::{
+    Alignment, ArchitectureSize, Enum, Flags, Function, Handle, Record, Resolve, Result_,
+    SizeAlign, Tuple, Type, TypeDefKind, TypeId, Variant,
+};
+
+/// The instructions generated for one function.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub struct Trace {
+    pub function: String,
+    /// The [`AbiVariant`], e.g. `GuestImport`.
+    pub variant: String,
+    pub lift_lower: &'static str,
+    #[cfg_attr(feature = "serde", serde(rename = "async"))]
+    pub async_: bool,
+    pub body: Block,
+}
+
+/// A sequence of instructions along with the values it produces.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Block {
+    pub instructions: Vec<Step>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub results: Vec<usize>,
+}
+
+/// A single instruction.
+///
+/// Values are numbered in the order they are produced across the whole
+/// [`Trace`], so `operands` may refer to results of steps in enclosing
+/// blocks.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Step {
+    /// Name of the [`Instruction`] variant, or `ReturnPointer` for a call to
+    /// [`Bindgen::return_pointer`].
+    pub instruction: &'static str,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub fields: BTreeMap<&'static str, Value>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub operands: Vec<usize>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub results: Vec<usize>,
+    /// Blocks consumed by this instruction, in the order they were built.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub blocks: Vec<Block>,
+}
+
+/// Value of an immediate field of an [`Instruction`].
+///
+/// Types are rendered using WIT syntax, and records, variants and the like
+/// by the names of their fields or cases.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Int(i64),
+    String(String),
+    List(Vec<Value>),
+    Map(BTreeMap<&'static str, Value>),
+}
+
+/// Conversion of the fields of [`Instruction`] to a [`Value`].
+pub(crate) trait ToValue {
+    fn to_value(&self, resolve: &Resolve) -> Value;
+}
+
+impl<T: ToValue + ?Sized> ToValue for &T {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        (**self).to_value(resolve)
+    }
+}
+
+impl<T: ToValue> ToValue for Option<T> {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        match self {
+            Some(v) => v.to_value(resolve),
+            None => Value::Null,
+        }
+    }
+}
+
+impl<T: ToValue> ToValue for [T] {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        Value::List(self.iter().map(|v| v.to_value(resolve)).collect())
+    }
+}
+
+impl ToValue for bool {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        Value::Bool(*self)
+    }
+}
+
+macro_rules! int_to_value {
+    ($($ty:ty)*) => ($(
+        impl ToValue for $ty {
+            fn to_value(&self, _resolve: &Resolve) -> Value {
+                Value::Int(*self as i64)
+            }
+        }
+    )*)
+}
+
+int_to_value!(i32 u32 usize);
+
+impl ToValue for str {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        Value::String(self.to_string())
+    }
+}
+
+impl ToValue for Type {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        Value::String(type_name(resolve, self))
+    }
+}
+
+impl ToValue for TypeId {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        Type::Id(*self).to_value(resolve)
+    }
+}
+
+impl ToValue for ArchitectureSize {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        size_value(*self)
+    }
+}
+
+fn size_value(size: ArchitectureSize) -> Value {
+    Value::Map(BTreeMap::from([
+        ("bytes", Value::Int(size.bytes as i64)),
+        ("pointers", Value::Int(size.pointers as i64)),
+    ]))
+}
+
+impl ToValue for Alignment {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        align_value(*self)
+    }
+}
+
+fn align_value(align: Alignment) -> Value {
+    match align {
+        Alignment::Pointer => Value::String("pointer".to_string()),
+        Alignment::Bytes(n) => Value::Int(n.get() as i64),
+    }
+}
+
+impl ToValue for WasmType {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        let name = match self {
+            WasmType::I32 => "i32",
+            WasmType::I64 => "i64",
+            WasmType::F32 => "f32",
+            WasmType::F64 => "f64",
+            WasmType::Pointer => "pointer",
+            WasmType::PointerOrI64 => "pointer-or-i64",
+            WasmType::Length => "length",
+        };
+        Value::String(name.to_string())
+    }
+}
+
+impl ToValue for Bitcast {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        Value::String(format!("{self:?}"))
+    }
+}
+
+impl ToValue for WasmSignature {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        Value::Map(BTreeMap::from([
+            ("params", self.params.to_value(resolve)),
+            ("results", self.results.to_value(resolve)),
+            ("indirect-params", Value::Bool(self.indirect_params)),
+            ("retptr", Value::Bool(self.retptr)),
+        ]))
+    }
+}
+
+impl ToValue for Function {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        Value::String(self.name.clone())
+    }
+}
+
+fn names<'a>(names: impl Iterator<Item = &'a String>) -> Value {
+    Value::List(names.map(|n| Value::String(n.clone())).collect())
+}
+
+impl ToValue for Record {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        names(self.fields.iter().map(|f| &f.name))
+    }
+}
+
+impl ToValue for Variant {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        names(self.cases.iter().map(|c| &c.name))
+    }
+}
+
+impl ToValue for Enum {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        names(self.cases.iter().map(|c| &c.name))
+    }
+}
+
+impl ToValue for Flags {
+    fn to_value(&self, _resolve: &Resolve) -> Value {
+        names(self.flags.iter().map(|f| &f.name))
+    }
+}
+
+impl ToValue for Tuple {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        self.types.to_value(resolve)
+    }
+}
+
+impl ToValue for Result_ {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        Value::Map(BTreeMap::from([
+            ("ok", self.ok.to_value(resolve)),
+            ("err", self.err.to_value(resolve)),
+        ]))
+    }
+}
+
+impl ToValue for Handle {
+    fn to_value(&self, resolve: &Resolve) -> Value {
+        Value::String(handle_name(resolve, self))
+    }
+}
+
+fn handle_name(resolve: &Resolve, handle: &Handle) -> String {
+    match handle {
+        Handle::Own(id) => format!("own<{}>", type_name(resolve, &Type::Id(*id))),
+        Handle::Borrow(id) => format!("borrow<{}>", type_name(resolve, &Type::Id(*id))),
+    }
+}
+
+/// Renders `ty` in WIT syntax, using the name of named types.
+pub fn type_name(resolve: &Resolve, ty: &Type) -> String {
+    let id = match ty {
+        Type::Bool => return "bool".to_string(),
+        Type::U8 => return "u8".to_string(),
+        Type::U16 => return "u16".to_string(),
+        Type::U32 => return "u32".to_string(),
+        Type::U64 => return "u64".to_string(),
+        Type::S8 => return "s8".to_string(),
+        Type::S16 => return "s16".to_string(),
+        Type::S32 => return "s32".to_string(),
+        Type::S64 => return "s64".to_string(),
+        Type::F32 => return "f32".to_string(),
+        Type::F64 => return "f64".to_string(),
+        Type::Char => return "char".to_string(),
+        Type::String => return "string".to_string(),
+        Type::ErrorContext => return "error-context".to_string(),
+        Type::Id(id) => *id,
+    };
+    let def = &resolve.types[id];
+    if let Some(name) = &def.name {
+        return name.clone();
+    }
+    let optional = |ty: &Option<Type>| match ty {
+        Some(ty) => type_name(resolve, ty),
+        None => "_".to_string(),
+    };
+    match &def.kind {
+        TypeDefKind::List(ty) => format!("list<{}>", type_name(resolve, ty)),
+        TypeDefKind::FixedSizeList(ty, size) => {
+            format!("list<{}, {size}>", type_name(resolve, ty))
+        }
+        TypeDefKind::Option(ty) => format!("option<{}>", type_name(resolve, ty)),
+        TypeDefKind::Result(r) => format!("result<{}, {}>", optional(&r.ok), optional(&r.err)),
+        TypeDefKind::Tuple(t) => format!(
+            "tuple<{}>",
+            t.types
+                .iter()
+                .map(|ty| type_name(resolve, ty))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeDefKind::Future(ty) => format!("future<{}>", optional(ty)),
+        TypeDefKind::Stream(ty) => format!("stream<{}>", optional(ty)),
+        TypeDefKind::Handle(h) => handle_name(resolve, h),
+        TypeDefKind::Type(ty) => type_name(resolve, ty),
+        TypeDefKind::Record(_)
+        | TypeDefKind::Variant(_)
+        | TypeDefKind::Enum(_)
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Resource
+        | TypeDefKind::Unknown => unreachable!("anonymous {:?}", def.kind),
+    }
+}
+
+/// A [`Bindgen`] which records instructions instead of generating code.
+///
+/// Lists are treated as canonical when every bit pattern of their element
+/// type is valid, the same way the C generator does.
+pub struct Recorder {
+    sizes: SizeAlign,
+    next_value: usize,
+    frames: Vec<Frame>,
+}
+
+#[derive(Default)]
+struct Frame {
+    instructions: Vec<Step>,
+    finished: Vec<Block>,
+}
+
+impl Recorder {
+    pub fn new(resolve: &Resolve, symmetric: bool) -> Recorder {
+        let mut sizes = if symmetric {
+            SizeAlign::new_symmetric()
+        } else {
+            SizeAlign::default()
+        };
+        sizes.fill(resolve);
+        Recorder {
+            sizes,
+            next_value: 0,
+            frames: vec![Frame::default()],
+        }
+    }
+
+    /// Returns the instructions recorded so far.
+    pub fn finish(mut self) -> Block {
+        assert_eq!(self.frames.len(), 1, "unbalanced blocks");
+        let frame = self.frames.pop().unwrap();
+        assert!(frame.finished.is_empty(), "unused blocks");
+        Block {
+            instructions: frame.instructions,
+            results: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, step: Step) {
+        self.frames.last_mut().unwrap().instructions.push(step);
+    }
+
+    fn values(&mut self, n: usize) -> Vec<usize> {
+        let values = (self.next_value..self.next_value + n).collect();
+        self.next_value += n;
+        values
+    }
+}
+
+impl Bindgen for Recorder {
+    type Operand = usize;
+
+    fn emit(
+        &mut self,
+        resolve: &Resolve,
+        inst: &Instruction<'_>,
+        operands: &mut Vec<usize>,
+        results: &mut Vec<usize>,
+    ) {
+        let values = self.values(inst.results_len());
+        results.extend(&values);
+        let blocks = std::mem::take(&mut self.frames.last_mut().unwrap().finished);
+        self.push(Step {
+            instruction: inst.name(),
+            fields: inst.fields(resolve).into_iter().collect(),
+            operands: operands.clone(),
+            results: values,
+            blocks,
+        });
+    }
+
+    fn return_pointer(&mut self, size: ArchitectureSize, align: Alignment) -> usize {
+        let value = self.values(1)[0];
+        self.push(Step {
+            instruction: "ReturnPointer",
+            fields: BTreeMap::from([("size", size_value(size)), ("align", align_value(align))]),
+            operands: Vec::new(),
+            results: vec![value],
+            blocks: Vec::new(),
+        });
+        value
+    }
+
+    fn push_block(&mut self) {
+        self.frames.push(Frame::default());
+    }
+
+    fn finish_block(&mut self, operands: &mut Vec<usize>) {
+        let frame = self.frames.pop().unwrap();
+        assert!(frame.finished.is_empty(), "unused blocks");
+        let block = Block {
+            instructions: frame.instructions,
+            results: std::mem::take(operands),
+        };
+        self.frames.last_mut().unwrap().finished.push(block);
+    }
+
+    fn sizes(&self) -> &SizeAlign {
+        &self.sizes
+    }
+
+    fn is_list_canonical(&self, resolve: &Resolve, element: &Type) -> bool {
+        resolve.all_bits_valid(element)
+    }
+}
+
+/// Records the instructions [`call`] generates for `func`.
+pub fn record(
+    resolve: &Resolve,
+    variant: AbiVariant,
+    lift_lower: LiftLower,
+    func: &Function,
+    async_: bool,
+) -> Trace {
+    let symmetric = matches!(lift_lower, LiftLower::Symmetric);
+    let mut recorder = Recorder::new(resolve, symmetric);
+    let guest_import = matches!(
+        variant,
+        AbiVariant::GuestImport | AbiVariant::GuestImportAsync
+    ) && lift_lower != LiftLower::LiftArgsLowerResults;
+    if async_ && guest_import {
+        guest_import_async(resolve, func, &mut recorder, symmetric);
+    } else {
+        call(resolve, variant, lift_lower, func, &mut recorder, async_);
+    }
+    Trace {
+        function: func.name.clone(),
+        variant: format!("{variant:?}"),
+        lift_lower: match lift_lower {
+            LiftLower::LiftArgsLowerResults => "lift-args-lower-results",
+            LiftLower::LowerArgsLiftResults => "lower-args-lift-results",
+            LiftLower::Symmetric => "symmetric",
+        },
+        async_,
+        body: recorder.finish(),
+    }
+}
+
+/// [`call`] only lifts and lowers async imports for hosts, so this emits what
+/// guest generators do piecewise instead: the arguments are lowered flat, or
+/// into memory when passed indirectly, and the result is lifted from memory
+/// once the subtask has returned.
+fn guest_import_async(
+    resolve: &Resolve,
+    func: &Function,
+    recorder: &mut Recorder,
+    symmetric: bool,
+) {
+    let sig = wasm_signature_symmetric(resolve, AbiVariant::GuestImportAsync, func, symmetric);
+    let mut memory = Vec::new();
+    if sig.indirect_params {
+        memory.extend(func.params.iter().map(|(_, ty)| *ty));
+    }
+    memory.extend(func.result);
+    let offsets = recorder
+        .sizes
+        .field_offsets(&memory)
+        .into_iter()
+        .map(|(offset, _)| offset)
+        .collect::<Vec<_>>();
+    let layout = recorder.sizes.record(&memory);
+    let area = (!memory.is_empty()).then(|| recorder.return_pointer(layout.size, layout.align));
+
+    let mut generator = Generator::new(resolve, recorder, symmetric);
+    generator.realloc = Some(Realloc::Export("cabi_realloc"));
+    for (nth, (_, ty)) in func.params.iter().enumerate() {
+        generator.emit(&Instruction::GetArg { nth });
+        if sig.indirect_params {
+            generator.write_to_memory(ty, area.unwrap(), offsets[nth]);
+        } else {
+            generator.lower(ty);
+        }
+    }
+    if sig.indirect_params {
+        generator.stack.push(area.unwrap());
+    }
+    if sig.retptr {
+        generator.stack.push(area.unwrap());
+    }
+    generator.emit(&Instruction::CallWasm {
+        name: &func.name,
+        sig: &sig,
+        module_prefix: "[async-lower]",
+    });
+    // The status of the subtask isn't part of what the function returns.
+    generator.stack.pop();
+    let amt = match &func.result {
+        Some(ty) => {
+            generator.read_from_memory(ty, area.unwrap(), *offsets.last().unwrap());
+            1
+        }
+        None => 0,
+    };
+    generator.emit(&Instruction::Return { amt, func });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_nest_under_their_instruction() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                "package a:b; world w { import f: func(a: option<u32>) -> u32; }",
+            )
+            .unwrap();
+        let world = resolve.select_world(pkg, None).unwrap();
+        let func = match &resolve.worlds[world].imports[0] {
+            wit_parser::WorldItem::Function(f) => f.clone(),
+            _ => unreachable!(),
+        };
+        let trace = record(
+            &resolve,
+            AbiVariant::GuestImport,
+            LiftLower::LowerArgsLiftResults,
+            &func,
+            false,
+        );
+
+        let names = |block: &Block| {
+            block
+                .instructions
+                .iter()
+                .map(|s| s.instruction)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(&trace.body),
+            ["GetArg", "OptionLower", "CallWasm", "U32FromI32", "Return"]
+        );
+        let lower = &trace.body.instructions[1];
+        assert_eq!(lower.operands, [0]);
+        assert_eq!(lower.fields["payload"], Value::String("u32".to_string()));
+        assert_eq!(lower.blocks.len(), 2);
+        assert_eq!(
+            names(&lower.blocks[0]),
+            ["VariantPayloadName", "I32Const", "ConstZero"]
+        );
+        assert_eq!(
+            names(&lower.blocks[1]),
+            ["VariantPayloadName", "I32Const", "I32FromU32"]
+        );
+        assert_eq!(lower.blocks[1].results.len(), 2);
+    }
+}
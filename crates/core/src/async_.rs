@@ -6,7 +6,11 @@ use wit_parser::{Function, FunctionKind, Resolve, WorldKey};
 /// Structure used to parse the command line argument `--async` consistently
 /// across guest generators.
 #[cfg_attr(feature = "clap", derive(clap::Parser))]
-#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(try_from = "AsyncFilterSetRepr")
+)]
 #[derive(Clone, Default, Debug)]
 pub struct AsyncFilterSet {
     /// Determines which functions to lift or lower `async`, if any.
@@ -37,14 +41,62 @@ pub struct AsyncFilterSet {
             value_name = "FILTER",
         ),
     )]
-    #[cfg_attr(feature = "serde", serde(rename = "async"))]
     async_: Vec<Async>,
 
     #[cfg_attr(feature = "clap", arg(skip))]
-    #[cfg_attr(feature = "serde", serde(skip))]
     used_options: HashSet<usize>,
 }
 
+/// Options in a configuration file are the same strings as passed to
+/// `--async`, for example `async = ["import:foo:bar/baz#method", "-all"]`.
+///
+/// The structured form these options used to be deserialized from, for
+/// example `async = [{ enabled = true, filter = { Function = "f" } }]`, is
+/// still accepted.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum AsyncFilterSetRepr {
+    Directives(Vec<String>),
+    Structured(Vec<Async>),
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<AsyncFilterSetRepr> for AsyncFilterSet {
+    type Error = anyhow::Error;
+
+    fn try_from(repr: AsyncFilterSetRepr) -> Result<AsyncFilterSet> {
+        match repr {
+            AsyncFilterSetRepr::Directives(directives) => directives.try_into(),
+            AsyncFilterSetRepr::Structured(async_) => Ok(AsyncFilterSet {
+                async_,
+                used_options: HashSet::new(),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<Vec<String>> for AsyncFilterSet {
+    type Error = anyhow::Error;
+
+    fn try_from(directives: Vec<String>) -> Result<AsyncFilterSet> {
+        let mut set = AsyncFilterSet::default();
+        for directive in directives.iter() {
+            set.push(directive);
+            match &set.async_.last().unwrap().filter {
+                AsyncFilter::Function(s) | AsyncFilter::Import(s) | AsyncFilter::Export(s)
+                    if s.is_empty() =>
+                {
+                    bail!("invalid async option `{directive}`: missing function name")
+                }
+                _ => {}
+            }
+        }
+        Ok(set)
+    }
+}
+
 #[cfg(feature = "clap")]
 fn parse_async(s: &str) -> Result<Async, String> {
     Ok(Async::parse(s))
@@ -126,7 +178,7 @@ impl AsyncFilterSet {
                 continue;
             }
             if !matches!(opt.filter, AsyncFilter::All) {
-                bail!("unused async option: {opt}");
+                bail!(UnusedAsyncOption(opt.to_string()));
             }
         }
         Ok(())
@@ -143,6 +195,19 @@ impl AsyncFilterSet {
     }
 }
 
+/// Error returned by [`AsyncFilterSet::ensure_all_used`] with the option, as
+/// it was originally passed, which didn't match any function.
+#[derive(Debug, Clone)]
+pub struct UnusedAsyncOption(pub String);
+
+impl fmt::Display for UnusedAsyncOption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unused async option: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnusedAsyncOption {}
+
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 struct Async {
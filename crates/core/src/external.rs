@@ -0,0 +1,255 @@
+//! Support for bindings generators which live outside of this repository.
+//!
+//! An external generator is an executable, conventionally named
+//! `wit-bindgen-<language>`, which is run once per world. It receives a
+//! [`Request`] encoded as JSON on stdin and must print a [`Response`] encoded
+//! as JSON on stdout; anything it writes to stderr is passed through. A
+//! non-zero exit status is reported as an error.
+//!
+//! The request looks like:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "resolve": { "worlds": [...], "interfaces": [...], ... },
+//!   "world": 0,
+//!   "args": ["--some-flag"],
+//!   "options": { "some-option": true }
+//! }
+//! ```
+//!
+//! where `resolve` is the serialized `wit_parser::Resolve`, in which items
+//! refer to each other by their index, and `world` is the index of the world
+//! to generate bindings for. `args` are passed through from the command line
+//! and `options` from the `options` table of a configuration file.
+//!
+//! The response lists the files to generate, with `contents` for text or
+//! `bytes` for binary files:
+//!
+//! ```json
+//! { "files": [{ "name": "my_world.zig", "contents": "..." }] }
+//! ```
+
+use crate::{Files, WorldGenerator};
+use anyhow::{bail, Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use wit_parser::{Function, InterfaceId, Resolve, TypeId, WorldId, WorldKey};
+
+/// Version of the protocol, sent as [`Request::version`].
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Prefix of the names of external generator executables.
+pub const PREFIX: &str = "wit-bindgen-";
+
+/// What is sent to an external generator on stdin.
+#[derive(serde::Serialize)]
+pub struct Request<'a> {
+    pub version: u32,
+    pub resolve: &'a Resolve,
+    /// Index of the world in `resolve.worlds`.
+    pub world: usize,
+    pub args: &'a [String],
+    pub options: &'a serde_json::Value,
+}
+
+/// What an external generator prints on stdout.
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Response {
+    pub files: Vec<ResponseFile>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponseFile {
+    pub name: String,
+    #[serde(default)]
+    pub contents: Option<String>,
+    #[serde(default)]
+    pub bytes: Option<Vec<u8>>,
+}
+
+/// A [`WorldGenerator`] which runs an external executable.
+pub struct External {
+    program: PathBuf,
+    args: Vec<String>,
+    options: serde_json::Value,
+}
+
+impl External {
+    pub fn new(program: impl Into<PathBuf>) -> External {
+        External {
+            program: program.into(),
+            args: Vec::new(),
+            options: serde_json::Value::Object(Default::default()),
+        }
+    }
+
+    /// Arguments passed through to the generator in [`Request::args`].
+    pub fn args(mut self, args: Vec<String>) -> External {
+        self.args = args;
+        self
+    }
+
+    /// Options passed through to the generator in [`Request::options`].
+    pub fn options(mut self, options: serde_json::Value) -> External {
+        self.options = options;
+        self
+    }
+
+    pub fn build(self) -> Box<dyn WorldGenerator> {
+        Box::new(self)
+    }
+
+    fn run(&self, resolve: &Resolve, world: WorldId) -> Result<Response> {
+        let request = serde_json::to_vec(&Request {
+            version: PROTOCOL_VERSION,
+            resolve,
+            world: world.index(),
+            args: &self.args,
+            options: &self.options,
+        })?;
+
+        let program = self.program.display();
+        let mut child = Command::new(&self.program)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("failed to spawn `{program}`"))?;
+
+        // Write the request from another thread so a generator which starts
+        // printing before it has read all of its input can't deadlock.
+        let mut stdin = child.stdin.take().unwrap();
+        let output = std::thread::scope(|s| {
+            let writer = s.spawn(move || stdin.write_all(&request));
+            let output = child.wait_with_output();
+            (writer.join().unwrap(), output)
+        });
+        let output = match output {
+            (_, Err(e)) => return Err(e).with_context(|| format!("failed to run `{program}`")),
+            (Err(e), Ok(output)) if output.status.success() => {
+                return Err(e).with_context(|| format!("failed to write to `{program}`"))
+            }
+            (_, Ok(output)) => output,
+        };
+        if !output.status.success() {
+            bail!("`{program}` failed: {}", output.status);
+        }
+        serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("invalid response from `{program}`"))
+    }
+}
+
+impl WorldGenerator for External {
+    fn generate(&mut self, resolve: &Resolve, id: WorldId, files: &mut Files) -> Result<()> {
+        let response = self.run(resolve, id)?;
+        for file in response.files {
+            let contents = match (&file.contents, &file.bytes) {
+                (Some(contents), None) => contents.as_bytes(),
+                (None, Some(bytes)) => bytes.as_slice(),
+                _ => bail!(
+                    "`{}` must provide exactly one of `contents` or `bytes` for {:?}",
+                    self.program.display(),
+                    file.name
+                ),
+            };
+            // Names are joined onto the output directory, so don't let them
+            // escape it.
+            if !Path::new(&file.name)
+                .components()
+                .all(|c| matches!(c, std::path::Component::Normal(_)))
+            {
+                bail!(
+                    "`{}` produced invalid file name {:?}",
+                    self.program.display(),
+                    file.name
+                );
+            }
+            files.push(&file.name, contents);
+        }
+        Ok(())
+    }
+
+    fn import_interface(
+        &mut self,
+        _resolve: &Resolve,
+        _name: &WorldKey,
+        _iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<()> {
+        unreachable!()
+    }
+
+    fn export_interface(
+        &mut self,
+        _resolve: &Resolve,
+        _name: &WorldKey,
+        _iface: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<()> {
+        unreachable!()
+    }
+
+    fn import_funcs(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) {
+        unreachable!()
+    }
+
+    fn export_funcs(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<()> {
+        unreachable!()
+    }
+
+    fn import_types(
+        &mut self,
+        _resolve: &Resolve,
+        _world: WorldId,
+        _types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) {
+        unreachable!()
+    }
+
+    fn finish(&mut self, _resolve: &Resolve, _world: WorldId, _files: &mut Files) -> Result<()> {
+        unreachable!()
+    }
+}
+
+/// Looks for the external generator for `language` on `PATH`, the way cargo
+/// finds its subcommands.
+pub fn find(language: &str) -> Option<PathBuf> {
+    let name = format!("{PREFIX}{language}{}", std::env::consts::EXE_SUFFIX);
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// Whether `path` is a file which can be run. On Unix this requires one of
+/// the execute bits, so a stray non-executable file doesn't shadow a
+/// generator later on `PATH`.
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
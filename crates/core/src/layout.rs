@@ -0,0 +1,466 @@
+//! Layouts of types and functions.
+//!
+//! [`Layouts`] caches the size, alignment, flattening and
+//! [`TypeInfo`](crate::TypeInfo) of every type in a [`Resolve`] so it can be
+//! shared between generators.
+//!
+//! [`world_layout`] produces a machine-readable description of the ABI of a
+//! world. This computes the same flat signatures and memory layouts the
+//! generators use, for each supported target, so they can be inspected
+//! without reading generated code. With the `serde` feature enabled it can be
+//! serialized, which is what `wit-bindgen abi` does.
+
+use crate::abi::{self, flat_types, wasm_signature_symmetric, AbiVariant, WasmType};
+use crate::{TypeInfo, Types};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, OnceLock};
+use wit_parser::{
+    Alignment, ArchitectureSize, ElementInfo, Function, Int, Resolve, SizeAlign, Type, TypeDefKind,
+    TypeId, WorldId, WorldItem,
+};
+
+/// Layout information about the types of a [`Resolve`], computed on first use.
+///
+/// A single `Layouts` can be handed to several generators through
+/// [`WorldGenerator::set_layouts`](crate::WorldGenerator::set_layouts) so that
+/// generating many targets from the same WIT only computes it once. It must
+/// only be used with the `Resolve` it was first used with, as it is after
+/// [`WorldGenerator::apply_resolve_options`](crate::WorldGenerator::apply_resolve_options).
+#[derive(Default)]
+pub struct Layouts {
+    canonical: OnceLock<Arc<SizeAlign>>,
+    symmetric: OnceLock<Arc<SizeAlign>>,
+    types: OnceLock<Arc<Types>>,
+    flat: Mutex<HashMap<TypeId, Option<Vec<WasmType>>>>,
+}
+
+impl Layouts {
+    /// Returns `layouts` if any were provided, or new layouts otherwise.
+    pub fn reuse(layouts: Option<Arc<Layouts>>) -> Arc<Layouts> {
+        layouts.unwrap_or_default()
+    }
+
+    /// Sizes and alignments for the canonical ABI, which cover both wasm32
+    /// and wasm64, or for the symmetric ABI.
+    pub fn sizes(&self, resolve: &Resolve, symmetric: bool) -> Arc<SizeAlign> {
+        let (cell, new): (_, fn() -> SizeAlign) = if symmetric {
+            (&self.symmetric, SizeAlign::new_symmetric)
+        } else {
+            (&self.canonical, SizeAlign::default)
+        };
+        cell.get_or_init(|| {
+            let mut sizes = new();
+            sizes.fill(resolve);
+            Arc::new(sizes)
+        })
+        .clone()
+    }
+
+    /// [`TypeInfo`] for every type, as computed by [`Types::analyze`].
+    pub fn types(&self, resolve: &Resolve) -> Arc<Types> {
+        self.types_ref(resolve).clone()
+    }
+
+    pub fn type_info(&self, resolve: &Resolve, id: TypeId) -> TypeInfo {
+        self.types_ref(resolve).get(id)
+    }
+
+    fn types_ref(&self, resolve: &Resolve) -> &Arc<Types> {
+        self.types.get_or_init(|| {
+            let mut types = Types::default();
+            types.analyze(resolve);
+            Arc::new(types)
+        })
+    }
+
+    /// The core wasm types `ty` flattens to, or `None` if there are too many
+    /// to pass as flat parameters.
+    pub fn flat(&self, resolve: &Resolve, ty: &Type) -> Option<Vec<WasmType>> {
+        let Type::Id(id) = ty else {
+            return flat_types(resolve, ty);
+        };
+        if let Some(flat) = self.flat.lock().unwrap().get(id) {
+            return flat.clone();
+        }
+        let flat = flat_types(resolve, ty);
+        self.flat.lock().unwrap().insert(*id, flat.clone());
+        flat
+    }
+}
+
+/// Target whose layout is described.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Target {
+    /// Canonical ABI with 32-bit linear memory.
+    Wasm32,
+    /// Canonical ABI with 64-bit linear memory.
+    Wasm64,
+    /// Symmetric (native) ABI, assuming a 64-bit host.
+    Symmetric,
+}
+
+impl Target {
+    pub const ALL: [Target; 3] = [Target::Wasm32, Target::Wasm64, Target::Symmetric];
+
+    pub fn pointer_size(&self) -> usize {
+        match self {
+            Target::Wasm32 => 4,
+            Target::Wasm64 | Target::Symmetric => 8,
+        }
+    }
+
+    fn size(&self, size: ArchitectureSize) -> usize {
+        match self {
+            Target::Wasm32 => size.size_wasm32(),
+            Target::Wasm64 | Target::Symmetric => size.size_wasm64(),
+        }
+    }
+
+    fn align(&self, align: Alignment) -> usize {
+        match self {
+            Target::Wasm32 => align.align_wasm32(),
+            Target::Wasm64 | Target::Symmetric => align.align_wasm64(),
+        }
+    }
+
+    fn flat_type(&self, ty: WasmType) -> &'static str {
+        match (self, ty) {
+            (_, WasmType::I32) => "i32",
+            (_, WasmType::I64) => "i64",
+            (_, WasmType::F32) => "f32",
+            (_, WasmType::F64) => "f64",
+            (Target::Wasm32, WasmType::Pointer | WasmType::Length) => "i32",
+            (Target::Wasm64, WasmType::Pointer | WasmType::Length) => "i64",
+            (Target::Wasm32 | Target::Wasm64, WasmType::PointerOrI64) => "i64",
+            (Target::Symmetric, WasmType::Pointer) => "pointer",
+            (Target::Symmetric, WasmType::Length) => "length",
+            (Target::Symmetric, WasmType::PointerOrI64) => "pointer-or-i64",
+        }
+    }
+}
+
+/// Layout of a whole world for every [`Target`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WorldLayout {
+    pub world: String,
+    pub targets: Vec<TargetLayout>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TargetLayout {
+    pub target: Target,
+    pub pointer_size: usize,
+    pub types: Vec<TypeLayout>,
+    pub functions: Vec<FunctionLayout>,
+}
+
+/// In-memory representation of a type.
+///
+/// Anonymous types are named by how they are spelled in WIT, like
+/// `list<u8, 5>`.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeLayout {
+    pub interface: Option<String>,
+    pub name: String,
+    pub kind: &'static str,
+    pub size: usize,
+    pub align: usize,
+    /// Offsets of record fields and tuple elements.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub fields: Vec<FieldLayout>,
+    /// Offset of the payload of a variant, option or result.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub payload_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FieldLayout {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FunctionLayout {
+    pub interface: Option<String>,
+    pub name: String,
+    pub import: bool,
+    pub signatures: Vec<SignatureLayout>,
+}
+
+/// Flattened core signature of a function for one [`AbiVariant`].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SignatureLayout {
+    pub variant: &'static str,
+    pub params: Vec<&'static str>,
+    pub results: Vec<&'static str>,
+    pub indirect_params: bool,
+    pub retptr: bool,
+    /// Memory holding the parameters when `indirect_params` is set.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub params_area: Option<AreaLayout>,
+    /// Memory holding the results when `retptr` is set; provided by the
+    /// caller for imports and by the callee for exports.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub return_area: Option<AreaLayout>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AreaLayout {
+    pub size: usize,
+    pub align: usize,
+}
+
+/// Computes the layout of `world` for every [`Target`].
+pub fn world_layout(resolve: &Resolve, world: WorldId) -> WorldLayout {
+    let layouts = Layouts::default();
+    WorldLayout {
+        world: resolve.worlds[world].name.clone(),
+        targets: Target::ALL
+            .iter()
+            .map(|target| target_layout(resolve, &layouts, world, *target))
+            .collect(),
+    }
+}
+
+/// Computes the layout of `world` for a single `target`.
+///
+/// Besides the named types of the world and its interfaces this includes the
+/// anonymous types, like `tuple<u64, f32>` or `option<mixed>`, that they and
+/// the function signatures use.
+pub fn target_layout(
+    resolve: &Resolve,
+    layouts: &Layouts,
+    world: WorldId,
+    target: Target,
+) -> TargetLayout {
+    let sizes = layouts.sizes(resolve, target == Target::Symmetric);
+
+    let mut layout = TargetLayout {
+        target,
+        pointer_size: target.pointer_size(),
+        types: Vec::new(),
+        functions: Vec::new(),
+    };
+    let mut seen = HashSet::new();
+    let push_type = |layout: &mut TargetLayout,
+                     seen: &mut HashSet<TypeId>,
+                     interface: Option<&str>,
+                     id: TypeId| {
+        let mut anonymous = Vec::new();
+        for ty in contents(&resolve.types[id].kind) {
+            anonymous_types(resolve, ty, seen, &mut anonymous);
+        }
+        for id in anonymous.into_iter().chain([id]) {
+            layout
+                .types
+                .push(type_layout(resolve, &sizes, target, interface, id));
+        }
+    };
+    let push_function = |layout: &mut TargetLayout,
+                         seen: &mut HashSet<TypeId>,
+                         interface: Option<&str>,
+                         func: &Function,
+                         import: bool| {
+        let mut anonymous = Vec::new();
+        for ty in func.params.iter().map(|(_, ty)| ty).chain(&func.result) {
+            anonymous_types(resolve, ty, seen, &mut anonymous);
+        }
+        for id in anonymous {
+            layout
+                .types
+                .push(type_layout(resolve, &sizes, target, interface, id));
+        }
+        layout.functions.push(function_layout(
+            resolve, &sizes, target, interface, func, import,
+        ));
+    };
+    let world = &resolve.worlds[world];
+    for (import, items) in [(true, &world.imports), (false, &world.exports)] {
+        for (key, item) in items.iter() {
+            match item {
+                WorldItem::Interface { id, .. } => {
+                    let name = resolve.name_world_key(key);
+                    let iface = &resolve.interfaces[*id];
+                    for (_, ty) in iface.types.iter() {
+                        push_type(&mut layout, &mut seen, Some(&name), *ty);
+                    }
+                    for (_, func) in iface.functions.iter() {
+                        push_function(&mut layout, &mut seen, Some(&name), func, import);
+                    }
+                }
+                WorldItem::Function(func) => {
+                    push_function(&mut layout, &mut seen, None, func, import)
+                }
+                WorldItem::Type(ty) => push_type(&mut layout, &mut seen, None, *ty),
+            }
+        }
+    }
+    layout
+}
+
+/// Pushes the anonymous types used by `ty` which aren't in `seen` onto
+/// `out`, after the ones they use themselves.
+///
+/// Named types are listed on their own, so they aren't followed.
+fn anonymous_types(
+    resolve: &Resolve,
+    ty: &Type,
+    seen: &mut HashSet<TypeId>,
+    out: &mut Vec<TypeId>,
+) {
+    let Type::Id(id) = ty else { return };
+    let def = &resolve.types[*id];
+    if def.name.is_some() || !seen.insert(*id) {
+        return;
+    }
+    for ty in contents(&def.kind) {
+        anonymous_types(resolve, ty, seen, out);
+    }
+    out.push(*id);
+}
+
+/// The types directly used by a type definition.
+fn contents(kind: &TypeDefKind) -> Vec<&Type> {
+    match kind {
+        TypeDefKind::Record(r) => r.fields.iter().map(|f| &f.ty).collect(),
+        TypeDefKind::Tuple(t) => t.types.iter().collect(),
+        TypeDefKind::Variant(v) => v.cases.iter().filter_map(|c| c.ty.as_ref()).collect(),
+        TypeDefKind::Option(ty)
+        | TypeDefKind::List(ty)
+        | TypeDefKind::FixedSizeList(ty, _)
+        | TypeDefKind::Type(ty) => vec![ty],
+        TypeDefKind::Result(r) => r.ok.iter().chain(&r.err).collect(),
+        TypeDefKind::Future(ty) | TypeDefKind::Stream(ty) => ty.iter().collect(),
+        TypeDefKind::Enum(_)
+        | TypeDefKind::Flags(_)
+        | TypeDefKind::Resource
+        | TypeDefKind::Handle(_)
+        | TypeDefKind::Unknown => Vec::new(),
+    }
+}
+
+fn type_layout(
+    resolve: &Resolve,
+    sizes: &SizeAlign,
+    target: Target,
+    interface: Option<&str>,
+    id: TypeId,
+) -> TypeLayout {
+    let def = &resolve.types[id];
+    let ty = Type::Id(id);
+    let fields = |types: Vec<(String, &Type)>| {
+        let offsets = sizes.field_offsets(types.iter().map(|(_, ty)| *ty));
+        types
+            .iter()
+            .zip(offsets)
+            .map(|((name, ty), (offset, _))| FieldLayout {
+                name: name.clone(),
+                offset: target.size(offset),
+                size: target.size(sizes.size(ty)),
+            })
+            .collect()
+    };
+    let payload =
+        |tag: Int, cases: Vec<Option<&Type>>| Some(target.size(sizes.payload_offset(tag, cases)));
+    let (kind, fields, payload_offset) = match &def.kind {
+        TypeDefKind::Record(r) => (
+            "record",
+            fields(r.fields.iter().map(|f| (f.name.clone(), &f.ty)).collect()),
+            None,
+        ),
+        TypeDefKind::Tuple(t) => (
+            "tuple",
+            fields(
+                t.types
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| (i.to_string(), ty))
+                    .collect(),
+            ),
+            None,
+        ),
+        TypeDefKind::Variant(v) => (
+            "variant",
+            Vec::new(),
+            payload(v.tag(), v.cases.iter().map(|c| c.ty.as_ref()).collect()),
+        ),
+        TypeDefKind::Option(t) => ("option", Vec::new(), payload(Int::U8, vec![None, Some(t)])),
+        TypeDefKind::Result(r) => (
+            "result",
+            Vec::new(),
+            payload(Int::U8, vec![r.ok.as_ref(), r.err.as_ref()]),
+        ),
+        TypeDefKind::Enum(_) => ("enum", Vec::new(), None),
+        TypeDefKind::Flags(_) => ("flags", Vec::new(), None),
+        TypeDefKind::List(_) => ("list", Vec::new(), None),
+        TypeDefKind::FixedSizeList(..) => ("fixed-size-list", Vec::new(), None),
+        TypeDefKind::Resource => ("resource", Vec::new(), None),
+        TypeDefKind::Handle(_) => ("handle", Vec::new(), None),
+        TypeDefKind::Future(_) => ("future", Vec::new(), None),
+        TypeDefKind::Stream(_) => ("stream", Vec::new(), None),
+        TypeDefKind::Type(_) => ("alias", Vec::new(), None),
+        TypeDefKind::Unknown => unreachable!(),
+    };
+    TypeLayout {
+        interface: interface.map(|s| s.to_string()),
+        name: abi::type_name(resolve, &ty),
+        kind,
+        size: target.size(sizes.size(&ty)),
+        align: target.align(sizes.align(&ty)),
+        fields,
+        payload_offset,
+    }
+}
+
+fn function_layout(
+    resolve: &Resolve,
+    sizes: &SizeAlign,
+    target: Target,
+    interface: Option<&str>,
+    func: &Function,
+    import: bool,
+) -> FunctionLayout {
+    let signatures = [
+        (AbiVariant::GuestImport, "guest-import"),
+        (AbiVariant::GuestExport, "guest-export"),
+    ]
+    .into_iter()
+    .map(|(variant, variant_name)| {
+        let sig = match target {
+            Target::Wasm32 | Target::Wasm64 => resolve.wasm_signature(variant, func),
+            Target::Symmetric => wasm_signature_symmetric(resolve, variant, func, true),
+        };
+        let area = |info: ElementInfo| AreaLayout {
+            size: target.size(info.size),
+            align: target.align(info.align),
+        };
+        SignatureLayout {
+            variant: variant_name,
+            params: sig.params.iter().map(|t| target.flat_type(*t)).collect(),
+            results: sig.results.iter().map(|t| target.flat_type(*t)).collect(),
+            indirect_params: sig.indirect_params,
+            retptr: sig.retptr,
+            params_area: sig
+                .indirect_params
+                .then(|| area(sizes.record(func.params.iter().map(|(_, ty)| ty)))),
+            return_area: sig.retptr.then(|| area(sizes.record(func.result.iter()))),
+        }
+    })
+    .collect();
+    FunctionLayout {
+        interface: interface.map(|s| s.to_string()),
+        name: func.name.clone(),
+        import,
+        signatures,
+    }
+}
@@ -1,9 +1,14 @@
 use std::fmt::Write;
+use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 pub use wit_parser;
 use wit_parser::*;
 pub mod abi;
+#[cfg(feature = "external")]
+pub mod external;
+pub mod layout;
+use layout::Layouts;
 mod ns;
 pub use ns::Ns;
 pub mod source;
@@ -14,7 +19,7 @@ mod path;
 pub use path::name_package_module;
 mod async_;
 pub mod symmetric;
-pub use async_::AsyncFilterSet;
+pub use async_::{AsyncFilterSet, UnusedAsyncOption};
 
 #[derive(Default, Copy, Clone, PartialEq, Eq, Debug)]
 pub enum Direction {
@@ -53,6 +58,7 @@ pub trait WorldGenerator {
             self.import_funcs(resolve, id, &funcs, files);
         }
         funcs.clear();
+        types.clear();
 
         self.finish_imports(resolve, id, files);
 
@@ -67,9 +73,12 @@ pub trait WorldGenerator {
             match export {
                 WorldItem::Function(f) => funcs.push((unwrap_name(name), f)),
                 WorldItem::Interface { id, .. } => interfaces.push((name, id)),
-                WorldItem::Type(_) => unreachable!(),
+                WorldItem::Type(id) => types.push((unwrap_name(name), *id)),
             }
         }
+        if !types.is_empty() {
+            self.export_types(resolve, id, &types, files)?;
+        }
         if !funcs.is_empty() {
             self.export_funcs(resolve, id, &funcs, files)?;
         }
@@ -132,9 +141,41 @@ pub trait WorldGenerator {
         types: &[(&str, TypeId)],
         files: &mut Files,
     );
+    /// Called with the types, in practice resources, which a world exports
+    /// directly rather than through an interface.
+    ///
+    /// The functions of these resources are passed to `export_funcs`
+    /// afterwards, along with the world's other exported functions.
+    fn export_types(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        types: &[(&str, TypeId)],
+        files: &mut Files,
+    ) -> Result<()> {
+        let _ = (resolve, world, files);
+        let names = types.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+        bail!(
+            "this generator does not support types exported from a world: {}",
+            names.join(", ")
+        )
+    }
     fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) -> Result<()>;
     // modify resolve by command line options
     fn apply_resolve_options(&mut self, _resolve: &mut Resolve, _world: &mut WorldId) {}
+
+    /// Whether [`WorldGenerator::apply_resolve_options`] modifies the
+    /// `Resolve`, in which case it can't be shared with other generators.
+    fn modifies_resolve(&self) -> bool {
+        false
+    }
+
+    /// Provides layouts which may already have been computed for the
+    /// `Resolve` being generated, for example by another target of the same
+    /// `wit-bindgen generate --config` run.
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        let _ = layouts;
+    }
 }
 
 /// This is a possible replacement for the `Generator` trait above, currently
@@ -158,6 +199,7 @@ pub trait InterfaceGenerator<'a> {
     fn type_enum(&mut self, id: TypeId, name: &str, enum_: &Enum, docs: &Docs);
     fn type_alias(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
     fn type_list(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
+    fn type_fixed_size_list(&mut self, id: TypeId, name: &str, ty: &Type, size: u32, docs: &Docs);
     fn type_builtin(&mut self, id: TypeId, name: &str, ty: &Type, docs: &Docs);
     fn type_future(&mut self, id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs);
     fn type_stream(&mut self, id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs);
@@ -184,7 +226,9 @@ pub trait InterfaceGenerator<'a> {
             TypeDefKind::Future(t) => self.type_future(id, name, t, &ty.docs),
             TypeDefKind::Stream(t) => self.type_stream(id, name, t, &ty.docs),
             TypeDefKind::Handle(_) => panic!("handle types do not require definition"),
-            TypeDefKind::FixedSizeList(..) => todo!(),
+            TypeDefKind::FixedSizeList(t, size) => {
+                self.type_fixed_size_list(id, name, t, *size, &ty.docs)
+            }
             TypeDefKind::Unknown => unreachable!(),
         }
     }
@@ -242,6 +286,19 @@ pub fn dealias(resolve: &Resolve, mut id: TypeId) -> TypeId {
     }
 }
 
+/// Deserializes an option, such as a string encoding, from the same string
+/// as its command line flag takes, for use with `#[serde(deserialize_with)]`.
+#[cfg(feature = "serde")]
+pub fn deserialize_from_str<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+}
+
 fn hexdigit(v: u32) -> char {
     if v < 10 {
         char::from_u32(('0' as u32) + v).unwrap()
@@ -2,6 +2,27 @@
 
 use wit_parser::{Resolve, Type, TypeDefKind};
 
+/// Interface providing the handles behind futures and streams in the
+/// symmetric ABI, see `crates/symmetric_executor/wit/executor.wit`.
+pub const STREAM_INTERFACE: &str = "symmetric:runtime/symmetric-stream@0.2.1";
+
+/// Resource within [`STREAM_INTERFACE`] used for both `future<T>` and
+/// `stream<T>`; the value is passed as an owned handle.
+pub const STREAM_RESOURCE: &str = "stream-obj";
+
+/// Whether `ty` is lowered to a [`STREAM_RESOURCE`] handle in the symmetric
+/// ABI, i.e. it is a future or stream (possibly behind type aliases).
+pub fn is_stream_handle(resolve: &Resolve, ty: &Type) -> bool {
+    match ty {
+        Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => true,
+            TypeDefKind::Type(ty) => is_stream_handle(resolve, ty),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 // figure out whether deallocation is needed in the caller
 fn needs_dealloc2(resolve: &Resolve, tp: &Type) -> bool {
     match tp {
@@ -38,13 +59,13 @@ fn needs_dealloc2(resolve: &Resolve, tp: &Type) -> bool {
                         .map_or(false, |tp| needs_dealloc2(resolve, tp))
             }
             TypeDefKind::List(_l) => true,
-            TypeDefKind::Future(_) => todo!(),
-            TypeDefKind::Stream(_) => todo!(),
+            // ownership of the stream-obj handle moves to the callee
+            TypeDefKind::Future(_) | TypeDefKind::Stream(_) => false,
             TypeDefKind::Type(tp) => needs_dealloc2(resolve, tp),
             TypeDefKind::Unknown => false,
-            TypeDefKind::FixedSizeList(_, _) => todo!(),
+            TypeDefKind::FixedSizeList(tp, _) => needs_dealloc2(resolve, tp),
         },
-        Type::ErrorContext => todo!(),
+        Type::ErrorContext => false,
     }
 }
 
@@ -71,7 +92,8 @@ fn has_non_canonical_list2(resolve: &Resolve, ty: &Type, maybe: bool) -> bool {
         | Type::F32
         | Type::F64
         | Type::Char
-        | Type::String => false,
+        | Type::String
+        | Type::ErrorContext => false,
         Type::Id(id) => match &resolve.types[*id].kind {
             TypeDefKind::Record(r) => r
                 .fields
@@ -108,9 +130,8 @@ fn has_non_canonical_list2(resolve: &Resolve, ty: &Type, maybe: bool) -> bool {
             TypeDefKind::Future(_) | TypeDefKind::Stream(_) => false,
             TypeDefKind::Type(ty) => has_non_canonical_list2(resolve, ty, maybe),
             TypeDefKind::Unknown => false,
-            TypeDefKind::FixedSizeList(_, _) => todo!(),
+            TypeDefKind::FixedSizeList(ty, _) => has_non_canonical_list2(resolve, ty, maybe),
         },
-        Type::ErrorContext => todo!(),
     }
 }
 
@@ -142,7 +163,8 @@ fn has_non_canonical_list_rust2(resolve: &Resolve, ty: &Type) -> bool {
         | Type::F32
         | Type::F64
         | Type::Char
-        | Type::String => false,
+        | Type::String
+        | Type::ErrorContext => false,
         Type::Id(id) => match &resolve.types[*id].kind {
             TypeDefKind::Record(r) => r
                 .fields
@@ -175,7 +197,6 @@ fn has_non_canonical_list_rust2(resolve: &Resolve, ty: &Type) -> bool {
             TypeDefKind::Unknown => false,
             TypeDefKind::FixedSizeList(ty, _) => has_non_canonical_list_rust2(resolve, ty),
         },
-        Type::ErrorContext => todo!(),
     }
 }
 
@@ -183,3 +204,41 @@ pub fn has_non_canonical_list_rust(resolve: &Resolve, args: &[(String, Type)]) -
     args.iter()
         .any(|(_, ty)| has_non_canonical_list_rust2(resolve, ty))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn async_types_in_records() {
+        let mut resolve = Resolve::default();
+        let pkg = resolve
+            .push_str(
+                "test.wit",
+                r#"
+                    package a:b;
+                    interface i {
+                        record r { s: stream<u8>, f: future<string>, e: error-context }
+                        record l { s: stream<u8>, l: list<u8> }
+                        type s = stream<u32>;
+                        x: func(a: r, b: s);
+                        y: func(a: l);
+                    }
+                "#,
+            )
+            .unwrap();
+        let iface = resolve.packages[pkg].interfaces["i"];
+        let func = |name: &str| resolve.interfaces[iface].functions[name].clone();
+
+        let x = func("x");
+        assert!(!needs_dealloc(&resolve, &x.params));
+        assert!(!has_non_canonical_list(&resolve, &x.params));
+        assert!(!has_non_canonical_list_rust(&resolve, &x.params));
+        assert!(!is_stream_handle(&resolve, &x.params[0].1));
+        assert!(is_stream_handle(&resolve, &x.params[1].1));
+
+        let y = func("y");
+        assert!(needs_dealloc(&resolve, &y.params));
+        assert!(has_non_canonical_list_rust(&resolve, &y.params));
+    }
+}
@@ -7,18 +7,20 @@ use std::{
     path::PathBuf,
     process::{Command, Stdio},
     str::FromStr,
+    sync::Arc,
 };
 use symbol_name::{make_external_component, make_external_symbol};
 use wit_bindgen_c::to_c_ident;
 use wit_bindgen_core::{
     abi::{self, AbiVariant, Bindgen, Bitcast, LiftLower, WasmSignature, WasmType},
+    layout::Layouts,
     symmetric, uwrite, uwriteln,
     wit_parser::{
         Alignment, ArchitectureSize, Docs, Function, FunctionKind, Handle, Int, InterfaceId,
         Resolve, SizeAlign, Stability, Type, TypeDef, TypeDefKind, TypeId, TypeOwner, WorldId,
-        WorldKey,
+        WorldItem, WorldKey,
     },
-    Files, InterfaceGenerator, Source, Types, WorldGenerator,
+    Files, InterfaceGenerator, Source, WorldGenerator,
 };
 
 mod symbol_name;
@@ -114,7 +116,7 @@ struct Cpp {
     imported_interfaces: HashSet<InterfaceId>,
     user_class_files: HashMap<String, String>,
     defined_types: HashSet<(Vec<String>, String)>,
-    types: Types,
+    layouts: Option<Arc<Layouts>>,
 
     // needed for symmetric disambiguation
     interface_prefixes: HashMap<(Direction, WorldKey), String>,
@@ -125,6 +127,11 @@ struct Cpp {
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, rename_all = "kebab-case")
+)]
 pub struct Opts {
     /// Generate host bindings
     #[cfg_attr(feature = "clap", arg(long, default_value_t = bool::default()))]
@@ -195,11 +202,17 @@ pub struct Opts {
 
     /// Where to place output files
     #[cfg_attr(feature = "clap", arg(skip))]
+    #[cfg_attr(feature = "serde", serde(skip))]
     out_dir: Option<PathBuf>,
 }
 
 /// Supported API styles for the generated bindings.
 #[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum APIStyle {
     /// Imported functions borrow arguments, while exported functions receive owned arguments. Reduces the allocation overhead for the canonical ABI.
     #[default]
@@ -233,6 +246,11 @@ impl FromStr for APIStyle {
 }
 
 #[derive(Default, Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum Ownership {
     /// Generated types will be composed entirely of owning fields, regardless
     /// of whether they are used as parameters to imports or not.
@@ -316,6 +334,11 @@ impl Cpp {
         Cpp::default()
     }
 
+    /// Layouts of the `Resolve` being generated, available once preprocessed.
+    fn layouts(&self) -> &Layouts {
+        self.layouts.as_deref().unwrap()
+    }
+
     pub fn is_first_definition(&mut self, ns: &Vec<String>, name: &str) -> bool {
         let owned = (ns.to_owned(), name.to_owned());
         if !self.defined_types.contains(&owned) {
@@ -337,12 +360,7 @@ impl Cpp {
         in_guest_import: bool,
         wasm_import_module: Option<String>,
     ) -> CppInterfaceGenerator<'a> {
-        let mut sizes = if self.opts.symmetric {
-            SizeAlign::new_symmetric()
-        } else {
-            SizeAlign::default()
-        };
-        sizes.fill(resolve);
+        let sizes = Layouts::reuse(self.layouts.clone()).sizes(resolve, self.opts.symmetric);
 
         CppInterfaceGenerator {
             _src: Source::default(),
@@ -523,10 +541,14 @@ struct FileContext {
 }
 
 impl WorldGenerator for Cpp {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         let name = &resolve.worlds[world].name;
         self.world = name.to_string();
-        self.types.analyze(resolve);
+        self.layouts = Some(Layouts::reuse(self.layouts.take()));
         self.world_id = Some(world);
         //        self.sizes.fill(resolve);
         if !self.opts.host_side() {
@@ -689,6 +711,9 @@ impl WorldGenerator for Cpp {
         world_id: WorldId,
         files: &mut Files,
     ) -> std::result::Result<(), anyhow::Error> {
+        if self.opts.host {
+            check_host_sync(resolve, world_id)?;
+        }
         let world = &resolve.worlds[world_id];
         let snake = world.name.to_snake_case();
         let linking_symbol = wit_bindgen_c::component_type_object::linking_symbol(&world.name);
@@ -726,6 +751,14 @@ impl WorldGenerator for Cpp {
             );
         }
         if self.dependencies.needs_future || self.dependencies.needs_stream {
+            if self.opts.symmetric {
+                uwriteln!(
+                    self.c_src_head,
+                    "// futures and streams are `{}` handles from `{}`",
+                    symmetric::STREAM_RESOURCE,
+                    symmetric::STREAM_INTERFACE
+                );
+            }
             uwriteln!(self.c_src_head, "#include \"async_support.h\"")
         }
         self.finish_includes();
@@ -922,6 +955,35 @@ impl WorldGenerator for Cpp {
     }
 }
 
+/// Host bindings call exports and implement imports synchronously, so worlds
+/// with async functions are rejected instead of being generated with a
+/// mismatched ABI. Supporting them is a follow-up, see "Async host functions"
+/// in `DESIGN.md`.
+fn check_host_sync(resolve: &Resolve, world: WorldId) -> anyhow::Result<()> {
+    let world = &resolve.worlds[world];
+    for item in world.imports.values().chain(world.exports.values()) {
+        let funcs = match item {
+            WorldItem::Function(func) => vec![func],
+            WorldItem::Interface { id, .. } => resolve.interfaces[*id].functions.values().collect(),
+            WorldItem::Type(_) => Vec::new(),
+        };
+        for func in funcs {
+            if matches!(
+                func.kind,
+                FunctionKind::AsyncFreestanding
+                    | FunctionKind::AsyncMethod(_)
+                    | FunctionKind::AsyncStatic(_)
+            ) {
+                bail!(
+                    "C++ host bindings do not support async functions (`{}`)",
+                    func.name
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
 // determine namespace (for the lifted C++ function)
 fn namespace(resolve: &Resolve, owner: &TypeOwner, guest_export: bool, opts: &Opts) -> Vec<String> {
     let mut result = Vec::default();
@@ -1001,7 +1063,7 @@ struct CppInterfaceGenerator<'a> {
     resolve: &'a Resolve,
     interface: Option<InterfaceId>,
     _name: Option<&'a WorldKey>,
-    sizes: SizeAlign,
+    sizes: Arc<SizeAlign>,
     in_guest_import: bool,
     pub wasm_import_module: Option<String>,
 }
@@ -1030,7 +1092,9 @@ impl CppInterfaceGenerator<'_> {
             TypeDefKind::Future(_) => todo!("generate for future"),
             TypeDefKind::Stream(_) => todo!("generate for stream"),
             TypeDefKind::Handle(_) => todo!("generate for handle"),
-            TypeDefKind::FixedSizeList(_, _) => todo!(),
+            TypeDefKind::FixedSizeList(t, size) => {
+                self.type_fixed_size_list(id, name, t, *size, &ty.docs)
+            }
             TypeDefKind::Unknown => unreachable!(),
         }
     }
@@ -2008,7 +2072,12 @@ impl CppInterfaceGenerator<'_> {
                     format!("{}", name)
                 }
                 Ownership::CoarseBorrowing => {
-                    if self.gen.types.get(id).has_own_handle {
+                    if self
+                        .gen
+                        .layouts()
+                        .type_info(self.resolve, id)
+                        .has_own_handle
+                    {
                         format!("{}", name)
                     } else {
                         format!("{}Param", name)
@@ -2172,7 +2241,12 @@ impl CppInterfaceGenerator<'_> {
                         {
                             self.gen.dependencies.needs_span = true;
                             // If the list has an owning handle, it must support moving, so can't be const
-                            let constness = if self.gen.types.get(*id).has_own_handle {
+                            let constness = if self
+                                .gen
+                                .layouts()
+                                .type_info(self.resolve, *id)
+                                .has_own_handle
+                            {
                                 ""
                             } else {
                                 " const"
@@ -2286,7 +2360,12 @@ impl CppInterfaceGenerator<'_> {
             match self.gen.opts.ownership {
                 Ownership::Owning => (Flavor::InStruct, false),
                 Ownership::CoarseBorrowing => {
-                    if self.gen.types.get(id).has_own_handle {
+                    if self
+                        .gen
+                        .layouts()
+                        .type_info(self.resolve, id)
+                        .has_own_handle
+                    {
                         (Flavor::InStruct, false)
                     } else {
                         (Flavor::BorrowedArgument, true)
@@ -2668,6 +2747,17 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for CppInterfaceGenerator<'a>
         // nothing to do here
     }
 
+    fn type_fixed_size_list(
+        &mut self,
+        _id: TypeId,
+        _name: &str,
+        _ty: &wit_bindgen_core::wit_parser::Type,
+        _size: u32,
+        _docs: &wit_bindgen_core::wit_parser::Docs,
+    ) {
+        // nothing to do here, std::array is used directly
+    }
+
     fn type_builtin(
         &mut self,
         _id: TypeId,
@@ -2833,6 +2923,13 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
             | Type::F64
             | Type::Char => false,
             Type::String => false,
+            // these are `stream-obj` resources in the symmetric ABI
+            Type::Id(_)
+                if self.gen.gen.opts.symmetric
+                    && symmetric::is_stream_handle(self.gen.resolve, ty) =>
+            {
+                true
+            }
             Type::Id(id) => self.has_resources(id),
             Type::ErrorContext => todo!(),
         }
@@ -2855,7 +2952,7 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
                 Type::Id(id) => self.has_resources(id),
                 _ => false,
             },
-            TypeDefKind::FixedSizeList(_, _) => todo!(),
+            TypeDefKind::FixedSizeList(ty, _) => self.has_resources2(ty),
             TypeDefKind::Unknown => todo!(),
         }
     }
@@ -3343,7 +3440,11 @@ impl<'a, 'b> Bindgen for FunctionBindgen<'a, 'b> {
                 self.push_str("};\n");
                 results.push(result);
             }
-            abi::Instruction::FixedSizeListLower { .. } => todo!(),
+            abi::Instruction::FixedSizeListLower { size, .. } => {
+                for i in 0..(*size as usize) {
+                    results.push(format!("{}[{i}]", operands[0]));
+                }
+            }
             abi::Instruction::FixedSizeListLowerMemory {
                 element,
                 size: elemsize,
@@ -4206,6 +4307,12 @@ impl<'a, 'b> Bindgen for FunctionBindgen<'a, 'b> {
                 }
             }
             abi::Instruction::AsyncTaskReturn { .. } => todo!(),
+            abi::Instruction::AsyncCallbackLoop { .. }
+            | abi::Instruction::AsyncTaskReturnArg { .. }
+            | abi::Instruction::AsyncSubtaskStart { .. }
+            | abi::Instruction::AsyncSubtaskResult => {
+                unreachable!("async host functions are rejected by `check_host_sync`")
+            }
             abi::Instruction::DropHandle { .. } => todo!(),
         }
     }
@@ -4256,6 +4363,10 @@ impl<'a, 'b> Bindgen for FunctionBindgen<'a, 'b> {
         &self.gen.sizes
     }
 
+    fn layouts(&self) -> Option<&Layouts> {
+        self.gen.gen.layouts.as_deref()
+    }
+
     fn is_list_canonical(
         &self,
         resolve: &Resolve,
@@ -4265,7 +4376,7 @@ impl<'a, 'b> Bindgen for FunctionBindgen<'a, 'b> {
             return false;
         }
         match ty {
-            Type::Id(id) => !self.gen.gen.types.get(*id).has_resource,
+            Type::Id(id) => !self.gen.gen.layouts().type_info(resolve, *id).has_resource,
             _ => true,
         }
     }
@@ -52,16 +52,19 @@ fn push_wamr(ty: &Type, resolve: &Resolve, params_str: &mut String) {
             TypeDefKind::List(_t) => {
                 params_str.push_str("*~");
             }
-            TypeDefKind::Future(_) => todo!(),
-            TypeDefKind::Stream(_) => todo!(),
             TypeDefKind::Unknown => todo!(),
-            TypeDefKind::Resource => {
+            // futures and streams are passed as handles, like resources
+            TypeDefKind::Resource | TypeDefKind::Future(_) | TypeDefKind::Stream(_) => {
                 params_str.push('i');
             }
             TypeDefKind::Handle(_h) => {
                 params_str.push('i');
             }
-            TypeDefKind::FixedSizeList(_, _) => todo!(),
+            TypeDefKind::FixedSizeList(t, size) => {
+                for _ in 0..*size {
+                    push_wamr(t, resolve, params_str);
+                }
+            }
         },
         Type::ErrorContext => todo!(),
     }
@@ -125,7 +128,11 @@ fn wamr_add_result(sig: &mut WamrSig, resolve: &Resolve, ty: &Type) {
             TypeDefKind::Handle(_h) => {
                 sig.wamr_result = "i".into();
             }
-            TypeDefKind::FixedSizeList(_, _) => todo!(),
+            TypeDefKind::FixedSizeList(t, size) => {
+                for _ in 0..*size {
+                    push_wamr(t, resolve, &mut sig.wamr_types);
+                }
+            }
         },
         Type::ErrorContext => todo!(),
     }
@@ -1,6 +1,5 @@
 use wit_bindgen_symmetric_rt::{
-    async_support::Stream,
-    symmetric_stream::{Address, Buffer},
+    symmetric_stream::{Address, Buffer, StreamObj as Stream},
     CallbackState,
 };
 
@@ -1,4 +1,6 @@
-use wit_bindgen_symmetric_rt::{async_support::Stream, register, CallbackState, EventSubscription};
+use wit_bindgen_symmetric_rt::{
+    register, symmetric_stream::StreamObj as Stream, CallbackState, EventSubscription,
+};
 
 extern "C" fn timer_call(data: *mut ()) -> CallbackState {
     let stream: Stream = unsafe { Stream::from_handle(data as usize) };
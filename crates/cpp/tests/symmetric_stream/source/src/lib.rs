@@ -1,6 +1,8 @@
 use std::sync::atomic::{AtomicU32, Ordering};
 
-use wit_bindgen_symmetric_rt::{async_support::Stream, register, CallbackState, EventSubscription};
+use wit_bindgen_symmetric_rt::{
+    register, symmetric_stream::StreamObj as Stream, CallbackState, EventSubscription,
+};
 
 static COUNT: AtomicU32 = AtomicU32::new(1);
 
@@ -1,6 +1,5 @@
-use futures::{SinkExt, StreamExt};
 use stream_world::test::test::stream_source::create;
-use wit_bindgen_symmetric_rt::async_support;
+use wit_bindgen::rt::async_support;
 
 mod stream_world;
 
@@ -14,11 +13,9 @@ impl stream_world::exports::test::test::stream_test::Guest for MyStruct {
         let mut input = create();
 
         async_support::spawn(async move {
-            while let Some(values) = input.next().await {
-                println!("received {} values", values.len());
-                for value in values {
-                    writer.feed(vec![value, value + 1]).await.unwrap();
-                }
+            while let Some(value) = input.next().await {
+                println!("received {value}");
+                writer.write_all(vec![value, value + 1]).await;
             }
         });
         reader
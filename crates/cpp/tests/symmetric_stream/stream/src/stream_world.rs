@@ -13,7 +13,7 @@ pub mod test {
 
             use super::super::super::_rt;
             #[allow(unused_unsafe, clippy::all)]
-            pub fn create() -> wit_bindgen_symmetric_rt::async_support::StreamReader<u32> {
+            pub fn create() -> wit_bindgen::rt::async_support::StreamReader<u32> {
                 unsafe {
                     #[link(wasm_import_module = "test:test/stream-source")]
                     #[link(name = "source")]
@@ -22,7 +22,7 @@ pub mod test {
                         fn testX3AtestX2Fstream_sourceX00create() -> *mut u8;
                     }
                     let ret = testX3AtestX2Fstream_sourceX00create();
-                    wit_bindgen_symmetric_rt::async_support::StreamReader::from_handle(
+                    wit_bindgen::rt::async_support::StreamReader::new(
                         ret,
                         <u32 as super::super::super::wit_stream::StreamPayload>::VTABLE,
                     )
@@ -53,7 +53,7 @@ pub mod exports {
                     (result0).take_handle() as *mut u8
                 }
                 pub trait Guest {
-                    fn create() -> wit_bindgen_symmetric_rt::async_support::StreamReader<u32>;
+                    fn create() -> wit_bindgen::rt::async_support::StreamReader<u32>;
                 }
                 #[doc(hidden)]
 
@@ -88,7 +88,7 @@ pub mod wit_stream {
     #![allow(dead_code, unused_variables, clippy::all)]
 
     pub trait StreamPayload: Unpin + Sized + 'static {
-        const VTABLE: &'static wit_bindgen_symmetric_rt::async_support::StreamVtable<Self>;
+        const VTABLE: &'static wit_bindgen::rt::async_support::StreamVtable<Self>;
     }
     #[doc(hidden)]
     #[allow(unused_unsafe)]
@@ -103,23 +103,22 @@ pub mod wit_stream {
             }
         }
 
-        pub static VTABLE: wit_bindgen_symmetric_rt::async_support::StreamVtable<u32> =
-            wit_bindgen_symmetric_rt::async_support::StreamVtable::<u32> {
+        pub static VTABLE: wit_bindgen::rt::async_support::StreamVtable<u32> =
+            wit_bindgen::rt::async_support::StreamVtable::<u32> {
                 layout: unsafe { ::std::alloc::Layout::from_size_align_unchecked(4, 4) },
                 lift: Some(lift),
                 lower: Some(lower),
             };
         impl super::StreamPayload for u32 {
-            const VTABLE: &'static wit_bindgen_symmetric_rt::async_support::StreamVtable<Self> =
-                &VTABLE;
+            const VTABLE: &'static wit_bindgen::rt::async_support::StreamVtable<Self> = &VTABLE;
         }
     }
     /// Creates a new Component Model `stream` with the specified payload type.
     pub fn new<T: StreamPayload>() -> (
-        wit_bindgen_symmetric_rt::async_support::StreamWriter<T>,
-        wit_bindgen_symmetric_rt::async_support::StreamReader<T>,
+        wit_bindgen::rt::async_support::StreamWriter<T>,
+        wit_bindgen::rt::async_support::StreamReader<T>,
     ) {
-        wit_bindgen_symmetric_rt::async_support::stream_support::new_stream(T::VTABLE)
+        wit_bindgen::rt::async_support::stream_new::<T>(T::VTABLE)
     }
 }
 
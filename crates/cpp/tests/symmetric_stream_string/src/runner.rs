@@ -14,7 +14,7 @@ pub mod a {
             use super::super::super::_rt;
             #[allow(unused_unsafe, clippy::all)]
             #[allow(async_fn_in_trait)]
-            pub fn f() -> wit_bindgen_symmetric_rt::async_support::StreamReader<_rt::String> {
+            pub fn f() -> wit_bindgen::rt::async_support::StreamReader<_rt::String> {
                 unsafe {
                     #[link(wasm_import_module = "a:b/the-test")]
                     unsafe extern "C" {
@@ -22,7 +22,10 @@ pub mod a {
                         fn aX3AbX2Fthe_testX00f() -> *mut u8;
                     }
                     let ret = aX3AbX2Fthe_testX00f();
-                    wit_bindgen_symmetric_rt::async_support::StreamReader::from_handle(ret, <_rt::String as super::super::super::wit_stream::StreamPayload>::VTABLE)
+                    wit_bindgen::rt::async_support::StreamReader::new(
+                        ret,
+                        <_rt::String as super::super::super::wit_stream::StreamPayload>::VTABLE,
+                    )
                 }
             }
         }
@@ -92,25 +95,23 @@ pub mod wit_stream {
             }
         }
 
-        pub static VTABLE: wit_bindgen_symmetric_rt::async_support::StreamVtable<
-            super::super::_rt::String,
-        > = wit_bindgen_symmetric_rt::async_support::StreamVtable::<super::super::_rt::String> {
-            layout: unsafe { ::std::alloc::Layout::from_size_align_unchecked(8, 4) },
-            lift: Some(lift),
-            lower: Some(lower),
-        };
+        pub static VTABLE: wit_bindgen::rt::async_support::StreamVtable<super::super::_rt::String> =
+            wit_bindgen::rt::async_support::StreamVtable::<super::super::_rt::String> {
+                layout: unsafe { ::std::alloc::Layout::from_size_align_unchecked(8, 4) },
+                lift: Some(lift),
+                lower: Some(lower),
+            };
 
         impl super::StreamPayload for super::super::_rt::String {
-            const VTABLE: &'static wit_bindgen_symmetric_rt::async_support::StreamVtable<Self> =
-                &VTABLE;
+            const VTABLE: &'static wit_bindgen::rt::async_support::StreamVtable<Self> = &VTABLE;
         }
     }
     /// Creates a new Component Model `stream` with the specified payload type.
     pub fn new<T: StreamPayload>() -> (
-        wit_bindgen_symmetric_rt::async_support::StreamWriter<T>,
-        wit_bindgen_symmetric_rt::async_support::StreamReader<T>,
+        wit_bindgen::rt::async_support::StreamWriter<T>,
+        wit_bindgen::rt::async_support::StreamReader<T>,
     ) {
-        wit_bindgen_symmetric_rt::async_support::stream_support::new_stream(T::VTABLE)
+        wit_bindgen::rt::async_support::stream_new::<T>(T::VTABLE)
     }
 }
 
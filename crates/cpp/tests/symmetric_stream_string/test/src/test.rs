@@ -25,7 +25,7 @@ pub mod exports {
                 }
                 pub trait Guest {
                     #[allow(async_fn_in_trait)]
-                    fn f() -> wit_bindgen_symmetric_rt::async_support::StreamReader<_rt::String>;
+                    fn f() -> wit_bindgen::rt::async_support::StreamReader<_rt::String>;
                 }
                 #[doc(hidden)]
 
@@ -106,25 +106,23 @@ pub mod wit_stream {
             }
         }
 
-        pub static VTABLE: wit_bindgen_symmetric_rt::async_support::StreamVtable<
-            super::super::_rt::String,
-        > = wit_bindgen_symmetric_rt::async_support::StreamVtable::<super::super::_rt::String> {
-            layout: unsafe { ::std::alloc::Layout::from_size_align_unchecked(8, 4) },
-            lift: Some(lift),
-            lower: Some(lower),
-        };
+        pub static VTABLE: wit_bindgen::rt::async_support::StreamVtable<super::super::_rt::String> =
+            wit_bindgen::rt::async_support::StreamVtable::<super::super::_rt::String> {
+                layout: unsafe { ::std::alloc::Layout::from_size_align_unchecked(8, 4) },
+                lift: Some(lift),
+                lower: Some(lower),
+            };
 
         impl super::StreamPayload for super::super::_rt::String {
-            const VTABLE: &'static wit_bindgen_symmetric_rt::async_support::StreamVtable<Self> =
-                &VTABLE;
+            const VTABLE: &'static wit_bindgen::rt::async_support::StreamVtable<Self> = &VTABLE;
         }
     }
     /// Creates a new Component Model `stream` with the specified payload type.
     pub fn new<T: StreamPayload>() -> (
-        wit_bindgen_symmetric_rt::async_support::StreamWriter<T>,
-        wit_bindgen_symmetric_rt::async_support::StreamReader<T>,
+        wit_bindgen::rt::async_support::StreamWriter<T>,
+        wit_bindgen::rt::async_support::StreamReader<T>,
     ) {
-        wit_bindgen_symmetric_rt::async_support::stream_support::new_stream(T::VTABLE)
+        wit_bindgen::rt::async_support::stream_new::<T>(T::VTABLE)
     }
 }
 
@@ -6,6 +6,7 @@ use std::fmt::Write;
 use std::mem;
 use std::ops::Deref;
 use wit_bindgen_core::abi::{Bindgen, Bitcast, Instruction};
+use wit_bindgen_core::layout::Layouts;
 use wit_bindgen_core::{uwrite, uwriteln, Direction, Ns};
 use wit_parser::abi::WasmType;
 use wit_parser::{
@@ -371,6 +372,18 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         }
         ret
     }
+
+    /// Fixed-size lists are plain arrays in C#, so their length is only known
+    /// when lowering them.
+    fn check_fixed_size_list(&mut self, array: &str, size: u32) {
+        uwriteln!(
+            self.src,
+            r#"if ({array}.Length != {size})
+            {{
+                throw new global::System.ArgumentException($"expected {size} elements, got {{{array}.Length}}");
+            }}"#
+        );
+    }
 }
 
 impl Bindgen for FunctionBindgen<'_, '_> {
@@ -1244,6 +1257,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             }
 
             Instruction::AsyncTaskReturn { .. }
+            | Instruction::AsyncCallbackLoop { .. }
+            | Instruction::AsyncTaskReturnArg { .. }
+            | Instruction::AsyncSubtaskStart { .. }
+            | Instruction::AsyncSubtaskResult
             | Instruction::FutureLower { .. }
             | Instruction::FutureLift { .. }
             | Instruction::StreamLower { .. }
@@ -1252,9 +1269,47 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             | Instruction::ErrorContextLift { .. }
             | Instruction::DropHandle { .. }
              => todo!(),
-            Instruction::FixedSizeListLift { .. } => todo!(),
-            Instruction::FixedSizeListLower { .. } => todo!(),
-            Instruction::FixedSizeListLowerMemory { .. } => todo!(),
+
+            Instruction::FixedSizeListLift { element, .. } => {
+                let ty = self.interface_gen.type_name_with_qualifier(element, true);
+                results.push(format!("new {ty}[] {{ {} }}", operands.join(", ")));
+            }
+
+            Instruction::FixedSizeListLower { size, .. } => {
+                let array = &operands[0];
+                self.check_fixed_size_list(array, *size);
+                for i in 0..*size {
+                    results.push(format!("{array}[{i}]"));
+                }
+            }
+
+            Instruction::FixedSizeListLowerMemory { element, size, .. } => {
+                let Block {
+                    body,
+                    results: block_results,
+                    element: block_element,
+                    base,
+                } = self.blocks.pop().unwrap();
+                assert!(block_results.is_empty());
+
+                let array = &operands[0];
+                let address = &operands[1];
+                let elem_size = self.interface_gen.csharp_gen.sizes.size(element).size_wasm32();
+                let ty = self.interface_gen.type_name_with_qualifier(element, true);
+                let index = self.locals.tmp("index");
+                self.check_fixed_size_list(array, *size);
+
+                uwrite!(
+                    self.src,
+                    "
+                    for (int {index} = 0; {index} < {size}; ++{index}) {{
+                        {ty} {block_element} = {array}[{index}];
+                        int {base} = (int){address} + ({index} * {elem_size});
+                        {body}
+                    }}
+                    "
+                );
+            }
         }
     }
 
@@ -1344,6 +1399,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         &self.interface_gen.csharp_gen.sizes
     }
 
+    fn layouts(&self) -> Option<&Layouts> {
+        self.interface_gen.csharp_gen.layouts.as_deref()
+    }
+
     fn is_list_canonical(&self, _resolve: &Resolve, element: &Type) -> bool {
         crate::world_generator::is_primitive(element)
     }
@@ -111,6 +111,9 @@ impl InterfaceGenerator<'_> {
             TypeDefKind::Option(t) => self.type_option(type_id, typedef_name, t, &type_def.docs),
             TypeDefKind::Record(t) => self.type_record(type_id, typedef_name, t, &type_def.docs),
             TypeDefKind::List(t) => self.type_list(type_id, typedef_name, t, &type_def.docs),
+            TypeDefKind::FixedSizeList(t, size) => {
+                self.type_fixed_size_list(type_id, typedef_name, t, *size, &type_def.docs)
+            }
             TypeDefKind::Variant(t) => self.type_variant(type_id, typedef_name, t, &type_def.docs),
             TypeDefKind::Result(t) => self.type_result(type_id, typedef_name, t, &type_def.docs),
             TypeDefKind::Handle(_) => {
@@ -532,6 +535,7 @@ impl InterfaceGenerator<'_> {
                     TypeDefKind::Option(_ty) => "".to_owned(),
                     TypeDefKind::Result(_result) => "".to_owned(),
                     TypeDefKind::List(_list) => "".to_owned(),
+                    TypeDefKind::FixedSizeList(..) => "".to_owned(),
                     TypeDefKind::Tuple(_tuple) => "".to_owned(),
                     TypeDefKind::Type(inner_type) => self.global_if_user_type(inner_type),
                     _ => "global::".to_owned(),
@@ -608,6 +612,10 @@ impl InterfaceGenerator<'_> {
                             )
                         }
                     }
+                    TypeDefKind::FixedSizeList(ty, _) => {
+                        // The length of the array is checked when lowering it.
+                        format!("{}[]", self.type_name_with_qualifier(ty, qualifier))
+                    }
                     TypeDefKind::Tuple(tuple) => {
                         let count = tuple.types.len();
                         self.csharp_gen.tuple_counts.insert(count);
@@ -1183,6 +1191,17 @@ impl<'a> CoreInterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.type_name(&Type::Id(id));
     }
 
+    fn type_fixed_size_list(
+        &mut self,
+        id: TypeId,
+        _name: &str,
+        _ty: &Type,
+        _size: u32,
+        _docs: &Docs,
+    ) {
+        self.type_name(&Type::Id(id));
+    }
+
     fn type_builtin(&mut self, _id: TypeId, _name: &str, _ty: &Type, _docs: &Docs) {
         unimplemented!();
     }
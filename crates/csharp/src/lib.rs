@@ -11,8 +11,17 @@ pub use csproj::CSProject;
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, rename_all = "kebab-case")
+)]
 pub struct Opts {
     #[cfg_attr(feature = "clap", arg(long, default_value_t = StringEncoding::default()))]
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "wit_bindgen_core::deserialize_from_str")
+    )]
     pub string_encoding: StringEncoding,
 
     /// Whether or not to generate a stub class for exported functions
@@ -47,6 +56,11 @@ impl Opts {
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
 pub enum CSharpRuntime {
     #[default]
     NativeAOT,
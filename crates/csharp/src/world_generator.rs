@@ -7,7 +7,9 @@ use indexmap::IndexMap;
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
 use std::ops::Deref;
+use std::sync::Arc;
 use std::{iter, mem};
+use wit_bindgen_core::layout::Layouts;
 use wit_bindgen_core::{uwrite, Direction, Files, InterfaceGenerator as _, WorldGenerator};
 use wit_component::WitPrinter;
 use wit_parser::abi::WasmType;
@@ -34,7 +36,8 @@ pub struct CSharp {
     pub(crate) needs_wit_exception: bool,
     pub(crate) interface_fragments: HashMap<String, InterfaceTypeAndFragments>,
     pub(crate) world_fragments: Vec<InterfaceFragment>,
-    pub(crate) sizes: SizeAlign,
+    pub(crate) sizes: Arc<SizeAlign>,
+    pub(crate) layouts: Option<Arc<Layouts>>,
     pub(crate) interface_names: HashMap<InterfaceId, String>,
     pub(crate) anonymous_type_owners: HashMap<TypeId, TypeOwner>,
     pub(crate) all_resources: HashMap<TypeId, ResourceInfo>,
@@ -89,10 +92,16 @@ impl CSharp {
 }
 
 impl WorldGenerator for CSharp {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         let name = &resolve.worlds[world].name;
         self.name = name.to_string();
-        self.sizes.fill(resolve);
+        let layouts = Layouts::reuse(self.layouts.take());
+        self.sizes = layouts.sizes(resolve, false);
+        self.layouts = Some(layouts);
     }
 
     fn import_interface(
@@ -7,9 +7,11 @@ use syn::parse::{Error, Parse, ParseStream, Result};
 use syn::punctuated::Punctuated;
 use syn::spanned::Spanned;
 use syn::{braced, token, LitStr, Token};
-use wit_bindgen_core::wit_parser::{PackageId, Resolve, UnresolvedPackageGroup, WorldId};
-use wit_bindgen_core::AsyncFilterSet;
-use wit_bindgen_rust::{Opts, Ownership, WithOption};
+use wit_bindgen_core::wit_parser::{
+    PackageId, Resolve, UnresolvedPackageGroup, WorldId, WorldItem,
+};
+use wit_bindgen_core::{AsyncFilterSet, UnusedAsyncOption};
+use wit_bindgen_rust::{MissingWith, Opts, Ownership, UnusedWith, WithOption};
 
 #[proc_macro]
 pub fn generate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -48,6 +50,90 @@ struct Config {
     world: WorldId,
     files: Vec<PathBuf>,
     debug: bool,
+    spans: Spans,
+}
+
+/// Locations of options in the macro input which errors are reported at.
+#[derive(Default)]
+struct Spans {
+    /// The `world` option's value.
+    world: Option<Span>,
+    /// The `path` or `inline` option's value.
+    source: Option<Span>,
+    /// Keys of the `with` option.
+    with: HashMap<String, Span>,
+    /// Values of the `async` option.
+    async_: HashMap<String, Span>,
+}
+
+impl Spans {
+    /// Where to report an error which isn't about any option in particular.
+    fn world_or_source(&self) -> Span {
+        self.world.or(self.source).unwrap_or_else(Span::call_site)
+    }
+
+    /// Converts an error from generating bindings into one pointing at the
+    /// option responsible for it, where there is one.
+    fn generate_error(&self, resolve: &Resolve, world: WorldId, err: anyhow::Error) -> Error {
+        if let Some(UnusedWith(keys)) = err.downcast_ref() {
+            let names = with_names(resolve);
+            let mut errors = keys.iter().map(|key| {
+                let span = self.with.get(key).copied();
+                let err = Error::new(
+                    span.unwrap_or_else(Span::call_site),
+                    format!("unused remapping provided via `with`: `{key}`"),
+                );
+                match similar_name(key, names.iter().map(|s| s.as_str())) {
+                    Some(similar) => with_help(
+                        err,
+                        format!("an interface or type with a similar name exists: `{similar}`"),
+                    ),
+                    None => err,
+                }
+            });
+            let mut ret = errors.next().unwrap();
+            for err in errors {
+                ret.combine(err);
+            }
+            return ret;
+        }
+
+        if let Some(UnusedAsyncOption(option)) = err.downcast_ref() {
+            let span = self.async_.get(option).copied();
+            let (prefix, name) = split_async_option(option);
+            let names = async_names(resolve, world);
+            let similar = similar_name(name, names.iter().map(|s| s.as_str()));
+            let ret = Error::new(span.unwrap_or_else(Span::call_site), err.to_string());
+            return match similar {
+                Some(similar) => with_help(
+                    ret,
+                    format!("a function with a similar name exists: `{prefix}{similar}`"),
+                ),
+                None => ret,
+            };
+        }
+
+        if let Some(MissingWith(name)) = err.downcast_ref() {
+            // A `with` key similar to the missing one is most likely a typo of
+            // it, so point there.
+            let name = name.clone();
+            let similar = similar_name(&name, self.with.keys().map(|k| k.as_str()));
+            let span = match similar {
+                Some(key) => self.with[key],
+                None => self.world_or_source(),
+            };
+            let ret = anyhow_to_syn(span, err);
+            return match similar {
+                Some(similar) => with_help(
+                    ret,
+                    format!("the `with` key `{similar}` is similar to `{name}`"),
+                ),
+                None => ret,
+            };
+        }
+
+        anyhow_to_syn(Span::call_site(), err)
+    }
 }
 
 /// The source of the wit package definition
@@ -67,6 +153,7 @@ impl Parse for Config {
         let mut features = Vec::new();
         let mut async_configured = false;
         let mut debug = false;
+        let mut spans = Spans::default();
 
         if input.peek(token::Brace) {
             let content;
@@ -75,6 +162,7 @@ impl Parse for Config {
             for field in fields.into_pairs() {
                 match field.into_value() {
                     Opt::Path(span, p) => {
+                        spans.source = Some(span);
                         let paths = p.into_iter().map(|f| PathBuf::from(f.value())).collect();
 
                         source = Some(match source {
@@ -90,8 +178,10 @@ impl Parse for Config {
                             return Err(Error::new(s.span(), "cannot specify second world"));
                         }
                         world = Some(s.value());
+                        spans.world = Some(s.span());
                     }
                     Opt::Inline(s) => {
+                        spans.source = Some(s.span());
                         source = Some(match source {
                             Some(Source::Inline(_, _)) => {
                                 return Err(Error::new(s.span(), "cannot specify second source"));
@@ -123,7 +213,12 @@ impl Parse for Config {
                         opts.additional_derive_ignore =
                             list.into_iter().map(|i| i.value()).collect()
                     }
-                    Opt::With(with) => opts.with.extend(with),
+                    Opt::With(with) => {
+                        for (key, option) in with {
+                            spans.with.insert(key.value(), key.span());
+                            opts.with.push((key.value(), option));
+                        }
+                    }
                     Opt::GenerateAll => {
                         opts.generate_all = true;
                     }
@@ -151,6 +246,15 @@ impl Parse for Config {
                     Opt::DisableCustomSectionLinkHelpers(disable) => {
                         opts.disable_custom_section_link_helpers = disable.value();
                     }
+                    Opt::Serde(enable) => {
+                        opts.serde = enable.value();
+                    }
+                    Opt::MockableImports(enable) => {
+                        opts.mockable_imports = enable.value();
+                    }
+                    Opt::NoAlloc(enable) => {
+                        opts.no_alloc = enable.value();
+                    }
                     Opt::Symmetric(enable) => {
                         opts.symmetric = enable.value();
                     }
@@ -160,7 +264,7 @@ impl Parse for Config {
                     Opt::Debug(enable) => {
                         debug = enable.value();
                     }
-                    Opt::Async(val, span) => {
+                    Opt::Async(val, span, filters) => {
                         if async_configured {
                             return Err(Error::new(span, "cannot specify second async config"));
                         }
@@ -172,27 +276,47 @@ impl Parse for Config {
                             ));
                         }
                         opts.async_ = val;
+                        for filter in filters {
+                            spans.async_.insert(filter.value(), filter.span());
+                        }
                     }
                 }
             }
         } else {
-            world = input.parse::<Option<syn::LitStr>>()?.map(|s| s.value());
+            if let Some(s) = input.parse::<Option<syn::LitStr>>()? {
+                world = Some(s.value());
+                spans.world = Some(s.span());
+            }
             if input.parse::<Option<syn::token::In>>()?.is_some() {
-                source = Some(Source::Paths(vec![PathBuf::from(
-                    input.parse::<syn::LitStr>()?.value(),
-                )]));
+                let path = input.parse::<syn::LitStr>()?;
+                spans.source = Some(path.span());
+                source = Some(Source::Paths(vec![PathBuf::from(path.value())]));
             }
         }
-        let (resolve, pkgs, files) =
-            parse_source(&source, &features).map_err(|err| anyhow_to_syn(call_site, err))?;
-        let world = select_world(&resolve, &pkgs, world.as_deref())
-            .map_err(|e| anyhow_to_syn(call_site, e))?;
+        // Errors in WIT are reported at the source they were read from. Their
+        // message already contains a snippet of the offending file and line.
+        let (resolve, pkgs, files) = parse_source(&source, &features)
+            .map_err(|err| anyhow_to_syn(spans.source.unwrap_or(call_site), err))?;
+        let world = select_world(&resolve, &pkgs, world.as_deref()).map_err(|e| {
+            let mut err = anyhow_to_syn(spans.world_or_source(), e);
+            if let Some(similar) = world
+                .as_deref()
+                .and_then(|w| similar_world(&resolve, &pkgs, w))
+            {
+                err = with_help(
+                    err,
+                    format!("a world with a similar name exists: `{similar}`"),
+                );
+            }
+            err
+        })?;
         Ok(Config {
             opts,
             resolve,
             world,
             files,
             debug,
+            spans,
         })
     }
 }
@@ -285,7 +409,7 @@ impl Config {
         generator.apply_resolve_options(&mut self.resolve, &mut self.world);
         generator
             .generate(&self.resolve, self.world, &mut files)
-            .map_err(|e| anyhow_to_syn(Span::call_site(), e))?;
+            .map_err(|e| self.spans.generate_error(&self.resolve, self.world, e))?;
         let (_, src) = files.iter().next().unwrap();
         let mut src = std::str::from_utf8(src).unwrap().to_string();
 
@@ -328,6 +452,100 @@ impl Config {
     }
 }
 
+/// Appends a `help` note to `err`, reported at the same span.
+///
+/// Stable proc macros can only report errors, so the note shows up as a
+/// separate `error: help: ...` right after the error it belongs to.
+fn with_help(mut err: Error, help: String) -> Error {
+    err.combine(Error::new(err.span(), format!("help: {help}")));
+    err
+}
+
+/// Returns the name among `candidates` which `name` is most likely a typo of.
+fn similar_name<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let max = name.chars().count().max(3) / 3;
+    candidates
+        .filter(|c| *c != name)
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, c)| c)
+}
+
+/// Levenshtein distance between `a` and `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut prev = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitute.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Returns the name of a world in `pkgs` which `world` is most likely a typo
+/// of.
+fn similar_world(resolve: &Resolve, pkgs: &[PackageId], world: &str) -> Option<String> {
+    let mut names = Vec::new();
+    for pkg in pkgs {
+        let package = &resolve.packages[*pkg];
+        for name in package.worlds.keys() {
+            names.push(name.clone());
+            names.push(format!("{}/{name}", package.name));
+        }
+    }
+    similar_name(world, names.iter().map(|s| s.as_str())).map(|s| s.to_string())
+}
+
+/// Names of interfaces and types which may be remapped with `with`.
+fn with_names(resolve: &Resolve) -> Vec<String> {
+    let mut names = Vec::new();
+    for (id, iface) in resolve.interfaces.iter() {
+        let Some(name) = resolve.id_of(id) else {
+            continue;
+        };
+        for ty in iface.types.keys() {
+            names.push(format!("{name}/{ty}"));
+        }
+        names.push(name);
+    }
+    names
+}
+
+/// Names of functions in `world` as matched by `async` filters.
+fn async_names(resolve: &Resolve, world: WorldId) -> Vec<String> {
+    let world = &resolve.worlds[world];
+    let mut names = Vec::new();
+    for (key, item) in world.imports.iter().chain(world.exports.iter()) {
+        match item {
+            WorldItem::Function(func) => names.push(func.name.clone()),
+            WorldItem::Interface { id, .. } => {
+                let iface = resolve.name_world_key(key);
+                for func in resolve.interfaces[*id].functions.values() {
+                    names.push(format!("{iface}#{}", func.name));
+                }
+            }
+            WorldItem::Type(_) => {}
+        }
+    }
+    names
+}
+
+/// Splits an `async` filter into its `-`, `import:` or `export:` prefixes and
+/// the function name it matches.
+fn split_async_option(option: &str) -> (&str, &str) {
+    let name = option.strip_prefix('-').unwrap_or(option);
+    let name = name
+        .strip_prefix("import:")
+        .or_else(|| name.strip_prefix("export:"))
+        .unwrap_or(name);
+    option.split_at(option.len() - name.len())
+}
+
 mod kw {
     syn::custom_keyword!(std_feature);
     syn::custom_keyword!(raw_strings);
@@ -354,6 +572,9 @@ mod kw {
     syn::custom_keyword!(generate_unused_types);
     syn::custom_keyword!(features);
     syn::custom_keyword!(disable_custom_section_link_helpers);
+    syn::custom_keyword!(serde);
+    syn::custom_keyword!(mockable_imports);
+    syn::custom_keyword!(no_alloc);
     syn::custom_keyword!(symmetric);
     syn::custom_keyword!(invert_direction);
     syn::custom_keyword!(imports);
@@ -402,7 +623,7 @@ enum Opt {
     // Parse as paths so we can take the concrete types/macro names rather than raw strings
     AdditionalDerives(Vec<syn::Path>),
     AdditionalDerivesIgnore(Vec<syn::LitStr>),
-    With(HashMap<String, WithOption>),
+    With(Vec<(syn::LitStr, WithOption)>),
     GenerateAll,
     TypeSectionSuffix(syn::LitStr),
     DisableRunCtorsOnceWorkaround(syn::LitBool),
@@ -413,9 +634,12 @@ enum Opt {
     GenerateUnusedTypes(syn::LitBool),
     Features(Vec<syn::LitStr>),
     DisableCustomSectionLinkHelpers(syn::LitBool),
+    Serde(syn::LitBool),
+    MockableImports(syn::LitBool),
+    NoAlloc(syn::LitBool),
     Symmetric(syn::LitBool),
     InvertDirection(syn::LitBool),
-    Async(AsyncFilterSet, Span),
+    Async(AsyncFilterSet, Span, Vec<syn::LitStr>),
     Debug(syn::LitBool),
 }
 
@@ -535,7 +759,7 @@ impl Parse for Opt {
             let _lbrace = braced!(contents in input);
             let fields: Punctuated<_, Token![,]> =
                 contents.parse_terminated(with_field_parse, Token![,])?;
-            Ok(Opt::With(HashMap::from_iter(fields.into_iter())))
+            Ok(Opt::With(fields.into_iter().collect()))
         } else if l.peek(kw::generate_all) {
             input.parse::<kw::generate_all>()?;
             Ok(Opt::GenerateAll)
@@ -574,6 +798,18 @@ impl Parse for Opt {
             input.parse::<kw::disable_custom_section_link_helpers>()?;
             input.parse::<Token![:]>()?;
             Ok(Opt::DisableCustomSectionLinkHelpers(input.parse()?))
+        } else if l.peek(kw::serde) {
+            input.parse::<kw::serde>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::Serde(input.parse()?))
+        } else if l.peek(kw::mockable_imports) {
+            input.parse::<kw::mockable_imports>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::MockableImports(input.parse()?))
+        } else if l.peek(kw::no_alloc) {
+            input.parse::<kw::no_alloc>()?;
+            input.parse::<Token![:]>()?;
+            Ok(Opt::NoAlloc(input.parse()?))
         } else if l.peek(kw::symmetric) {
             input.parse::<kw::symmetric>()?;
             input.parse::<Token![:]>()?;
@@ -591,15 +827,16 @@ impl Parse for Opt {
             input.parse::<Token![:]>()?;
             if input.peek(syn::LitBool) {
                 let enabled = input.parse::<syn::LitBool>()?.value;
-                Ok(Opt::Async(AsyncFilterSet::all(enabled), span))
+                Ok(Opt::Async(AsyncFilterSet::all(enabled), span, Vec::new()))
             } else {
                 let mut set = AsyncFilterSet::default();
                 let contents;
                 syn::bracketed!(contents in input);
-                for val in contents.parse_terminated(|p| p.parse::<syn::LitStr>(), Token![,])? {
+                let filters = contents.parse_terminated(|p| p.parse::<syn::LitStr>(), Token![,])?;
+                for val in filters.iter() {
                     set.push(&val.value());
                 }
-                Ok(Opt::Async(set, span))
+                Ok(Opt::Async(set, span, filters.into_iter().collect()))
             }
         } else {
             Err(l.error())
@@ -607,8 +844,8 @@ impl Parse for Opt {
     }
 }
 
-fn with_field_parse(input: ParseStream<'_>) -> Result<(String, WithOption)> {
-    let interface = input.parse::<syn::LitStr>()?.value();
+fn with_field_parse(input: ParseStream<'_>) -> Result<(syn::LitStr, WithOption)> {
+    let interface = input.parse::<syn::LitStr>()?;
     input.parse::<Token![:]>()?;
     let start = input.span();
     let path = input.parse::<syn::Path>()?;
@@ -659,3 +896,72 @@ fn fmt(input: &str) -> Result<String> {
     let syntax_tree = syn::parse_file(&input)?;
     Ok(prettyplease::unparse(&syntax_tree))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance() {
+        assert_eq!(super::edit_distance("", ""), 0);
+        assert_eq!(super::edit_distance("abc", "abc"), 0);
+        assert_eq!(super::edit_distance("", "abc"), 3);
+        assert_eq!(super::edit_distance("abc", ""), 3);
+        assert_eq!(super::edit_distance("abc", "abd"), 1);
+        assert_eq!(super::edit_distance("abc", "ac"), 1);
+        assert_eq!(super::edit_distance("ac", "abc"), 1);
+        assert_eq!(super::edit_distance("kitten", "sitting"), 3);
+        assert_eq!(super::edit_distance("flaw", "lawn"), 2);
+        // Characters rather than bytes are counted.
+        assert_eq!(super::edit_distance("größe", "grosse"), 3);
+    }
+
+    #[test]
+    fn similar_name() {
+        let names = ["a:b/foo", "a:b/bar", "a:b/foo/ty", "run"];
+        let similar = |name| super::similar_name(name, names.iter().copied());
+        assert_eq!(similar("a:b/fo"), Some("a:b/foo"));
+        assert_eq!(similar("a:b/bat"), Some("a:b/bar"));
+        assert_eq!(similar("a:b/foo/tx"), Some("a:b/foo/ty"));
+        // An exact match isn't a typo of itself.
+        assert_eq!(similar("run"), None);
+        // Short names allow one edit, longer ones one per three characters.
+        assert_eq!(similar("rux"), Some("run"));
+        assert_eq!(similar("rxx"), None);
+        assert_eq!(similar("x:y/fxx"), None);
+        // The closest candidate wins.
+        let similar = super::similar_name("abcdef", ["abxxef", "abcdex"].into_iter());
+        assert_eq!(similar, Some("abcdex"));
+    }
+
+    #[test]
+    fn split_async_option() {
+        assert_eq!(super::split_async_option("f"), ("", "f"));
+        assert_eq!(super::split_async_option("all"), ("", "all"));
+        assert_eq!(super::split_async_option("-f"), ("-", "f"));
+        assert_eq!(
+            super::split_async_option("import:a:b/i#f"),
+            ("import:", "a:b/i#f")
+        );
+        assert_eq!(super::split_async_option("export:f"), ("export:", "f"));
+        assert_eq!(super::split_async_option("-import:f"), ("-import:", "f"));
+        assert_eq!(
+            super::split_async_option("-export:a:b/i#f"),
+            ("-export:", "a:b/i#f")
+        );
+        // Only one of `import:` and `export:` is a prefix.
+        assert_eq!(
+            super::split_async_option("import:export:f"),
+            ("import:", "export:f")
+        );
+    }
+
+    #[test]
+    fn with_help() {
+        let mut err = Error::new(Span::call_site(), "first");
+        err.combine(Error::new(Span::call_site(), "second"));
+        let err = super::with_help(err, "try this".to_string());
+        let messages = err.into_iter().map(|e| e.to_string()).collect::<Vec<_>>();
+        assert_eq!(messages, ["first", "second", "help: try this"]);
+    }
+}
@@ -33,19 +33,44 @@ macro_rules! rtdebug {
 
 }
 
+macro_rules! rttrace {
+    ($($event:tt)*) => {
+        #[cfg(feature = "trace")]
+        crate::async_support::trace::emit(crate::async_support::trace::Event::$($event)*);
+    };
+}
+
 mod abi_buffer;
+mod backend;
 mod cabi;
 mod error_context;
+#[cfg(not(feature = "symmetric"))]
 mod future_support;
+mod join_set;
+mod select;
+#[cfg(feature = "stream-adapters")]
+mod stream_adapters;
+#[cfg(not(feature = "symmetric"))]
 mod stream_support;
+#[cfg(not(feature = "symmetric"))]
 mod subtask;
+#[cfg(feature = "trace")]
+pub mod trace;
+#[cfg(not(feature = "symmetric"))]
 mod waitable;
-mod waitable_set;
 
-use self::waitable_set::WaitableSet;
+use self::backend::WaitableSet;
 pub use abi_buffer::*;
+#[cfg(feature = "symmetric")]
+use backend::{future_support, stream_support, subtask};
+#[cfg(feature = "symmetric")]
+pub use backend::{wait_on, WaitOn};
 pub use error_context::*;
 pub use future_support::*;
+pub use join_set::*;
+pub use select::*;
+#[cfg(feature = "stream-adapters")]
+pub use stream_adapters::*;
 pub use stream_support::*;
 #[doc(hidden)]
 pub use subtask::Subtask;
@@ -135,6 +160,9 @@ impl FutureState {
             EVENT_FUTURE_WRITE => rtdebug!("EVENT_FUTURE_WRITE({event1:#x}, {event2:#x})"),
             EVENT_CANCEL => {
                 rtdebug!("EVENT_CANCEL");
+                rttrace!(TaskCancelled {
+                    task: self as *mut FutureState as usize
+                });
 
                 // Cancellation is mapped to destruction in Rust, so return a
                 // code/bool indicating we're done. The caller will then
@@ -145,6 +173,12 @@ impl FutureState {
             _ => unreachable!(),
         }
         if event0 != EVENT_NONE {
+            rttrace!(EventReceived {
+                task: self as *mut FutureState as usize,
+                event: event0,
+                waitable: event1,
+                code: event2,
+            });
             self.deliver_waitable_event(event1, event2)
         }
 
@@ -195,6 +229,9 @@ impl FutureState {
                     Poll::Ready(None) => {
                         assert!(!me.remaining_work());
                         assert!(me.tasks.is_empty());
+                        rttrace!(TaskFinish {
+                            task: me as *mut FutureState as usize
+                        });
                         break (CALLBACK_CODE_EXIT, true);
                     }
 
@@ -308,18 +345,28 @@ const CALLBACK_CODE_YIELD: u32 = 1;
 const CALLBACK_CODE_WAIT: u32 = 2;
 const _CALLBACK_CODE_POLL: u32 = 3;
 
+#[cfg(not(feature = "symmetric"))]
 const STATUS_STARTING: u32 = 0;
+#[cfg(not(feature = "symmetric"))]
 const STATUS_STARTED: u32 = 1;
+#[cfg(not(feature = "symmetric"))]
 const STATUS_RETURNED: u32 = 2;
+#[cfg(not(feature = "symmetric"))]
 const STATUS_STARTED_CANCELLED: u32 = 3;
+#[cfg(not(feature = "symmetric"))]
 const STATUS_RETURNED_CANCELLED: u32 = 4;
 
+#[cfg(not(feature = "symmetric"))]
 const BLOCKED: u32 = 0xffff_ffff;
+#[cfg(not(feature = "symmetric"))]
 const COMPLETED: u32 = 0x0;
+#[cfg(not(feature = "symmetric"))]
 const DROPPED: u32 = 0x1;
+#[cfg(not(feature = "symmetric"))]
 const CANCELLED: u32 = 0x2;
 
 /// Return code of stream/future operations.
+#[cfg(not(feature = "symmetric"))]
 #[derive(PartialEq, Debug, Copy, Clone)]
 enum ReturnCode {
     /// The operation is blocked and has not completed.
@@ -334,6 +381,7 @@ enum ReturnCode {
     Cancelled(u32),
 }
 
+#[cfg(not(feature = "symmetric"))]
 impl ReturnCode {
     fn decode(val: u32) -> ReturnCode {
         if val == BLOCKED {
@@ -361,6 +409,9 @@ pub fn start_task(task: impl Future<Output = ()> + 'static) -> i32 {
     // Allocate a new `FutureState` which will track all state necessary for
     // our exported task.
     let state = Box::into_raw(Box::new(FutureState::new(Box::pin(task))));
+    rttrace!(TaskStart {
+        task: state as usize
+    });
 
     // Store our `FutureState` into our context-local-storage slot and then
     // pretend we got EVENT_NONE to kick off everything.
@@ -369,8 +420,8 @@ pub fn start_task(task: impl Future<Output = ()> + 'static) -> i32 {
     // task, and then `callback` is only invoked when context-local storage is
     // valid.
     unsafe {
-        assert!(context_get().is_null());
-        context_set(state.cast());
+        assert!(backend::context_get().is_null());
+        backend::context_set(state.cast());
         callback(EVENT_NONE, 0, 0) as i32
     }
 }
@@ -380,16 +431,16 @@ pub fn start_task(task: impl Future<Output = ()> + 'static) -> i32 {
 ///
 /// # Unsafety
 ///
-/// This function assumes that `context_get()` returns a `FutureState`.
+/// This function assumes that `context.get` returns a `FutureState`.
 #[doc(hidden)]
 pub unsafe fn callback(event0: u32, event1: u32, event2: u32) -> u32 {
     // Acquire our context-local state, assert it's not-null, and then reset
     // the state to null while we're running to help prevent any unintended
     // usage.
-    let state = context_get().cast::<FutureState>();
+    let state = backend::context_get().cast::<FutureState>();
     assert!(!state.is_null());
     unsafe {
-        context_set(ptr::null_mut());
+        backend::context_set(ptr::null_mut());
     }
 
     // Use `state` to run the `callback` function in the context of our event
@@ -401,7 +452,7 @@ pub unsafe fn callback(event0: u32, event1: u32, event2: u32) -> u32 {
         if done {
             drop(Box::from_raw(state));
         } else {
-            context_set(state.cast());
+            backend::context_set(state.cast());
         }
         rtdebug!(" => (cb) {rc:#x}");
         rc
@@ -419,20 +470,18 @@ pub fn spawn(future: impl Future<Output = ()> + 'static) {
 
 /// Run the specified future to completion, returning the result.
 ///
-/// This uses `waitable-set.wait` to poll for progress on any in-progress calls
-/// to async-lowered imports as necessary.
+/// With the canonical ABI this uses `waitable-set.wait` to poll for progress
+/// on any in-progress calls to async-lowered imports as necessary. With the
+/// symmetric ABI the `symmetric-executor` is run until the future completes.
 // TODO: refactor so `'static` bounds aren't necessary
 pub fn block_on<T: 'static>(future: impl Future<Output = T> + 'static) -> T {
     let (tx, mut rx) = oneshot::channel();
     let state = &mut FutureState::new(Box::pin(future.map(move |v| drop(tx.send(v)))) as BoxFuture);
-    let mut event = (EVENT_NONE, 0, 0);
-    loop {
-        match state.callback(event.0, event.1, event.2) {
-            (_, true) => break rx.try_recv().unwrap().unwrap(),
-            (CALLBACK_CODE_YIELD, false) => event = state.waitable_set.as_ref().unwrap().poll(),
-            _ => event = state.waitable_set.as_ref().unwrap().wait(),
-        }
-    }
+    rttrace!(TaskStart {
+        task: state as *mut FutureState as usize
+    });
+    backend::run(state);
+    rx.try_recv().unwrap().unwrap()
 }
 
 /// Call the `yield` canonical built-in function.
@@ -456,21 +505,10 @@ pub fn block_on<T: 'static>(future: impl Future<Output = T> + 'static) -> T {
 /// at this yield point. The caller should return back and exit from the task
 /// ASAP in this situation.
 pub fn yield_blocking() -> bool {
-    #[cfg(not(target_arch = "wasm32"))]
-    unsafe fn yield_() -> bool {
-        unreachable!();
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    #[link(wasm_import_module = "$root")]
-    extern "C" {
-        #[link_name = "[yield]"]
-        fn yield_() -> bool;
-    }
     // Note that the return value from the raw intrinsic is inverted, the
     // canonical ABI returns "did this task get cancelled" while this function
     // works as "should work continue going".
-    unsafe { !yield_() }
+    !backend::yield_()
 }
 
 /// The asynchronous counterpart to [`yield_blocking`].
@@ -516,51 +554,8 @@ pub async fn yield_async() {
 /// component instance until further notice (i.e. until `backpressure.set` is
 /// called again with `enabled` set to `false`).
 pub fn backpressure_set(enabled: bool) {
-    #[cfg(not(target_arch = "wasm32"))]
-    unsafe fn backpressure_set(_: i32) {
-        unreachable!();
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    #[link(wasm_import_module = "$root")]
-    extern "C" {
-        #[link_name = "[backpressure-set]"]
-        fn backpressure_set(_: i32);
-    }
-
-    unsafe { backpressure_set(if enabled { 1 } else { 0 }) }
-}
-
-fn context_get() -> *mut u8 {
-    #[cfg(not(target_arch = "wasm32"))]
-    unsafe fn get() -> *mut u8 {
-        unreachable!()
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    #[link(wasm_import_module = "$root")]
-    extern "C" {
-        #[link_name = "[context-get-0]"]
-        fn get() -> *mut u8;
-    }
-
-    unsafe { get() }
-}
-
-unsafe fn context_set(value: *mut u8) {
-    #[cfg(not(target_arch = "wasm32"))]
-    unsafe fn set(_: *mut u8) {
-        unreachable!()
-    }
-
-    #[cfg(target_arch = "wasm32")]
-    #[link(wasm_import_module = "$root")]
-    extern "C" {
-        #[link_name = "[context-set-0]"]
-        fn set(value: *mut u8);
-    }
-
-    unsafe { set(value) }
+    rttrace!(Backpressure { enabled });
+    backend::backpressure_set(enabled)
 }
 
 #[doc(hidden)]
@@ -582,18 +577,6 @@ impl TaskCancelOnDrop {
 
 impl Drop for TaskCancelOnDrop {
     fn drop(&mut self) {
-        #[cfg(not(target_arch = "wasm32"))]
-        unsafe fn cancel() {
-            unreachable!()
-        }
-
-        #[cfg(target_arch = "wasm32")]
-        #[link(wasm_import_module = "[export]$root")]
-        extern "C" {
-            #[link_name = "[task-cancel]"]
-            fn cancel();
-        }
-
-        unsafe { cancel() }
+        backend::task_cancel()
     }
 }
@@ -125,22 +125,22 @@ impl<T: 'static> AbiBuffer<T> {
     /// This signals that `amt` items are no longer going to be yielded from
     /// `abi_ptr_and_len`. Additionally this will perform any deallocation
     /// necessary for the starting `amt` items in this list.
-    #[cfg(not(feature = "symmetric"))]
     pub(crate) fn advance(&mut self, amt: usize) {
         assert!(amt + self.cursor <= self.rust_storage.len());
-        let Some(dealloc_lists) = self.vtable.dealloc_lists else {
-            self.cursor += amt;
-            return;
-        };
-        let (mut ptr, len) = self.abi_ptr_and_len();
-        assert!(amt <= len);
-        for _ in 0..amt {
-            // SAFETY: we're managing the pointer passed to `dealloc_lists` and
-            // it was initialized with a `lower`, and then the pointer
-            // arithmetic should all be in-bounds.
-            unsafe {
-                dealloc_lists(ptr.cast_mut());
-                ptr = ptr.add(self.vtable.layout.size());
+        // In the symmetric ABI the reader takes ownership of the lists in the
+        // lowered values, so there's nothing to deallocate.
+        #[cfg(not(feature = "symmetric"))]
+        if let Some(dealloc_lists) = self.vtable.dealloc_lists {
+            let (mut ptr, len) = self.abi_ptr_and_len();
+            assert!(amt <= len);
+            for _ in 0..amt {
+                // SAFETY: we're managing the pointer passed to `dealloc_lists`
+                // and it was initialized with a `lower`, and then the pointer
+                // arithmetic should all be in-bounds.
+                unsafe {
+                    dealloc_lists(ptr.cast_mut());
+                    ptr = ptr.add(self.vtable.layout.size());
+                }
             }
         }
         self.cursor += amt;
@@ -202,38 +202,59 @@ impl<T> Drop for AbiBuffer<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
     use std::vec;
 
-    extern "C" fn cancel(_: u32) -> u32 {
-        todo!()
-    }
-    extern "C" fn drop(_: u32) {
-        todo!()
-    }
-    extern "C" fn new() -> u64 {
-        todo!()
-    }
-    extern "C" fn start_read(_: u32, _: *mut u8, _: usize) -> u32 {
-        todo!()
+    #[cfg(not(feature = "symmetric"))]
+    const fn vtable<T>(
+        lift: Option<unsafe fn(*mut u8) -> T>,
+        lower: Option<unsafe fn(T, *mut u8)>,
+        dealloc_lists: Option<unsafe fn(*mut u8)>,
+    ) -> StreamVtable<T> {
+        extern "C" fn cancel(_: u32) -> u32 {
+            todo!()
+        }
+        extern "C" fn drop(_: u32) {
+            todo!()
+        }
+        extern "C" fn new() -> u64 {
+            todo!()
+        }
+        extern "C" fn start_read(_: u32, _: *mut u8, _: usize) -> u32 {
+            todo!()
+        }
+        extern "C" fn start_write(_: u32, _: *const u8, _: usize) -> u32 {
+            todo!()
+        }
+
+        StreamVtable {
+            cancel_read: cancel,
+            cancel_write: cancel,
+            drop_readable: drop,
+            drop_writable: drop,
+            dealloc_lists,
+            lift,
+            lower,
+            layout: unsafe { Layout::from_size_align_unchecked(1, 1) },
+            new,
+            start_read,
+            start_write,
+        }
     }
-    extern "C" fn start_write(_: u32, _: *const u8, _: usize) -> u32 {
-        todo!()
+
+    #[cfg(feature = "symmetric")]
+    const fn vtable<T>(
+        lift: Option<unsafe fn(*mut u8) -> T>,
+        lower: Option<unsafe fn(T, *mut u8)>,
+        _dealloc_lists: Option<unsafe fn(*mut u8)>,
+    ) -> StreamVtable<T> {
+        StreamVtable {
+            lift,
+            lower,
+            layout: unsafe { Layout::from_size_align_unchecked(1, 1) },
+        }
     }
 
-    static BLANK: StreamVtable<u8> = StreamVtable {
-        cancel_read: cancel,
-        cancel_write: cancel,
-        drop_readable: drop,
-        drop_writable: drop,
-        dealloc_lists: None,
-        lift: None,
-        lower: None,
-        layout: unsafe { Layout::from_size_align_unchecked(1, 1) },
-        new,
-        start_read,
-        start_write,
-    };
+    static BLANK: StreamVtable<u8> = vtable(None, None, None);
 
     #[test]
     fn blank_advance_to_end() {
@@ -297,21 +318,13 @@ mod tests {
     #[derive(PartialEq, Eq, Debug)]
     struct B(u8);
 
-    static OP: StreamVtable<B> = StreamVtable {
-        cancel_read: cancel,
-        cancel_write: cancel,
-        drop_readable: drop,
-        drop_writable: drop,
-        dealloc_lists: Some(|_ptr| {}),
-        lift: Some(|ptr| unsafe { B(*ptr - 1) }),
-        lower: Some(|b, ptr| unsafe {
+    static OP: StreamVtable<B> = vtable(
+        Some(|ptr| unsafe { B(*ptr - 1) }),
+        Some(|b, ptr| unsafe {
             *ptr = b.0 + 1;
         }),
-        layout: unsafe { Layout::from_size_align_unchecked(1, 1) },
-        new,
-        start_read,
-        start_write,
-    };
+        Some(|_ptr| {}),
+    );
 
     #[test]
     fn op_advance_to_end() {
@@ -376,26 +389,21 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "symmetric"))]
     fn dealloc_lists() {
+        use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
         static DEALLOCS: AtomicUsize = AtomicUsize::new(0);
-        static OP: StreamVtable<B> = StreamVtable {
-            cancel_read: cancel,
-            cancel_write: cancel,
-            drop_readable: drop,
-            drop_writable: drop,
-            dealloc_lists: Some(|ptr| {
+        static OP: StreamVtable<B> = vtable(
+            Some(|ptr| unsafe { B(*ptr) }),
+            Some(|b, ptr| unsafe {
+                *ptr = b.0;
+            }),
+            Some(|ptr| {
                 let prev = DEALLOCS.fetch_add(1, Relaxed);
                 assert_eq!(unsafe { usize::from(*ptr) }, prev + 1);
             }),
-            lift: Some(|ptr| unsafe { B(*ptr) }),
-            lower: Some(|b, ptr| unsafe {
-                *ptr = b.0;
-            }),
-            layout: unsafe { Layout::from_size_align_unchecked(1, 1) },
-            new,
-            start_read,
-            start_write,
-        };
+        );
 
         assert_eq!(DEALLOCS.load(Relaxed), 0);
         let buf = vec![B(1), B(2), B(3), B(4)];
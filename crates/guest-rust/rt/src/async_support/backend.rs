@@ -0,0 +1,36 @@
+//! The ABI-specific half of the task executor.
+//!
+//! Everything else in `async_support` that drives tasks, for example
+//! `FutureState`, `spawn`, `block_on`, `JoinSet` and `select`, is shared
+//! between the canonical and the symmetric ABI. It's built on the primitives
+//! here, and exactly one backend provides them:
+//!
+//! * `canonical`, the default, uses the component model's `waitable-set`,
+//!   `context`, `yield`, `backpressure.set` and `task.cancel` intrinsics.
+//! * `symmetric`, enabled with the `symmetric` feature, uses event
+//!   subscriptions of the `symmetric-executor` runtime instead.
+//!
+//! Both provide the same items:
+//!
+//! * `WaitableSet`, the set of waitables that a task is blocked on, with
+//!   `new`, `join`, `remove_waitable_from_all_sets` and `as_raw`.
+//! * `run`, which drives a `FutureState` to completion for `block_on`.
+//! * `yield_`, `backpressure_set`, `context_get`, `context_set` and
+//!   `task_cancel`.
+//!
+//! Streams, futures and `Subtask` differ more between the two ABIs, so the
+//! symmetric backend provides its own `stream_support`, `future_support` and
+//! `subtask` modules with the same API, built on the `symmetric-stream`
+//! runtime. Async exports (`start_task` and `callback`) aren't supported with
+//! the symmetric ABI yet. The symmetric backend is tested by
+//! `crates/symmetric_executor/rt-test`.
+
+#[cfg(not(feature = "symmetric"))]
+mod canonical;
+#[cfg(not(feature = "symmetric"))]
+pub use canonical::*;
+
+#[cfg(feature = "symmetric")]
+mod symmetric;
+#[cfg(feature = "symmetric")]
+pub use symmetric::*;
@@ -1,5 +1,7 @@
-//! Low-level FFI-like bindings around `waitable-set` in the canonical ABI.
+//! Backend for the canonical ABI, built on the component model's
+//! `waitable-set`, `context` and task intrinsics.
 
+use super::super::{FutureState, CALLBACK_CODE_YIELD, EVENT_NONE};
 use std::num::NonZeroU32;
 
 pub struct WaitableSet(NonZeroU32);
@@ -98,3 +100,98 @@ extern "C" {
     #[link_name = "[waitable-set-poll]"]
     fn poll(_: u32, _: *mut [u32; 2]) -> u32;
 }
+
+/// Runs `state` to completion, blocking on its waitable set with
+/// `waitable-set.wait` whenever it can't make progress.
+pub fn run(state: &mut FutureState) {
+    let mut event = (EVENT_NONE, 0, 0);
+    loop {
+        match state.callback(event.0, event.1, event.2) {
+            (_, true) => break,
+            (CALLBACK_CODE_YIELD, false) => event = state.waitable_set.as_ref().unwrap().poll(),
+            _ => event = state.waitable_set.as_ref().unwrap().wait(),
+        }
+    }
+}
+
+/// Calls the `yield` intrinsic, returning whether the current task was
+/// cancelled while suspended.
+pub fn yield_() -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn yield_() -> bool {
+        unreachable!();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[link(wasm_import_module = "$root")]
+    extern "C" {
+        #[link_name = "[yield]"]
+        fn yield_() -> bool;
+    }
+
+    unsafe { yield_() }
+}
+
+pub fn backpressure_set(enabled: bool) {
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn backpressure_set(_: i32) {
+        unreachable!();
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[link(wasm_import_module = "$root")]
+    extern "C" {
+        #[link_name = "[backpressure-set]"]
+        fn backpressure_set(_: i32);
+    }
+
+    unsafe { backpressure_set(if enabled { 1 } else { 0 }) }
+}
+
+pub fn context_get() -> *mut u8 {
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn get() -> *mut u8 {
+        unreachable!()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[link(wasm_import_module = "$root")]
+    extern "C" {
+        #[link_name = "[context-get-0]"]
+        fn get() -> *mut u8;
+    }
+
+    unsafe { get() }
+}
+
+pub unsafe fn context_set(value: *mut u8) {
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn set(_: *mut u8) {
+        unreachable!()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[link(wasm_import_module = "$root")]
+    extern "C" {
+        #[link_name = "[context-set-0]"]
+        fn set(value: *mut u8);
+    }
+
+    unsafe { set(value) }
+}
+
+pub fn task_cancel() {
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe fn cancel() {
+        unreachable!()
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[link(wasm_import_module = "[export]$root")]
+    extern "C" {
+        #[link_name = "[task-cancel]"]
+        fn cancel();
+    }
+
+    unsafe { cancel() }
+}
@@ -0,0 +1,325 @@
+//! Backend for the symmetric ABI, built on event subscriptions of the
+//! `symmetric-executor` runtime.
+//!
+//! Waitables here stand in for event subscriptions handed to [`wait_on`].
+//! Joining one to a task's set doesn't block on anything. Instead, once the
+//! task is done being polled, a callback is registered with the executor which
+//! delivers the event straight back to the task and polls it again. `run`
+//! then only has to hand control to the executor until the task is done.
+//!
+//! There's no host-managed task state in the symmetric ABI, so the context
+//! slot is a plain global and `yield`, `backpressure.set` and `task.cancel`
+//! do nothing.
+//!
+//! Streams, futures and calls to async imports are built on `wait_on` and
+//! the `symmetric-stream` runtime in the submodules here.
+
+use super::super::{cabi, FutureState, CALLBACK_CODE_YIELD, EVENT_NONE, EVENT_SUBTASK};
+use std::boxed::Box;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::c_void;
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::ptr;
+use std::task::{Context, Poll, Waker};
+use std::vec::Vec;
+
+pub mod future_support;
+mod stream_obj;
+pub mod stream_support;
+pub mod subtask;
+
+/// Event subscriptions, keyed by the waitable standing in for them, which
+/// haven't been registered with the executor yet.
+static mut SUBSCRIPTIONS: BTreeMap<u32, usize> = BTreeMap::new();
+
+/// Waitables registered with the executor whose event hasn't been delivered
+/// yet, along with the data pointer of their registration.
+///
+/// The executor doesn't support cancelling a registration, so removing a
+/// waitable only removes it from here. Its callback still runs once the event
+/// fires but then finds it's been removed and does nothing.
+static mut REGISTERED: BTreeMap<u32, *mut u8> = BTreeMap::new();
+
+static mut NEXT_WAITABLE: u32 = 1;
+
+static mut CONTEXT: *mut u8 = ptr::null_mut();
+
+const CALLBACK_STATE_READY: u8 = 1;
+
+pub struct WaitableSet {
+    /// Waitables joined while the task was being polled, registered with the
+    /// executor by `drive` once polling returns.
+    joined: RefCell<Vec<u32>>,
+}
+
+impl WaitableSet {
+    pub fn new() -> WaitableSet {
+        WaitableSet {
+            joined: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub fn join(&self, waitable: u32) {
+        rtdebug!("symmetric join({waitable})");
+        self.joined.borrow_mut().push(waitable);
+    }
+
+    pub fn remove_waitable_from_all_sets(waitable: u32) {
+        rtdebug!("symmetric remove({waitable})");
+        unsafe {
+            REGISTERED.remove(&waitable);
+            if let Some(subscription) = SUBSCRIPTIONS.remove(&waitable) {
+                subscription_drop(subscription as *mut u8);
+            }
+        }
+    }
+
+    pub fn as_raw(&self) -> u32 {
+        0
+    }
+}
+
+/// Polls `state` with `event`, registering everything it started waiting on
+/// with the executor afterwards.
+///
+/// Returns whether the task is done.
+unsafe fn drive(state: *mut FutureState, mut event: (u32, u32, u32)) -> bool {
+    loop {
+        let (code, done) = (*state).callback(event.0, event.1, event.2);
+        if done {
+            return true;
+        }
+        let joined = match &(*state).waitable_set {
+            Some(set) => mem::take(&mut *set.joined.borrow_mut()),
+            None => Vec::new(),
+        };
+        for waitable in joined {
+            register(state, waitable);
+        }
+        if code != CALLBACK_CODE_YIELD {
+            return false;
+        }
+        event = (EVENT_NONE, 0, 0);
+    }
+}
+
+struct Registration {
+    state: *mut FutureState,
+    waitable: u32,
+}
+
+unsafe fn register(state: *mut FutureState, waitable: u32) {
+    // The waitable may have been removed before polling returned, in which
+    // case there's nothing left to wait for.
+    let Some(subscription) = SUBSCRIPTIONS.remove(&waitable) else {
+        return;
+    };
+    let data = Box::into_raw(Box::new(Registration { state, waitable })).cast::<u8>();
+    REGISTERED.insert(waitable, data);
+    // Note that the executor may run `callback` right away if the event is
+    // already active.
+    let registration = executor_register(
+        subscription as *mut u8,
+        callback as *const () as *mut u8,
+        data,
+    );
+    registration_drop(registration);
+}
+
+extern "C" fn callback(data: *mut u8) -> u8 {
+    unsafe {
+        let Registration { state, waitable } = *Box::from_raw(data.cast::<Registration>());
+        if REGISTERED.get(&waitable) == Some(&data) {
+            // The executor doesn't tell events apart, so they're all
+            // delivered as subtask progress. The waitable removes itself from
+            // `REGISTERED` as part of delivery.
+            drive(state, (EVENT_SUBTASK, waitable, 0));
+        }
+    }
+    CALLBACK_STATE_READY
+}
+
+/// Runs `state` to completion by running the `symmetric-executor`.
+///
+/// This must not be called while polling a task, as the executor may then
+/// deliver events to that task.
+pub fn run(state: &mut FutureState) {
+    if unsafe { drive(state, (EVENT_NONE, 0, 0)) } {
+        return;
+    }
+    unsafe { executor_run() };
+    assert!(
+        state.tasks.is_empty(),
+        "symmetric executor ran out of events before the task completed"
+    );
+}
+
+/// Waits for `subscription`, an event subscription of the
+/// `symmetric-executor`, to become active.
+///
+/// This is the symmetric ABI's counterpart of waiting on a waitable, for
+/// example to wait for a call to an async import to finish or for a
+/// `symmetric-stream` to become ready.
+///
+/// # Safety
+///
+/// `subscription` must be a valid event subscription handle, ownership of
+/// which is transferred to the returned future.
+pub unsafe fn wait_on(subscription: *mut u8) -> WaitOn {
+    WaitOn {
+        subscription: Some(subscription as usize),
+        waitable: None,
+        status: Box::new(WaitStatus {
+            ready: false,
+            waker: None,
+        }),
+    }
+}
+
+/// Future returned by [`wait_on`].
+pub struct WaitOn {
+    /// The subscription until it's handed off to a waitable on first poll.
+    subscription: Option<usize>,
+    waitable: Option<u32>,
+    /// Boxed so that its address can be registered with the current task.
+    status: Box<WaitStatus>,
+}
+
+struct WaitStatus {
+    ready: bool,
+    waker: Option<Waker>,
+}
+
+impl Future for WaitOn {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let me = self.get_mut();
+        if me.status.ready {
+            return Poll::Ready(());
+        }
+        me.status.waker = Some(cx.waker().clone());
+        if let Some(subscription) = me.subscription.take() {
+            unsafe {
+                if subscription_ready(subscription as *mut u8) != 0 {
+                    subscription_drop(subscription as *mut u8);
+                    me.status.ready = true;
+                    return Poll::Ready(());
+                }
+                let waitable = NEXT_WAITABLE;
+                NEXT_WAITABLE = NEXT_WAITABLE.wrapping_add(1).max(1);
+                SUBSCRIPTIONS.insert(waitable, subscription);
+                me.waitable = Some(waitable);
+
+                let task = cabi::wasip3_task_set(ptr::null_mut());
+                assert!(!task.is_null());
+                assert!((*task).version >= cabi::WASIP3_TASK_V1);
+                rttrace!(WakerRegistered { waitable });
+                let status: *mut WaitStatus = &mut *me.status;
+                ((*task).waitable_register)((*task).ptr, waitable, wake, status.cast());
+                cabi::wasip3_task_set(task);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+unsafe extern "C" fn wake(ptr: *mut c_void, _code: u32) {
+    let status = &mut *ptr.cast::<WaitStatus>();
+    status.ready = true;
+    if let Some(waker) = status.waker.take() {
+        waker.wake();
+    }
+}
+
+impl Drop for WaitOn {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(subscription) = self.subscription {
+                subscription_drop(subscription as *mut u8);
+            }
+            if let (Some(waitable), false) = (self.waitable, self.status.ready) {
+                rttrace!(WakerUnregistered { waitable });
+                let task = cabi::wasip3_task_set(ptr::null_mut());
+                assert!(!task.is_null());
+                ((*task).waitable_unregister)((*task).ptr, waitable);
+                cabi::wasip3_task_set(task);
+            }
+        }
+    }
+}
+
+pub fn yield_() -> bool {
+    false
+}
+
+pub fn backpressure_set(enabled: bool) {
+    let _ = enabled;
+}
+
+pub fn context_get() -> *mut u8 {
+    unsafe { CONTEXT }
+}
+
+pub unsafe fn context_set(value: *mut u8) {
+    CONTEXT = value;
+}
+
+pub fn task_cancel() {}
+
+// Unit tests of this crate don't run on the executor, so they don't link it.
+#[cfg_attr(not(test), link(name = "symmetric_executor"))]
+#[link(wasm_import_module = "symmetric:runtime/symmetric-executor@0.2.1")]
+extern "C" {
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]event-subscription.ready")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BmethodX5Devent_subscriptionX2Eready"
+    )]
+    fn subscription_ready(subscription: *mut u8) -> i32;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]event-subscription.dup")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BmethodX5Devent_subscriptionX2Edup"
+    )]
+    fn subscription_dup(subscription: *mut u8) -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]event-subscription.reset")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BmethodX5Devent_subscriptionX2Ereset"
+    )]
+    fn subscription_reset(subscription: *mut u8);
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[resource-drop]event-subscription"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5Bresource_dropX5Devent_subscription"
+    )]
+    fn subscription_drop(subscription: *mut u8);
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[resource-drop]callback-registration"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5Bresource_dropX5Dcallback_registration"
+    )]
+    fn registration_drop(registration: *mut u8);
+    #[cfg_attr(target_arch = "wasm32", link_name = "register")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00register"
+    )]
+    fn executor_register(trigger: *mut u8, callback: *mut u8, data: *mut u8) -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "run")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00run"
+    )]
+    fn executor_run();
+}
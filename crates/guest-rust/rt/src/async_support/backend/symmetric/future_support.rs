@@ -0,0 +1,366 @@
+//! Futures in the symmetric ABI, with the same API as the canonical ABI's.
+//!
+//! A future is a stream which carries exactly one value. Like in the canonical
+//! ABI a `FutureWriter` which is dropped without writing a value writes the
+//! default value in the background.
+
+use super::stream_obj::{self, Read, Reader, Writer};
+use super::WaitOn;
+use crate::Cleanup;
+use std::alloc::Layout;
+use std::fmt;
+use std::future::{Future, IntoFuture};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Function table used for [`FutureWriter`] and [`FutureReader`]
+///
+/// Instances of this table are generated by `wit_bindgen::generate!`. This is
+/// not a trait to enable different `FutureVtable<()>` instances to exist, for
+/// example, through different calls to `wit_bindgen::generate!`.
+#[doc(hidden)]
+pub struct FutureVtable<T> {
+    /// The in-memory layout of a lowered `T`.
+    pub layout: Layout,
+
+    /// A callback to consume a value of `T` and lower it to the layout of
+    /// `T`, passing ownership of all of `T`'s lists and resources to `dst`.
+    pub lower: unsafe fn(value: T, dst: *mut u8),
+
+    /// The dual of `lower`.
+    pub lift: unsafe fn(dst: *mut u8) -> T,
+}
+
+/// Helper function to create a new read/write pair for a `symmetric-stream`
+/// stream carrying one value.
+///
+/// # Unsafety
+///
+/// This function is unsafe as it requires the functions within `vtable` to
+/// correctly uphold the contracts of the ABI.
+pub unsafe fn future_new<T>(
+    default: fn() -> T,
+    vtable: &'static FutureVtable<T>,
+) -> (FutureWriter<T>, FutureReader<T>) {
+    let (writer, reader) = stream_obj::new();
+    unsafe {
+        (
+            FutureWriter::new(writer, default, vtable),
+            FutureReader::new(reader, vtable),
+        )
+    }
+}
+
+/// Represents the writable end of a future.
+///
+/// A [`FutureWriter`] can be used to send a single value of `T` to the other
+/// end of a `future`.
+pub struct FutureWriter<T: 'static> {
+    obj: Writer,
+    vtable: &'static FutureVtable<T>,
+
+    /// Whether or not a value should be written during `drop`.
+    should_write_default_value: bool,
+
+    /// Constructor for the default value to write during `drop`.
+    default: fn() -> T,
+}
+
+impl<T> FutureWriter<T> {
+    #[doc(hidden)]
+    pub unsafe fn new(
+        handle: *mut u8,
+        default: fn() -> T,
+        vtable: &'static FutureVtable<T>,
+    ) -> Self {
+        Self {
+            obj: Writer::new(handle),
+            vtable,
+            should_write_default_value: true,
+            default,
+        }
+    }
+
+    /// Write the specified `value` to this `future`.
+    ///
+    /// This method is equivalent to an `async fn` which sends the `value` into
+    /// this future. The asynchronous operation acts as a rendezvous where the
+    /// operation does not complete until the other side has started reading.
+    ///
+    /// # Return Value
+    ///
+    /// The returned future resolves to `Ok(())` if the value was sent, and
+    /// to an error with the value if the other end was dropped.
+    ///
+    /// # Cancellation
+    ///
+    /// The returned future can be cancelled via `drop`, in which case the
+    /// value is lost and the default value is written in the background. The
+    /// [`FutureWrite::cancel`] method can be used to get the value back.
+    pub fn write(self, value: T) -> FutureWrite<T> {
+        FutureWrite {
+            writer: Some(self),
+            value: Some(value),
+            wait: None,
+        }
+    }
+}
+
+impl<T> fmt::Debug for FutureWriter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FutureWriter")
+            .field("handle", &self.obj.handle())
+            .finish()
+    }
+}
+
+impl<T> Drop for FutureWriter<T> {
+    fn drop(&mut self) {
+        // See the canonical ABI's `FutureWriter` for why the default value is
+        // written in a separate task.
+        if self.should_write_default_value {
+            let clone = FutureWriter {
+                obj: self.obj.take(),
+                vtable: self.vtable,
+                should_write_default_value: false,
+                default: self.default,
+            };
+            crate::async_support::spawn(async move {
+                let value = (clone.default)();
+                let _ = clone.write(value).await;
+            });
+        }
+    }
+}
+
+/// Represents a write operation which may be cancelled prior to completion.
+///
+/// This is returned by [`FutureWriter::write`].
+pub struct FutureWrite<T: 'static> {
+    /// The writer and the value to write until the write completes.
+    writer: Option<FutureWriter<T>>,
+    value: Option<T>,
+    wait: Option<WaitOn>,
+}
+
+// Nothing in a write is pinned structurally.
+impl<T> Unpin for FutureWrite<T> {}
+
+impl<T: 'static> Future for FutureWrite<T> {
+    type Output = Result<(), FutureWriteError<T>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = self.get_mut();
+        let writer = me.writer.as_mut().expect("cannot poll after completion");
+        if !writer.obj.is_done() && writer.obj.poll_ready(&mut me.wait, cx).is_pending() {
+            return Poll::Pending;
+        }
+        let mut writer = me.writer.take().unwrap();
+        let value = me.value.take().unwrap();
+        if writer.obj.is_done() {
+            writer.should_write_default_value = false;
+            return Poll::Ready(Err(FutureWriteError { value }));
+        }
+        let (ptr, cleanup) = Cleanup::new(writer.vtable.layout);
+        // SAFETY: `ptr` is allocated with `vtable.layout` and the stream is
+        // ready to write.
+        unsafe {
+            (writer.vtable.lower)(value, ptr);
+            if writer.obj.write(ptr, 1, writer.vtable.layout).is_some() {
+                writer.should_write_default_value = false;
+                return Poll::Ready(Ok(()));
+            }
+            // The reader is gone, so take the value back.
+            writer.should_write_default_value = false;
+            let value = (writer.vtable.lift)(ptr);
+            drop(cleanup);
+            Poll::Ready(Err(FutureWriteError { value }))
+        }
+    }
+}
+
+impl<T: 'static> FutureWrite<T> {
+    /// Cancel this write if it hasn't already completed.
+    ///
+    /// This method can be used to cancel a write-in-progress and re-acquire
+    /// the writer and the value being sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation has already been completed via `Future::poll`,
+    /// or if this method is called twice.
+    pub fn cancel(self: Pin<&mut Self>) -> FutureWriteCancel<T> {
+        let me = self.get_mut();
+        me.wait = None;
+        let writer = me.writer.take().expect("cannot cancel after completion");
+        let value = me.value.take().unwrap();
+        FutureWriteCancel::Cancelled(value, writer)
+    }
+}
+
+/// Error type in the result of [`FutureWrite`], or the error type that is a
+/// result of a failure to write a future.
+pub struct FutureWriteError<T> {
+    /// The value that could not be sent.
+    pub value: T,
+}
+
+impl<T> fmt::Debug for FutureWriteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FutureWriteError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for FutureWriteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "read end dropped".fmt(f)
+    }
+}
+
+impl<T> std::error::Error for FutureWriteError<T> {}
+
+/// Result of [`FutureWrite::cancel`].
+#[derive(Debug)]
+pub enum FutureWriteCancel<T: 'static> {
+    /// The cancel request raced with the receipt of the sent value, and the
+    /// value was actually sent. Neither the value nor the writer are made
+    /// available here as both are gone.
+    AlreadySent,
+
+    /// The other end was dropped before cancellation happened.
+    ///
+    /// In this case the original value is returned back to the caller but the
+    /// writer itself is not longer accessible as it's no longer usable.
+    Dropped(T),
+
+    /// The pending write was successfully cancelled and the value being written
+    /// is returned along with the writer to resume again in the future if
+    /// necessary.
+    Cancelled(T, FutureWriter<T>),
+}
+
+/// Represents the readable end of a future.
+pub struct FutureReader<T: 'static> {
+    obj: Reader,
+    vtable: &'static FutureVtable<T>,
+}
+
+impl<T> fmt::Debug for FutureReader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FutureReader")
+            .field("handle", &self.obj.handle())
+            .finish()
+    }
+}
+
+impl<T> FutureReader<T> {
+    #[doc(hidden)]
+    pub unsafe fn new(handle: *mut u8, vtable: &'static FutureVtable<T>) -> Self {
+        Self {
+            obj: Reader::new(handle),
+            vtable,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn take_handle(&self) -> *mut u8 {
+        self.obj.take_handle()
+    }
+}
+
+impl<T> IntoFuture for FutureReader<T> {
+    type Output = T;
+    type IntoFuture = FutureRead<T>;
+
+    /// Convert this object into a `Future` which will resolve when a value is
+    /// written to the writable end of this `future`.
+    fn into_future(self) -> Self::IntoFuture {
+        FutureRead {
+            reader: Some(self),
+            wait: None,
+        }
+    }
+}
+
+impl<T> Drop for FutureReader<T> {
+    fn drop(&mut self) {
+        if let Some(received) = self.obj.close() {
+            if received.count > 0 {
+                // SAFETY: `received` owns the lowered value.
+                drop(unsafe { (self.vtable.lift)(received.ptr) });
+            }
+        }
+    }
+}
+
+/// Represents a read operation which may be cancelled prior to completion.
+///
+/// This represents a read operation on a [`FutureReader`] and is created via
+/// `IntoFuture`.
+pub struct FutureRead<T: 'static> {
+    /// The reader until the read completes.
+    reader: Option<FutureReader<T>>,
+    wait: Option<WaitOn>,
+}
+
+// Nothing in a read is pinned structurally.
+impl<T> Unpin for FutureRead<T> {}
+
+impl<T> FutureRead<T> {
+    fn take_value(&mut self, read: Read) -> Result<T, FutureReader<T>> {
+        let reader = self.reader.take().unwrap();
+        match read {
+            Read::Values(received) => {
+                assert_eq!(received.count, 1);
+                // SAFETY: `received` owns the lowered value.
+                Ok(unsafe { (reader.vtable.lift)(received.ptr) })
+            }
+            Read::Dropped | Read::Cancelled => Err(reader),
+        }
+    }
+}
+
+impl<T: 'static> Future for FutureRead<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = self.get_mut();
+        let reader = me.reader.as_mut().expect("cannot poll after completion");
+        let layout = reader.vtable.layout;
+        match reader.obj.poll_read(&mut me.wait, layout, 1, cx) {
+            Poll::Ready(read) => match me.take_value(read) {
+                Ok(value) => Poll::Ready(value),
+                // Writers always write a value before they're dropped.
+                Err(_) => panic!("future writer dropped without writing a value"),
+            },
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl<T> FutureRead<T> {
+    /// Cancel this read if it hasn't already completed.
+    ///
+    /// Return values include:
+    ///
+    /// * `Ok(value)` - future completed before this cancellation request
+    ///   was received.
+    /// * `Err(reader)` - read operation was cancelled and it can be retried in
+    ///   the future if desired.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation has already been completed via `Future::poll`,
+    /// or if this method is called twice.
+    pub fn cancel(self: Pin<&mut Self>) -> Result<T, FutureReader<T>> {
+        let me = self.get_mut();
+        me.wait = None;
+        let read = me
+            .reader
+            .as_mut()
+            .expect("cannot cancel after completion")
+            .obj
+            .cancel();
+        me.take_value(read)
+    }
+}
@@ -0,0 +1,465 @@
+//! The reading and writing ends of a `symmetric-stream` stream object, which
+//! both streams and futures are built on.
+//!
+//! Both ends share one stream object. The reader starts a read by handing the
+//! writer a buffer, which makes the stream ready to write. The writer copies
+//! lowered values into that buffer and finishes the write, which makes the
+//! stream ready to read and hands the values over to the reader. Finishing a
+//! write without a buffer closes the stream.
+//!
+//! The stream object has no way to tell the writer that the reader is gone,
+//! so a dropped reader starts one last read with an empty buffer. Reads here
+//! never hand the writer an empty buffer otherwise.
+
+use super::{subscription_drop, subscription_dup, subscription_ready, subscription_reset};
+use super::{wait_on, WaitOn};
+use crate::Cleanup;
+use std::alloc::Layout;
+use std::future::Future;
+use std::pin::Pin;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering::Relaxed};
+use std::task::{Context, Poll};
+
+/// Creates a new stream object, returning the handles of its writing and
+/// reading end.
+pub(super) fn new() -> (*mut u8, *mut u8) {
+    unsafe {
+        let writer = stream_new();
+        let reader = stream_clone(writer);
+        rtdebug!("stream_obj.new() = [{writer:?}, {reader:?}]");
+        (writer, reader)
+    }
+}
+
+/// Result of a read.
+pub(super) enum Read {
+    /// The writer handed over values.
+    Values(Received),
+    /// The writer is gone.
+    Dropped,
+    /// The read was cancelled before the writer handed over anything.
+    Cancelled,
+}
+
+/// Lowered values handed over by the writer.
+pub(super) struct Received {
+    /// The first of `count` values, the rest following at intervals of the
+    /// payload's size.
+    pub ptr: *mut u8,
+    pub count: usize,
+    _alloc: Option<Cleanup>,
+}
+
+impl Received {
+    fn empty(layout: Layout) -> Received {
+        Received {
+            ptr: layout.align() as *mut u8,
+            count: 0,
+            _alloc: None,
+        }
+    }
+}
+
+pub(super) struct Reader {
+    /// The stream object, or null once it's been taken.
+    handle: AtomicPtr<u8>,
+    pending: Option<PendingRead>,
+    done: bool,
+}
+
+/// A read the writer has been handed a buffer for.
+struct PendingRead {
+    ptr: *mut u8,
+    alloc: Option<Cleanup>,
+    /// Subscription to the stream becoming ready to read, which is duplicated
+    /// for every wait so that waits can be dropped.
+    subscription: *mut u8,
+}
+
+impl Reader {
+    /// # Safety
+    ///
+    /// `handle` must be an owned handle to the reading end of a stream
+    /// object.
+    pub unsafe fn new(handle: *mut u8) -> Reader {
+        Reader {
+            handle: AtomicPtr::new(handle),
+            pending: None,
+            done: false,
+        }
+    }
+
+    pub fn take_handle(&self) -> *mut u8 {
+        assert!(self.pending.is_none(), "cannot take handle while reading");
+        let ret = self.handle.swap(ptr::null_mut(), Relaxed);
+        assert!(!ret.is_null());
+        ret
+    }
+
+    pub fn handle(&self) -> *mut u8 {
+        self.handle.load(Relaxed)
+    }
+
+    /// Reads up to `capacity` values of `layout`, waiting with `wait` until
+    /// the writer hands them over.
+    ///
+    /// A read which is still pending when `wait` is dropped is picked up
+    /// again by the next call.
+    pub fn poll_read(
+        &mut self,
+        wait: &mut Option<WaitOn>,
+        layout: Layout,
+        capacity: usize,
+        cx: &mut Context<'_>,
+    ) -> Poll<Read> {
+        let handle = self.handle();
+        if self.pending.is_none() {
+            if self.done || unsafe { stream_is_write_closed(handle) != 0 } {
+                self.done = true;
+                return Poll::Ready(Read::Dropped);
+            }
+            if capacity == 0 {
+                return Poll::Ready(Read::Values(Received::empty(layout)));
+            }
+            let size = layout.size().checked_mul(capacity).unwrap();
+            let (ptr, alloc) = Cleanup::new(Layout::from_size_align(size, layout.align()).unwrap());
+            // The writer takes a null buffer to mean that there's no read.
+            let ptr = if ptr.is_null() {
+                layout.align() as *mut u8
+            } else {
+                ptr
+            };
+            unsafe {
+                let subscription = stream_read_ready_subscribe(handle);
+                subscription_reset(subscription);
+                stream_start_reading(handle, buffer_new(ptr, capacity as i64));
+                rtdebug!("stream_obj.start_reading({handle:?}, {ptr:?}, {capacity})");
+                self.pending = Some(PendingRead {
+                    ptr,
+                    alloc,
+                    subscription,
+                });
+            }
+        }
+        let pending = self.pending.as_ref().unwrap();
+        let wait =
+            wait.get_or_insert_with(|| unsafe { wait_on(subscription_dup(pending.subscription)) });
+        match Pin::new(wait).poll(cx) {
+            Poll::Ready(()) => Poll::Ready(self.finish_read()),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Cancels a pending read.
+    ///
+    /// This takes the buffer back from the writer if it hasn't written to it
+    /// yet, and completes the read if the writer is done with it. A read
+    /// that's still being written to stays pending.
+    pub fn cancel(&mut self) -> Read {
+        let Some(pending) = &self.pending else {
+            return Read::Cancelled;
+        };
+        let handle = self.handle();
+        unsafe {
+            if stream_is_ready_to_write(handle) != 0 {
+                // Starting a write is the only way to take back the buffer.
+                buffer_drop(stream_start_writing(handle));
+                subscription_drop(pending.subscription);
+                self.pending = None;
+                return Read::Cancelled;
+            }
+            let subscription = subscription_dup(pending.subscription);
+            let ready = subscription_ready(subscription) != 0;
+            subscription_drop(subscription);
+            if !ready {
+                return Read::Cancelled;
+            }
+        }
+        self.finish_read()
+    }
+
+    fn finish_read(&mut self) -> Read {
+        let pending = self.pending.take().unwrap();
+        let handle = self.handle();
+        unsafe {
+            subscription_drop(pending.subscription);
+            let mut ret = [ptr::null_mut::<u8>(); 2];
+            stream_read_result(handle, ret.as_mut_ptr().cast());
+            rtdebug!("stream_obj.read_result({handle:?}) = {ret:?}");
+            if *ret.as_ptr().cast::<u8>() == 0 {
+                self.done = true;
+                return Read::Dropped;
+            }
+            let buffer = ret[1];
+            let count = buffer_get_size(buffer) as usize;
+            buffer_drop(buffer);
+            Read::Values(Received {
+                ptr: pending.ptr,
+                count,
+                _alloc: pending.alloc,
+            })
+        }
+    }
+
+    /// Tells the writer that no more reads are coming and drops the stream
+    /// object, returning the values of a read which was still pending.
+    pub fn close(&mut self) -> Option<Received> {
+        let handle = self.handle();
+        if handle.is_null() {
+            return None;
+        }
+        let received = match self.cancel() {
+            Read::Values(values) => Some(values),
+            Read::Dropped | Read::Cancelled => None,
+        };
+        self.handle.store(ptr::null_mut(), Relaxed);
+        unsafe {
+            if let Some(pending) = self.pending.take() {
+                // The writer is still writing into the buffer, so it's leaked.
+                if let Some(alloc) = pending.alloc {
+                    alloc.forget();
+                }
+                subscription_drop(pending.subscription);
+            } else if stream_is_write_closed(handle) == 0 {
+                stream_start_reading(handle, buffer_new(ptr::NonNull::dangling().as_ptr(), 0));
+            }
+            stream_drop(handle);
+        }
+        received
+    }
+}
+
+impl Drop for Reader {
+    fn drop(&mut self) {
+        // Any values are leaked here, the typed readers close the stream
+        // themselves to drop them.
+        let _ = self.close();
+    }
+}
+
+pub(super) struct Writer {
+    /// The stream object, or null once it's been moved elsewhere.
+    handle: *mut u8,
+    done: bool,
+}
+
+impl Writer {
+    /// # Safety
+    ///
+    /// `handle` must be an owned handle to the writing end of a stream
+    /// object.
+    pub unsafe fn new(handle: *mut u8) -> Writer {
+        Writer {
+            handle,
+            done: false,
+        }
+    }
+
+    pub fn handle(&self) -> *mut u8 {
+        self.handle
+    }
+
+    /// Moves the stream object out of `self`, leaving `self` inert.
+    pub fn take(&mut self) -> Writer {
+        Writer {
+            handle: std::mem::replace(&mut self.handle, ptr::null_mut()),
+            done: self.done,
+        }
+    }
+
+    /// Whether the reader is known to be gone.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Waits with `wait` until the reader has started a read.
+    pub fn poll_ready(&mut self, wait: &mut Option<WaitOn>, cx: &mut Context<'_>) -> Poll<()> {
+        loop {
+            if let Some(w) = wait {
+                match Pin::new(w).poll(cx) {
+                    Poll::Ready(()) => *wait = None,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            unsafe {
+                if stream_is_ready_to_write(self.handle) != 0 {
+                    return Poll::Ready(());
+                }
+                let subscription = stream_write_ready_subscribe(self.handle);
+                subscription_reset(subscription);
+                // The read may have started before the subscription was reset.
+                if stream_is_ready_to_write(self.handle) != 0 {
+                    subscription_drop(subscription);
+                    return Poll::Ready(());
+                }
+                *wait = Some(wait_on(subscription));
+            }
+        }
+    }
+
+    /// Hands over up to `len` lowered values of `layout` at `src` to the
+    /// reader, returning how many were handed over or `None` if the reader is
+    /// gone.
+    ///
+    /// # Safety
+    ///
+    /// The stream must be ready to write, see `poll_ready`, and `src` must
+    /// point to `len` values, ownership of the ones handed over passing to
+    /// the reader.
+    pub unsafe fn write(&mut self, src: *const u8, len: usize, layout: Layout) -> Option<usize> {
+        let buffer = stream_start_writing(self.handle);
+        let capacity = buffer_capacity(buffer) as usize;
+        if capacity == 0 {
+            buffer_drop(buffer);
+            self.done = true;
+            return None;
+        }
+        let count = capacity.min(len);
+        let bytes = layout.size() * count;
+        if bytes > 0 {
+            ptr::copy_nonoverlapping(src, buffer_get_address(buffer), bytes);
+        }
+        buffer_set_size(buffer, count as i64);
+        stream_finish_writing(self.handle, 1, buffer);
+        rtdebug!("stream_obj.finish_writing({:?}, {count})", self.handle);
+        Some(count)
+    }
+}
+
+impl Drop for Writer {
+    fn drop(&mut self) {
+        if self.handle.is_null() {
+            return;
+        }
+        unsafe {
+            if stream_is_write_closed(self.handle) == 0 {
+                rtdebug!("stream_obj.finish_writing({:?}, EOF)", self.handle);
+                stream_finish_writing(self.handle, 0, ptr::null_mut());
+            }
+            stream_drop(self.handle);
+        }
+    }
+}
+
+#[cfg_attr(not(test), link(name = "symmetric_stream"))]
+#[link(wasm_import_module = "symmetric:runtime/symmetric-stream@0.2.1")]
+extern "C" {
+    #[cfg_attr(target_arch = "wasm32", link_name = "[constructor]stream-obj")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BconstructorX5Dstream_obj"
+    )]
+    fn stream_new() -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]stream-obj.clone")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Eclone"
+    )]
+    fn stream_clone(stream: *mut u8) -> *mut u8;
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[method]stream-obj.is-write-closed"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Eis_write_closed"
+    )]
+    fn stream_is_write_closed(stream: *mut u8) -> i32;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]stream-obj.start-reading")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Estart_reading"
+    )]
+    fn stream_start_reading(stream: *mut u8, buffer: *mut u8);
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[method]stream-obj.read-ready-subscribe"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Eread_ready_subscribe"
+    )]
+    fn stream_read_ready_subscribe(stream: *mut u8) -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]stream-obj.read-result")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Eread_result"
+    )]
+    fn stream_read_result(stream: *mut u8, ret: *mut u8);
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[method]stream-obj.is-ready-to-write"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Eis_ready_to_write"
+    )]
+    fn stream_is_ready_to_write(stream: *mut u8) -> i32;
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[method]stream-obj.write-ready-subscribe"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Ewrite_ready_subscribe"
+    )]
+    fn stream_write_ready_subscribe(stream: *mut u8) -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]stream-obj.start-writing")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Estart_writing"
+    )]
+    fn stream_start_writing(stream: *mut u8) -> *mut u8;
+    #[cfg_attr(
+        target_arch = "wasm32",
+        link_name = "[method]stream-obj.finish-writing"
+    )]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5Dstream_objX2Efinish_writing"
+    )]
+    fn stream_finish_writing(stream: *mut u8, is_some: i32, buffer: *mut u8);
+    #[cfg_attr(target_arch = "wasm32", link_name = "[resource-drop]stream-obj")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5Bresource_dropX5Dstream_obj"
+    )]
+    fn stream_drop(stream: *mut u8);
+    #[cfg_attr(target_arch = "wasm32", link_name = "[constructor]buffer")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BconstructorX5Dbuffer"
+    )]
+    fn buffer_new(address: *mut u8, capacity: i64) -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]buffer.get-address")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5DbufferX2Eget_address"
+    )]
+    fn buffer_get_address(buffer: *mut u8) -> *mut u8;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]buffer.get-size")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5DbufferX2Eget_size"
+    )]
+    fn buffer_get_size(buffer: *mut u8) -> i64;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]buffer.set-size")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5DbufferX2Eset_size"
+    )]
+    fn buffer_set_size(buffer: *mut u8, size: i64);
+    #[cfg_attr(target_arch = "wasm32", link_name = "[method]buffer.capacity")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5BmethodX5DbufferX2Ecapacity"
+    )]
+    fn buffer_capacity(buffer: *mut u8) -> i64;
+    #[cfg_attr(target_arch = "wasm32", link_name = "[resource-drop]buffer")]
+    #[cfg_attr(
+        not(target_arch = "wasm32"),
+        link_name = "symmetricX3AruntimeX2Fsymmetric_streamX400X2E2X2E1X00X5Bresource_dropX5Dbuffer"
+    )]
+    fn buffer_drop(buffer: *mut u8);
+}
@@ -0,0 +1,349 @@
+//! Streams in the symmetric ABI, with the same API as the canonical ABI's.
+//!
+//! Values are handed over by copying them in their lowered form into the
+//! reader's buffer, which moves ownership of any lists and resources in them
+//! to the reader.
+
+use super::stream_obj::{self, Read, Reader, Received, Writer};
+use super::WaitOn;
+use crate::async_support::AbiBuffer;
+use std::alloc::Layout;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec::Vec;
+
+/// Operations that a stream requires throughout the implementation.
+///
+/// This is generated by `wit_bindgen::generate!` primarily.
+#[doc(hidden)]
+pub struct StreamVtable<T> {
+    /// The in-memory layout of a single lowered value of `T`.
+    pub layout: Layout,
+
+    /// An optional callback which lowers an owned `T` value into the `dst`
+    /// pointer, passing ownership of all of `T`'s lists and resources to
+    /// `dst`.
+    ///
+    /// If this is `None` then `T` has the same layout in-memory in Rust as it
+    /// does lowered, and values are copied as-is.
+    pub lower: Option<unsafe fn(value: T, dst: *mut u8)>,
+
+    /// Dual of `lower`.
+    pub lift: Option<unsafe fn(dst: *mut u8) -> T>,
+}
+
+/// Helper function to create a new read/write pair for a `symmetric-stream`
+/// stream.
+pub fn stream_new<T>(vtable: &'static StreamVtable<T>) -> (StreamWriter<T>, StreamReader<T>) {
+    let (writer, reader) = stream_obj::new();
+    unsafe {
+        (
+            StreamWriter::new(writer, vtable),
+            StreamReader::new(reader, vtable),
+        )
+    }
+}
+
+/// Represents the writable end of a `symmetric-stream` stream.
+pub struct StreamWriter<T: 'static> {
+    obj: Writer,
+    vtable: &'static StreamVtable<T>,
+}
+
+impl<T> StreamWriter<T> {
+    #[doc(hidden)]
+    pub unsafe fn new(handle: *mut u8, vtable: &'static StreamVtable<T>) -> Self {
+        Self {
+            obj: Writer::new(handle),
+            vtable,
+        }
+    }
+
+    /// Initiate a write of the `values` provided into this stream.
+    ///
+    /// This performs at most a single write of the `values` provided, which
+    /// completes once the reader has started a read. See the canonical ABI's
+    /// `StreamWriter::write` for the return values.
+    ///
+    /// # Cancellation
+    ///
+    /// The returned [`StreamWrite`] future can be cancelled via `drop`, which
+    /// loses `values`, or via [`StreamWrite::cancel`], which yields them back.
+    /// Writes happen all at once, so a write that's cancelled hasn't sent any
+    /// values.
+    pub fn write(&mut self, values: Vec<T>) -> StreamWrite<'_, T> {
+        self.write_buf(AbiBuffer::new(values, self.vtable))
+    }
+
+    /// Same as [`StreamWriter::write`], except this takes [`AbiBuffer<T>`]
+    /// instead of `Vec<T>`.
+    pub fn write_buf(&mut self, values: AbiBuffer<T>) -> StreamWrite<'_, T> {
+        StreamWrite {
+            writer: self,
+            buf: Some(values),
+            wait: None,
+        }
+    }
+
+    /// Writes all of the `values` provided into this stream.
+    ///
+    /// Upon completion the same vector will be returned and any remaining
+    /// elements in the vector were not sent because the stream was dropped.
+    pub async fn write_all(&mut self, values: Vec<T>) -> Vec<T> {
+        let (mut status, mut buf) = self.write(values).await;
+        while let StreamResult::Complete(_) = status {
+            if buf.remaining() == 0 {
+                break;
+            }
+            (status, buf) = self.write_buf(buf).await;
+        }
+        assert!(buf.remaining() == 0 || matches!(status, StreamResult::Dropped));
+        buf.into_vec()
+    }
+
+    /// Writes the singular `value` provided
+    ///
+    /// If the other end hangs up then the value is returned back as
+    /// `Some(value)`, otherwise `None` is returned indicating the value was
+    /// sent.
+    pub async fn write_one(&mut self, value: T) -> Option<T> {
+        self.write_all(std::vec![value]).await.pop()
+    }
+}
+
+impl<T> fmt::Debug for StreamWriter<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamWriter")
+            .field("handle", &self.obj.handle())
+            .finish()
+    }
+}
+
+/// Represents a write operation which may be cancelled prior to completion.
+pub struct StreamWrite<'a, T: 'static> {
+    writer: &'a mut StreamWriter<T>,
+    /// The values to write until the write completes.
+    buf: Option<AbiBuffer<T>>,
+    wait: Option<WaitOn>,
+}
+
+/// Result of a [`StreamWriter::write`] or [`StreamReader::read`] operation,
+/// yielded by the [`StreamWrite`] or [`StreamRead`] futures.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StreamResult {
+    /// The provided number of values were successfully transferred.
+    ///
+    /// For writes this is how many items were written, and for reads this is
+    /// how many items were read.
+    Complete(usize),
+    /// No values were written, the other end has dropped its handle.
+    Dropped,
+    /// No values were written, the operation was cancelled.
+    Cancelled,
+}
+
+// Nothing in a write is pinned structurally.
+impl<T> Unpin for StreamWrite<'_, T> {}
+
+impl<T: 'static> Future for StreamWrite<'_, T> {
+    type Output = (StreamResult, AbiBuffer<T>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = self.get_mut();
+        let buf = me.buf.as_mut().expect("cannot poll after completion");
+        let writer = &mut *me.writer;
+        let result = if writer.obj.is_done() {
+            StreamResult::Dropped
+        } else if buf.remaining() == 0 {
+            StreamResult::Complete(0)
+        } else {
+            if writer.obj.poll_ready(&mut me.wait, cx).is_pending() {
+                return Poll::Pending;
+            }
+            let (ptr, len) = buf.abi_ptr_and_len();
+            // SAFETY: the stream is ready to write and `buf` hands over the
+            // values written.
+            match unsafe { writer.obj.write(ptr, len, writer.vtable.layout) } {
+                Some(amt) => {
+                    buf.advance(amt);
+                    StreamResult::Complete(amt)
+                }
+                None => StreamResult::Dropped,
+            }
+        };
+        Poll::Ready((result, me.buf.take().unwrap()))
+    }
+}
+
+impl<T: 'static> StreamWrite<'_, T> {
+    /// Cancel this write if it hasn't already completed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation has already been completed via `Future::poll`,
+    /// or if this method is called twice.
+    pub fn cancel(self: Pin<&mut Self>) -> (StreamResult, AbiBuffer<T>) {
+        let me = self.get_mut();
+        me.wait = None;
+        let buf = me.buf.take().expect("cannot cancel after completion");
+        (StreamResult::Cancelled, buf)
+    }
+}
+
+/// Represents the readable end of a `symmetric-stream` stream.
+pub struct StreamReader<T: 'static> {
+    obj: Reader,
+    vtable: &'static StreamVtable<T>,
+}
+
+impl<T> fmt::Debug for StreamReader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamReader")
+            .field("handle", &self.obj.handle())
+            .finish()
+    }
+}
+
+impl<T> StreamReader<T> {
+    #[doc(hidden)]
+    pub unsafe fn new(handle: *mut u8, vtable: &'static StreamVtable<T>) -> Self {
+        Self {
+            obj: Reader::new(handle),
+            vtable,
+        }
+    }
+
+    #[doc(hidden)]
+    pub fn take_handle(&self) -> *mut u8 {
+        self.obj.take_handle()
+    }
+
+    /// Starts a new read operation on this stream into `buf`.
+    ///
+    /// This method will read values into the spare capacity of the `buf`
+    /// provided. If `buf` has no spare capacity then the read completes
+    /// right away without reading anything.
+    ///
+    /// # Cancellation
+    ///
+    /// Cancelling the returned future can be done with `drop` like all Rust
+    /// futures, in which case the read stays pending and its values are
+    /// yielded by the next read. To accurately determine if values were read
+    /// the [`StreamRead::cancel`] method must be used.
+    pub fn read(&mut self, buf: Vec<T>) -> StreamRead<'_, T> {
+        StreamRead {
+            reader: self,
+            buf: Some(buf),
+            wait: None,
+        }
+    }
+
+    /// Reads a single item from this stream.
+    pub async fn next(&mut self) -> Option<T> {
+        let (_result, mut buf) = self.read(Vec::with_capacity(1)).await;
+        buf.pop()
+    }
+
+    /// Reads all items from this stream and returns the list.
+    pub async fn collect(mut self) -> Vec<T> {
+        let mut ret = Vec::new();
+        loop {
+            if ret.len() == ret.capacity() {
+                ret.reserve(1);
+            }
+            let (status, buf) = self.read(ret).await;
+            ret = buf;
+            match status {
+                StreamResult::Complete(_) => {}
+                StreamResult::Dropped => break,
+                StreamResult::Cancelled => unreachable!(),
+            }
+        }
+        ret
+    }
+
+    /// Lifts the values in `received` and appends them to `buf`.
+    fn take(&self, received: Received, buf: &mut Vec<T>) -> StreamResult {
+        buf.reserve(received.count);
+        let mut ptr = received.ptr;
+        for _ in 0..received.count {
+            // SAFETY: `received` owns the lowered values.
+            unsafe {
+                buf.push(match self.vtable.lift {
+                    Some(lift) => lift(ptr),
+                    None => ptr.cast::<T>().read(),
+                });
+                ptr = ptr.add(self.vtable.layout.size());
+            }
+        }
+        StreamResult::Complete(received.count)
+    }
+}
+
+impl<T> Drop for StreamReader<T> {
+    fn drop(&mut self) {
+        if let Some(received) = self.obj.close() {
+            self.take(received, &mut Vec::new());
+        }
+    }
+}
+
+/// Represents a read operation which may be cancelled prior to completion.
+pub struct StreamRead<'a, T: 'static> {
+    reader: &'a mut StreamReader<T>,
+    /// The buffer to read into until the read completes.
+    buf: Option<Vec<T>>,
+    wait: Option<WaitOn>,
+}
+
+// Nothing in a read is pinned structurally.
+impl<T> Unpin for StreamRead<'_, T> {}
+
+impl<T: 'static> Future for StreamRead<'_, T> {
+    type Output = (StreamResult, Vec<T>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let me = self.get_mut();
+        let buf = me.buf.as_mut().expect("cannot poll after completion");
+        let reader = &mut *me.reader;
+        let capacity = buf.capacity() - buf.len();
+        let result = match reader
+            .obj
+            .poll_read(&mut me.wait, reader.vtable.layout, capacity, cx)
+        {
+            Poll::Ready(Read::Values(received)) => reader.take(received, buf),
+            Poll::Ready(Read::Dropped) => StreamResult::Dropped,
+            Poll::Ready(Read::Cancelled) => StreamResult::Cancelled,
+            Poll::Pending => return Poll::Pending,
+        };
+        Poll::Ready((result, me.buf.take().unwrap()))
+    }
+}
+
+impl<T> StreamRead<'_, T> {
+    /// Cancel this read if it hasn't already completed.
+    ///
+    /// This may race with the writer and so this may actually complete with
+    /// some results.
+    ///
+    /// The final result of cancellation is returned, along with the original
+    /// buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operation has already been completed via `Future::poll`,
+    /// or if this method is called twice.
+    pub fn cancel(self: Pin<&mut Self>) -> (StreamResult, Vec<T>) {
+        let me = self.get_mut();
+        me.wait = None;
+        let mut buf = me.buf.take().expect("cannot cancel after completion");
+        let result = match me.reader.obj.cancel() {
+            Read::Values(received) => me.reader.take(received, &mut buf),
+            Read::Dropped => StreamResult::Dropped,
+            Read::Cancelled => StreamResult::Cancelled,
+        };
+        (result, buf)
+    }
+}
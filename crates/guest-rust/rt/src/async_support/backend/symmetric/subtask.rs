@@ -0,0 +1,86 @@
+//! Calls to async imports in the symmetric ABI.
+//!
+//! An async import either finishes right away or returns an event
+//! subscription which becomes active once it has. It borrows its parameters
+//! until then, so they're owned by the call like in the canonical ABI.
+
+use super::wait_on;
+use crate::Cleanup;
+use std::alloc::Layout;
+use std::future::Future;
+use std::mem::ManuallyDrop;
+
+/// Raw operations used to invoke an imported asynchronous function.
+///
+/// This trait is implemented by generated bindings and is used to implement
+/// asynchronous imports.
+///
+/// # Unsafety
+///
+/// All operations/constants must be self-consistent for how this module expects
+/// them all to be used.
+pub unsafe trait Subtask {
+    /// The in-memory layout of both parameters and results allocated with
+    /// parameters coming first.
+    const ABI_LAYOUT: Layout;
+
+    /// The offset, in bytes, from the start of `ABI_LAYOUT` to where the
+    /// results will be stored.
+    const RESULTS_OFFSET: usize;
+
+    /// The parameters to this task.
+    type Params;
+    /// The representation of lowered parameters for this task.
+    type ParamsLower: Copy;
+    /// The results of this task.
+    type Results;
+
+    /// The raw function import, returning the event subscription to wait on
+    /// or null if the call has already finished.
+    unsafe fn call_import(params: Self::ParamsLower, results: *mut u8) -> *mut u8;
+
+    /// Bindings-generated version of lowering `params`.
+    ///
+    /// This may use the heap-allocated `dst`, which is an uninitialized
+    /// allocation of `Self::ABI_LAYOUT`.
+    unsafe fn params_lower(params: Self::Params, dst: *mut u8) -> Self::ParamsLower;
+
+    /// Bindings-generated version of deallocating any lists stored within
+    /// `lower`.
+    unsafe fn params_dealloc_lists(lower: Self::ParamsLower);
+
+    /// Bindings-generated version of deallocating not only owned lists within
+    /// `lower` but also deallocating any owned resources.
+    unsafe fn params_dealloc_lists_and_own(lower: Self::ParamsLower);
+
+    /// Bindings-generated version of lifting the results stored at `src`.
+    unsafe fn results_lift(src: *mut u8) -> Self::Results;
+
+    /// Helper function to actually perform this asynchronous call with
+    /// `params`.
+    fn call(params: Self::Params) -> impl Future<Output = Self::Results>
+    where
+        Self: Sized,
+    {
+        async {
+            unsafe {
+                // The callee may still be using the parameters and writing
+                // the results if this future is dropped before the call
+                // finishes, in which case they're leaked.
+                let (ptr_params, cleanup) = Cleanup::new(Self::ABI_LAYOUT);
+                let cleanup = ManuallyDrop::new(cleanup);
+                let ptr_results = ptr_params.add(Self::RESULTS_OFFSET);
+                let params_lower = Self::params_lower(params, ptr_params);
+                let subscription = Self::call_import(params_lower, ptr_results);
+                rtdebug!("<import>({ptr_params:?}, {ptr_results:?}) = {subscription:?}");
+                if !subscription.is_null() {
+                    wait_on(subscription).await;
+                }
+                Self::params_dealloc_lists(params_lower);
+                let results = Self::results_lift(ptr_results);
+                drop(ManuallyDrop::into_inner(cleanup));
+                results
+            }
+        }
+    }
+}
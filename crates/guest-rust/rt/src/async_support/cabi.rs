@@ -62,10 +62,12 @@ extern "C" {
     pub fn wasip3_task_set(ptr: *mut wasip3_task) -> *mut wasip3_task;
 }
 
+/// Outside of wasm, where the symmetric ABI is used, there's no C definition
+/// to link against so the pointer is stored here instead.
 #[cfg(not(target_family = "wasm"))]
 pub unsafe extern "C" fn wasip3_task_set(ptr: *mut wasip3_task) -> *mut wasip3_task {
-    let _ = ptr;
-    unreachable!();
+    static mut TASK: *mut wasip3_task = core::ptr::null_mut();
+    core::mem::replace(&mut TASK, ptr)
 }
 
 /// The first version of `wasip3_task` which implies the existence of the
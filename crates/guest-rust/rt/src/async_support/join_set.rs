@@ -0,0 +1,258 @@
+//! Structured collections of concurrently running futures.
+//!
+//! Unlike [`spawn`](super::spawn) the futures managed here are owned by a
+//! value on the stack of the task that created them. They make progress while
+//! that task polls the collection, and they're dropped, and thus cancelled,
+//! when the collection is dropped. Dropping a future which is blocked on an
+//! async import or a future/stream operation cancels that operation in the
+//! component model, so no work started here outlives its owner.
+
+use std::boxed::Box;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::future::{poll_fn, Future};
+use std::mem;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use std::vec::Vec;
+
+use futures::stream::{FuturesUnordered, StreamExt};
+
+type LocalBoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// A set of futures whose results are collected in completion order.
+///
+/// Futures are added with [`JoinSet::spawn`] and their results are retrieved
+/// with [`JoinSet::join_next`]. Futures only make progress while
+/// `join_next` is being awaited. Dropping the set, or calling
+/// [`JoinSet::cancel_all`], cancels everything still in it.
+///
+/// A set created with [`JoinSet::bounded`] runs at most a fixed number of
+/// futures at once. Any others are queued without being polled, so for
+/// example an async import queued in a bounded set isn't called until a slot
+/// frees up.
+pub struct JoinSet<T> {
+    running: FuturesUnordered<LocalBoxFuture<'static, T>>,
+    queued: VecDeque<LocalBoxFuture<'static, T>>,
+    limit: usize,
+}
+
+impl<T> JoinSet<T> {
+    /// Creates a new empty set which runs all of its futures concurrently.
+    pub fn new() -> JoinSet<T> {
+        JoinSet::bounded(usize::MAX)
+    }
+
+    /// Creates a new empty set which runs at most `limit` futures at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `limit` is zero.
+    pub fn bounded(limit: usize) -> JoinSet<T> {
+        assert!(limit > 0, "a join set must be able to run a future");
+        JoinSet {
+            running: FuturesUnordered::new(),
+            queued: VecDeque::new(),
+            limit,
+        }
+    }
+
+    /// Adds `future` to this set.
+    pub fn spawn(&mut self, future: impl Future<Output = T> + 'static) {
+        self.queued.push_back(Box::pin(future));
+        self.start_queued();
+    }
+
+    /// Returns the number of futures in this set, including queued ones.
+    pub fn len(&self) -> usize {
+        self.running.len() + self.queued.len()
+    }
+
+    /// Returns whether this set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Waits for the next future in this set to complete and returns its
+    /// result, or `None` if the set is empty.
+    pub async fn join_next(&mut self) -> Option<T> {
+        poll_fn(|cx| self.poll_join_next(cx)).await
+    }
+
+    /// Waits for every future in this set to complete, returning their results
+    /// in completion order.
+    pub async fn join_all(mut self) -> Vec<T> {
+        let mut results = Vec::with_capacity(self.len());
+        while let Some(result) = self.join_next().await {
+            results.push(result);
+        }
+        results
+    }
+
+    /// Polls for the next future in this set to complete.
+    ///
+    /// This is the poll-based version of [`JoinSet::join_next`].
+    pub fn poll_join_next(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let result = self.running.poll_next_unpin(cx);
+        if let Poll::Ready(Some(_)) = result {
+            self.start_queued();
+        }
+        result
+    }
+
+    /// Cancels every future in this set by dropping it, leaving the set empty.
+    pub fn cancel_all(&mut self) {
+        self.queued.clear();
+        self.running.clear();
+    }
+
+    /// Moves queued futures into the running set while there's room.
+    ///
+    /// Futures moved here are polled by the next call to `poll_join_next`.
+    fn start_queued(&mut self) {
+        while self.running.len() < self.limit {
+            match self.queued.pop_front() {
+                Some(future) => self.running.push(future),
+                None => break,
+            }
+        }
+    }
+}
+
+impl<T> Default for JoinSet<T> {
+    fn default() -> JoinSet<T> {
+        JoinSet::new()
+    }
+}
+
+/// A scope for background futures which may borrow from their surroundings.
+///
+/// Futures are added with [`Scope::spawn`], which only needs a shared
+/// reference, so the body passed to [`Scope::run`] may spawn more futures into
+/// the scope it's running in. They make progress while `run` or
+/// [`Scope::join`] is being awaited, and every future left in the scope is
+/// cancelled when it's dropped.
+///
+/// ```ignore
+/// let scope = Scope::new();
+/// let answer = scope
+///     .run(async {
+///         scope.spawn(report_progress(&state));
+///         compute(&state).await
+///     })
+///     .await;
+/// // `report_progress` is cancelled here if it's still running.
+/// drop(scope);
+/// ```
+pub struct Scope<'a> {
+    /// Futures which have been polled, or are about to be. These are moved
+    /// out while `poll_tasks` polls them.
+    tasks: RefCell<FuturesUnordered<LocalBoxFuture<'a, ()>>>,
+    /// Futures spawned since `poll_tasks` last moved them into `tasks`.
+    spawned: RefCell<Vec<LocalBoxFuture<'a, ()>>>,
+    /// Number of futures moved out of `tasks` by `poll_tasks`.
+    polling: Cell<usize>,
+    /// Set by `cancel_all` so `poll_tasks` drops the futures it moved out.
+    cancelled: Cell<bool>,
+}
+
+impl<'a> Scope<'a> {
+    /// Creates a new empty scope.
+    pub fn new() -> Scope<'a> {
+        Scope {
+            tasks: RefCell::new(FuturesUnordered::new()),
+            spawned: RefCell::new(Vec::new()),
+            polling: Cell::new(0),
+            cancelled: Cell::new(false),
+        }
+    }
+
+    /// Adds `future` to this scope.
+    pub fn spawn(&self, future: impl Future<Output = ()> + 'a) {
+        self.spawned.borrow_mut().push(Box::pin(future));
+    }
+
+    /// Returns the number of futures in this scope which haven't completed.
+    pub fn len(&self) -> usize {
+        self.tasks.borrow().len() + self.spawned.borrow().len() + self.polling.get()
+    }
+
+    /// Returns whether this scope has no futures left.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Runs `body` to completion while also driving the futures in this
+    /// scope.
+    ///
+    /// Futures which haven't completed by the time `body` does are left in the
+    /// scope, to be driven by a later call to `run` or `join` or cancelled
+    /// when the scope is dropped.
+    pub async fn run<F: Future>(&self, body: F) -> F::Output {
+        let mut body = pin!(body);
+        poll_fn(|cx| {
+            if let Poll::Ready(result) = body.as_mut().poll(cx) {
+                return Poll::Ready(result);
+            }
+            let _ = self.poll_tasks(cx);
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Waits for every future in this scope to complete.
+    pub async fn join(&self) {
+        poll_fn(|cx| self.poll_tasks(cx)).await
+    }
+
+    /// Cancels every future in this scope by dropping it.
+    ///
+    /// When called from one of the scope's own futures that future is
+    /// dropped too, as soon as it next yields.
+    pub fn cancel_all(&self) {
+        self.spawned.borrow_mut().clear();
+        self.tasks.borrow_mut().clear();
+        if self.polling.get() > 0 {
+            self.cancelled.set(true);
+        }
+    }
+
+    fn poll_tasks(&self, cx: &mut Context<'_>) -> Poll<()> {
+        // The futures are moved out while they're polled so that they can
+        // spawn into, or cancel, this scope themselves.
+        let mut tasks = mem::take(&mut *self.tasks.borrow_mut());
+        let result = loop {
+            tasks.extend(self.spawned.borrow_mut().drain(..));
+            self.polling.set(tasks.len());
+            let result = tasks.poll_next_unpin(cx);
+            if self.cancelled.replace(false) {
+                tasks.clear();
+            }
+            self.polling.set(0);
+            if !self.spawned.borrow().is_empty() {
+                continue;
+            }
+            match result {
+                // A future completed, keep going to see if more have.
+                Poll::Ready(Some(())) => {}
+                Poll::Ready(None) => break Poll::Ready(()),
+                Poll::Pending if tasks.is_empty() => break Poll::Ready(()),
+                Poll::Pending => break Poll::Pending,
+            }
+        };
+        let mut slot = self.tasks.borrow_mut();
+        if slot.is_empty() {
+            *slot = tasks;
+        } else {
+            // A future polled `run` or `join` on this scope itself.
+            slot.extend(tasks);
+        }
+        result
+    }
+}
+
+impl Default for Scope<'_> {
+    fn default() -> Self {
+        Scope::new()
+    }
+}
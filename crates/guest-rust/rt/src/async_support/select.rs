@@ -0,0 +1,181 @@
+//! Racing asynchronous operations against each other.
+//!
+//! Dropping a future which is blocked on a component model operation already
+//! cancels that operation, but the result of the cancellation is lost in the
+//! process. For example a dropped [`FutureRead`] may have actually received a
+//! value, or a dropped [`StreamWrite`] may have transferred some of its items.
+//! The [`select`] function here instead cancels the losing operation through
+//! its `cancel` method and hands back whatever the cancellation produced.
+
+use crate::async_support::{
+    AbiBuffer, FutureRead, FutureReader, FutureWrite, FutureWriteCancel, StreamRead, StreamResult,
+    StreamWrite,
+};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec::Vec;
+
+/// A [`Future`] which can be synchronously cancelled while it's in flight.
+///
+/// This is implemented for the read and write operations on futures and
+/// streams as well as for [`CancelOnDrop`], which adapts any other future such
+/// as calls to async imports.
+pub trait Cancelable: Future {
+    /// What's produced when this operation is cancelled.
+    type Cancel;
+
+    /// Cancels this operation, returning what happened to it.
+    ///
+    /// # Panics
+    ///
+    /// Implementations may panic if this is called after the operation has
+    /// completed or if it's called twice.
+    fn cancel(self: Pin<&mut Self>) -> Self::Cancel;
+}
+
+impl<T: 'static> Cancelable for FutureRead<T> {
+    type Cancel = Result<T, FutureReader<T>>;
+
+    fn cancel(self: Pin<&mut Self>) -> Self::Cancel {
+        FutureRead::cancel(self)
+    }
+}
+
+impl<T: 'static> Cancelable for FutureWrite<T> {
+    type Cancel = FutureWriteCancel<T>;
+
+    fn cancel(self: Pin<&mut Self>) -> Self::Cancel {
+        FutureWrite::cancel(self)
+    }
+}
+
+impl<T: 'static> Cancelable for StreamRead<'_, T> {
+    type Cancel = (StreamResult, Vec<T>);
+
+    fn cancel(self: Pin<&mut Self>) -> Self::Cancel {
+        StreamRead::cancel(self)
+    }
+}
+
+impl<T: 'static> Cancelable for StreamWrite<'_, T> {
+    type Cancel = (StreamResult, AbiBuffer<T>);
+
+    fn cancel(self: Pin<&mut Self>) -> Self::Cancel {
+        StreamWrite::cancel(self)
+    }
+}
+
+/// Adapter returned by [`cancel_on_drop`].
+pub struct CancelOnDrop<F> {
+    future: Option<F>,
+}
+
+/// Adapts `future` into a [`Cancelable`] which is cancelled by dropping it.
+///
+/// This is intended for futures which have no `cancel` method of their own,
+/// such as those returned by async imports. Dropping such a future cancels any
+/// subtask it has in flight with `subtask.cancel`, and likewise for any
+/// future or stream operations it's blocked on.
+pub fn cancel_on_drop<F: Future>(future: F) -> CancelOnDrop<F> {
+    CancelOnDrop {
+        future: Some(future),
+    }
+}
+
+impl<F> CancelOnDrop<F> {
+    fn pin_project(self: Pin<&mut Self>) -> Pin<&mut Option<F>> {
+        // SAFETY: we've chosen that when `Self` is pinned that it translates to
+        // always pinning the inner field, so that's codified here.
+        unsafe { Pin::new_unchecked(&mut self.get_unchecked_mut().future) }
+    }
+}
+
+impl<F: Future> Future for CancelOnDrop<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        match self.pin_project().as_pin_mut() {
+            Some(future) => future.poll(cx),
+            None => panic!("cannot poll after cancelling"),
+        }
+    }
+}
+
+impl<F: Future> Cancelable for CancelOnDrop<F> {
+    type Cancel = ();
+
+    fn cancel(self: Pin<&mut Self>) {
+        // Note that `Pin::set` runs the destructor of the future in-place,
+        // upholding the pin guarantee.
+        self.pin_project().set(None);
+    }
+}
+
+/// The result of [`select`].
+pub enum Selected<A: Cancelable, B: Cancelable> {
+    /// The first operation completed and the second was cancelled.
+    First(A::Output, B::Cancel),
+    /// The second operation completed and the first was cancelled.
+    Second(A::Cancel, B::Output),
+}
+
+/// Future returned by [`select`].
+pub struct Select<A, B> {
+    a: A,
+    b: B,
+    done: bool,
+}
+
+/// Waits for whichever of `a` or `b` completes first, then cancels the other.
+///
+/// The loser is cancelled through [`Cancelable::cancel`] before this future
+/// resolves, so its cancellation result is returned alongside the winner's
+/// output. If both operations are ready at the same time then `a` wins.
+///
+/// Timeouts can be built from this by racing an operation against an imported
+/// timer, for example `wasi:clocks`' `wait-for`, wrapped in [`cancel_on_drop`].
+pub fn select<A, B>(a: A, b: B) -> Select<A, B>
+where
+    A: Cancelable,
+    B: Cancelable,
+{
+    Select { a, b, done: false }
+}
+
+impl<A, B> Select<A, B> {
+    fn pin_project(self: Pin<&mut Self>) -> (Pin<&mut A>, Pin<&mut B>, &mut bool) {
+        // SAFETY: we've chosen that when `Self` is pinned that it translates to
+        // always pinning the `a` and `b` fields, so that's codified here.
+        unsafe {
+            let me = self.get_unchecked_mut();
+            (
+                Pin::new_unchecked(&mut me.a),
+                Pin::new_unchecked(&mut me.b),
+                &mut me.done,
+            )
+        }
+    }
+}
+
+impl<A, B> Future for Select<A, B>
+where
+    A: Cancelable,
+    B: Cancelable,
+{
+    type Output = Selected<A, B>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let (mut a, b, done) = self.pin_project();
+        assert!(!*done, "cannot poll after completion");
+        if let Poll::Ready(a) = a.as_mut().poll(cx) {
+            *done = true;
+            return Poll::Ready(Selected::First(a, b.cancel()));
+        }
+        if let Poll::Ready(b) = b.poll(cx) {
+            *done = true;
+            return Poll::Ready(Selected::Second(a.cancel(), b));
+        }
+        Poll::Pending
+    }
+}
@@ -0,0 +1,541 @@
+//! Adapters from [`StreamReader`] and [`StreamWriter`] to the `Stream`,
+//! `Sink`, `AsyncRead` and `AsyncWrite` traits of the `futures` crate.
+//!
+//! The read and write operations on streams borrow their reader or writer for
+//! as long as they're in flight, which poll-based traits have no way to
+//! express. The adapters here instead own their reader or writer and move it
+//! into each operation as it's started, getting it back once the operation
+//! completes. Dropping an adapter cancels whatever operation it has in flight.
+//!
+//! Reads are performed directly into the spare capacity of a buffer owned by
+//! the adapter, so when `T` has the same representation in Rust as in the
+//! canonical ABI, such as for `stream<u8>`, values are never copied after the
+//! host writes them. Writes go through [`AbiBuffer`](super::AbiBuffer) which
+//! tracks partial writes until everything has been sent.
+
+use crate::async_support::{StreamReader, StreamResult, StreamWriter};
+use futures::io::{AsyncBufRead, AsyncRead, AsyncWrite};
+use futures::{ready, Sink, Stream};
+use std::boxed::Box;
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec::Vec;
+
+/// Default number of values read or written at a time by the adapters over
+/// streams of arbitrary values.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Default number of bytes read or written at a time by the byte adapters.
+const DEFAULT_BYTE_CAPACITY: usize = 8 * 1024;
+
+type Op<T> = Pin<Box<dyn Future<Output = T>>>;
+
+/// Owns a [`StreamReader`] and threads it through successive reads.
+struct Reader<T: 'static> {
+    state: ReaderState<T>,
+}
+
+enum ReaderState<T: 'static> {
+    Idle(StreamReader<T>),
+    Reading(Op<(StreamReader<T>, StreamResult, Vec<T>)>),
+    Done,
+}
+
+impl<T> Reader<T> {
+    fn new(reader: StreamReader<T>) -> Reader<T> {
+        Reader {
+            state: ReaderState::Idle(reader),
+        }
+    }
+
+    /// Polls a read into the spare capacity of the buffer returned by `start`.
+    ///
+    /// `start` is only called when a new read is started and must return an
+    /// empty vector with some spare capacity. The vector is returned back once
+    /// the read completes, or `None` is returned if the writable end of the
+    /// stream was dropped.
+    fn poll_read(
+        &mut self,
+        cx: &mut Context<'_>,
+        start: impl FnOnce() -> Vec<T>,
+    ) -> Poll<Option<Vec<T>>> {
+        if let ReaderState::Idle(_) = self.state {
+            let ReaderState::Idle(mut reader) = mem::replace(&mut self.state, ReaderState::Done)
+            else {
+                unreachable!()
+            };
+            let buf = start();
+            debug_assert!(buf.is_empty() && buf.capacity() > 0);
+            self.state = ReaderState::Reading(Box::pin(async move {
+                let (result, buf) = reader.read(buf).await;
+                (reader, result, buf)
+            }));
+        }
+        let ReaderState::Reading(op) = &mut self.state else {
+            return Poll::Ready(None);
+        };
+        let (reader, result, buf) = ready!(op.as_mut().poll(cx));
+        match result {
+            StreamResult::Complete(_) => {
+                self.state = ReaderState::Idle(reader);
+                Poll::Ready(Some(buf))
+            }
+            StreamResult::Dropped => {
+                self.state = ReaderState::Done;
+                Poll::Ready(None)
+            }
+            StreamResult::Cancelled => unreachable!(),
+        }
+    }
+}
+
+/// Owns a [`StreamWriter`] and threads it through successive writes.
+struct Writer<T: 'static> {
+    state: WriterState<T>,
+}
+
+enum WriterState<T: 'static> {
+    Idle(StreamWriter<T>),
+    Writing(Op<(StreamWriter<T>, StreamResult, super::AbiBuffer<T>)>),
+    Done,
+}
+
+impl<T> Writer<T> {
+    fn new(writer: StreamWriter<T>) -> Writer<T> {
+        Writer {
+            state: WriterState::Idle(writer),
+        }
+    }
+
+    /// Moves `values` into a new write once the write in flight, if any, has
+    /// completed.
+    ///
+    /// With `flush` this doesn't return until everything has been written.
+    /// Otherwise this returns as soon as `values` is empty, either because
+    /// it was already empty or because its contents were moved into a write
+    /// which is now in flight.
+    ///
+    /// Returns `false` if the readable end of the stream was dropped, in which
+    /// case any values not yet written are lost.
+    fn poll_write(&mut self, cx: &mut Context<'_>, values: &mut Vec<T>, flush: bool) -> Poll<bool> {
+        loop {
+            match mem::replace(&mut self.state, WriterState::Done) {
+                WriterState::Idle(mut writer) => {
+                    if values.is_empty() {
+                        self.state = WriterState::Idle(writer);
+                        return Poll::Ready(true);
+                    }
+                    let values = mem::take(values);
+                    self.state = WriterState::Writing(Box::pin(async move {
+                        let (result, buf) = writer.write(values).await;
+                        (writer, result, buf)
+                    }));
+                }
+                WriterState::Writing(mut op) => {
+                    let Poll::Ready((mut writer, result, buf)) = op.as_mut().poll(cx) else {
+                        self.state = WriterState::Writing(op);
+                        if !flush && values.is_empty() {
+                            return Poll::Ready(true);
+                        }
+                        return Poll::Pending;
+                    };
+                    match result {
+                        StreamResult::Dropped => return Poll::Ready(false),
+
+                        // FIXME(WebAssembly/component-model#490)
+                        StreamResult::Complete(_) | StreamResult::Cancelled => {
+                            if buf.remaining() > 0 {
+                                self.state = WriterState::Writing(Box::pin(async move {
+                                    let (result, buf) = writer.write_buf(buf).await;
+                                    (writer, result, buf)
+                                }));
+                                continue;
+                            }
+
+                            // Everything was written, so hang on to the
+                            // allocation for the next write if nothing else
+                            // has been buffered in the meantime.
+                            if values.capacity() == 0 {
+                                *values = buf.into_vec();
+                            }
+                            self.state = WriterState::Idle(writer);
+                        }
+                    }
+                }
+                WriterState::Done => return Poll::Ready(false),
+            }
+        }
+    }
+
+    /// Returns whether nothing more can be written, either because the
+    /// stream was closed or because its readable end was dropped.
+    fn is_done(&self) -> bool {
+        matches!(self.state, WriterState::Done)
+    }
+
+    /// Drops the writable end of the stream, signaling that nothing more will
+    /// be written.
+    fn close(&mut self) {
+        self.state = WriterState::Done;
+    }
+}
+
+/// A [`Stream`] of the values read from a [`StreamReader`].
+///
+/// Created with [`StreamReader::into_stream`].
+pub struct StreamReaderStream<T: 'static> {
+    reader: Reader<T>,
+    buf: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> StreamReaderStream<T> {
+    /// Creates a stream which reads up to `capacity` values at a time from
+    /// `reader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(reader: StreamReader<T>, capacity: usize) -> StreamReaderStream<T> {
+        assert!(capacity > 0, "cannot read zero values at a time");
+        StreamReaderStream {
+            reader: Reader::new(reader),
+            buf: VecDeque::new(),
+            capacity,
+        }
+    }
+}
+
+impl<T> StreamReader<T> {
+    /// Converts this reader into a [`Stream`] of its values.
+    pub fn into_stream(self) -> StreamReaderStream<T> {
+        StreamReaderStream::with_capacity(self, DEFAULT_CAPACITY)
+    }
+}
+
+// Values of `T` are never pinned, only the operation in flight is and that's
+// already behind a `Pin<Box<_>>`.
+impl<T> Unpin for StreamReaderStream<T> {}
+
+impl<T> Stream for StreamReaderStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let me = self.get_mut();
+        loop {
+            if let Some(value) = me.buf.pop_front() {
+                return Poll::Ready(Some(value));
+            }
+            let (buf, capacity) = (&mut me.buf, me.capacity);
+            let read = ready!(me.reader.poll_read(cx, || {
+                // Converting an empty `VecDeque` into a `Vec` reuses its
+                // allocation from the previous read.
+                let mut buf = Vec::from(mem::take(buf));
+                buf.reserve(capacity);
+                buf
+            }));
+            match read {
+                Some(values) => me.buf = VecDeque::from(values),
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.buf.len(), None)
+    }
+}
+
+impl<T> fmt::Debug for StreamReaderStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamReaderStream")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+/// A [`Sink`] of values written to a [`StreamWriter`].
+///
+/// Values are buffered and written up to a fixed number at a time, so the
+/// sink must be flushed or closed for buffered values to be written. Closing
+/// the sink drops the writable end of the stream.
+///
+/// Created with [`StreamWriter::into_sink`].
+pub struct StreamWriterSink<T: 'static> {
+    writer: Writer<T>,
+    buf: Vec<T>,
+    capacity: usize,
+}
+
+impl<T> StreamWriterSink<T> {
+    /// Creates a sink which buffers up to `capacity` values before writing
+    /// them to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(writer: StreamWriter<T>, capacity: usize) -> StreamWriterSink<T> {
+        assert!(capacity > 0, "cannot write zero values at a time");
+        StreamWriterSink {
+            writer: Writer::new(writer),
+            buf: Vec::new(),
+            capacity,
+        }
+    }
+}
+
+impl<T> StreamWriter<T> {
+    /// Converts this writer into a [`Sink`] of values.
+    pub fn into_sink(self) -> StreamWriterSink<T> {
+        StreamWriterSink::with_capacity(self, DEFAULT_CAPACITY)
+    }
+}
+
+// See the `Unpin` implementation for `StreamReaderStream`.
+impl<T> Unpin for StreamWriterSink<T> {}
+
+impl<T> Sink<T> for StreamWriterSink<T> {
+    type Error = StreamDroppedError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let me = self.get_mut();
+        if me.writer.is_done() {
+            return Poll::Ready(Err(StreamDroppedError));
+        }
+        if me.buf.len() < me.capacity {
+            return Poll::Ready(Ok(()));
+        }
+        match ready!(me.writer.poll_write(cx, &mut me.buf, false)) {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Ready(Err(StreamDroppedError)),
+        }
+    }
+
+    fn start_send(self: Pin<&mut Self>, value: T) -> Result<(), Self::Error> {
+        self.get_mut().buf.push(value);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let me = self.get_mut();
+        match ready!(me.writer.poll_write(cx, &mut me.buf, true)) {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Ready(Err(StreamDroppedError)),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        self.writer.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T> fmt::Debug for StreamWriterSink<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamWriterSink")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+/// Error returned by [`StreamWriterSink`] once the readable end of its stream
+/// has been dropped.
+///
+/// Values which were buffered but not yet written when this happened are
+/// discarded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamDroppedError;
+
+impl fmt::Display for StreamDroppedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        "the readable end of this stream has been dropped".fmt(f)
+    }
+}
+
+impl std::error::Error for StreamDroppedError {}
+
+/// An [`AsyncRead`] and [`AsyncBufRead`] over the bytes of a
+/// `stream<u8>`.
+///
+/// Bytes are read into an internal buffer which [`AsyncBufRead`] hands out
+/// directly. The end of the stream is reached once its writable end is
+/// dropped.
+///
+/// Created with [`StreamReader::into_async_read`].
+pub struct StreamReaderAsyncRead {
+    reader: Reader<u8>,
+    buf: Vec<u8>,
+    pos: usize,
+    capacity: usize,
+}
+
+impl StreamReaderAsyncRead {
+    /// Creates a reader which reads up to `capacity` bytes at a time from
+    /// `reader`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(reader: StreamReader<u8>, capacity: usize) -> StreamReaderAsyncRead {
+        assert!(capacity > 0, "cannot read zero bytes at a time");
+        StreamReaderAsyncRead {
+            reader: Reader::new(reader),
+            buf: Vec::new(),
+            pos: 0,
+            capacity,
+        }
+    }
+}
+
+impl StreamReader<u8> {
+    /// Converts this reader into an [`AsyncRead`] over its bytes.
+    pub fn into_async_read(self) -> StreamReaderAsyncRead {
+        StreamReaderAsyncRead::with_capacity(self, DEFAULT_BYTE_CAPACITY)
+    }
+}
+
+impl AsyncBufRead for StreamReaderAsyncRead {
+    fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+        let me = self.get_mut();
+        while me.pos == me.buf.len() {
+            let (buf, pos, capacity) = (&mut me.buf, &mut me.pos, me.capacity);
+            let read = ready!(me.reader.poll_read(cx, || {
+                let mut buf = mem::take(buf);
+                buf.clear();
+                buf.reserve(capacity);
+                *pos = 0;
+                buf
+            }));
+            match read {
+                Some(bytes) => me.buf = bytes,
+                None => break,
+            }
+        }
+        Poll::Ready(Ok(&me.buf[me.pos..]))
+    }
+
+    fn consume(self: Pin<&mut Self>, amt: usize) {
+        let me = self.get_mut();
+        me.pos = (me.pos + amt).min(me.buf.len());
+    }
+}
+
+impl AsyncRead for StreamReaderAsyncRead {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        dst: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if dst.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        let src = ready!(self.as_mut().poll_fill_buf(cx))?;
+        let amt = src.len().min(dst.len());
+        dst[..amt].copy_from_slice(&src[..amt]);
+        self.consume(amt);
+        Poll::Ready(Ok(amt))
+    }
+}
+
+impl fmt::Debug for StreamReaderAsyncRead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamReaderAsyncRead")
+            .field("buffered", &(self.buf.len() - self.pos))
+            .finish()
+    }
+}
+
+/// An [`AsyncWrite`] into the bytes of a `stream<u8>`.
+///
+/// Bytes are copied into an internal buffer, since the canonical ABI may read
+/// them after `poll_write` has returned, and written from there. Writes that
+/// fail because the readable end of the stream was dropped return an error of
+/// kind [`io::ErrorKind::BrokenPipe`]. Closing the writer drops the writable
+/// end of the stream.
+///
+/// Created with [`StreamWriter::into_async_write`].
+pub struct StreamWriterAsyncWrite {
+    writer: Writer<u8>,
+    buf: Vec<u8>,
+    capacity: usize,
+}
+
+impl StreamWriterAsyncWrite {
+    /// Creates a writer which buffers up to `capacity` bytes before waiting
+    /// for them to be written to `writer`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn with_capacity(writer: StreamWriter<u8>, capacity: usize) -> StreamWriterAsyncWrite {
+        assert!(capacity > 0, "cannot write zero bytes at a time");
+        StreamWriterAsyncWrite {
+            writer: Writer::new(writer),
+            buf: Vec::new(),
+            capacity,
+        }
+    }
+}
+
+impl StreamWriter<u8> {
+    /// Converts this writer into an [`AsyncWrite`] of bytes.
+    pub fn into_async_write(self) -> StreamWriterAsyncWrite {
+        StreamWriterAsyncWrite::with_capacity(self, DEFAULT_BYTE_CAPACITY)
+    }
+}
+
+fn broken_pipe() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, StreamDroppedError)
+}
+
+impl AsyncWrite for StreamWriterAsyncWrite {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        src: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let me = self.get_mut();
+        if me.writer.is_done() {
+            return Poll::Ready(Err(broken_pipe()));
+        }
+        if me.buf.len() >= me.capacity && !ready!(me.writer.poll_write(cx, &mut me.buf, false)) {
+            return Poll::Ready(Err(broken_pipe()));
+        }
+        let amt = src.len().min(me.capacity - me.buf.len());
+        me.buf.extend_from_slice(&src[..amt]);
+
+        // Start writing what's been buffered right away if nothing else is
+        // being written, so bytes make progress even without a flush. A
+        // dropped reader is reported by the next call instead.
+        let _ = me.writer.poll_write(cx, &mut me.buf, false);
+        Poll::Ready(Ok(amt))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let me = self.get_mut();
+        match ready!(me.writer.poll_write(cx, &mut me.buf, true)) {
+            true => Poll::Ready(Ok(())),
+            false => Poll::Ready(Err(broken_pipe())),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        self.writer.close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl fmt::Debug for StreamWriterAsyncWrite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamWriterAsyncWrite")
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
@@ -0,0 +1,112 @@
+//! Hooks reporting what the async runtime is doing.
+//!
+//! This is intended for debugging tasks which never complete: with a hook
+//! installed through [`set_hook`] every task, waitable and cancellation the
+//! runtime deals with is reported as an [`Event`]. Forwarding events to a
+//! facade such as `tracing` is left to the hook, while the `trace-log` feature
+//! additionally forwards every event to the `log` crate at the trace level.
+//!
+//! Without the `trace` feature none of this exists and the runtime contains no
+//! instrumentation at all.
+
+use std::mem;
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// Identifier of a task in [`Event`]s.
+///
+/// This is the address of the runtime's state for the task, so it's unique
+/// among running tasks but may be reused once a task has finished.
+pub type TaskId = usize;
+
+/// A hook installed with [`set_hook`].
+pub type Hook = fn(&Event);
+
+/// Something the async runtime did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// A task was started, either by an async export or by `block_on`.
+    TaskStart {
+        /// The new task.
+        task: TaskId,
+    },
+    /// A task completed all of its work, including spawned futures.
+    TaskFinish {
+        /// The completed task.
+        task: TaskId,
+    },
+    /// The host cancelled a task, which drops all of its futures.
+    TaskCancelled {
+        /// The cancelled task.
+        task: TaskId,
+    },
+    /// A task received an event from the host, or from `waitable-set.wait` or
+    /// `waitable-set.poll` within `block_on`.
+    EventReceived {
+        /// The task receiving the event.
+        task: TaskId,
+        /// The event code, for example `EVENT_SUBTASK` or `EVENT_STREAM_READ`.
+        event: u32,
+        /// The waitable the event is for.
+        waitable: u32,
+        /// The event's payload, such as a subtask status or a stream return
+        /// code.
+        code: u32,
+    },
+    /// An in-progress subtask, future or stream operation registered its waker
+    /// with the current task, which now waits on `waitable`.
+    WakerRegistered {
+        /// The waitable being waited on.
+        waitable: u32,
+    },
+    /// An operation stopped waiting on `waitable` before it received an
+    /// event, for example because it's being cancelled.
+    WakerUnregistered {
+        /// The waitable no longer waited on.
+        waitable: u32,
+    },
+    /// An in-progress subtask, future or stream operation was cancelled.
+    OperationCancelled {
+        /// The waitable of the cancelled operation.
+        waitable: u32,
+        /// The status code returned by the cancellation.
+        code: u32,
+    },
+    /// Backpressure was enabled or disabled with `backpressure.set`.
+    Backpressure {
+        /// Whether backpressure is now enabled.
+        enabled: bool,
+    },
+}
+
+/// The installed [`Hook`], or null if there's none.
+static HOOK: AtomicPtr<()> = AtomicPtr::new(ptr::null_mut());
+
+/// Installs `hook` to receive every [`Event`], returning the previously
+/// installed hook.
+///
+/// Passing `None` removes the current hook.
+pub fn set_hook(hook: Option<Hook>) -> Option<Hook> {
+    let hook = hook.map_or(ptr::null_mut(), |hook| hook as *mut ());
+    to_hook(HOOK.swap(hook, Ordering::Relaxed))
+}
+
+fn to_hook(hook: *mut ()) -> Option<Hook> {
+    if hook.is_null() {
+        None
+    } else {
+        // SAFETY: only `set_hook` stores into `HOOK`, and it only stores null
+        // or a `Hook`.
+        Some(unsafe { mem::transmute::<*mut (), Hook>(hook) })
+    }
+}
+
+pub(crate) fn emit(event: Event) {
+    #[cfg(feature = "trace-log")]
+    log::trace!(target: "wit_bindgen_rt::async_support", "{event:?}");
+
+    if let Some(hook) = to_hook(HOOK.load(Ordering::Relaxed)) {
+        hook(&event);
+    }
+}
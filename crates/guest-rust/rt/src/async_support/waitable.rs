@@ -167,6 +167,7 @@ where
         let (_, mut completion_status) = self.pin_project();
         debug_assert!(completion_status.as_mut().code_mut().is_none());
         *completion_status.as_mut().waker_mut() = Some(cx.waker().clone());
+        rttrace!(WakerRegistered { waitable });
 
         // SAFETY: There's quite a lot going on here. First is the usage of
         // `task` below, and for that see `unregister_waker` below for why this
@@ -205,6 +206,7 @@ where
     /// This relinquishes control of the original `completion_status` pointer
     /// passed to `register_waker` after this call has completed.
     pub fn unregister_waker(self: Pin<&mut Self>, waitable: u32) {
+        rttrace!(WakerUnregistered { waitable });
         // SAFETY: the contract of `wasip3_task_set` is that the returned
         // pointer is valid for the lifetime of our entire task, so it's valid
         // for this stack frame. Additionally we assert it's non-null to
@@ -423,6 +425,10 @@ where
             unreachable!()
         };
         let code = S::in_progress_cancel(in_progress);
+        rttrace!(OperationCancelled {
+            waitable: S::in_progress_waitable(in_progress),
+            code,
+        });
         match self.poll_complete_with_code(None, Some(code)) {
             Poll::Ready(result) => S::result_into_cancel(result),
             Poll::Pending => unreachable!(),
@@ -674,6 +674,15 @@
 ///     // By default this set is empty.
 ///     additional_derives: [PartialEq, Eq, Hash, Clone],
 ///
+///     // Implements `serde::Serialize` and `serde::Deserialize` for generated
+///     // types, keeping the WIT names of fields and cases and serializing
+///     // flags as a list of the names of the flags which are set. Results are
+///     // Rust's `Result` and so use `Ok` and `Err` rather than WIT's names.
+///     // Types containing resources, futures or streams are skipped. This
+///     // requires a dependency on `serde` with its `derive` feature and is
+///     // `false` by default.
+///     serde: false,
+///
 ///     // When generating bindings for interfaces that are not defined in the
 ///     // same package as `world`, this option can be used to either generate
 ///     // those bindings or point to already generated bindings.
@@ -826,6 +835,21 @@
 ///     // `#[used]` statics being emitted.
 ///     disable_custom_section_link_helpers: false,
 ///
+///     // Generates an `Imports` trait for each imported interface along with
+///     // a `set_imports` function to install an implementation of it for the
+///     // current thread. Calls to imported functions then go to that
+///     // implementation instead, which allows testing a component natively
+///     // with a mock host. This is `false` by default.
+///     mockable_imports: false,
+///
+///     // Generates bindings which never allocate, for targets without a heap.
+///     // Exports then receive strings and lists as `&str` and `&[T]` borrowed
+///     // from the caller, and WIT which can't be bound without allocating,
+///     // such as functions returning strings or lists, is rejected. Return
+///     // fixed-size lists such as `list<u8, 32>` instead. This requires the
+///     // symmetric ABI and is `false` by default.
+///     no_alloc: false,
+///
 ///     // Write generated code to a .rs file, which allows the compiler to
 ///     // emit more useful diagnostics for errors in the generated code.  This
 ///     // is primarily useful for `wit-bindgen` developers.
@@ -895,7 +919,17 @@ pub mod rt {
 
 #[cfg(feature = "async")]
 pub use wit_bindgen_rt::async_support::{
-    backpressure_set, block_on, spawn, yield_async, yield_blocking, AbiBuffer, FutureRead,
-    FutureReader, FutureWrite, FutureWriteCancel, FutureWriteError, FutureWriter, StreamRead,
-    StreamReader, StreamResult, StreamWrite, StreamWriter,
+    backpressure_set, block_on, cancel_on_drop, select, spawn, yield_async, yield_blocking,
+    AbiBuffer, CancelOnDrop, Cancelable, FutureRead, FutureReader, FutureWrite, FutureWriteCancel,
+    FutureWriteError, FutureWriter, JoinSet, Scope, Select, Selected, StreamRead, StreamReader,
+    StreamResult, StreamWrite, StreamWriter,
+};
+
+#[cfg(feature = "trace")]
+pub use wit_bindgen_rt::async_support::trace;
+
+#[cfg(feature = "stream-adapters")]
+pub use wit_bindgen_rt::async_support::{
+    StreamDroppedError, StreamReaderAsyncRead, StreamReaderStream, StreamWriterAsyncWrite,
+    StreamWriterSink,
 };
@@ -3,8 +3,9 @@ use heck::*;
 use pulldown_cmark::{html, Event, LinkType, Parser, Tag};
 use std::collections::HashMap;
 use std::fmt::Write;
+use std::sync::Arc;
 use wit_bindgen_core::{
-    uwriteln, wit_parser, Files, InterfaceGenerator as _, Source, WorldGenerator,
+    layout::Layouts, uwriteln, wit_parser, Files, InterfaceGenerator as _, Source, WorldGenerator,
 };
 use wit_parser::*;
 
@@ -13,11 +14,17 @@ struct Markdown {
     src: Source,
     opts: Opts,
     hrefs: HashMap<String, String>,
-    sizes: SizeAlign,
+    sizes: Arc<SizeAlign>,
+    layouts: Option<Arc<Layouts>>,
 }
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, rename_all = "kebab-case")
+)]
 pub struct Opts {
     /// Output a `.md` file containing HTML.
     ///
@@ -37,8 +44,12 @@ impl Opts {
 }
 
 impl WorldGenerator for Markdown {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
-        self.sizes.fill(resolve);
+        self.sizes = Layouts::reuse(self.layouts.take()).sizes(resolve, false);
 
         let world = &resolve.worlds[world];
         uwriteln!(
@@ -418,8 +429,12 @@ impl InterfaceGenerator<'_> {
                         self.print_ty(&Type::Id(*ty));
                         self.push_str(">");
                     }
+                    TypeDefKind::FixedSizeList(t, size) => {
+                        self.push_str("list<");
+                        self.print_ty(t);
+                        self.push_str(&format!(", {size}>"));
+                    }
                     TypeDefKind::Unknown => unreachable!(),
-                    TypeDefKind::FixedSizeList(..) => todo!(),
                 }
             }
         }
@@ -649,6 +664,17 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         self.type_alias(id, name, &Type::Id(id), docs);
     }
 
+    fn type_fixed_size_list(
+        &mut self,
+        id: TypeId,
+        name: &str,
+        _ty: &Type,
+        _size: u32,
+        docs: &Docs,
+    ) {
+        self.type_alias(id, name, &Type::Id(id), docs);
+    }
+
     fn type_future(&mut self, id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs) {
         _ = (id, name, ty, docs);
         todo!()
@@ -1,10 +1,12 @@
 use anyhow::Result;
 use core::panic;
 use heck::{ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
-use std::{collections::HashMap, fmt::Write, mem, ops::Deref};
+use std::{collections::HashMap, fmt::Write, mem, ops::Deref, sync::Arc};
 use wit_bindgen_core::{
     abi::{self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmType},
-    dealias, uwrite, uwriteln,
+    dealias,
+    layout::Layouts,
+    uwrite, uwriteln,
     wit_parser::{
         Alignment, ArchitectureSize, Docs, Enum, Flags, FlagsRepr, Function, FunctionKind, Handle,
         Int, InterfaceId, Record, Resolve, Result_, SizeAlign, Tuple, Type, TypeDef, TypeDefKind,
@@ -196,6 +198,11 @@ pub(all) struct Cleanup {
 
 #[derive(Default, Debug, Clone)]
 #[cfg_attr(feature = "clap", derive(clap::Args))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, rename_all = "kebab-case")
+)]
 pub struct Opts {
     /// Whether or not to derive Show for all types
     #[cfg_attr(feature = "clap", arg(long, default_value_t = false))]
@@ -214,12 +221,18 @@ pub struct Opts {
     pub ignore_module_file: bool,
     /// The package/dir to generate the program entrance
     #[cfg_attr(feature = "clap", arg(long, default_value = "gen"))]
+    #[cfg_attr(feature = "serde", serde(default = "default_gen_dir"))]
     pub gen_dir: String,
     /// The project name ; or the package path prefix if the project is part of a larger project
     #[cfg_attr(feature = "clap", arg(long, default_value = None))]
     pub project_name: Option<String>,
 }
 
+#[cfg(feature = "serde")]
+fn default_gen_dir() -> String {
+    "gen".to_string()
+}
+
 impl Opts {
     pub fn build(&self) -> Box<dyn WorldGenerator> {
         Box::new(MoonBit {
@@ -250,7 +263,8 @@ pub struct MoonBit {
     export_interface_fragments: HashMap<String, Vec<InterfaceFragment>>,
     import_world_fragments: Vec<InterfaceFragment>,
     export_world_fragments: Vec<InterfaceFragment>,
-    sizes: SizeAlign,
+    sizes: Arc<SizeAlign>,
+    layouts: Option<Arc<Layouts>>,
     import_interface_names: HashMap<InterfaceId, String>,
     export_interface_names: HashMap<InterfaceId, String>,
     interface_ns: Ns,
@@ -285,9 +299,15 @@ impl MoonBit {
 }
 
 impl WorldGenerator for MoonBit {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         self.name = world_name(resolve, world);
-        self.sizes.fill(resolve);
+        let layouts = Layouts::reuse(self.layouts.take());
+        self.sizes = layouts.sizes(resolve, false);
+        self.layouts = Some(layouts);
     }
 
     fn import_interface(
@@ -1011,6 +1031,13 @@ impl InterfaceGenerator<'_> {
                             "Array".into()
                         }
                     }
+                    TypeDefKind::FixedSizeList(ty, _) => {
+                        if type_variable {
+                            format!("FixedArray[{}]", self.type_name(ty, type_variable))
+                        } else {
+                            "FixedArray".into()
+                        }
+                    }
                     TypeDefKind::Tuple(tuple) => {
                         if type_variable {
                             format!(
@@ -1510,6 +1537,17 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
         // Not needed
     }
 
+    fn type_fixed_size_list(
+        &mut self,
+        _id: TypeId,
+        _name: &str,
+        _ty: &Type,
+        _size: u32,
+        _docs: &Docs,
+    ) {
+        // Not needed
+    }
+
     fn type_future(&mut self, id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs) {
         _ = (id, name, ty, docs);
         todo!()
@@ -2661,6 +2699,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             }
 
             Instruction::AsyncTaskReturn { .. }
+            | Instruction::AsyncCallbackLoop { .. }
+            | Instruction::AsyncTaskReturnArg { .. }
+            | Instruction::AsyncSubtaskStart { .. }
+            | Instruction::AsyncSubtaskResult
             | Instruction::FutureLower { .. }
             | Instruction::FutureLift { .. }
             | Instruction::StreamLower { .. }
@@ -2668,9 +2710,45 @@ impl Bindgen for FunctionBindgen<'_, '_> {
             | Instruction::ErrorContextLower { .. }
             | Instruction::ErrorContextLift { .. }
             | Instruction::DropHandle { .. } => todo!(),
-            Instruction::FixedSizeListLift { .. } => todo!(),
-            Instruction::FixedSizeListLower { .. } => todo!(),
-            Instruction::FixedSizeListLowerMemory { .. } => todo!(),
+
+            Instruction::FixedSizeListLift { element, .. } => {
+                let ty = self.gen.type_name(element, true);
+                results.push(format!("([{}] : FixedArray[{ty}])", operands.join(", ")));
+            }
+
+            Instruction::FixedSizeListLower { size, .. } => {
+                let op = &operands[0];
+                for i in 0..*size {
+                    results.push(format!("({op})[{i}]"));
+                }
+            }
+
+            Instruction::FixedSizeListLowerMemory { element, size, .. } => {
+                let Block {
+                    body,
+                    results: block_results,
+                    element: block_element,
+                    base,
+                } = self.blocks.pop().unwrap();
+                assert!(block_results.is_empty());
+
+                let op = &operands[0];
+                let address = &operands[1];
+                let elem_size = self.gen.gen.sizes.size(element).size_wasm32();
+                let ty = self.gen.type_name(element, true);
+                let index = self.locals.tmp("index");
+
+                uwrite!(
+                    self.src,
+                    "
+                    for {index} = 0; {index} < {size}; {index} = {index} + 1 {{
+                        let {block_element} : {ty} = ({op})[({index})]
+                        let {base} = ({address}) + ({index} * {elem_size})
+                        {body}
+                    }}
+                    "
+                );
+            }
         }
     }
 
@@ -2745,6 +2823,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         &self.gen.gen.sizes
     }
 
+    fn layouts(&self) -> Option<&Layouts> {
+        self.gen.gen.layouts.as_deref()
+    }
+
     fn is_list_canonical(&self, _resolve: &Resolve, element: &Type) -> bool {
         matches!(
             element,
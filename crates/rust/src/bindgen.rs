@@ -3,6 +3,7 @@ use heck::*;
 use std::fmt::Write as _;
 use std::mem;
 use wit_bindgen_core::abi::{AbiVariant, Bindgen, Instruction, LiftLower, WasmType};
+use wit_bindgen_core::layout::Layouts;
 use wit_bindgen_core::{dealias, make_external_symbol, uwrite, uwriteln, wit_parser::*, Source};
 
 pub(super) struct FunctionBindgen<'a, 'b> {
@@ -191,6 +192,16 @@ impl<'a, 'b> FunctionBindgen<'a, 'b> {
         ret
     }
 
+    /// Whether lists are only lent for the duration of a call, either by
+    /// lowering them for an import (`import`) or by lifting them in an export.
+    ///
+    /// That's the case for synchronous calls in the symmetric ABI. Everything
+    /// that's always owned, for example stream and future payloads, is handed
+    /// over instead like in the canonical ABI.
+    fn lends_lists(&self, import: bool) -> bool {
+        self.r#gen.r#gen.opts.symmetric && self.r#gen.in_import == import && !self.always_owned
+    }
+
     fn lift_lower(&self) -> LiftLower {
         if self.r#gen.in_import {
             LiftLower::LowerArgsLiftResults
@@ -264,6 +275,10 @@ impl Bindgen for FunctionBindgen<'_, '_> {
         &self.r#gen.sizes
     }
 
+    fn layouts(&self) -> Option<&Layouts> {
+        self.r#gen.r#gen.layouts.as_deref()
+    }
+
     fn is_list_canonical(&self, _resolve: &Resolve, ty: &Type) -> bool {
         self.r#gen.is_list_canonical(ty)
     }
@@ -723,7 +738,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 let val = format!("vec{}", tmp);
                 let ptr = format!("ptr{}", tmp);
                 let len = format!("len{}", tmp);
-                if realloc.is_none() || (self.r#gen.in_import && self.r#gen.r#gen.opts.symmetric) {
+                if realloc.is_none() || self.lends_lists(true) {
                     self.push_str(&format!("let {} = {};\n", val, operands[0]));
                 } else {
                     let op0 = operands.pop().unwrap();
@@ -731,7 +746,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 }
                 self.push_str(&format!("let {} = {}.as_ptr().cast::<u8>();\n", ptr, val));
                 self.push_str(&format!("let {} = {}.len();\n", len, val));
-                if realloc.is_some() && !(self.r#gen.in_import && self.r#gen.r#gen.opts.symmetric) {
+                if realloc.is_some() && !self.lends_lists(true) {
                     self.push_str(&format!("::core::mem::forget({});\n", val));
                 }
                 results.push(format!("{ptr}.cast_mut()"));
@@ -742,8 +757,15 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 let tmp = self.tmp();
                 let len = format!("len{}", tmp);
                 self.push_str(&format!("let {} = {};\n", len, operands[1]));
-                let vec = self.r#gen.path_to_vec();
-                let result = if !self.r#gen.r#gen.opts.symmetric || self.r#gen.in_import {
+                let result = if self.r#gen.r#gen.opts.no_alloc && !self.r#gen.in_import {
+                    // Borrowed from the caller, which must not access zero
+                    // page memory in the symmetric ABI.
+                    format!(
+                        "if {1} > 0 {{ ::core::slice::from_raw_parts({}.cast(), {1}) }} else {{ &[] }}",
+                        operands[0], len
+                    )
+                } else if !self.lends_lists(false) {
+                    let vec = self.r#gen.path_to_vec();
                     format!(
                         "{vec}::from_raw_parts({}.cast(), {1}, {1})",
                         operands[0], len
@@ -762,7 +784,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 let val = format!("vec{}", tmp);
                 let ptr = format!("ptr{}", tmp);
                 let len = format!("len{}", tmp);
-                if realloc.is_none() || (self.r#gen.in_import && self.r#gen.r#gen.opts.symmetric) {
+                if realloc.is_none() || self.lends_lists(true) {
                     self.push_str(&format!("let {} = {};\n", val, operands[0]));
                 } else {
                     let op0 = format!("{}.into_bytes()", operands[0]);
@@ -770,7 +792,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 }
                 self.push_str(&format!("let {} = {}.as_ptr().cast::<u8>();\n", ptr, val));
                 self.push_str(&format!("let {} = {}.len();\n", len, val));
-                if realloc.is_some() && !(self.r#gen.in_import && self.r#gen.r#gen.opts.symmetric) {
+                if realloc.is_some() && !self.lends_lists(true) {
                     self.push_str(&format!("::core::mem::forget({});\n", val));
                 }
                 results.push(format!("{ptr}.cast_mut()"));
@@ -781,7 +803,21 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 let tmp = self.tmp();
                 let len = format!("len{}", tmp);
                 uwriteln!(self.src, "let {len} = {};", operands[1]);
-                if self.r#gen.r#gen.opts.symmetric && !self.r#gen.in_import {
+                if self.r#gen.r#gen.opts.no_alloc && !self.r#gen.in_import {
+                    // Borrowed from the caller like lists above.
+                    uwriteln!(
+                        self.src,
+                        "let bytes{tmp}: &[u8] = if {len} > 0 {{
+                           ::core::slice::from_raw_parts({}, {len})
+                        }} else {{ &[] }};",
+                        operands[0]
+                    );
+                    if self.r#gen.r#gen.opts.raw_strings {
+                        results.push(format!("bytes{tmp}"));
+                    } else {
+                        results.push(format!("{}(bytes{tmp})", self.r#gen.path_to_str_lift()));
+                    }
+                } else if self.lends_lists(false) {
                     uwriteln!(
                         self.src,
                         "let string{tmp} = String::from(std::str::from_utf8(std::slice::from_raw_parts({}, {len})).unwrap());",
@@ -838,7 +874,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 self.push_str(&format!(
                     "let ({result}, {cleanup}) = {rt}::Cleanup::new({layout});"
                 ));
-                if self.r#gen.r#gen.opts.symmetric && self.r#gen.in_import {
+                if self.lends_lists(true) {
                     //if !self.r#gen.needs_deallocate {
                     //    self.push_str("// ");
                     //} else {
@@ -923,7 +959,7 @@ impl Bindgen for FunctionBindgen<'_, '_> {
                 uwriteln!(self.src, "{result}.push(e{tmp});");
                 uwriteln!(self.src, "}}");
                 results.push(result);
-                if !self.r#gen.r#gen.opts.symmetric || self.r#gen.in_import {
+                if !self.lends_lists(false) {
                     let dealloc = self.r#gen.path_to_cabi_dealloc();
                     self.push_str(&format!(
                         "{dealloc}({base}, {len} * {size}, {align});\n",
@@ -1246,6 +1282,13 @@ impl Bindgen for FunctionBindgen<'_, '_> {
 
             Instruction::Malloc { .. } => unimplemented!(),
 
+            Instruction::AsyncCallbackLoop { .. }
+            | Instruction::AsyncTaskReturnArg { .. }
+            | Instruction::AsyncSubtaskStart { .. }
+            | Instruction::AsyncSubtaskResult => {
+                unreachable!("{} is only emitted for host bindings", inst.name())
+            }
+
             Instruction::GuestDeallocate { size, align } => {
                 let dealloc = self.r#gen.path_to_cabi_dealloc();
                 self.push_str(&format!(
@@ -8,6 +8,7 @@ use heck::*;
 use std::collections::{BTreeMap, BTreeSet};
 use std::fmt::Write as _;
 use std::mem;
+use std::sync::Arc;
 use wit_bindgen_core::abi::{self, AbiVariant, LiftLower};
 use wit_bindgen_core::{
     dealias, make_external_component, make_external_symbol, symmetric, uwrite, uwriteln,
@@ -18,7 +19,7 @@ pub struct InterfaceGenerator<'a> {
     pub src: Source,
     pub(super) identifier: Identifier<'a>,
     pub in_import: bool,
-    pub sizes: SizeAlign,
+    pub sizes: Arc<SizeAlign>,
     pub(super) r#gen: &'a mut RustWasm,
     pub wasm_import_module: &'a str,
     pub resolve: &'a Resolve,
@@ -146,16 +147,32 @@ impl<'i> InterfaceGenerator<'i> {
 
         traits.insert(None, ("Guest".to_string(), Vec::new()));
 
-        if let Some((id, _)) = interface {
-            for (name, id) in self.resolve.interfaces[id].types.iter() {
-                match self.resolve.types[*id].kind {
-                    TypeDefKind::Resource => {}
-                    _ => continue,
-                }
-                resources_to_drop.push(name);
-                let camel = name.to_upper_camel_case();
-                traits.insert(Some(*id), (format!("Guest{camel}"), Vec::new()));
+        // Resources are exported either by the interface or, for world-level
+        // exports, directly by the world.
+        let types = match (interface, &self.identifier) {
+            (Some((id, _)), _) => self.resolve.interfaces[id]
+                .types
+                .iter()
+                .map(|(name, id)| (name.as_str(), *id))
+                .collect::<Vec<_>>(),
+            (None, Identifier::World(world)) => self.resolve.worlds[*world]
+                .exports
+                .iter()
+                .filter_map(|(key, item)| match (key, item) {
+                    (WorldKey::Name(name), WorldItem::Type(id)) => Some((name.as_str(), *id)),
+                    _ => None,
+                })
+                .collect(),
+            (None, _) => Vec::new(),
+        };
+        for (name, id) in types {
+            match self.resolve.types[id].kind {
+                TypeDefKind::Resource => {}
+                _ => continue,
             }
+            resources_to_drop.push(name);
+            let camel = name.to_upper_camel_case();
+            traits.insert(Some(id), (format!("Guest{camel}"), Vec::new()));
         }
 
         for func in funcs {
@@ -180,7 +197,9 @@ impl<'i> InterfaceGenerator<'i> {
                 ..Default::default()
             };
             sig.update_for_func(&func);
-            self.print_signature(func, true, &sig);
+            // Without an allocator exports borrow their arguments from the
+            // caller instead of taking ownership of them.
+            self.print_signature(func, !self.r#gen.opts.no_alloc, &sig);
             self.src.push_str(";\n");
             let trait_method = mem::replace(&mut self.src, prev);
             methods.push(trait_method);
@@ -216,8 +235,7 @@ impl<'i> InterfaceGenerator<'i> {
             }
 
             uwriteln!(self.src, "pub trait {trait_name}: 'static {{");
-            let (_, interface_name) = interface.unwrap();
-            let module = self.resolve.name_world_key(interface_name);
+            let module = self.export_module();
             let wasm_import_module = format!("[export]{module}");
             let new_name = format!("[resource-new]{resource_name}");
             let external_new =
@@ -357,12 +375,7 @@ macro_rules! {macro_name} {{
         }
         let export_prefix = self.r#gen.opts.export_prefix.as_deref().unwrap_or("");
         for name in resources_to_drop {
-            let module = match self.identifier {
-                Identifier::Interface(_, key) => self.resolve.name_world_key(key),
-                Identifier::World(_) | Identifier::StreamOrFuturePayload => {
-                    unreachable!()
-                }
-            };
+            let module = self.export_module();
             let camel = name.to_upper_camel_case();
             if self.gen.opts.symmetric {
                 let dtor_symbol = make_external_symbol(
@@ -386,12 +399,18 @@ macro_rules! {macro_name} {{
                     &(String::from("[dtor]") + &name),
                     AbiVariant::GuestExport,
                 );
+                // World-level resources have no interface to prefix their
+                // destructor with, like world-level functions.
+                let dtor_export = match interface {
+                    Some(_) => format!("{module}#[dtor]{name}"),
+                    None => format!("[dtor]{name}"),
+                };
                 uwriteln!(
                     self.src,
                     r#"
                 const _: () = {{
                     #[doc(hidden)]
-                    #[cfg_attr(target_arch = "wasm32", export_name = "{export_prefix}{module}#[dtor]{name}")]
+                    #[cfg_attr(target_arch = "wasm32", export_name = "{export_prefix}{dtor_export}")]
                     #[cfg_attr(not(target_arch = "wasm32"), no_mangle)]
                     #[allow(non_snake_case)]
                     unsafe extern "C" fn {dtor_symbol}(rep: *mut u8) {{
@@ -413,6 +432,16 @@ macro_rules! {macro_name} {{
         Ok(macro_name)
     }
 
+    /// Returns the name of the module exported resources belong to, which is
+    /// `$root` for resources exported directly by a world.
+    fn export_module(&self) -> String {
+        match self.identifier {
+            Identifier::Interface(_, key) => self.resolve.name_world_key(key),
+            Identifier::World(_) => "$root".to_string(),
+            Identifier::StreamOrFuturePayload => unreachable!(),
+        }
+    }
+
     fn generate_interface_trait<'a>(
         &mut self,
         trait_name: &str,
@@ -439,11 +468,83 @@ macro_rules! {macro_name} {{
         funcs: impl Iterator<Item = &'a Function>,
         interface: Option<&WorldKey>,
     ) {
+        let funcs = funcs.collect::<Vec<_>>();
+        if self.r#gen.opts.mockable_imports {
+            self.generate_mockable_imports(&funcs, interface);
+        }
         for func in funcs {
             self.generate_guest_import(func, interface);
         }
     }
 
+    /// Returns whether calls to the import `func` can be redirected to an
+    /// implementation of the `Imports` trait of its interface.
+    ///
+    /// Only sync freestanding functions are covered, methods of resources and
+    /// async functions always go to the component's imports.
+    fn is_mockable(&mut self, func: &Function, interface: Option<&WorldKey>) -> bool {
+        self.r#gen.opts.mockable_imports
+            && matches!(func.kind, FunctionKind::Freestanding)
+            && !self.r#gen.skip.contains(&func.name)
+            && !self.r#gen.is_async(self.resolve, interface, func, true)
+    }
+
+    /// Generates the `Imports` trait for `funcs` along with `set_imports` to
+    /// install an implementation of it for the current thread.
+    fn generate_mockable_imports(&mut self, funcs: &[&Function], interface: Option<&WorldKey>) {
+        let funcs = funcs
+            .iter()
+            .copied()
+            .filter(|func| self.is_mockable(func, interface))
+            .collect::<Vec<_>>();
+        if funcs.is_empty() {
+            return;
+        }
+
+        self.src.push_str(
+            "/// The functions imported by this interface.
+            ///
+            /// An implementation installed with [`set_imports`] is called
+            /// instead of the component's imports on the current thread, for
+            /// example to test the component natively with a mock host.
+            pub trait Imports {
+            ",
+        );
+        for func in funcs {
+            let sig = FnSig {
+                private: true,
+                self_arg: Some("&self".into()),
+                ..Default::default()
+            };
+            self.print_signature(func, false, &sig);
+            self.src.push_str(";\n");
+        }
+        self.src.push_str("}\n");
+
+        self.src.push_str(
+            "::std::thread_local! {
+                static IMPORTS: ::core::cell::RefCell<
+                    ::core::option::Option<::std::rc::Rc<dyn Imports>>,
+                > = const { ::core::cell::RefCell::new(None) };
+            }
+
+            /// Installs `imports` as the implementation of this interface's
+            /// functions on the current thread, returning the previous one.
+            ///
+            /// Passing `None` goes back to calling the component's imports.
+            pub fn set_imports(
+                imports: ::core::option::Option<::std::rc::Rc<dyn Imports>>,
+            ) -> ::core::option::Option<::std::rc::Rc<dyn Imports>> {
+                IMPORTS.with(|i| i.replace(imports))
+            }
+
+            fn _imports() -> ::core::option::Option<::std::rc::Rc<dyn Imports>> {
+                IMPORTS.with(|i| i.borrow().clone())
+            }
+            ",
+        );
+    }
+
     pub fn align_area(&mut self, alignment: Alignment) {
         match alignment {
             Alignment::Pointer => uwriteln!(
@@ -806,7 +907,38 @@ pub mod vtable{ordinal} {{
         self.src.push_str("#[allow(unused_unsafe, clippy::all)]\n");
         let params = self.print_signature(func, async_, &sig);
         self.src.push_str("{\n");
+        let mockable = self.is_mockable(func, interface);
+        if mockable {
+            let args = func
+                .params
+                .iter()
+                .map(|(name, _)| to_rust_ident(name))
+                .collect::<Vec<_>>()
+                .join(", ");
+            let name = to_rust_ident(&func.name);
+            uwriteln!(
+                self.src,
+                "if let Some(imports) = _imports() {{
+                    return imports.{name}({args});
+                }}"
+            );
+        }
+        // Without a wasm runtime nothing provides the imports themselves, so
+        // only the installed implementation can be called natively.
+        let native_panic = mockable && !self.r#gen.opts.symmetric;
+        if native_panic {
+            uwriteln!(
+                self.src,
+                "#[cfg(not(target_family = \"wasm\"))]
+                {{
+                    panic!(\"no implementation of `{}` was installed with `set_imports`\");
+                }}
+                #[cfg(target_family = \"wasm\")]",
+                func.name
+            );
+        }
         if self.gen.opts.symmetric
+            && !self.gen.opts.no_alloc
             && symmetric::has_non_canonical_list_rust(self.resolve, &func.params)
         {
             self.needs_deallocate = true;
@@ -1025,12 +1157,15 @@ const ABI_LAYOUT: ::core::alloc::Layout = unsafe {{
         if func.result.is_some() {
             args.push_str("_results");
         }
+        // Symmetric imports return the event subscription to wait on for
+        // the call to finish, or null if it already has.
+        let status = if symmetric { "*mut u8" } else { "u32" };
         uwriteln!(
             self.src,
             r#"
-unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
+unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> {status} {{
     {intrinsic}
-    unsafe {{ call({args}) as u32 }}
+    unsafe {{ call({args}) as {status} }}
 }}
             "#
         );
@@ -1472,7 +1607,7 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
             };
             sig.update_for_func(&func);
             self.src.push_str("#[allow(unused_variables)]\n");
-            self.print_signature(func, true, &sig);
+            self.print_signature(func, !self.r#gen.opts.no_alloc, &sig);
             self.src.push_str("{ unreachable!() }\n");
         }
 
@@ -2103,6 +2238,9 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
             } else if info.is_clone() {
                 derives.insert("Clone".to_string());
             }
+            let serde_derives = self.serde_derives(id, mode);
+            let serde = !serde_derives.is_empty();
+            derives.extend(serde_derives);
             if !derives.is_empty() {
                 self.push_str("#[derive(");
                 self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
@@ -2113,6 +2251,9 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
             self.push_str(" {\n");
             for field in record.fields.iter() {
                 self.rustdoc(&field.docs);
+                if serde {
+                    self.print_serde_rename(&field.name, &to_rust_ident(&field.name));
+                }
                 self.push_str("pub ");
                 self.push_str(&to_rust_ident(&field.name));
                 self.push_str(": ");
@@ -2172,10 +2313,14 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
     {
         self.print_rust_enum(
             id,
-            variant
-                .cases
-                .iter()
-                .map(|c| (c.name.to_upper_camel_case(), &c.docs, c.ty.as_ref())),
+            variant.cases.iter().map(|c| {
+                (
+                    c.name.to_upper_camel_case(),
+                    &*c.name,
+                    &c.docs,
+                    c.ty.as_ref(),
+                )
+            }),
             docs,
         );
     }
@@ -2183,7 +2328,7 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
     fn print_rust_enum<'b>(
         &mut self,
         id: TypeId,
-        cases: impl IntoIterator<Item = (String, &'b Docs, Option<&'b Type>)> + Clone,
+        cases: impl IntoIterator<Item = (String, &'b str, &'b Docs, Option<&'b Type>)> + Clone,
         docs: &Docs,
     ) where
         Self: Sized,
@@ -2213,6 +2358,9 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
             } else if info.is_clone() {
                 derives.insert("Clone".to_string());
             }
+            let serde_derives = self.serde_derives(id, mode);
+            let serde = !serde_derives.is_empty();
+            derives.extend(serde_derives);
             if !derives.is_empty() {
                 self.push_str("#[derive(");
                 self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
@@ -2221,8 +2369,11 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
             self.push_str(&format!("pub enum {name}"));
             self.print_generics(mode.lifetime);
             self.push_str(" {\n");
-            for (case_name, docs, payload) in cases.clone() {
+            for (case_name, wit_name, docs, payload) in cases.clone() {
                 self.rustdoc(docs);
+                if serde {
+                    self.print_serde_rename(wit_name, &case_name);
+                }
                 self.push_str(&case_name);
                 if let Some(ty) = payload {
                     self.push_str("(");
@@ -2240,7 +2391,7 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
                 cases
                     .clone()
                     .into_iter()
-                    .map(|(name, _docs, ty)| (name, ty)),
+                    .map(|(name, _wit_name, _docs, ty)| (name, ty)),
             );
 
             if info.error {
@@ -2369,6 +2520,9 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
                 .into_iter()
                 .map(|s| s.to_string()),
         );
+        let serde_derives = self.serde_derives(id, TypeMode::owned());
+        let serde = !serde_derives.is_empty();
+        derives.extend(serde_derives);
         self.push_str("#[derive(");
         self.push_str(&derives.into_iter().collect::<Vec<_>>().join(", "));
         self.push_str(")]\n");
@@ -2376,6 +2530,9 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
         for case in enum_.cases.iter() {
             self.rustdoc(&case.docs);
             self.push_str(&case_attr(case));
+            if serde {
+                self.print_serde_rename(&case.name, &case.name.to_upper_camel_case());
+            }
             self.push_str(&case.name.to_upper_camel_case());
             self.push_str(",\n");
         }
@@ -2561,16 +2718,19 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
     }
 
     pub fn is_exported_resource(&self, ty: TypeId) -> bool {
-        let ty = dealias(self.resolve, ty);
-        let ty = &self.resolve.types[ty];
+        let ty_id = dealias(self.resolve, ty);
+        let ty = &self.resolve.types[ty_id];
         match &ty.kind {
             TypeDefKind::Resource => {}
             _ => return false,
         }
 
         match ty.owner {
-            // Worlds cannot export types of any kind as of this writing.
-            TypeOwner::World(_) => false,
+            // World-level types are exported only if the world lists them
+            // among its exports.
+            TypeOwner::World(w) => self.resolve.worlds[w].exports.values().any(
+                |item| matches!(item, WorldItem::Type(id) if dealias(self.resolve, *id) == ty_id),
+            ),
 
             // Interfaces are "stateful" currently where whatever we last saw
             // them as dictates whether it's exported or not.
@@ -2591,7 +2751,127 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
     }
 
     fn info(&self, ty: TypeId) -> TypeInfo {
-        self.r#gen.types.get(ty)
+        self.r#gen.layouts().type_info(self.resolve, ty)
+    }
+
+    /// Returns the serde traits to derive for `id` when rendered in `mode`.
+    ///
+    /// This is empty unless the `serde` option is enabled and `id` can be
+    /// serialized. Borrowed types can't be deserialized in general so they
+    /// only get `Serialize`.
+    fn serde_derives(&self, id: TypeId, mode: TypeMode) -> Vec<String> {
+        if !self.r#gen.opts.serde || !self.serde_supported(&Type::Id(id)) {
+            return Vec::new();
+        }
+        let mut derives = vec!["::serde::Serialize".to_string()];
+        if mode.lifetime.is_none() {
+            derives.push("::serde::Deserialize".to_string());
+        }
+        derives
+    }
+
+    /// Returns whether `ty` has a serialized form, which excludes resources,
+    /// futures, streams and error contexts, along with tuples and fixed-size
+    /// lists larger than what serde implements its traits for.
+    fn serde_supported(&self, ty: &Type) -> bool {
+        let id = match ty {
+            Type::ErrorContext => return false,
+            Type::Id(id) => *id,
+            _ => return true,
+        };
+        match &self.resolve.types[id].kind {
+            TypeDefKind::Record(r) => r.fields.iter().all(|f| self.serde_supported(&f.ty)),
+            TypeDefKind::Tuple(t) => {
+                t.types.len() <= 16 && t.types.iter().all(|ty| self.serde_supported(ty))
+            }
+            TypeDefKind::Variant(v) => v
+                .cases
+                .iter()
+                .filter_map(|c| c.ty.as_ref())
+                .all(|ty| self.serde_supported(ty)),
+            TypeDefKind::FixedSizeList(ty, size) => *size <= 32 && self.serde_supported(ty),
+            TypeDefKind::Option(ty) | TypeDefKind::List(ty) | TypeDefKind::Type(ty) => {
+                self.serde_supported(ty)
+            }
+            TypeDefKind::Result(r) => r.ok.iter().chain(&r.err).all(|ty| self.serde_supported(ty)),
+            TypeDefKind::Flags(_) | TypeDefKind::Enum(_) => true,
+            TypeDefKind::Resource
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Unknown => false,
+        }
+    }
+
+    /// Renames a field or case to its WIT name when serialized, if its Rust
+    /// name differs.
+    fn print_serde_rename(&mut self, wit_name: &str, rust_name: &str) {
+        if wit_name != rust_name {
+            self.push_str(&format!("#[serde(rename = \"{wit_name}\")]\n"));
+        }
+    }
+
+    /// Implements the serde traits for the flags type `ty`, which is a
+    /// sequence of the names of the flags which are set.
+    ///
+    /// `bitflags` can't derive these and its own optional serde support uses
+    /// a `|`-separated string of Rust constant names.
+    pub(crate) fn print_flags_serde(&mut self, ty: &str, flags: &Flags) {
+        let name = ty.to_upper_camel_case();
+        let string = self.path_to_string();
+        let mut pairs = String::new();
+        let mut names = String::new();
+        let mut arms = String::new();
+        for flag in flags.flags.iter() {
+            let constant = flag.name.to_shouty_snake_case();
+            uwrite!(pairs, "({name}::{constant}, \"{}\"), ", flag.name);
+            uwrite!(names, "\"{}\", ", flag.name);
+            uwriteln!(arms, "\"{}\" => {name}::{constant},", flag.name);
+        }
+        self.push_str(&format!(
+            "impl ::serde::Serialize for {name} {{
+                fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error> {{
+                    use ::serde::ser::SerializeSeq;
+                    let flags = [{pairs}];
+                    let len = flags.iter().filter(|(flag, _)| self.contains(*flag)).count();
+                    let mut seq = serializer.serialize_seq(Some(len))?;
+                    for (flag, name) in flags {{
+                        if self.contains(flag) {{
+                            seq.serialize_element(name)?;
+                        }}
+                    }}
+                    seq.end()
+                }}
+            }}
+
+            impl<'de> ::serde::Deserialize<'de> for {name} {{
+                fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::core::result::Result<Self, D::Error> {{
+                    struct Visitor;
+
+                    impl<'de> ::serde::de::Visitor<'de> for Visitor {{
+                        type Value = {name};
+
+                        fn expecting(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {{
+                            f.write_str(\"a sequence of `{ty}` flags\")
+                        }}
+
+                        fn visit_seq<A: ::serde::de::SeqAccess<'de>>(self, mut seq: A) -> ::core::result::Result<{name}, A::Error> {{
+                            let mut flags = {name}::empty();
+                            while let Some(flag) = seq.next_element::<{string}>()? {{
+                                flags |= match flag.as_str() {{
+                                    {arms}
+                                    _ => return Err(::serde::de::Error::unknown_variant(&flag, &[{names}])),
+                                }};
+                            }}
+                            Ok(flags)
+                        }}
+                    }}
+
+                    deserializer.deserialize_seq(Visitor)
+                }}
+            }}
+            "
+        ));
     }
 
     fn print_borrowed_str(&mut self, lifetime: &'static str) {
@@ -2626,6 +2906,10 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
         self.path_from_runtime_module(RuntimeItem::StringLift, "string_lift")
     }
 
+    pub fn path_to_str_lift(&mut self) -> String {
+        self.path_from_runtime_module(RuntimeItem::StrLift, "str_lift")
+    }
+
     pub fn path_to_cabi_dealloc(&mut self) -> String {
         self.path_from_runtime_module(RuntimeItem::CabiDealloc, "cabi_dealloc")
     }
@@ -2690,18 +2974,7 @@ unsafe fn call_import(_params: Self::ParamsLower, _results: *mut u8) -> u32 {{
     }
 
     pub fn is_list_canonical(&self, ty: &Type) -> bool {
-        if !self.resolve.all_bits_valid(ty) {
-            return false;
-        }
-        match ty {
-            // Note that tuples in Rust are not ABI-compatible with component
-            // model tuples, so those are exempted here from canonical lists.
-            Type::Id(id) => {
-                let info = self.r#gen.types.get(*id);
-                !info.has_resource && !info.has_tuple
-            }
-            _ => true,
-        }
+        self.r#gen.is_list_canonical(self.resolve, ty)
     }
 }
 
@@ -2758,11 +3031,7 @@ impl<'a> wit_bindgen_core::InterfaceGenerator<'a> for InterfaceGenerator<'a> {
             );
             self.wasm_import_module.to_string()
         } else {
-            let module = match self.identifier {
-                Identifier::Interface(_, key) => self.resolve.name_world_key(key),
-                Identifier::World(_) => unimplemented!("resource exports from worlds"),
-                Identifier::StreamOrFuturePayload => unreachable!(),
-            };
+            let module = self.export_module();
             let box_path = self.path_to_box();
             uwriteln!(
                 self.src,
@@ -2976,6 +3245,10 @@ impl<'a> {camel}Borrow<'a>{{
         }
         self.src.push_str("}\n");
         self.src.push_str("}\n");
+
+        if self.r#gen.opts.serde {
+            self.print_flags_serde(name, flags);
+        }
     }
 
     fn type_variant(&mut self, id: TypeId, _name: &str, variant: &Variant, docs: &Docs) {
@@ -3035,6 +3308,17 @@ impl<'a> {camel}Borrow<'a>{{
         }
     }
 
+    fn type_fixed_size_list(&mut self, id: TypeId, _name: &str, ty: &Type, size: u32, docs: &Docs) {
+        for (name, mode) in self.modes_of(id) {
+            self.rustdoc(docs);
+            self.push_str(&format!("pub type {}", name));
+            self.print_generics(mode.lifetime);
+            self.push_str(" = [");
+            self.print_ty(ty, mode);
+            self.push_str(&format!("; {size}];\n"));
+        }
+    }
+
     fn type_future(&mut self, _id: TypeId, name: &str, ty: &Option<Type>, docs: &Docs) {
         let async_support = self.r#gen.async_support_path();
         let mode = TypeMode {
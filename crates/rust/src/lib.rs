@@ -7,14 +7,17 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::{self, Write as _};
 use std::mem;
 use std::str::FromStr;
+use std::sync::Arc;
 use wit_bindgen_core::abi::{Bitcast, WasmType};
+use wit_bindgen_core::layout::Layouts;
 use wit_bindgen_core::{
-    dealias, name_package_module, uwrite, uwriteln, wit_parser::*, AsyncFilterSet, Files,
-    InterfaceGenerator as _, Source, Types, WorldGenerator,
+    dealias, name_package_module, symmetric, uwrite, uwriteln, wit_parser::*, AsyncFilterSet,
+    Files, InterfaceGenerator as _, Source, WorldGenerator,
 };
 
 mod bindgen;
 mod interface;
+mod wamr;
 
 struct InterfaceName {
     /// True when this interface name has been remapped through the use of `with` in the `bindgen!`
@@ -33,7 +36,7 @@ enum Direction {
 
 #[derive(Default)]
 struct RustWasm {
-    types: Types,
+    layouts: Option<Arc<Layouts>>,
     src_preamble: Source,
     src: Source,
     opts: Opts,
@@ -62,6 +65,10 @@ struct RustWasm {
 
     future_payloads: IndexMap<String, String>,
     stream_payloads: IndexMap<String, String>,
+
+    /// Why the world can't be bound with the `no_alloc` option, reported
+    /// instead of generating anything.
+    no_alloc_error: Option<anyhow::Error>,
 }
 
 #[derive(Default)]
@@ -112,6 +119,7 @@ enum RuntimeItem {
     StdAllocModule,
     VecType,
     StringLift,
+    StrLift,
     InvalidEnumDiscriminant,
     CharLift,
     BoolLift,
@@ -231,6 +239,32 @@ pub struct Opts {
     #[cfg_attr(feature = "clap", arg(long, value_name = "NAME"))]
     pub additional_derive_ignore: Vec<String>,
 
+    /// Implement `serde::Serialize` and `serde::Deserialize` for generated
+    /// types, following their WIT definition.
+    ///
+    /// This requires the `serde` crate, with its `derive` feature, as a
+    /// dependency. Values map to the serde data model as follows:
+    ///
+    /// * records are structs whose fields keep their WIT names,
+    /// * variants are externally tagged by the WIT name of their case, so a
+    ///   case without a payload is a string and one with a payload is a map
+    ///   with a single entry,
+    /// * enums are the WIT name of their case, as a string,
+    /// * flags are a sequence of the WIT names of the flags which are set,
+    /// * options are `null` or their payload, as for `Option`,
+    /// * results are a map with a single `Ok` or `Err` entry, as for `Result`,
+    /// * tuples and lists are sequences and `char`s are strings.
+    ///
+    /// Results are the one exception to keeping WIT names: they are generated
+    /// as Rust's own `Result`, whose serde implementation uses `Ok` and `Err`
+    /// rather than WIT's `ok` and `err`.
+    ///
+    /// Types which contain resources, futures, streams or error contexts have
+    /// no meaningful serialized form and are skipped. Borrowed types which
+    /// only exist as parameters of imports only implement `Serialize`.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub serde: bool,
+
     /// Remapping of wit interface and type names to Rust module names and types.
     ///
     /// Argument must be of the form `k=v` and this option can be passed
@@ -297,13 +331,55 @@ pub struct Opts {
     #[cfg_attr(feature = "clap", arg(long))]
     pub disable_custom_section_link_helpers: bool,
 
+    /// Generate an `Imports` trait for each imported interface whose
+    /// implementation can be installed at runtime with `set_imports`,
+    /// replacing the component's imports on the current thread.
+    ///
+    /// This is intended for testing components natively, without a wasm
+    /// runtime, and requires the standard library.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub mockable_imports: bool,
+
+    /// Generate bindings which never allocate, for targets without a heap.
+    ///
+    /// Exported functions receive strings and lists as `&str` and `&[T]`
+    /// borrowed from the caller for the duration of the call, and imported
+    /// functions are passed borrows as usual. WIT which can't be bound
+    /// without allocating, for example functions returning strings or lists,
+    /// lists of strings, exported resources, futures and streams, is
+    /// rejected during generation.
+    ///
+    /// Results are never borrowed, as there's nothing which outlives the call
+    /// to borrow them from, so functions can only return values of a fixed
+    /// size. Use fixed-size lists such as `list<u8, 32>` for those instead.
+    /// Lifting results into caller-provided buffers isn't supported: a
+    /// string or list returned in the symmetric ABI is memory the callee
+    /// allocated, which the caller would still have to free.
+    ///
+    /// This requires the symmetric ABI, where the caller keeps ownership of
+    /// its arguments.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub no_alloc: bool,
+
+    /// Generate host bindings which run a module with the C API of
+    /// wasm-micro-runtime (WAMR), instead of guest bindings.
+    ///
+    /// Exports become functions taking the module's `Instance` and imports
+    /// become `Host` traits, whose implementation as the world's `Imports`
+    /// is passed to `register`.
+    #[cfg_attr(feature = "clap", arg(long))]
+    pub host: bool,
+
     #[cfg_attr(feature = "clap", clap(flatten))]
-    #[cfg_attr(feature = "serde", serde(flatten))]
+    #[cfg_attr(feature = "serde", serde(rename = "async"))]
     pub async_: AsyncFilterSet,
 }
 
 impl Opts {
     pub fn build(self) -> Box<dyn WorldGenerator> {
+        if self.host {
+            return Box::new(wamr::WamrHost::new(self));
+        }
         let mut r = RustWasm::new();
         r.skip = self.skip.iter().cloned().collect();
         r.opts = self;
@@ -323,12 +399,7 @@ impl RustWasm {
         resolve: &'a Resolve,
         in_import: bool,
     ) -> InterfaceGenerator<'a> {
-        let mut sizes = if self.opts.symmetric {
-            SizeAlign::new_symmetric()
-        } else {
-            SizeAlign::default()
-        };
-        sizes.fill(resolve);
+        let sizes = Layouts::reuse(self.layouts.clone()).sizes(resolve, self.opts.symmetric);
 
         InterfaceGenerator {
             identifier,
@@ -446,6 +517,49 @@ impl RustWasm {
         Ok(remapped)
     }
 
+    /// Returns the `types` to define, skipping those which are remapped
+    /// through `with`.
+    fn types_to_define<'a>(
+        &mut self,
+        resolve: &Resolve,
+        types: impl IntoIterator<Item = (&'a str, TypeId)>,
+    ) -> Vec<(&'a str, TypeId)> {
+        let mut to_define = Vec::new();
+        for (name, ty_id) in types {
+            let full_name = full_wit_type_name(resolve, ty_id);
+            if let Some(type_gen) = self.with.get(&full_name) {
+                // skip type definition generation for remapped types
+                if type_gen.generated() {
+                    to_define.push((name, ty_id));
+                }
+            } else {
+                to_define.push((name, ty_id));
+            }
+            self.generated_types.insert(full_name);
+        }
+        to_define
+    }
+
+    fn check_with_used(&self) -> Result<()> {
+        let remapped_keys = self
+            .with
+            .iter()
+            .map(|(k, _)| k)
+            .cloned()
+            .collect::<HashSet<String>>();
+
+        let mut unused_keys = remapped_keys
+            .difference(&self.generated_types)
+            .collect::<Vec<&String>>();
+
+        unused_keys.sort();
+
+        if !unused_keys.is_empty() {
+            bail!(UnusedWith(unused_keys.into_iter().cloned().collect()));
+        }
+        Ok(())
+    }
+
     fn finish_runtime_module(&mut self) {
         if !self.rt_module.is_empty() {
             // As above, disable rustfmt, as we use prettyplease.
@@ -588,6 +702,20 @@ pub unsafe fn string_lift(bytes: Vec<u8>) -> String {
                 );
             }
 
+            RuntimeItem::StrLift => {
+                self.src.push_str(
+                    "\
+pub unsafe fn str_lift(bytes: &[u8]) -> &str {
+    if cfg!(debug_assertions) {
+        core::str::from_utf8(bytes).unwrap()
+    } else {
+        unsafe { core::str::from_utf8_unchecked(bytes) }
+    }
+}
+                    ",
+                );
+            }
+
             RuntimeItem::InvalidEnumDiscriminant => {
                 self.src.push_str(
                     "\
@@ -991,14 +1119,20 @@ macro_rules! __export_{world_name}_impl {{
         self.src.set_indent(old_indent);
 
         if let Some(func_name) = func_name {
-            let rt = self.runtime_path().to_string();
+            // `cabi_realloc` allocates, and nothing calls it in `no_alloc`
+            // bindings.
+            let link_realloc = if self.opts.no_alloc {
+                String::new()
+            } else {
+                format!("{}::maybe_link_cabi_realloc();", self.runtime_path())
+            };
             uwriteln!(
                 self.src,
                 "
                 #[inline(never)]
                 #[doc(hidden)]
                 pub fn {func_name}() {{
-                    {rt}::maybe_link_cabi_realloc();
+                    {link_realloc}
                 }}
             ",
             );
@@ -1016,9 +1150,170 @@ macro_rules! __export_{world_name}_impl {{
             .async_
             .is_async(resolve, interface, func, is_import)
     }
+
+    /// Layouts of the `Resolve` being generated, available once preprocessed.
+    fn layouts(&self) -> &Layouts {
+        self.layouts.as_deref().unwrap()
+    }
+
+    /// Returns whether a list of `ty` has the same representation in Rust as
+    /// in the canonical ABI, meaning it's lifted and lowered without copying.
+    fn is_list_canonical(&self, resolve: &Resolve, ty: &Type) -> bool {
+        if !resolve.all_bits_valid(ty) {
+            return false;
+        }
+        match ty {
+            // Note that tuples in Rust are not ABI-compatible with component
+            // model tuples, so those are exempted here from canonical lists.
+            Type::Id(id) => {
+                let info = self.layouts().type_info(resolve, *id);
+                !info.has_resource && !info.has_tuple
+            }
+            _ => true,
+        }
+    }
+
+    fn check(&mut self) -> Result<()> {
+        match self.no_alloc_error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// Rejects everything in `world` that the `no_alloc` option can't bind
+    /// without allocating.
+    fn check_no_alloc(&mut self, resolve: &Resolve, world: WorldId) -> Result<()> {
+        if !self.opts.symmetric {
+            bail!("the `no_alloc` option requires the symmetric ABI");
+        }
+        if self.opts.mockable_imports {
+            bail!("the `no_alloc` option can't be combined with `mockable_imports`");
+        }
+        let world = &resolve.worlds[world];
+        for (is_import, items) in [(true, &world.imports), (false, &world.exports)] {
+            for (key, item) in items.iter() {
+                match item {
+                    WorldItem::Function(func) => {
+                        self.check_no_alloc_func(resolve, None, func, is_import)?
+                    }
+                    WorldItem::Interface { id, .. } => {
+                        let iface = &resolve.interfaces[*id];
+                        for (name, ty) in iface.types.iter() {
+                            check_no_alloc_type(resolve, name, *ty, is_import)?;
+                        }
+                        for func in iface.functions.values() {
+                            self.check_no_alloc_func(resolve, Some(key), func, is_import)?;
+                        }
+                    }
+                    WorldItem::Type(ty) => {
+                        check_no_alloc_type(resolve, &resolve.name_world_key(key), *ty, is_import)?
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_no_alloc_func(
+        &mut self,
+        resolve: &Resolve,
+        interface: Option<&WorldKey>,
+        func: &Function,
+        is_import: bool,
+    ) -> Result<()> {
+        if self.is_async(resolve, interface, func, is_import) {
+            bail!(
+                "function `{}` is async, which `no_alloc` bindings don't support",
+                func.name
+            );
+        }
+        for (name, ty) in func.params.iter() {
+            // Strings and lists passed directly as parameters are always
+            // borrowed. Nested within other types they're only borrowed for
+            // imports, and only if the generated types are borrowing as well.
+            let borrowed = match ty {
+                Type::String => true,
+                Type::Id(id) => matches!(resolve.types[*id].kind, TypeDefKind::List(_)),
+                _ => false,
+            } || (is_import && !matches!(self.opts.ownership, Ownership::Owning));
+            if let Some(what) = self.find_alloc(resolve, ty, borrowed) {
+                bail!(
+                    "function `{}` can't be bound without allocating, as parameter `{name}` \
+                     contains {what}",
+                    func.name
+                );
+            }
+        }
+        if let Some(ty) = &func.result {
+            if let Some(what) = self.find_alloc(resolve, ty, false) {
+                bail!(
+                    "function `{}` can't be bound without allocating, as its result contains \
+                     {what}; `no_alloc` bindings can only return values of a fixed size, for \
+                     example `list<u8, 32>` rather than `list<u8>` or `string`",
+                    func.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a description of what needs allocating to represent `ty`, if
+    /// anything, given whether strings and lists within it can be borrowed.
+    fn find_alloc(&self, resolve: &Resolve, ty: &Type, borrowed: bool) -> Option<&'static str> {
+        // Both are `stream-obj` handles here, whose readers and writers are
+        // boxed by the runtime.
+        if symmetric::is_stream_handle(resolve, ty) {
+            return Some("a future or stream");
+        }
+        let id = match ty {
+            Type::String if !borrowed => return Some("a string"),
+            Type::ErrorContext => return Some("an error context"),
+            Type::Id(id) => *id,
+            _ => return None,
+        };
+        let find = |ty: &Type| self.find_alloc(resolve, ty, borrowed);
+        match &resolve.types[id].kind {
+            TypeDefKind::List(_) if !borrowed => Some("a list"),
+            TypeDefKind::List(ty) if !self.is_list_canonical(resolve, ty) => {
+                Some("a list whose elements must be converted")
+            }
+            TypeDefKind::Record(r) => r.fields.iter().find_map(|f| find(&f.ty)),
+            TypeDefKind::Tuple(t) => t.types.iter().find_map(find),
+            TypeDefKind::Variant(v) => v.cases.iter().find_map(|c| c.ty.as_ref().and_then(find)),
+            TypeDefKind::Option(ty) | TypeDefKind::FixedSizeList(ty, _) | TypeDefKind::Type(ty) => {
+                find(ty)
+            }
+            TypeDefKind::Result(r) => {
+                r.ok.as_ref()
+                    .and_then(find)
+                    .or_else(|| r.err.as_ref().and_then(find))
+            }
+            TypeDefKind::List(_)
+            | TypeDefKind::Flags(_)
+            | TypeDefKind::Enum(_)
+            | TypeDefKind::Resource
+            | TypeDefKind::Handle(_)
+            | TypeDefKind::Future(_)
+            | TypeDefKind::Stream(_)
+            | TypeDefKind::Unknown => None,
+        }
+    }
+}
+
+/// Rejects exported resources in `no_alloc` mode, as their representations
+/// are boxed.
+fn check_no_alloc_type(resolve: &Resolve, name: &str, ty: TypeId, is_import: bool) -> Result<()> {
+    if !is_import && matches!(resolve.types[ty].kind, TypeDefKind::Resource) {
+        bail!("resource `{name}` can't be exported without allocating");
+    }
+    Ok(())
 }
 
 impl WorldGenerator for RustWasm {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
     fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
         wit_bindgen_core::generated_preamble(&mut self.src_preamble, env!("CARGO_PKG_VERSION"));
 
@@ -1075,6 +1370,9 @@ impl WorldGenerator for RustWasm {
                 self.opts.additional_derive_ignore
             );
         }
+        if self.opts.serde {
+            uwriteln!(self.src_preamble, "//   * serde");
+        }
         for (k, v) in self.opts.with.iter() {
             uwriteln!(self.src_preamble, "//   * with {k:?} = {v}");
         }
@@ -1112,11 +1410,22 @@ impl WorldGenerator for RustWasm {
                 "//   * disable_custom_section_link_helpers"
             );
         }
+        if self.opts.mockable_imports {
+            uwriteln!(self.src_preamble, "//   * mockable_imports");
+        }
+        if self.opts.no_alloc {
+            uwriteln!(self.src_preamble, "//   * no_alloc");
+        }
         for opt in self.opts.async_.debug_opts() {
             uwriteln!(self.src_preamble, "//   * async: {opt}");
         }
-        self.types.analyze(resolve);
+        self.layouts = Some(Layouts::reuse(self.layouts.take()));
         self.world = Some(world);
+        if self.opts.no_alloc {
+            if let Err(e) = self.check_no_alloc(resolve, world) {
+                self.no_alloc_error = Some(e);
+            }
+        }
 
         let world = &resolve.worlds[world];
         // Specify that all imports local to the world's package should be generated
@@ -1144,25 +1453,20 @@ impl WorldGenerator for RustWasm {
         id: InterfaceId,
         _files: &mut Files,
     ) -> Result<()> {
+        self.check()?;
         if let Some(prefix) = self
             .interface_prefixes
             .get(&(Direction::Import, name.clone()))
         {
             self.import_prefix = Some(prefix.clone());
         }
-        let mut to_define = Vec::new();
-        for (name, ty_id) in resolve.interfaces[id].types.iter() {
-            let full_name = full_wit_type_name(resolve, *ty_id);
-            if let Some(type_gen) = self.with.get(&full_name) {
-                // skip type definition generation for remapped types
-                if type_gen.generated() {
-                    to_define.push((name, ty_id));
-                }
-            } else {
-                to_define.push((name, ty_id));
-            }
-            self.generated_types.insert(full_name);
-        }
+        let to_define = self.types_to_define(
+            resolve,
+            resolve.interfaces[id]
+                .types
+                .iter()
+                .map(|(name, id)| (name.as_str(), *id)),
+        );
 
         self.interface_last_seen_as_import.insert(id, true);
         let wasm_import_module = resolve.name_world_key(name);
@@ -1178,7 +1482,7 @@ impl WorldGenerator for RustWasm {
         }
 
         for (name, ty_id) in to_define {
-            r#gen.define_type(&name, *ty_id);
+            r#gen.define_type(name, ty_id);
         }
 
         r#gen.generate_imports(resolve.interfaces[id].functions.values(), Some(name));
@@ -1198,6 +1502,9 @@ impl WorldGenerator for RustWasm {
         funcs: &[(&str, &Function)],
         _files: &mut Files,
     ) {
+        if self.no_alloc_error.is_some() {
+            return;
+        }
         self.import_funcs_called = true;
 
         let mut r#gen = self.interface(Identifier::World(world), "$root", resolve, true);
@@ -1215,6 +1522,7 @@ impl WorldGenerator for RustWasm {
         id: InterfaceId,
         _files: &mut Files,
     ) -> Result<()> {
+        self.check()?;
         let old_prefix = self.opts.export_prefix.clone();
         if let Some(prefix) = self
             .interface_prefixes
@@ -1223,19 +1531,13 @@ impl WorldGenerator for RustWasm {
             self.opts.export_prefix =
                 Some(prefix.clone() + old_prefix.as_ref().unwrap_or(&String::new()));
         }
-        let mut to_define = Vec::new();
-        for (name, ty_id) in resolve.interfaces[id].types.iter() {
-            let full_name = full_wit_type_name(resolve, *ty_id);
-            if let Some(type_gen) = self.with.get(&full_name) {
-                // skip type definition generation for remapped types
-                if type_gen.generated() {
-                    to_define.push((name, ty_id));
-                }
-            } else {
-                to_define.push((name, ty_id));
-            }
-            self.generated_types.insert(full_name);
-        }
+        let to_define = self.types_to_define(
+            resolve,
+            resolve.interfaces[id]
+                .types
+                .iter()
+                .map(|(name, id)| (name.as_str(), *id)),
+        );
 
         self.interface_last_seen_as_import.insert(id, false);
         let wasm_import_module = format!("[export]{}", resolve.name_world_key(name));
@@ -1251,7 +1553,7 @@ impl WorldGenerator for RustWasm {
         }
 
         for (name, ty_id) in to_define {
-            r#gen.define_type(&name, *ty_id);
+            r#gen.define_type(name, ty_id);
         }
 
         let macro_name =
@@ -1286,6 +1588,7 @@ impl WorldGenerator for RustWasm {
         funcs: &[(&str, &Function)],
         _files: &mut Files,
     ) -> Result<()> {
+        self.check()?;
         let mut r#gen = self.interface(Identifier::World(world), "[export]$root", resolve, false);
         let macro_name = r#gen.generate_exports(None, funcs.iter().map(|f| f.1))?;
         let src = r#gen.finish();
@@ -1309,25 +1612,37 @@ impl WorldGenerator for RustWasm {
         types: &[(&str, TypeId)],
         _files: &mut Files,
     ) {
-        let mut to_define = Vec::new();
-        for (name, ty_id) in types {
-            let full_name = full_wit_type_name(resolve, *ty_id);
-            if let Some(type_gen) = self.with.get(&full_name) {
-                // skip type definition generation for remapped types
-                if type_gen.generated() {
-                    to_define.push((name, ty_id));
-                }
-            } else {
-                to_define.push((name, ty_id));
-            }
-            self.generated_types.insert(full_name);
+        if self.no_alloc_error.is_some() {
+            return;
         }
+        let to_define = self.types_to_define(resolve, types.iter().copied());
         let mut r#gen = self.interface(Identifier::World(world), "$root", resolve, true);
         for (name, ty) in to_define {
+            r#gen.define_type(name, ty);
+        }
+        let src = r#gen.finish();
+        self.src.push_str(&src);
+    }
+
+    fn export_types(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) -> Result<()> {
+        self.check()?;
+        for (_, ty) in types {
+            self.generated_types
+                .insert(full_wit_type_name(resolve, *ty));
+        }
+        let mut r#gen = self.interface(Identifier::World(world), "[export]$root", resolve, false);
+        for (name, ty) in types {
             r#gen.define_type(name, *ty);
         }
         let src = r#gen.finish();
         self.src.push_str(&src);
+        Ok(())
     }
 
     fn finish_imports(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) {
@@ -1340,6 +1655,7 @@ impl WorldGenerator for RustWasm {
     }
 
     fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) -> Result<()> {
+        self.check()?;
         let name = &resolve.worlds[world].name;
 
         let imports = mem::take(&mut self.import_modules);
@@ -1429,22 +1745,7 @@ impl WorldGenerator for RustWasm {
         let module_name = name.to_snake_case();
         files.push(&format!("{module_name}.rs"), src.as_bytes());
 
-        let remapped_keys = self
-            .with
-            .iter()
-            .map(|(k, _)| k)
-            .cloned()
-            .collect::<HashSet<String>>();
-
-        let mut unused_keys = remapped_keys
-            .difference(&self.generated_types)
-            .collect::<Vec<&String>>();
-
-        unused_keys.sort();
-
-        if !unused_keys.is_empty() {
-            bail!("unused remappings provided via `with`: {unused_keys:?}");
-        }
+        self.check_with_used()?;
 
         // Error about unused async configuration to help catch configuration
         // errors.
@@ -1453,6 +1754,10 @@ impl WorldGenerator for RustWasm {
         Ok(())
     }
 
+    fn modifies_resolve(&self) -> bool {
+        self.opts.invert_direction
+    }
+
     fn apply_resolve_options(&mut self, resolve: &mut Resolve, world: &mut WorldId) {
         if self.opts.invert_direction {
             resolve.invert_direction(*world);
@@ -1875,6 +2180,19 @@ impl fmt::Display for MissingWith {
 
 impl std::error::Error for MissingWith {}
 
+/// Error returned when keys passed to `with` don't name any interface or type
+/// in the world.
+#[derive(Debug, Clone)]
+pub struct UnusedWith(pub Vec<String>);
+
+impl fmt::Display for UnusedWith {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unused remappings provided via `with`: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnusedWith {}
+
 // bail!("no remapping found for {with_name:?} - use the `generate!` macro's `with` option to force the interface to be generated or specify where it is already defined:
 // ```
 // with: {{\n\t{with_name:?}: generate\n}}
@@ -0,0 +1,2433 @@
+//! Host bindings which embed a core wasm module through the C API of
+//! [wasm-micro-runtime](https://github.com/bytecodealliance/wasm-micro-runtime).
+//!
+//! This is the Rust counterpart of the C++ generator's `--host` mode. Exports
+//! of the module are called through `wasm_runtime_call_wasm_a`, with
+//! arguments copied into its linear memory through its `cabi_realloc` and
+//! results lifted back into owned Rust values before `cabi_post_*` runs.
+//! Imports are implemented by the host through one `Host` trait per
+//! interface, which together make up the world's `Imports` trait. `register`
+//! installs an `Imports` implementation as WAMR native symbols until the
+//! `Registration` it returns is dropped.
+//!
+//! Functions selected with `--async` use the async ABI. Async exports are
+//! called through `[async-lift]` and their callback is driven until they call
+//! `task.return`; async imports are implemented by the same synchronous
+//! `Host` methods and always report their subtask as returned. As no event is
+//! ever pending, an export waiting on a waitable set traps.
+//!
+//! Types are defined by the guest generator, so options such as `with`,
+//! `additional_derive_attributes` and `serde` apply to them the same way.
+//! Flags only use the `bitflags` crate when `bitflags_path` or `runtime_path`
+//! is set, to keep the bindings free of dependencies otherwise. With `stubs`,
+//! a `Stub` type implements every `Host` trait.
+//!
+//! Only wasm32 modules are supported; resources, futures, streams and error
+//! contexts are rejected, as are borrowing ownership, `raw_strings`,
+//! `mockable_imports` and `no_alloc`.
+
+use crate::{
+    compute_module_path, full_wit_type_name, perform_cast, to_rust_ident, to_upper_camel_case,
+    Identifier, InterfaceName, Opts, Ownership, RustWasm, TypeGeneration,
+};
+use anyhow::{bail, Result};
+use heck::*;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write as _;
+use std::mem;
+use std::sync::Arc;
+use wit_bindgen_core::abi::{self, AbiVariant, Bindgen, Bitcast, Instruction, LiftLower, WasmType};
+use wit_bindgen_core::layout::Layouts;
+use wit_bindgen_core::{
+    dealias, uwrite, uwriteln, wit_parser::*, Files, InterfaceGenerator as _, Source,
+    WorldGenerator,
+};
+
+#[derive(Default)]
+pub(crate) struct WamrHost {
+    opts: Opts,
+    /// Generator of guest bindings with the same options, which defines the
+    /// types so they look the same as in guest bindings.
+    types: RustWasm,
+    sizes: Arc<SizeAlign>,
+    layouts: Option<Arc<Layouts>>,
+    /// Reason why this world can't be generated, reported by the first
+    /// callback after `preprocess`.
+    error: Option<anyhow::Error>,
+    /// Contents of each module of the generated file, keyed by its path.
+    modules: BTreeMap<Vec<String>, Source>,
+    /// The module each named type is defined in.
+    type_paths: HashMap<TypeId, Vec<String>>,
+    /// Paths of the `Host` traits which make up the `Imports` trait.
+    host_traits: Vec<String>,
+    /// Trampolines for imported functions, defined within `register`.
+    trampolines: Source,
+    /// Native symbols to register, per wasm import module.
+    natives: Vec<(String, Vec<NativeSymbol>)>,
+    /// Implementations of the `Host` traits for `Stub`, with `stubs`.
+    stubs: Source,
+    /// Functions using the async ABI, by direction and `--async` name.
+    async_funcs: HashSet<(bool, String)>,
+}
+
+struct NativeSymbol {
+    name: String,
+    trampoline: String,
+    signature: String,
+}
+
+impl WamrHost {
+    pub(crate) fn new(opts: Opts) -> WamrHost {
+        let mut types = RustWasm::new();
+        types.opts = Opts {
+            host: false,
+            ..opts.clone()
+        };
+        WamrHost {
+            opts,
+            types,
+            ..WamrHost::default()
+        }
+    }
+
+    fn check(&mut self) -> Result<()> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn check_world(&mut self, resolve: &Resolve, world: WorldId) -> Result<()> {
+        if self.opts.symmetric {
+            bail!("host bindings do not support the symmetric ABI");
+        }
+        if self.opts.wasm64 {
+            bail!("host bindings only support wasm32 modules");
+        }
+        if !matches!(self.opts.ownership, Ownership::Owning) {
+            bail!("host bindings only support owning types");
+        }
+        for (enabled, option) in [
+            (self.opts.raw_strings, "raw_strings"),
+            (self.opts.mockable_imports, "mockable_imports"),
+            (self.opts.no_alloc, "no_alloc"),
+        ] {
+            if enabled {
+                bail!("host bindings do not support `{option}`");
+            }
+        }
+        let world = &resolve.worlds[world];
+        for (is_import, items) in [(true, &world.imports), (false, &world.exports)] {
+            for (key, item) in items.iter() {
+                match item {
+                    WorldItem::Function(func) => self.check_func(resolve, None, func, is_import)?,
+                    WorldItem::Interface { id, .. } => {
+                        let iface = &resolve.interfaces[*id];
+                        for (name, ty) in iface.types.iter() {
+                            check_type(resolve, &Type::Id(*ty))
+                                .map_err(|e| e.context(format!("in type `{name}`")))?;
+                        }
+                        for (_, func) in iface.functions.iter() {
+                            self.check_func(resolve, Some(key), func, is_import)?;
+                        }
+                    }
+                    WorldItem::Type(ty) => check_type(resolve, &Type::Id(*ty)).map_err(|e| {
+                        e.context(format!("in type `{}`", resolve.name_world_key(key)))
+                    })?,
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_func(
+        &mut self,
+        resolve: &Resolve,
+        interface: Option<&WorldKey>,
+        func: &Function,
+        is_import: bool,
+    ) -> Result<()> {
+        let context = || format!("in function `{}`", func.name);
+        if self
+            .opts
+            .async_
+            .is_async(resolve, interface, func, is_import)
+        {
+            self.async_funcs
+                .insert((is_import, async_name(resolve, interface, func)));
+        }
+        for ty in func.params.iter().map(|(_, ty)| ty).chain(&func.result) {
+            check_type(resolve, ty).map_err(|e| e.context(context()))?;
+        }
+        Ok(())
+    }
+
+    fn is_async(
+        &self,
+        resolve: &Resolve,
+        interface: Option<&WorldKey>,
+        func: &Function,
+        is_import: bool,
+    ) -> bool {
+        self.async_funcs
+            .contains(&(is_import, async_name(resolve, interface, func)))
+    }
+
+    /// Returns the relative path from `module` to the root of the file.
+    /// The functions of `funcs` which aren't skipped.
+    fn funcs<'a>(&self, funcs: impl Iterator<Item = &'a Function>) -> Vec<&'a Function> {
+        funcs
+            .filter(|func| !self.opts.skip.contains(&func.name))
+            .collect()
+    }
+
+    fn root(module: &[String]) -> String {
+        "super::".repeat(module.len())
+    }
+
+    fn type_path(&self, resolve: &Resolve, module: &[String], id: TypeId) -> String {
+        let name = to_upper_camel_case(resolve.types[id].name.as_ref().unwrap());
+        if let Some(TypeGeneration::Remap(path)) =
+            self.types.with.get(&full_wit_type_name(resolve, id))
+        {
+            return path.clone();
+        }
+        if let TypeOwner::Interface(iface) = resolve.types[id].owner {
+            if let Some(InterfaceName {
+                remapped: true,
+                path,
+            }) = self.types.interface_names.get(&iface)
+            {
+                return format!("{}{path}::{name}", Self::root(module));
+            }
+        }
+        let home = &self.type_paths[&id];
+        if home == module {
+            return name;
+        }
+        let mut path = Self::root(module);
+        for part in home {
+            path.push_str(part);
+            path.push_str("::");
+        }
+        path.push_str(&name);
+        path
+    }
+
+    /// Renders `ty` as seen from `module`.
+    ///
+    /// Parameters of exports are `borrowed`, so strings and lists at their
+    /// top level are taken by reference.
+    fn type_name(&self, resolve: &Resolve, module: &[String], ty: &Type, borrowed: bool) -> String {
+        match ty {
+            Type::Bool => "bool".into(),
+            Type::U8 => "u8".into(),
+            Type::U16 => "u16".into(),
+            Type::U32 => "u32".into(),
+            Type::U64 => "u64".into(),
+            Type::S8 => "i8".into(),
+            Type::S16 => "i16".into(),
+            Type::S32 => "i32".into(),
+            Type::S64 => "i64".into(),
+            Type::F32 => "f32".into(),
+            Type::F64 => "f64".into(),
+            Type::Char => "char".into(),
+            Type::String if borrowed => "&str".into(),
+            Type::String => "::std::string::String".into(),
+            Type::Id(id) => {
+                let def = &resolve.types[*id];
+                if let (true, TypeDefKind::List(elem)) =
+                    (borrowed, &resolve.types[dealias(resolve, *id)].kind)
+                {
+                    return format!("&[{}]", self.type_name(resolve, module, elem, false));
+                }
+                if def.name.is_some() {
+                    return self.type_path(resolve, module, *id);
+                }
+                self.kind_name(resolve, module, &def.kind, borrowed)
+            }
+            Type::ErrorContext => unreachable!(),
+        }
+    }
+
+    /// Renders an anonymous type, or the definition of a named one.
+    fn kind_name(
+        &self,
+        resolve: &Resolve,
+        module: &[String],
+        kind: &TypeDefKind,
+        borrowed: bool,
+    ) -> String {
+        match kind {
+            TypeDefKind::List(elem) => format!(
+                "::std::vec::Vec<{}>",
+                self.type_name(resolve, module, elem, false)
+            ),
+            TypeDefKind::FixedSizeList(elem, size) => {
+                format!("[{}; {size}]", self.type_name(resolve, module, elem, false))
+            }
+            TypeDefKind::Option(ty) => format!(
+                "::core::option::Option<{}>",
+                self.type_name(resolve, module, ty, false)
+            ),
+            TypeDefKind::Result(r) => {
+                let ok = self.optional_type_name(resolve, module, r.ok.as_ref());
+                let err = self.optional_type_name(resolve, module, r.err.as_ref());
+                format!("::core::result::Result<{ok}, {err}>")
+            }
+            TypeDefKind::Tuple(t) => {
+                let mut s = "(".to_string();
+                for ty in t.types.iter() {
+                    s.push_str(&self.type_name(resolve, module, ty, false));
+                    s.push_str(", ");
+                }
+                s.push(')');
+                s
+            }
+            TypeDefKind::Type(ty) => self.type_name(resolve, module, ty, borrowed),
+            kind => unreachable!("anonymous {kind:?}"),
+        }
+    }
+
+    fn optional_type_name(
+        &self,
+        resolve: &Resolve,
+        module: &[String],
+        ty: Option<&Type>,
+    ) -> String {
+        match ty {
+            Some(ty) => self.type_name(resolve, module, ty, false),
+            None => "()".into(),
+        }
+    }
+
+    /// Defines the `types` whose home is `module` with the guest generator,
+    /// as seen from `identifier`.
+    ///
+    /// Flags are defined without the `bitflags` crate unless a path to it or
+    /// to the `wit-bindgen` runtime was given.
+    fn define_types(
+        &mut self,
+        resolve: &Resolve,
+        module: &[String],
+        identifier: Identifier<'_>,
+        in_import: bool,
+        types: &[(&str, TypeId)],
+        src: &mut Source,
+    ) -> Result<()> {
+        for (_, id) in types {
+            self.type_paths
+                .entry(*id)
+                .or_insert_with(|| module.to_vec());
+        }
+        let types = types
+            .iter()
+            .filter(|(_, id)| self.type_paths[id] == module)
+            .copied()
+            .collect::<Vec<_>>();
+        let bitflags = self.opts.bitflags_path.is_some() || self.opts.runtime_path.is_some();
+        let to_define = self.types.types_to_define(resolve, types);
+        let name = match identifier {
+            Identifier::Interface(id, name) => Some((id, name)),
+            _ => None,
+        };
+        let mut r#gen = self.types.interface(identifier, "", resolve, in_import);
+        if let Some((id, name)) = name {
+            if r#gen.r#gen.name_interface(resolve, id, name, !in_import)? {
+                return Ok(());
+            }
+        }
+        for (name, id) in to_define {
+            match &resolve.types[id].kind {
+                TypeDefKind::Flags(flags) if !bitflags => {
+                    docs(&mut r#gen.src, &resolve.types[id].docs);
+                    define_flags(&to_upper_camel_case(name), flags, &mut r#gen.src);
+                    if self.opts.serde {
+                        r#gen.print_flags_serde(name, flags);
+                    }
+                }
+                _ => r#gen.define_type(name, id),
+            }
+        }
+        src.push_str(&r#gen.finish());
+        Ok(())
+    }
+
+    /// Generates a function which calls the export `func` of an instance.
+    fn export_func(
+        &mut self,
+        resolve: &Resolve,
+        module: &[String],
+        interface: Option<&WorldKey>,
+        func: &Function,
+        src: &mut Source,
+    ) {
+        let root = Self::root(module);
+        let is_async = self.is_async(resolve, interface, func, false);
+        let mangling = ManglingAndAbi::Legacy(if is_async {
+            LiftLowerAbi::AsyncCallback
+        } else {
+            LiftLowerAbi::Sync
+        });
+        let export_name = |kind| {
+            resolve.wasm_export_name(
+                mangling,
+                WasmExport::Func {
+                    interface,
+                    func,
+                    kind,
+                },
+            )
+        };
+
+        docs(src, &func.docs);
+        src.push_str("#[allow(unused_parens, clippy::all)]\n");
+        uwrite!(
+            src,
+            "pub fn {}(instance: &{root}Instance",
+            to_rust_ident(&func.name)
+        );
+        let mut params = Vec::new();
+        for (name, ty) in func.params.iter() {
+            let name = param_name(name);
+            let ty = self.type_name(resolve, module, ty, true);
+            uwrite!(src, ", {name}: {ty}");
+            params.push(name);
+        }
+        let result = self.optional_type_name(resolve, module, func.result.as_ref());
+        uwriteln!(src, ") -> ::core::result::Result<{result}, {root}Trap> {{");
+        uwriteln!(src, "unsafe {{");
+        uwriteln!(src, "let exec_env = instance.as_raw();");
+
+        let mut bindgen = FunctionBindgen::new(self, module, params);
+        bindgen.export_name = export_name(WasmExportKind::Normal);
+        if is_async {
+            bindgen.callback = export_name(WasmExportKind::Callback);
+        } else {
+            bindgen.post_return = export_name(WasmExportKind::PostReturn);
+        }
+        abi::call(
+            resolve,
+            if is_async {
+                AbiVariant::GuestExportAsync
+            } else {
+                AbiVariant::GuestExport
+            },
+            LiftLower::LowerArgsLiftResults,
+            func,
+            &mut bindgen,
+            is_async,
+        );
+        src.push_str(&bindgen.src);
+        let task_return = bindgen.task_return.take();
+        uwriteln!(src, "}}");
+        uwriteln!(src, "}}");
+
+        if let Some((name, params)) = task_return {
+            let wasm_module = match interface {
+                Some(key) => format!("[export]{}", resolve.name_world_key(key)),
+                None => "[export]$root".to_string(),
+            };
+            self.task_return(wasm_module, name, &params);
+        }
+    }
+
+    /// Adds the native symbol `name` of `wasm_module` through which an async
+    /// export passes its results, as `params`, to `task.return`.
+    fn task_return(&mut self, wasm_module: String, name: String, params: &[WasmType]) {
+        let trampoline = format!("trampoline{}", self.native_count());
+        let mut signature = "(".to_string();
+        uwrite!(
+            self.trampolines,
+            "extern \"C\" fn {trampoline}<H: Imports>(exec_env: _rt::wasm_exec_env_t"
+        );
+        let mut results = Vec::new();
+        for (i, ty) in params.iter().enumerate() {
+            uwrite!(self.trampolines, ", arg{i}: {}", wasm_type(*ty));
+            signature.push(signature_char(*ty));
+            results.push(format!("_rt::val_{}(arg{i})", wasm_type(*ty)));
+        }
+        signature.push(')');
+        uwriteln!(
+            self.trampolines,
+            ") {{
+                unsafe {{ _rt::task_return(exec_env, &[{}]) }}
+            }}",
+            results.join(", ")
+        );
+        let symbol = NativeSymbol {
+            name,
+            trampoline,
+            signature,
+        };
+        match self.natives.iter_mut().find(|(m, _)| *m == wasm_module) {
+            Some((_, symbols)) => symbols.push(symbol),
+            None => self.natives.push((wasm_module, vec![symbol])),
+        }
+    }
+
+    fn native_count(&self) -> usize {
+        self.natives.iter().map(|(_, s)| s.len()).sum()
+    }
+
+    /// Generates the `Host` trait for `funcs` in `module`, together with the
+    /// trampolines which WAMR calls for them.
+    fn import_funcs_in(
+        &mut self,
+        resolve: &Resolve,
+        module: &[String],
+        interface: Option<&WorldKey>,
+        wasm_module: &str,
+        funcs: &[&Function],
+        src: &mut Source,
+    ) {
+        uwriteln!(src, "pub trait Host {{");
+        for func in funcs {
+            docs(src, &func.docs);
+            uwrite!(src, "fn {}(&self", to_rust_ident(&func.name));
+            for (name, ty) in func.params.iter() {
+                let ty = self.type_name(resolve, module, ty, false);
+                uwrite!(src, ", {}: {ty}", to_rust_ident(name));
+            }
+            uwrite!(src, ")");
+            if let Some(ty) = &func.result {
+                uwrite!(src, " -> {}", self.type_name(resolve, module, ty, false));
+            }
+            uwriteln!(src, ";");
+        }
+        uwriteln!(src, "}}");
+
+        let mut host_trait = module.join("::");
+        if !host_trait.is_empty() {
+            host_trait.push_str("::");
+        }
+        host_trait.push_str("Host");
+
+        if self.opts.stubs {
+            let mut stub = mem::take(&mut self.stubs);
+            uwriteln!(stub, "impl {host_trait} for Stub {{");
+            for func in funcs {
+                uwrite!(stub, "fn {}(&self", to_rust_ident(&func.name));
+                for (name, ty) in func.params.iter() {
+                    let ty = self.type_name(resolve, &[], ty, false);
+                    uwrite!(stub, ", _{}: {ty}", to_rust_ident(name));
+                }
+                uwrite!(stub, ")");
+                if let Some(ty) = &func.result {
+                    uwrite!(stub, " -> {}", self.type_name(resolve, &[], ty, false));
+                }
+                uwriteln!(stub, " {{ unreachable!() }}");
+            }
+            uwriteln!(stub, "}}");
+            self.stubs = stub;
+        }
+
+        let mut symbols = Vec::new();
+        for func in funcs {
+            let is_async = self.is_async(resolve, interface, func, true);
+            let variant = if is_async {
+                AbiVariant::GuestImportAsync
+            } else {
+                AbiVariant::GuestImport
+            };
+            let sig = resolve.wasm_signature(variant, func);
+            let trampoline = format!("trampoline{}", self.native_count() + symbols.len());
+            let mut signature = "(".to_string();
+            let mut trampolines = mem::take(&mut self.trampolines);
+            uwrite!(
+                trampolines,
+                "extern \"C\" fn {trampoline}<H: Imports>(exec_env: _rt::wasm_exec_env_t"
+            );
+            let mut params = Vec::new();
+            for (i, ty) in sig.params.iter().enumerate() {
+                uwrite!(trampolines, ", arg{i}: {}", wasm_type(*ty));
+                signature.push(signature_char(*ty));
+                params.push(format!("arg{i}"));
+            }
+            signature.push(')');
+            uwrite!(trampolines, ")");
+            let result = match sig.results.as_slice() {
+                [] => "()",
+                [ty] => {
+                    signature.push(signature_char(*ty));
+                    uwrite!(trampolines, " -> {}", wasm_type(*ty));
+                    wasm_type(*ty)
+                }
+                _ => unreachable!(),
+            };
+            trampolines.push_str(&format!(
+                " {{
+                    unsafe {{
+                        let host = _rt::host::<H>(exec_env);
+                        _rt::guard(exec_env, || -> ::core::result::Result<{result}, Trap> {{
+                    "
+            ));
+            let mut bindgen = FunctionBindgen::new(self, &[], params);
+            bindgen.host_trait = host_trait.clone();
+            abi::call(
+                resolve,
+                variant,
+                LiftLower::LiftArgsLowerResults,
+                func,
+                &mut bindgen,
+                is_async,
+            );
+            trampolines.push_str(&bindgen.src);
+            uwriteln!(trampolines, "}})");
+            uwriteln!(trampolines, "}}");
+            uwriteln!(trampolines, "}}");
+            self.trampolines = trampolines;
+
+            symbols.push(NativeSymbol {
+                name: if is_async {
+                    format!("[async-lower]{}", func.name)
+                } else {
+                    func.name.clone()
+                },
+                trampoline,
+                signature,
+            });
+        }
+        self.host_traits.push(host_trait);
+        self.natives.push((wasm_module.to_string(), symbols));
+    }
+
+    fn finish_register(&mut self, resolve: &Resolve, world: WorldId) -> Source {
+        let mut src = Source::default();
+        if self.natives.is_empty() {
+            return src;
+        }
+        let world_name = &resolve.worlds[world].name;
+        let bounds = self
+            .host_traits
+            .iter()
+            .map(|t| format!("{t} + "))
+            .collect::<String>();
+        src.push_str(&format!(
+            "
+            /// Everything the `{world_name}` world imports, implemented by
+            /// the host.
+            pub trait Imports: {bounds}Send + Sync + 'static {{}}
+
+            impl<T: {bounds}Send + Sync + 'static> Imports for T {{}}
+
+            /// Registers `host` as the implementation of the imports of the
+            /// `{world_name}` world.
+            ///
+            /// This must be called before loading any module built for this
+            /// world. Dropping the returned [`Registration`] unregisters the
+            /// imports and drops `host`.
+            ///
+            /// # Safety
+            ///
+            /// The WAMR runtime must be initialized, and the returned
+            /// [`Registration`] must outlive every instance of a module loaded
+            /// while it was registered.
+            #[allow(unused_parens, clippy::all)]
+            pub unsafe fn register<H: Imports>(host: H) -> ::core::result::Result<Registration, Trap> {{
+            "
+        ));
+        src.push_str(&self.trampolines);
+        uwriteln!(
+            src,
+            "
+            let mut registration = Registration::new(host);
+            let host = registration.host();
+            unsafe {{"
+        );
+        for (module, symbols) in self.natives.iter() {
+            uwriteln!(src, "registration.register(c{module:?}, ::std::vec![");
+            for symbol in symbols {
+                src.push_str(&format!(
+                    "_rt::NativeSymbol {{
+                        symbol: c{:?}.as_ptr(),
+                        func_ptr: {}::<H> as *mut ::core::ffi::c_void,
+                        signature: c{:?}.as_ptr(),
+                        attachment: host,
+                    }},
+                ",
+                    symbol.name, symbol.trampoline, symbol.signature,
+                ));
+            }
+            uwriteln!(src, "])?;");
+        }
+        if !self.async_funcs.is_empty() {
+            uwriteln!(src, "_rt::register_intrinsics(&mut registration)?;");
+        }
+        uwriteln!(src, "}}");
+        uwriteln!(src, "Ok(registration)");
+        uwriteln!(src, "}}");
+        src
+    }
+}
+
+impl WorldGenerator for WamrHost {
+    fn set_layouts(&mut self, layouts: Arc<Layouts>) {
+        self.layouts = Some(layouts);
+    }
+
+    fn preprocess(&mut self, resolve: &Resolve, world: WorldId) {
+        let layouts = Layouts::reuse(self.layouts.take());
+        self.sizes = layouts.sizes(resolve, false);
+        self.types.set_layouts(layouts.clone());
+        self.types.preprocess(resolve, world);
+        self.layouts = Some(layouts);
+        if let Err(e) = self.check_world(resolve, world) {
+            self.error = Some(e);
+        }
+    }
+
+    fn import_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<()> {
+        self.check()?;
+        let module = compute_module_path(name, resolve, false);
+        let iface = &resolve.interfaces[id];
+        let mut src = Source::default();
+        let types = iface
+            .types
+            .iter()
+            .map(|(name, id)| (name.as_str(), *id))
+            .collect::<Vec<_>>();
+        self.define_types(
+            resolve,
+            &module,
+            Identifier::Interface(id, name),
+            true,
+            &types,
+            &mut src,
+        )?;
+        let funcs = self.funcs(iface.functions.values());
+        if !funcs.is_empty() {
+            self.import_funcs_in(
+                resolve,
+                &module,
+                Some(name),
+                &resolve.name_world_key(name),
+                &funcs,
+                &mut src,
+            );
+        }
+        self.modules.entry(module).or_default().push_str(&src);
+        Ok(())
+    }
+
+    fn export_interface(
+        &mut self,
+        resolve: &Resolve,
+        name: &WorldKey,
+        id: InterfaceId,
+        _files: &mut Files,
+    ) -> Result<()> {
+        self.check()?;
+        let module = compute_module_path(name, resolve, true);
+        let iface = &resolve.interfaces[id];
+        let mut src = Source::default();
+        let types = iface
+            .types
+            .iter()
+            .map(|(name, id)| (name.as_str(), *id))
+            .collect::<Vec<_>>();
+        self.define_types(
+            resolve,
+            &module,
+            Identifier::Interface(id, name),
+            false,
+            &types,
+            &mut src,
+        )?;
+        for func in self.funcs(iface.functions.values()) {
+            self.export_func(resolve, &module, Some(name), func, &mut src);
+        }
+        self.modules.entry(module).or_default().push_str(&src);
+        Ok(())
+    }
+
+    fn import_funcs(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) {
+        if self.error.is_some() {
+            return;
+        }
+        let funcs = self.funcs(funcs.iter().map(|(_, f)| *f));
+        if funcs.is_empty() {
+            return;
+        }
+        let mut src = Source::default();
+        self.import_funcs_in(resolve, &[], None, "$root", &funcs, &mut src);
+        self.modules.entry(Vec::new()).or_default().push_str(&src);
+    }
+
+    fn export_funcs(
+        &mut self,
+        resolve: &Resolve,
+        _world: WorldId,
+        funcs: &[(&str, &Function)],
+        _files: &mut Files,
+    ) -> Result<()> {
+        self.check()?;
+        let mut src = Source::default();
+        for func in self.funcs(funcs.iter().map(|(_, f)| *f)) {
+            self.export_func(resolve, &[], None, func, &mut src);
+        }
+        self.modules.entry(Vec::new()).or_default().push_str(&src);
+        Ok(())
+    }
+
+    fn import_types(
+        &mut self,
+        resolve: &Resolve,
+        world: WorldId,
+        types: &[(&str, TypeId)],
+        _files: &mut Files,
+    ) {
+        if self.error.is_some() {
+            return;
+        }
+        let mut src = Source::default();
+        let identifier = Identifier::World(world);
+        if let Err(e) = self.define_types(resolve, &[], identifier, true, types, &mut src) {
+            self.error = Some(e);
+            return;
+        }
+        self.modules.entry(Vec::new()).or_default().push_str(&src);
+    }
+
+    fn finish(&mut self, resolve: &Resolve, world: WorldId, files: &mut Files) -> Result<()> {
+        self.check()?;
+        self.types.check_with_used()?;
+        self.opts.async_.ensure_all_used()?;
+
+        let world_name = &resolve.worlds[world].name;
+        // Any `use` of interfaces remapped through `with`.
+        let mut src = mem::take(&mut self.types.src);
+        src.push_str(&self.finish_register(resolve, world));
+        if self.opts.stubs {
+            uwriteln!(src, "#[derive(Debug)]\npub struct Stub;");
+            src.push_str(&self.stubs);
+        }
+        src.push_str(&format!(
+            "
+            /// An instance of a module built for the `{world_name}` world.
+            pub struct Instance {{
+                exec_env: _rt::wasm_exec_env_t,
+            }}
+
+            impl Instance {{
+                /// Wraps the WAMR execution environment `exec_env`.
+                ///
+                /// # Safety
+                ///
+                /// `exec_env` must belong to an instance of a module built for
+                /// this world and must outlive the returned value.
+                pub unsafe fn from_raw(exec_env: *mut ::core::ffi::c_void) -> Instance {{
+                    Instance {{ exec_env }}
+                }}
+
+                pub fn as_raw(&self) -> *mut ::core::ffi::c_void {{
+                    self.exec_env
+                }}
+            }}
+
+            pub use _rt::{{Registration, Trap}};
+            "
+        ));
+        src.push_str(RUNTIME);
+        if !self.async_funcs.is_empty() {
+            src.push_str(ASYNC_RUNTIME);
+        }
+        for ty in ["i32", "i64", "f32", "f64"] {
+            let from: &[&str] = match ty {
+                "i32" => &["i32", "u32", "i16", "u16", "i8", "u8", "char", "bool"],
+                "i64" => &["i64", "u64"],
+                _ => &[ty],
+            };
+            as_trait(&mut src, ty, from);
+        }
+        uwriteln!(src, "}}");
+
+        let mut modules = mem::take(&mut self.modules);
+        let mut root = modules.remove(&Vec::new()).unwrap_or_default();
+        root.push_str(&src);
+        emit_modules(&mut root, &modules, &[], self.opts.format);
+
+        let mut src = root;
+        if self.opts.format {
+            let syntax_tree = syn::parse_file(src.as_str()).unwrap();
+            *src.as_mut_string() = prettyplease::unparse(&syntax_tree);
+        }
+        let mut preamble = mem::take(&mut self.types.src_preamble);
+        uwriteln!(preamble, "//   * host");
+        *src.as_mut_string() = format!("{}{}", preamble.as_str(), src.as_str());
+
+        files.push(
+            &format!("{}.rs", world_name.to_snake_case()),
+            src.as_bytes(),
+        );
+        Ok(())
+    }
+}
+
+/// Writes the modules nested directly within `parent`.
+fn emit_modules(
+    src: &mut Source,
+    modules: &BTreeMap<Vec<String>, Source>,
+    parent: &[String],
+    format: bool,
+) {
+    let mut children = modules
+        .keys()
+        .filter(|path| path.len() > parent.len() && path.starts_with(parent))
+        .map(|path| &path[parent.len()])
+        .collect::<Vec<_>>();
+    children.dedup();
+    for child in children {
+        let mut path = parent.to_vec();
+        path.push(child.clone());
+        if parent.is_empty() {
+            if format {
+                uwriteln!(src, "#[rustfmt::skip]");
+            }
+            uwriteln!(src, "#[allow(dead_code, clippy::all)]");
+        }
+        uwriteln!(src, "pub mod {child} {{");
+        if let Some(contents) = modules.get(&path) {
+            src.push_str(contents);
+        }
+        emit_modules(src, modules, &path, format);
+        uwriteln!(src, "}}");
+    }
+}
+
+fn check_type(resolve: &Resolve, ty: &Type) -> Result<()> {
+    let id = match ty {
+        Type::ErrorContext => bail!("host bindings do not support error contexts"),
+        Type::Id(id) => *id,
+        _ => return Ok(()),
+    };
+    match &resolve.types[id].kind {
+        TypeDefKind::Record(r) => {
+            for field in r.fields.iter() {
+                check_type(resolve, &field.ty)?;
+            }
+        }
+        TypeDefKind::Tuple(t) => {
+            for ty in t.types.iter() {
+                check_type(resolve, ty)?;
+            }
+        }
+        TypeDefKind::Variant(v) => {
+            for ty in v.cases.iter().filter_map(|c| c.ty.as_ref()) {
+                check_type(resolve, ty)?;
+            }
+        }
+        TypeDefKind::Flags(f) => {
+            if f.flags.is_empty() || f.flags.len() > 64 {
+                bail!("host bindings only support flags with 1 to 64 members");
+            }
+        }
+        TypeDefKind::Enum(_) => {}
+        TypeDefKind::Option(ty)
+        | TypeDefKind::List(ty)
+        | TypeDefKind::FixedSizeList(ty, _)
+        | TypeDefKind::Type(ty) => check_type(resolve, ty)?,
+        TypeDefKind::Result(r) => {
+            for ty in r.ok.iter().chain(&r.err) {
+                check_type(resolve, ty)?;
+            }
+        }
+        TypeDefKind::Resource | TypeDefKind::Handle(_) => {
+            bail!("host bindings do not support resources")
+        }
+        TypeDefKind::Future(_) => bail!("host bindings do not support futures"),
+        TypeDefKind::Stream(_) => bail!("host bindings do not support streams"),
+        TypeDefKind::Unknown => unreachable!(),
+    }
+    Ok(())
+}
+
+fn define_flags(name: &str, flags: &Flags, src: &mut Source) {
+    let repr = flags_repr(flags);
+    src.push_str(&format!(
+        "#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+         pub struct {name} {{
+             bits: {repr},
+         }}
+
+         impl {name} {{
+     "
+    ));
+    for (i, flag) in flags.flags.iter().enumerate() {
+        docs(src, &flag.docs);
+        uwriteln!(
+            src,
+            "pub const {}: {name} = {name} {{ bits: 1 << {i} }};",
+            flag.name.to_shouty_snake_case()
+        );
+    }
+    let all = match flags.flags.len() {
+        64 => u64::MAX,
+        n => (1u64 << n) - 1,
+    };
+    src.push_str(&format!(
+        "
+            pub const fn empty() -> {name} {{
+                {name} {{ bits: 0 }}
+            }}
+
+            pub const fn all() -> {name} {{
+                {name} {{ bits: {all:#x} }}
+            }}
+
+            pub const fn bits(&self) -> {repr} {{
+                self.bits
+            }}
+
+            /// Creates a set from its bit representation, keeping bits
+            /// which don't correspond to a flag.
+            pub const fn from_bits_retain(bits: {repr}) -> {name} {{
+                {name} {{ bits }}
+            }}
+
+            pub const fn is_empty(&self) -> bool {{
+                self.bits == 0
+            }}
+
+            pub const fn contains(&self, other: {name}) -> bool {{
+                self.bits & other.bits == other.bits
+            }}
+        }}
+
+        impl ::core::ops::BitOr for {name} {{
+            type Output = {name};
+            fn bitor(self, rhs: {name}) -> {name} {{
+                {name} {{ bits: self.bits | rhs.bits }}
+            }}
+        }}
+
+        impl ::core::ops::BitOrAssign for {name} {{
+            fn bitor_assign(&mut self, rhs: {name}) {{
+                self.bits |= rhs.bits;
+            }}
+        }}
+
+        impl ::core::ops::BitAnd for {name} {{
+            type Output = {name};
+            fn bitand(self, rhs: {name}) -> {name} {{
+                {name} {{ bits: self.bits & rhs.bits }}
+            }}
+        }}
+
+        impl ::core::ops::Not for {name} {{
+            type Output = {name};
+            fn not(self) -> {name} {{
+                {name} {{ bits: !self.bits & {all:#x} }}
+            }}
+        }}
+        "
+    ));
+}
+
+/// Returns the name which `--async` matches `func` against.
+fn async_name(resolve: &Resolve, interface: Option<&WorldKey>, func: &Function) -> String {
+    match interface {
+        Some(key) => format!("{}#{}", resolve.name_world_key(key), func.name),
+        None => func.name.clone(),
+    }
+}
+
+fn docs(src: &mut Source, docs: &Docs) {
+    if let Some(contents) = &docs.contents {
+        for line in contents.trim().lines() {
+            uwriteln!(src, "/// {line}");
+        }
+    }
+}
+
+/// Names of export parameters, which must not clash with the generated
+/// `instance` parameter or the `exec_env` local.
+fn param_name(name: &str) -> String {
+    let name = to_rust_ident(name);
+    match name.as_str() {
+        "instance" | "exec_env" => format!("{name}_"),
+        _ => name,
+    }
+}
+
+fn flags_repr(flags: &Flags) -> &'static str {
+    match flags.repr() {
+        FlagsRepr::U8 => "u8",
+        FlagsRepr::U16 => "u16",
+        FlagsRepr::U32(1) => "u32",
+        FlagsRepr::U32(2) => "u64",
+        FlagsRepr::U32(n) => unreachable!("unsupported number of flags: {}", n * 32),
+    }
+}
+
+fn wasm_type(ty: WasmType) -> &'static str {
+    match ty {
+        WasmType::I32 | WasmType::Pointer | WasmType::Length => "i32",
+        WasmType::I64 | WasmType::PointerOrI64 => "i64",
+        WasmType::F32 => "f32",
+        WasmType::F64 => "f64",
+    }
+}
+
+/// The character WAMR uses for `ty` in the signature of a native symbol.
+fn signature_char(ty: WasmType) -> char {
+    match ty {
+        WasmType::I32 | WasmType::Pointer | WasmType::Length => 'i',
+        WasmType::I64 | WasmType::PointerOrI64 => 'I',
+        WasmType::F32 => 'f',
+        WasmType::F64 => 'F',
+    }
+}
+
+fn as_trait(src: &mut Source, ty: &str, from: &[&str]) {
+    let upcase = ty.to_uppercase();
+    src.push_str(&format!(
+        "
+        pub fn as_{ty}<T: As{upcase}>(t: T) -> {ty} {{
+            t.as_{ty}()
+        }}
+
+        pub trait As{upcase} {{
+            fn as_{ty}(self) -> {ty};
+        }}
+
+        impl<'a, T: Copy + As{upcase}> As{upcase} for &'a T {{
+            fn as_{ty}(self) -> {ty} {{
+                (*self).as_{ty}()
+            }}
+        }}
+        "
+    ));
+    for from in from {
+        src.push_str(&format!(
+            "
+            impl As{upcase} for {from} {{
+                #[inline]
+                fn as_{ty}(self) -> {ty} {{
+                    self as {ty}
+                }}
+            }}
+            "
+        ));
+    }
+}
+
+struct FunctionBindgen<'a> {
+    r#gen: &'a WamrHost,
+    module: &'a [String],
+    rt: String,
+    params: Vec<String>,
+    src: Source,
+    blocks: Vec<String>,
+    block_storage: Vec<Source>,
+    tmp: usize,
+    /// Core wasm names of the export being called and its post-return
+    /// function, or its callback if it's async.
+    export_name: String,
+    post_return: String,
+    callback: String,
+    /// The array holding the results of the export being called.
+    call_results: Option<String>,
+    /// The array holding the arguments of the async export being called,
+    /// which is only called once its `task.return` handler is known.
+    async_args: Option<String>,
+    /// Name and parameters of the `task.return` import of the async export
+    /// being called.
+    task_return: Option<(String, Vec<WasmType>)>,
+    /// The value returned by the async import being implemented.
+    async_result: Option<String>,
+    /// Path to the trait implementing the import being called.
+    host_trait: String,
+}
+
+impl<'a> FunctionBindgen<'a> {
+    fn new(r#gen: &'a WamrHost, module: &'a [String], params: Vec<String>) -> FunctionBindgen<'a> {
+        FunctionBindgen {
+            r#gen,
+            module,
+            rt: format!("{}_rt", WamrHost::root(module)),
+            params,
+            src: Source::default(),
+            blocks: Vec::new(),
+            block_storage: Vec::new(),
+            tmp: 0,
+            export_name: String::new(),
+            post_return: String::new(),
+            callback: String::new(),
+            call_results: None,
+            async_args: None,
+            task_return: None,
+            async_result: None,
+            host_trait: String::new(),
+        }
+    }
+
+    fn tmp(&mut self) -> usize {
+        let ret = self.tmp;
+        self.tmp += 1;
+        ret
+    }
+
+    fn type_path(&self, resolve: &Resolve, id: TypeId) -> String {
+        self.r#gen.type_path(resolve, self.module, id)
+    }
+
+    fn let_results(&mut self, amt: usize, results: &mut Vec<String>) {
+        let tmp = self.tmp();
+        match amt {
+            0 => {}
+            1 => {
+                uwrite!(self.src, "let result{tmp} = ");
+                results.push(format!("result{tmp}"));
+            }
+            n => {
+                uwrite!(self.src, "let (");
+                for i in 0..n {
+                    uwrite!(self.src, "result{tmp}_{i}, ");
+                    results.push(format!("result{tmp}_{i}"));
+                }
+                uwrite!(self.src, ") = ");
+            }
+        }
+    }
+
+    fn load(&mut self, ty: &str, operands: &[String], offset: ArchitectureSize) -> String {
+        format!(
+            "{}::load::<{ty}>(exec_env, {}, {})?",
+            self.rt,
+            operands[0],
+            offset.size_wasm32()
+        )
+    }
+
+    fn store(&mut self, ty: &str, operands: &[String], offset: ArchitectureSize) {
+        // Only the narrow stores take an `i32` which needs to be truncated.
+        let value = match ty {
+            "u8" | "u16" => format!("{} as {ty}", operands[0]),
+            _ => operands[0].clone(),
+        };
+        uwriteln!(
+            self.src,
+            "{}::store::<{ty}>(exec_env, {}, {}, {value})?;",
+            self.rt,
+            operands[1],
+            offset.size_wasm32(),
+        );
+    }
+
+    fn invalid_discriminant(&self, what: &str) -> String {
+        format!(
+            "return Err({}::Trap::new(\"invalid {what} discriminant\"))",
+            self.rt
+        )
+    }
+}
+
+impl Bindgen for FunctionBindgen<'_> {
+    type Operand = String;
+
+    fn push_block(&mut self) {
+        let prev_src = mem::take(&mut self.src);
+        self.block_storage.push(prev_src);
+    }
+
+    fn finish_block(&mut self, operands: &mut Vec<String>) {
+        let prev_src = self.block_storage.pop().unwrap();
+        let src = mem::replace(&mut self.src, prev_src);
+        let expr = match operands.len() {
+            0 => "()".to_string(),
+            1 => operands[0].clone(),
+            _ => format!("({})", operands.join(", ")),
+        };
+        if src.is_empty() {
+            self.blocks.push(expr);
+        } else if operands.is_empty() {
+            self.blocks.push(format!("{{\n{}}}", &src[..]));
+        } else {
+            self.blocks.push(format!("{{\n{}\n{}\n}}", &src[..], expr));
+        }
+    }
+
+    fn return_pointer(&mut self, _size: ArchitectureSize, _align: Alignment) -> String {
+        unreachable!("return areas are provided by the module")
+    }
+
+    fn sizes(&self) -> &SizeAlign {
+        &self.r#gen.sizes
+    }
+
+    fn layouts(&self) -> Option<&Layouts> {
+        self.r#gen.layouts.as_deref()
+    }
+
+    fn is_list_canonical(&self, resolve: &Resolve, ty: &Type) -> bool {
+        let ty = match ty {
+            Type::Id(id) => match &resolve.types[dealias(resolve, *id)].kind {
+                TypeDefKind::Type(ty) => *ty,
+                _ => return false,
+            },
+            ty => *ty,
+        };
+        matches!(
+            ty,
+            Type::U8
+                | Type::S8
+                | Type::U16
+                | Type::S16
+                | Type::U32
+                | Type::S32
+                | Type::U64
+                | Type::S64
+                | Type::F32
+                | Type::F64
+        )
+    }
+
+    fn emit(
+        &mut self,
+        resolve: &Resolve,
+        inst: &Instruction<'_>,
+        operands: &mut Vec<String>,
+        results: &mut Vec<String>,
+    ) {
+        let rt = self.rt.clone();
+        match inst {
+            Instruction::GetArg { nth } => results.push(self.params[*nth].clone()),
+            Instruction::I32Const { val } => results.push(format!("{val}i32")),
+            Instruction::ConstZero { tys } => {
+                for ty in tys.iter() {
+                    results.push(match ty {
+                        WasmType::I32 | WasmType::Pointer | WasmType::Length => "0i32".into(),
+                        WasmType::I64 | WasmType::PointerOrI64 => "0i64".into(),
+                        WasmType::F32 => "0.0f32".into(),
+                        WasmType::F64 => "0.0f64".into(),
+                    });
+                }
+            }
+            Instruction::Bitcasts { casts } => {
+                for (cast, operand) in casts.iter().zip(operands.iter()) {
+                    results.push(host_cast(operand, cast));
+                }
+            }
+
+            Instruction::I32FromChar
+            | Instruction::I32FromBool
+            | Instruction::I32FromU8
+            | Instruction::I32FromS8
+            | Instruction::I32FromU16
+            | Instruction::I32FromS16
+            | Instruction::I32FromU32
+            | Instruction::I32FromS32 => results.push(format!("{rt}::as_i32({})", operands[0])),
+            Instruction::I64FromU64 | Instruction::I64FromS64 => {
+                results.push(format!("{rt}::as_i64({})", operands[0]))
+            }
+            Instruction::CoreF32FromF32 => results.push(format!("{rt}::as_f32({})", operands[0])),
+            Instruction::CoreF64FromF64 => results.push(format!("{rt}::as_f64({})", operands[0])),
+
+            Instruction::S8FromI32 => results.push(format!("({} as i8)", operands[0])),
+            Instruction::U8FromI32 => results.push(format!("({} as u8)", operands[0])),
+            Instruction::S16FromI32 => results.push(format!("({} as i16)", operands[0])),
+            Instruction::U16FromI32 => results.push(format!("({} as u16)", operands[0])),
+            Instruction::U32FromI32 => results.push(format!("({} as u32)", operands[0])),
+            Instruction::U64FromI64 => results.push(format!("({} as u64)", operands[0])),
+            Instruction::S32FromI32
+            | Instruction::S64FromI64
+            | Instruction::F32FromCoreF32
+            | Instruction::F64FromCoreF64 => results.push(operands[0].clone()),
+            Instruction::CharFromI32 => results.push(format!("{rt}::char_lift({})?", operands[0])),
+            Instruction::BoolFromI32 => results.push(format!("({} != 0)", operands[0])),
+
+            Instruction::I32Load { offset }
+            | Instruction::PointerLoad { offset }
+            | Instruction::LengthLoad { offset } => {
+                results.push(self.load("i32", operands, *offset))
+            }
+            Instruction::I32Load8U { offset } => {
+                let load = self.load("u8", operands, *offset);
+                results.push(format!("i32::from({load})"));
+            }
+            Instruction::I32Load8S { offset } => {
+                let load = self.load("i8", operands, *offset);
+                results.push(format!("i32::from({load})"));
+            }
+            Instruction::I32Load16U { offset } => {
+                let load = self.load("u16", operands, *offset);
+                results.push(format!("i32::from({load})"));
+            }
+            Instruction::I32Load16S { offset } => {
+                let load = self.load("i16", operands, *offset);
+                results.push(format!("i32::from({load})"));
+            }
+            Instruction::I64Load { offset } => results.push(self.load("i64", operands, *offset)),
+            Instruction::F32Load { offset } => results.push(self.load("f32", operands, *offset)),
+            Instruction::F64Load { offset } => results.push(self.load("f64", operands, *offset)),
+
+            Instruction::I32Store { offset }
+            | Instruction::PointerStore { offset }
+            | Instruction::LengthStore { offset } => self.store("i32", operands, *offset),
+            Instruction::I32Store8 { offset } => self.store("u8", operands, *offset),
+            Instruction::I32Store16 { offset } => self.store("u16", operands, *offset),
+            Instruction::I64Store { offset } => self.store("i64", operands, *offset),
+            Instruction::F32Store { offset } => self.store("f32", operands, *offset),
+            Instruction::F64Store { offset } => self.store("f64", operands, *offset),
+
+            Instruction::ListCanonLower { element, .. } => {
+                let tmp = self.tmp();
+                let align = self.r#gen.sizes.align(element).align_wasm32();
+                uwriteln!(self.src, "let vec{tmp} = &({})[..];", operands[0]);
+                uwriteln!(
+                    self.src,
+                    "let ptr{tmp} = {rt}::store_list(exec_env, vec{tmp}, {align})?;"
+                );
+                results.push(format!("ptr{tmp}"));
+                results.push(format!("vec{tmp}.len() as i32"));
+            }
+            Instruction::StringLower { .. } => {
+                let tmp = self.tmp();
+                uwriteln!(self.src, "let vec{tmp} = ({}).as_bytes();", operands[0]);
+                uwriteln!(
+                    self.src,
+                    "let ptr{tmp} = {rt}::store_list(exec_env, vec{tmp}, 1)?;"
+                );
+                results.push(format!("ptr{tmp}"));
+                results.push(format!("vec{tmp}.len() as i32"));
+            }
+            Instruction::ListLower { element, .. } => {
+                let body = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                let size = self.r#gen.sizes.size(element).size_wasm32();
+                let align = self.r#gen.sizes.align(element).align_wasm32();
+                uwriteln!(self.src, "let vec{tmp} = &({})[..];", operands[0]);
+                uwriteln!(
+                    self.src,
+                    "let result{tmp} = {rt}::alloc(exec_env, vec{tmp}.len() * {size}, {align})?;"
+                );
+                uwriteln!(self.src, "for (i, e) in vec{tmp}.iter().enumerate() {{");
+                uwriteln!(
+                    self.src,
+                    "let base = result{tmp}.wrapping_add((i * {size}) as i32);"
+                );
+                uwriteln!(self.src, "{body}");
+                uwriteln!(self.src, "}}");
+                results.push(format!("result{tmp}"));
+                results.push(format!("vec{tmp}.len() as i32"));
+            }
+            Instruction::ListCanonLift { element, .. } => {
+                let ty = self.r#gen.type_name(resolve, self.module, element, false);
+                results.push(format!(
+                    "{rt}::load_list::<{ty}>(exec_env, {}, {})?",
+                    operands[0], operands[1]
+                ));
+            }
+            Instruction::StringLift => results.push(format!(
+                "{rt}::load_string(exec_env, {}, {})?",
+                operands[0], operands[1]
+            )),
+            Instruction::ListLift { element, .. } => {
+                let body = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                let size = self.r#gen.sizes.size(element).size_wasm32();
+                uwriteln!(self.src, "let base{tmp} = {};", operands[0]);
+                uwriteln!(
+                    self.src,
+                    "let len{tmp} = {rt}::list_len(exec_env, base{tmp}, {}, {size})?;",
+                    operands[1]
+                );
+                uwriteln!(
+                    self.src,
+                    "let mut result{tmp} = ::std::vec::Vec::with_capacity(len{tmp});"
+                );
+                uwriteln!(self.src, "for i in 0..len{tmp} {{");
+                uwriteln!(
+                    self.src,
+                    "let base = base{tmp}.wrapping_add((i * {size}) as i32);"
+                );
+                uwriteln!(self.src, "let e{tmp} = {body};");
+                uwriteln!(self.src, "result{tmp}.push(e{tmp});");
+                uwriteln!(self.src, "}}");
+                results.push(format!("result{tmp}"));
+            }
+            Instruction::FixedSizeListLift { size, .. } => {
+                let tmp = self.tmp();
+                let elems = operands.drain(..*size as usize).collect::<Vec<_>>();
+                uwriteln!(self.src, "let result{tmp} = [{}];", elems.join(", "));
+                results.push(format!("result{tmp}"));
+            }
+            Instruction::FixedSizeListLower { size, .. } => {
+                let tmp = self.tmp();
+                let elems = (0..*size)
+                    .map(|i| format!("e{tmp}_{i}"))
+                    .collect::<Vec<_>>();
+                uwriteln!(self.src, "let [{}] = {};", elems.join(", "), operands[0]);
+                results.extend(elems);
+            }
+            Instruction::FixedSizeListLowerMemory { element, .. } => {
+                let body = self.blocks.pop().unwrap();
+                let size = self.r#gen.sizes.size(element).size_wasm32();
+                uwriteln!(
+                    self.src,
+                    "for (i, e) in ({}).iter().enumerate() {{",
+                    operands[0]
+                );
+                uwriteln!(
+                    self.src,
+                    "let base = {}.wrapping_add((i * {size}) as i32);",
+                    operands[1]
+                );
+                uwriteln!(self.src, "{body}");
+                uwriteln!(self.src, "}}");
+            }
+            Instruction::IterElem { .. } => results.push("e".to_string()),
+            Instruction::IterBasePointer => results.push("base".to_string()),
+
+            Instruction::RecordLower { record, ty, .. } => {
+                let tmp = self.tmp();
+                let path = self.type_path(resolve, *ty);
+                uwrite!(self.src, "let {path} {{ ");
+                for (i, field) in record.fields.iter().enumerate() {
+                    uwrite!(self.src, "{}: t{tmp}_{i}, ", to_rust_ident(&field.name));
+                    results.push(format!("t{tmp}_{i}"));
+                }
+                uwriteln!(self.src, "}} = {};", operands[0]);
+            }
+            Instruction::RecordLift { record, ty, .. } => {
+                let tmp = self.tmp();
+                let path = self.type_path(resolve, *ty);
+                uwrite!(self.src, "let r{tmp} = {path} {{ ");
+                for (field, operand) in record.fields.iter().zip(operands.iter()) {
+                    uwrite!(self.src, "{}: {operand}, ", to_rust_ident(&field.name));
+                }
+                uwriteln!(self.src, "}};");
+                results.push(format!("r{tmp}"));
+            }
+            Instruction::TupleLower { tuple, .. } => {
+                let tmp = self.tmp();
+                uwrite!(self.src, "let (");
+                for i in 0..tuple.types.len() {
+                    uwrite!(self.src, "t{tmp}_{i}, ");
+                    results.push(format!("t{tmp}_{i}"));
+                }
+                uwriteln!(self.src, ") = {};", operands[0]);
+            }
+            Instruction::TupleLift { .. } => {
+                let tmp = self.tmp();
+                uwriteln!(self.src, "let t{tmp} = ({}, );", operands.join(", "));
+                results.push(format!("t{tmp}"));
+            }
+
+            Instruction::FlagsLower { flags, .. } => {
+                let op = &operands[0];
+                match flags_repr(flags) {
+                    "u64" => {
+                        results.push(format!("(({op}).bits() as i32)"));
+                        results.push(format!("((({op}).bits() >> 32) as i32)"));
+                    }
+                    "u32" => results.push(format!("(({op}).bits() as i32)")),
+                    _ => results.push(format!("i32::from(({op}).bits())")),
+                }
+            }
+            Instruction::FlagsLift { flags, ty, .. } => {
+                let path = self.type_path(resolve, *ty);
+                let bits = match flags_repr(flags) {
+                    "u64" => format!(
+                        "({} as u32 as u64) | (({} as u32 as u64) << 32)",
+                        operands[0], operands[1]
+                    ),
+                    repr => format!("{} as {repr}", operands[0]),
+                };
+                results.push(format!("{path}::from_bits_retain({bits})"));
+            }
+
+            Instruction::VariantPayloadName => results.push("e".to_string()),
+            Instruction::VariantLower {
+                variant,
+                ty,
+                results: result_types,
+                ..
+            } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let path = self.type_path(resolve, *ty);
+                self.let_results(result_types.len(), results);
+                uwriteln!(self.src, "match {} {{", operands[0]);
+                for (case, block) in variant.cases.iter().zip(blocks) {
+                    let case_name = case.name.to_upper_camel_case();
+                    if case.ty.is_some() {
+                        uwriteln!(self.src, "{path}::{case_name}(e) => {block},");
+                    } else {
+                        uwriteln!(self.src, "{path}::{case_name} => {block},");
+                    }
+                }
+                uwriteln!(self.src, "}};");
+            }
+            Instruction::VariantLift { variant, ty, .. } => {
+                let blocks = self
+                    .blocks
+                    .drain(self.blocks.len() - variant.cases.len()..)
+                    .collect::<Vec<_>>();
+                let tmp = self.tmp();
+                let path = self.type_path(resolve, *ty);
+                uwriteln!(self.src, "let v{tmp} = match {} {{", operands[0]);
+                for (i, (case, block)) in variant.cases.iter().zip(blocks).enumerate() {
+                    let case_name = case.name.to_upper_camel_case();
+                    if case.ty.is_some() {
+                        uwriteln!(self.src, "{i} => {path}::{case_name}({block}),");
+                    } else {
+                        uwriteln!(self.src, "{i} => {path}::{case_name},");
+                    }
+                }
+                uwriteln!(self.src, "_ => {},", self.invalid_discriminant("variant"));
+                uwriteln!(self.src, "}};");
+                results.push(format!("v{tmp}"));
+            }
+            Instruction::EnumLower { enum_, ty, .. } => {
+                let tmp = self.tmp();
+                let path = self.type_path(resolve, *ty);
+                uwriteln!(self.src, "let e{tmp} = match {} {{", operands[0]);
+                for (i, case) in enum_.cases.iter().enumerate() {
+                    let case_name = case.name.to_upper_camel_case();
+                    uwriteln!(self.src, "{path}::{case_name} => {i}i32,");
+                }
+                uwriteln!(self.src, "}};");
+                results.push(format!("e{tmp}"));
+            }
+            Instruction::EnumLift { enum_, ty, .. } => {
+                let tmp = self.tmp();
+                let path = self.type_path(resolve, *ty);
+                uwriteln!(self.src, "let e{tmp} = match {} {{", operands[0]);
+                for (i, case) in enum_.cases.iter().enumerate() {
+                    let case_name = case.name.to_upper_camel_case();
+                    uwriteln!(self.src, "{i} => {path}::{case_name},");
+                }
+                uwriteln!(self.src, "_ => {},", self.invalid_discriminant("enum"));
+                uwriteln!(self.src, "}};");
+                results.push(format!("e{tmp}"));
+            }
+            Instruction::OptionLower {
+                results: result_types,
+                ..
+            } => {
+                let some = self.blocks.pop().unwrap();
+                let none = self.blocks.pop().unwrap();
+                self.let_results(result_types.len(), results);
+                self.src.push_str(&format!(
+                    "match {} {{
+                        Some(e) => {some},
+                        None => {none},
+                    }};
+                ",
+                    operands[0]
+                ));
+            }
+            Instruction::OptionLift { .. } => {
+                let some = self.blocks.pop().unwrap();
+                let _none = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                self.src.push_str(&format!(
+                    "let o{tmp} = match {} {{
+                        0 => None,
+                        1 => Some({some}),
+                        _ => {},
+                    }};
+                ",
+                    operands[0],
+                    self.invalid_discriminant("option")
+                ));
+                results.push(format!("o{tmp}"));
+            }
+            Instruction::ResultLower {
+                result,
+                results: result_types,
+                ..
+            } => {
+                let err = self.blocks.pop().unwrap();
+                let ok = self.blocks.pop().unwrap();
+                self.let_results(result_types.len(), results);
+                let ok_binding = if result.ok.is_some() { "e" } else { "_" };
+                let err_binding = if result.err.is_some() { "e" } else { "_" };
+                self.src.push_str(&format!(
+                    "match {} {{
+                        Ok({ok_binding}) => {ok},
+                        Err({err_binding}) => {err},
+                    }};
+                ",
+                    operands[0]
+                ));
+            }
+            Instruction::ResultLift { result, .. } => {
+                let err = self.blocks.pop().unwrap();
+                let ok = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                let ok = if result.ok.is_some() { ok } else { "()".into() };
+                let err = if result.err.is_some() {
+                    err
+                } else {
+                    "()".into()
+                };
+                self.src.push_str(&format!(
+                    "let r{tmp} = match {} {{
+                        0 => Ok({ok}),
+                        1 => Err({err}),
+                        _ => {},
+                    }};
+                ",
+                    operands[0],
+                    self.invalid_discriminant("result")
+                ));
+                results.push(format!("r{tmp}"));
+            }
+
+            Instruction::CallWasm {
+                sig, module_prefix, ..
+            } => {
+                let tmp = self.tmp();
+                uwrite!(self.src, "let mut args{tmp} = [");
+                for (ty, operand) in sig.params.iter().zip(operands.iter()) {
+                    uwrite!(self.src, "{rt}::val_{}({operand}), ", wasm_type(*ty));
+                }
+                uwriteln!(self.src, "];");
+                // Async exports are called by `AsyncCallbackLoop`; the status
+                // code they return is consumed there.
+                if module_prefix.starts_with("[async-lift") {
+                    self.async_args = Some(format!("args{tmp}"));
+                    results.extend(sig.results.iter().map(|_| "()".to_string()));
+                    return;
+                }
+                uwriteln!(
+                    self.src,
+                    "let mut ret{tmp} = [{rt}::wasm_val_t::default(); {}];",
+                    sig.results.len()
+                );
+                uwriteln!(
+                    self.src,
+                    "{rt}::call(exec_env, c{:?}, &mut args{tmp}, &mut ret{tmp})?;",
+                    self.export_name
+                );
+                for (i, ty) in sig.results.iter().enumerate() {
+                    results.push(format!("{rt}::get_{}(&ret{tmp}[{i}])", wasm_type(*ty)));
+                }
+                self.call_results = Some(format!("ret{tmp}"));
+            }
+            Instruction::CallInterface { func, async_ } => {
+                let tmp = self.tmp();
+                if func.result.is_some() {
+                    uwrite!(self.src, "let result{tmp} = ");
+                    results.push(format!("result{tmp}"));
+                    if *async_ {
+                        self.async_result = Some(format!("result{tmp}"));
+                    }
+                }
+                let args = ["host".to_string()]
+                    .into_iter()
+                    .chain(operands.iter().cloned())
+                    .collect::<Vec<_>>();
+                uwriteln!(
+                    self.src,
+                    "<H as {}>::{}({});",
+                    self.host_trait,
+                    to_rust_ident(&func.name),
+                    args.join(", ")
+                );
+            }
+            Instruction::Return { amt, .. } => {
+                let ret = match amt {
+                    0 => "()".to_string(),
+                    _ => operands[0].clone(),
+                };
+                match self.call_results.take() {
+                    // Results are lifted into owned values before the
+                    // post-return function may free them.
+                    Some(call_results) => {
+                        uwriteln!(self.src, "let ret = {ret};");
+                        uwriteln!(
+                            self.src,
+                            "{rt}::post_return(exec_env, c{:?}, &mut {call_results})?;",
+                            self.post_return
+                        );
+                        uwriteln!(self.src, "Ok(ret)");
+                    }
+                    None => uwriteln!(self.src, "Ok({ret})"),
+                }
+            }
+            Instruction::Malloc { size, align, .. } => {
+                let tmp = self.tmp();
+                uwriteln!(
+                    self.src,
+                    "let ptr{tmp} = {rt}::alloc(exec_env, {}, {})?;",
+                    size.size_wasm32(),
+                    align.align_wasm32()
+                );
+                results.push(format!("ptr{tmp}"));
+            }
+            Instruction::Flush { amt } => {
+                for operand in operands.iter().take(*amt) {
+                    let tmp = self.tmp();
+                    uwriteln!(self.src, "let f{tmp} = {operand};");
+                    results.push(format!("f{tmp}"));
+                }
+            }
+
+            Instruction::HandleLower { .. }
+            | Instruction::HandleLift { .. }
+            | Instruction::FutureLower { .. }
+            | Instruction::FutureLift { .. }
+            | Instruction::StreamLower { .. }
+            | Instruction::StreamLift { .. }
+            | Instruction::ErrorContextLower
+            | Instruction::ErrorContextLift
+            | Instruction::GuestDeallocate { .. }
+            | Instruction::GuestDeallocateString
+            | Instruction::GuestDeallocateList { .. }
+            | Instruction::GuestDeallocateVariant { .. }
+            | Instruction::DropHandle { .. }
+            | Instruction::AsyncTaskReturn { .. } => {
+                unreachable!("unsupported instruction in host bindings: {}", inst.name())
+            }
+
+            Instruction::AsyncCallbackLoop {
+                func,
+                callback,
+                task_return,
+                params,
+            } => {
+                let block = self.blocks.pop().unwrap();
+                let tmp = self.tmp();
+                let args = self.async_args.take().unwrap();
+                let callback = match callback {
+                    Some(_) => format!("Some(c{:?})", self.callback),
+                    None => "None".to_string(),
+                };
+                let task_return_arg = if params.is_empty() {
+                    "_"
+                } else {
+                    "task_return"
+                };
+                let binding = if func.result.is_some() {
+                    format!("let result{tmp} = ")
+                } else {
+                    String::new()
+                };
+                uwriteln!(
+                    self.src,
+                    "{binding}{rt}::call_async(
+                        exec_env,
+                        c{:?},
+                        &mut {args},
+                        {callback},
+                        move |{task_return_arg}: &[{rt}::wasm_val_t]| -> ::core::result::Result<_, {rt}::Trap> {{
+                            Ok({block})
+                        }},
+                    )?;",
+                    self.export_name,
+                );
+                if func.result.is_some() {
+                    results.push(format!("result{tmp}"));
+                }
+                self.task_return = Some((task_return.to_string(), params.to_vec()));
+            }
+            Instruction::AsyncTaskReturnArg { nth, ty } => {
+                results.push(format!("{rt}::get_{}(&task_return[{nth}])", wasm_type(*ty)));
+            }
+            Instruction::AsyncSubtaskResult => {
+                results.push(self.async_result.clone().unwrap());
+            }
+            Instruction::AsyncSubtaskStart { .. } => {
+                // Host functions complete before returning, so the result is
+                // written immediately and no subtask is ever created.
+                let block = self.blocks.pop().unwrap();
+                if block != "()" {
+                    uwriteln!(self.src, "{block}");
+                }
+                results.push(format!("{rt}::SUBTASK_RETURNED"));
+            }
+        }
+    }
+}
+
+/// Like `perform_cast`, except that pointers and lengths are guest addresses
+/// represented as `i32`.
+fn host_cast(operand: &str, cast: &Bitcast) -> String {
+    match cast {
+        Bitcast::I32ToP
+        | Bitcast::PToI32
+        | Bitcast::I32ToL
+        | Bitcast::LToI32
+        | Bitcast::PToL
+        | Bitcast::LToP
+        | Bitcast::P64ToI64
+        | Bitcast::I64ToP64 => operand.to_owned(),
+        Bitcast::PToP64 | Bitcast::LToI64 => format!("i64::from({operand})"),
+        Bitcast::P64ToP | Bitcast::I64ToL => format!("({operand} as i32)"),
+        Bitcast::Sequence(sequence) => {
+            let [first, second] = &**sequence;
+            host_cast(&host_cast(operand, first), second)
+        }
+        cast => perform_cast(operand, cast),
+    }
+}
+
+const RUNTIME: &str = r#"
+#[allow(dead_code, non_camel_case_types, unsafe_op_in_unsafe_fn, clippy::all)]
+mod _rt {
+    use ::core::ffi::{c_char, c_void, CStr};
+    pub use ::std::string::String;
+    pub use ::std::vec::Vec;
+
+    #[cfg(target_endian = "big")]
+    compile_error!("WAMR host bindings require a little-endian host");
+
+    pub type wasm_exec_env_t = *mut c_void;
+    pub type wasm_module_inst_t = *mut c_void;
+    pub type wasm_function_inst_t = *mut c_void;
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub union wasm_val_of_t {
+        pub i32: i32,
+        pub i64: i64,
+        pub f32: f32,
+        pub f64: f64,
+        pub ref_: *mut c_void,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    pub struct wasm_val_t {
+        pub kind: u8,
+        pub _paddings: [u8; 7],
+        pub of: wasm_val_of_t,
+    }
+
+    impl Default for wasm_val_t {
+        fn default() -> wasm_val_t {
+            val_i64(0)
+        }
+    }
+
+    #[repr(C)]
+    pub struct NativeSymbol {
+        pub symbol: *const c_char,
+        pub func_ptr: *mut c_void,
+        pub signature: *const c_char,
+        pub attachment: *mut c_void,
+    }
+
+    unsafe extern "C" {
+        fn wasm_runtime_get_module_inst(exec_env: wasm_exec_env_t) -> wasm_module_inst_t;
+        fn wasm_runtime_lookup_function(
+            module_inst: wasm_module_inst_t,
+            name: *const c_char,
+        ) -> wasm_function_inst_t;
+        fn wasm_runtime_call_wasm_a(
+            exec_env: wasm_exec_env_t,
+            function: wasm_function_inst_t,
+            num_results: u32,
+            results: *mut wasm_val_t,
+            num_args: u32,
+            args: *mut wasm_val_t,
+        ) -> bool;
+        fn wasm_runtime_get_exception(module_inst: wasm_module_inst_t) -> *const c_char;
+        fn wasm_runtime_set_exception(module_inst: wasm_module_inst_t, exception: *const c_char);
+        fn wasm_runtime_clear_exception(module_inst: wasm_module_inst_t);
+        fn wasm_runtime_validate_app_addr(
+            module_inst: wasm_module_inst_t,
+            app_offset: u64,
+            size: u64,
+        ) -> bool;
+        fn wasm_runtime_addr_app_to_native(
+            module_inst: wasm_module_inst_t,
+            app_offset: u64,
+        ) -> *mut c_void;
+        fn wasm_runtime_register_natives(
+            module_name: *const c_char,
+            native_symbols: *mut NativeSymbol,
+            n_native_symbols: u32,
+        ) -> bool;
+        fn wasm_runtime_unregister_natives(module_name: *const c_char, native_symbols: *mut NativeSymbol) -> bool;
+        fn wasm_runtime_get_function_attachment(exec_env: wasm_exec_env_t) -> *mut c_void;
+    }
+
+    /// An error raised by a module, or while exchanging values with it.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Trap {
+        message: String,
+    }
+
+    impl Trap {
+        pub fn new(message: impl Into<String>) -> Trap {
+            Trap {
+                message: message.into(),
+            }
+        }
+
+        pub fn message(&self) -> &str {
+            &self.message
+        }
+    }
+
+    impl ::core::fmt::Display for Trap {
+        fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+            f.write_str(&self.message)
+        }
+    }
+
+    impl ::std::error::Error for Trap {}
+
+    pub fn val_i32(v: i32) -> wasm_val_t {
+        wasm_val_t { kind: 0, _paddings: [0; 7], of: wasm_val_of_t { i32: v } }
+    }
+
+    pub fn val_i64(v: i64) -> wasm_val_t {
+        wasm_val_t { kind: 1, _paddings: [0; 7], of: wasm_val_of_t { i64: v } }
+    }
+
+    pub fn val_f32(v: f32) -> wasm_val_t {
+        wasm_val_t { kind: 2, _paddings: [0; 7], of: wasm_val_of_t { f32: v } }
+    }
+
+    pub fn val_f64(v: f64) -> wasm_val_t {
+        wasm_val_t { kind: 3, _paddings: [0; 7], of: wasm_val_of_t { f64: v } }
+    }
+
+    pub unsafe fn get_i32(v: &wasm_val_t) -> i32 {
+        v.of.i32
+    }
+
+    pub unsafe fn get_i64(v: &wasm_val_t) -> i64 {
+        v.of.i64
+    }
+
+    pub unsafe fn get_f32(v: &wasm_val_t) -> f32 {
+        v.of.f32
+    }
+
+    pub unsafe fn get_f64(v: &wasm_val_t) -> f64 {
+        v.of.f64
+    }
+
+    unsafe fn exception(module_inst: wasm_module_inst_t) -> Trap {
+        let message = wasm_runtime_get_exception(module_inst);
+        let trap = if message.is_null() {
+            Trap::new("unknown exception")
+        } else {
+            Trap::new(CStr::from_ptr(message).to_string_lossy())
+        };
+        wasm_runtime_clear_exception(module_inst);
+        trap
+    }
+
+    unsafe fn call_function(
+        exec_env: wasm_exec_env_t,
+        function: wasm_function_inst_t,
+        args: &mut [wasm_val_t],
+        results: &mut [wasm_val_t],
+    ) -> Result<(), Trap> {
+        let ok = wasm_runtime_call_wasm_a(
+            exec_env,
+            function,
+            results.len() as u32,
+            results.as_mut_ptr(),
+            args.len() as u32,
+            args.as_mut_ptr(),
+        );
+        if ok {
+            Ok(())
+        } else {
+            Err(exception(wasm_runtime_get_module_inst(exec_env)))
+        }
+    }
+
+    pub unsafe fn call(
+        exec_env: wasm_exec_env_t,
+        name: &CStr,
+        args: &mut [wasm_val_t],
+        results: &mut [wasm_val_t],
+    ) -> Result<(), Trap> {
+        let module_inst = wasm_runtime_get_module_inst(exec_env);
+        let function = wasm_runtime_lookup_function(module_inst, name.as_ptr());
+        if function.is_null() {
+            return Err(Trap::new(format!(
+                "function `{}` is not exported",
+                name.to_string_lossy()
+            )));
+        }
+        call_function(exec_env, function, args, results)
+    }
+
+    /// Calls the post-return function `name`, if the module exports one.
+    pub unsafe fn post_return(
+        exec_env: wasm_exec_env_t,
+        name: &CStr,
+        args: &mut [wasm_val_t],
+    ) -> Result<(), Trap> {
+        let module_inst = wasm_runtime_get_module_inst(exec_env);
+        let function = wasm_runtime_lookup_function(module_inst, name.as_ptr());
+        if function.is_null() {
+            return Ok(());
+        }
+        call_function(exec_env, function, args, &mut [])
+    }
+
+    /// Allocates memory in the module with its `cabi_realloc`.
+    pub unsafe fn alloc(exec_env: wasm_exec_env_t, size: usize, align: usize) -> Result<i32, Trap> {
+        let size = i32::try_from(size).map_err(|_| Trap::new("allocation too large"))?;
+        let mut args = [val_i32(0), val_i32(0), val_i32(align as i32), val_i32(size)];
+        let mut results = [wasm_val_t::default()];
+        call(exec_env, c"cabi_realloc", &mut args, &mut results)?;
+        Ok(get_i32(&results[0]))
+    }
+
+    /// Returns a native pointer to `len` bytes of linear memory at `addr`.
+    unsafe fn native(exec_env: wasm_exec_env_t, addr: u64, len: usize) -> Result<*mut u8, Trap> {
+        let module_inst = wasm_runtime_get_module_inst(exec_env);
+        if !wasm_runtime_validate_app_addr(module_inst, addr, len as u64) {
+            wasm_runtime_clear_exception(module_inst);
+            return Err(Trap::new("out of bounds memory access"));
+        }
+        Ok(wasm_runtime_addr_app_to_native(module_inst, addr).cast())
+    }
+
+    pub unsafe fn load<T: Copy>(exec_env: wasm_exec_env_t, addr: i32, offset: usize) -> Result<T, Trap> {
+        let addr = addr as u32 as u64 + offset as u64;
+        let ptr = native(exec_env, addr, ::core::mem::size_of::<T>())?;
+        Ok(ptr.cast::<T>().read_unaligned())
+    }
+
+    pub unsafe fn store<T: Copy>(
+        exec_env: wasm_exec_env_t,
+        addr: i32,
+        offset: usize,
+        value: T,
+    ) -> Result<(), Trap> {
+        let addr = addr as u32 as u64 + offset as u64;
+        let ptr = native(exec_env, addr, ::core::mem::size_of::<T>())?;
+        ptr.cast::<T>().write_unaligned(value);
+        Ok(())
+    }
+
+    /// Checks that a list of `len` elements of `size` bytes at `addr` is in
+    /// bounds and returns its length.
+    pub unsafe fn list_len(exec_env: wasm_exec_env_t, addr: i32, len: i32, size: usize) -> Result<usize, Trap> {
+        let len = len as u32 as usize;
+        let bytes = len
+            .checked_mul(size)
+            .ok_or_else(|| Trap::new("out of bounds memory access"))?;
+        native(exec_env, addr as u32 as u64, bytes)?;
+        Ok(len)
+    }
+
+    pub unsafe fn load_list<T: Copy>(exec_env: wasm_exec_env_t, addr: i32, len: i32) -> Result<Vec<T>, Trap> {
+        let len = list_len(exec_env, addr, len, ::core::mem::size_of::<T>())?;
+        let bytes = len * ::core::mem::size_of::<T>();
+        let src = native(exec_env, addr as u32 as u64, bytes)?;
+        let mut list = Vec::<T>::with_capacity(len);
+        ::core::ptr::copy_nonoverlapping(src, list.as_mut_ptr().cast::<u8>(), bytes);
+        list.set_len(len);
+        Ok(list)
+    }
+
+    pub unsafe fn load_string(exec_env: wasm_exec_env_t, addr: i32, len: i32) -> Result<String, Trap> {
+        String::from_utf8(load_list(exec_env, addr, len)?).map_err(|_| Trap::new("invalid utf-8 string"))
+    }
+
+    /// Copies `list` into memory allocated in the module.
+    pub unsafe fn store_list<T: Copy>(exec_env: wasm_exec_env_t, list: &[T], align: usize) -> Result<i32, Trap> {
+        let bytes = ::core::mem::size_of_val(list);
+        let addr = alloc(exec_env, bytes, align)?;
+        let dst = native(exec_env, addr as u32 as u64, bytes)?;
+        ::core::ptr::copy_nonoverlapping(list.as_ptr().cast::<u8>(), dst, bytes);
+        Ok(addr)
+    }
+
+    pub fn char_lift(i: i32) -> Result<char, Trap> {
+        char::from_u32(i as u32).ok_or_else(|| Trap::new("invalid char"))
+    }
+
+    /// Native symbols registered with WAMR, together with the host they are
+    /// attached to. Dropping it unregisters the symbols and drops the host.
+    pub struct Registration {
+        natives: Vec<(&'static CStr, *mut [NativeSymbol])>,
+        host: *mut c_void,
+        drop_host: unsafe fn(*mut c_void),
+    }
+
+    // The host is `Send + Sync` and the symbols are only read by WAMR.
+    unsafe impl Send for Registration {}
+    unsafe impl Sync for Registration {}
+
+    unsafe fn drop_host<H>(host: *mut c_void) {
+        drop(::std::boxed::Box::from_raw(host.cast::<H>()));
+    }
+
+    impl Registration {
+        pub fn new<H>(host: H) -> Registration {
+            Registration {
+                natives: Vec::new(),
+                host: ::std::boxed::Box::into_raw(::std::boxed::Box::new(host)).cast(),
+                drop_host: drop_host::<H>,
+            }
+        }
+
+        pub fn host(&self) -> *mut c_void {
+            self.host
+        }
+
+        pub unsafe fn register(&mut self, module: &'static CStr, symbols: Vec<NativeSymbol>) -> Result<(), Trap> {
+            // WAMR keeps referring to, and sorts, the symbols.
+            let symbols = ::std::boxed::Box::into_raw(symbols.into_boxed_slice());
+            if wasm_runtime_register_natives(module.as_ptr(), symbols.cast(), symbols.len() as u32) {
+                self.natives.push((module, symbols));
+                Ok(())
+            } else {
+                drop(::std::boxed::Box::from_raw(symbols));
+                Err(Trap::new(format!(
+                    "failed to register the imports of `{}`",
+                    module.to_string_lossy()
+                )))
+            }
+        }
+    }
+
+    impl Drop for Registration {
+        fn drop(&mut self) {
+            unsafe {
+                for (module, symbols) in self.natives.drain(..) {
+                    wasm_runtime_unregister_natives(module.as_ptr(), symbols.cast());
+                    drop(::std::boxed::Box::from_raw(symbols));
+                }
+                (self.drop_host)(self.host);
+            }
+        }
+    }
+
+    /// Returns the host registered with the native symbol being called.
+    pub unsafe fn host<'a, H>(exec_env: wasm_exec_env_t) -> &'a H {
+        &*wasm_runtime_get_function_attachment(exec_env).cast::<H>()
+    }
+
+    /// Runs the body of an import, raising errors and panics as an exception
+    /// in the calling module.
+    pub unsafe fn guard<R: Default>(exec_env: wasm_exec_env_t, body: impl FnOnce() -> Result<R, Trap>) -> R {
+        let trap = match ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(body)) {
+            Ok(Ok(ret)) => return ret,
+            Ok(Err(trap)) => trap,
+            Err(_) => Trap::new("host function panicked"),
+        };
+        let message = ::std::ffi::CString::new(trap.message.replace('\0', " ")).unwrap();
+        wasm_runtime_set_exception(wasm_runtime_get_module_inst(exec_env), message.as_ptr());
+        R::default()
+    }
+"#;
+
+const ASYNC_RUNTIME: &str = r#"
+    use ::core::any::Any;
+    use ::std::boxed::Box;
+    use ::std::cell::{Cell, RefCell};
+
+    /// Status returned by async imports, which always complete before
+    /// returning to the module.
+    pub const SUBTASK_RETURNED: i32 = 2;
+
+    const EVENT_NONE: i32 = 0;
+
+    const CALLBACK_CODE_EXIT: i32 = 0;
+    const CALLBACK_CODE_YIELD: i32 = 1;
+    const CALLBACK_CODE_WAIT: i32 = 2;
+    const CALLBACK_CODE_POLL: i32 = 3;
+
+    /// Lifts the results which an async export passes to `task.return`.
+    type TaskReturn = Box<dyn FnOnce(&[wasm_val_t]) -> Result<Box<dyn Any>, Trap>>;
+
+    /// An async export which the host is calling.
+    struct Task {
+        task_return: Option<TaskReturn>,
+        result: Option<Result<Box<dyn Any>, Trap>>,
+        context: i32,
+    }
+
+    thread_local! {
+        /// Async exports being called, innermost last.
+        static TASKS: RefCell<Vec<Task>> = const { RefCell::new(Vec::new()) };
+        static NEXT_WAITABLE_SET: Cell<i32> = const { Cell::new(1) };
+    }
+
+    /// Calls the async export `name` and, unless it's stackful, drives its
+    /// `callback` until the task exits. Returns what `task_return` lifted from
+    /// the results the export passed to `task.return`.
+    pub unsafe fn call_async<R: 'static>(
+        exec_env: wasm_exec_env_t,
+        name: &CStr,
+        args: &mut [wasm_val_t],
+        callback: Option<&CStr>,
+        task_return: impl FnOnce(&[wasm_val_t]) -> Result<R, Trap> + 'static,
+    ) -> Result<R, Trap> {
+        let task_return: TaskReturn =
+            Box::new(move |results| Ok(Box::new(task_return(results)?) as Box<dyn Any>));
+        TASKS.with(|tasks| {
+            tasks.borrow_mut().push(Task {
+                task_return: Some(task_return),
+                result: None,
+                context: 0,
+            })
+        });
+        let ret = run_task(exec_env, name, args, callback);
+        let task = TASKS.with(|tasks| tasks.borrow_mut().pop().unwrap());
+        ret?;
+        match task.result {
+            Some(result) => Ok(*result?.downcast::<R>().unwrap()),
+            None => Err(Trap::new(format!(
+                "`{}` exited without calling `task.return`",
+                name.to_string_lossy()
+            ))),
+        }
+    }
+
+    unsafe fn run_task(
+        exec_env: wasm_exec_env_t,
+        name: &CStr,
+        args: &mut [wasm_val_t],
+        callback: Option<&CStr>,
+    ) -> Result<(), Trap> {
+        let Some(callback) = callback else {
+            return call(exec_env, name, args, &mut []);
+        };
+        let mut ret = [wasm_val_t::default()];
+        call(exec_env, name, args, &mut ret)?;
+        loop {
+            match get_i32(&ret[0]) & 0xf {
+                CALLBACK_CODE_EXIT => return Ok(()),
+                // No waitable ever has an event pending.
+                CALLBACK_CODE_YIELD | CALLBACK_CODE_POLL => {
+                    let mut args = [val_i32(EVENT_NONE), val_i32(0), val_i32(0)];
+                    call(exec_env, callback, &mut args, &mut ret)?;
+                }
+                CALLBACK_CODE_WAIT => return Err(deadlock()),
+                code => return Err(Trap::new(format!("invalid callback code {code}"))),
+            }
+        }
+    }
+
+    fn deadlock() -> Trap {
+        Trap::new("deadlock: waiting for an event which is never delivered")
+    }
+
+    fn with_task<R>(f: impl FnOnce(&mut Task) -> R) -> Result<R, Trap> {
+        TASKS
+            .with(|tasks| tasks.borrow_mut().last_mut().map(f))
+            .ok_or_else(|| Trap::new("not called from within an async export"))
+    }
+
+    /// Implements `task.return` for the innermost async export.
+    pub unsafe fn task_return(exec_env: wasm_exec_env_t, results: &[wasm_val_t]) {
+        guard(exec_env, || {
+            let lift = with_task(|task| task.task_return.take())?
+                .ok_or_else(|| Trap::new("`task.return` called more than once"))?;
+            // The results may point into memory which the module frees once
+            // `task.return` returns, so they're lifted right away.
+            let result = lift(results);
+            with_task(|task| task.result = Some(result))
+        })
+    }
+
+    extern "C" fn context_get(exec_env: wasm_exec_env_t) -> i32 {
+        unsafe { guard(exec_env, || with_task(|task| task.context)) }
+    }
+
+    extern "C" fn context_set(exec_env: wasm_exec_env_t, value: i32) {
+        unsafe { guard(exec_env, || with_task(|task| task.context = value)) }
+    }
+
+    extern "C" fn waitable_set_new(_exec_env: wasm_exec_env_t) -> i32 {
+        NEXT_WAITABLE_SET.with(|next| {
+            let set = next.get();
+            next.set(set + 1);
+            set
+        })
+    }
+
+    extern "C" fn waitable_set_drop(_exec_env: wasm_exec_env_t, _set: i32) {}
+
+    extern "C" fn waitable_join(exec_env: wasm_exec_env_t, _waitable: i32, _set: i32) {
+        unsafe { guard(exec_env, || Err::<(), _>(Trap::new("no waitables exist to join"))) }
+    }
+
+    extern "C" fn waitable_set_wait(exec_env: wasm_exec_env_t, _set: i32, _event: i32) -> i32 {
+        unsafe { guard(exec_env, || Err::<i32, _>(deadlock())) }
+    }
+
+    extern "C" fn waitable_set_poll(exec_env: wasm_exec_env_t, _set: i32, event: i32) -> i32 {
+        unsafe {
+            guard(exec_env, || {
+                store::<i32>(exec_env, event, 0, 0)?;
+                store::<i32>(exec_env, event, 4, 0)?;
+                Ok(EVENT_NONE)
+            })
+        }
+    }
+
+    extern "C" fn yield_(_exec_env: wasm_exec_env_t) -> i32 {
+        // The task is never cancelled.
+        0
+    }
+
+    extern "C" fn backpressure_set(_exec_env: wasm_exec_env_t, _enabled: i32) {}
+
+    extern "C" fn subtask_cancel(exec_env: wasm_exec_env_t, _subtask: i32) -> i32 {
+        unsafe { guard(exec_env, || Err::<i32, _>(Trap::new("no subtasks exist to cancel"))) }
+    }
+
+    extern "C" fn subtask_drop(exec_env: wasm_exec_env_t, _subtask: i32) {
+        unsafe { guard(exec_env, || Err::<(), _>(Trap::new("no subtasks exist to drop"))) }
+    }
+
+    extern "C" fn task_cancel(exec_env: wasm_exec_env_t) {
+        unsafe { guard(exec_env, || Err::<(), _>(Trap::new("the task was not cancelled"))) }
+    }
+
+    fn intrinsic(symbol: &'static CStr, func_ptr: *mut c_void, signature: &'static CStr) -> NativeSymbol {
+        NativeSymbol {
+            symbol: symbol.as_ptr(),
+            func_ptr,
+            signature: signature.as_ptr(),
+            attachment: ::core::ptr::null_mut(),
+        }
+    }
+
+    /// Registers the canonical ABI intrinsics which async functions import.
+    pub unsafe fn register_intrinsics(registration: &mut Registration) -> Result<(), Trap> {
+        registration.register(
+            c"$root",
+            ::std::vec![
+                intrinsic(c"[context-get-0]", context_get as *mut c_void, c"()i"),
+                intrinsic(c"[context-set-0]", context_set as *mut c_void, c"(i)"),
+                intrinsic(c"[waitable-set-new]", waitable_set_new as *mut c_void, c"()i"),
+                intrinsic(c"[waitable-set-drop]", waitable_set_drop as *mut c_void, c"(i)"),
+                intrinsic(c"[waitable-join]", waitable_join as *mut c_void, c"(ii)"),
+                intrinsic(c"[waitable-set-wait]", waitable_set_wait as *mut c_void, c"(ii)i"),
+                intrinsic(c"[waitable-set-poll]", waitable_set_poll as *mut c_void, c"(ii)i"),
+                intrinsic(c"[yield]", yield_ as *mut c_void, c"()i"),
+                intrinsic(c"[backpressure-set]", backpressure_set as *mut c_void, c"(i)"),
+                intrinsic(c"[subtask-cancel]", subtask_cancel as *mut c_void, c"(i)i"),
+                intrinsic(c"[subtask-drop]", subtask_drop as *mut c_void, c"(i)"),
+            ],
+        )?;
+        registration.register(
+            c"[export]$root",
+            ::std::vec![intrinsic(c"[task-cancel]", task_cancel as *mut c_void, c"()")],
+        )
+    }
+"#;
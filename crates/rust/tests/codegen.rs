@@ -16,3 +16,232 @@ mod multiple_paths {
         generate_all,
     });
 }
+
+#[allow(unused)]
+mod no_alloc {
+    wit_bindgen::generate!({
+        inline: r#"
+        package test:no-alloc;
+
+        interface host {
+            record entry {
+                key: string,
+                values: list<u32>,
+            }
+
+            log: func(msg: string, level: u32);
+            sum: func(values: list<u32>) -> u64;
+            insert: func(entry: entry, extra: option<list<u8>>);
+        }
+
+        world test {
+            import host;
+            export count: func(s: string, bytes: list<u8>) -> u32;
+        }
+        "#,
+        symmetric: true,
+        no_alloc: true,
+        ownership: Borrowing {
+            duplicate_if_necessary: false
+        },
+    });
+
+    struct Component;
+
+    impl Guest for Component {
+        fn count(s: &str, bytes: &[u8]) -> u32 {
+            let entry = test::no_alloc::host::Entry {
+                key: s,
+                values: &[1, 2],
+            };
+            test::no_alloc::host::insert(entry, Some(bytes));
+            test::no_alloc::host::sum(&[bytes.len() as u32]) as u32
+        }
+    }
+}
+
+mod mockable_imports {
+    wit_bindgen::generate!({
+        inline: r#"
+        package test:mockable;
+
+        interface host {
+            add: func(a: u32, b: u32) -> u32;
+            greet: func(name: string) -> string;
+        }
+
+        world test {
+            import host;
+        }
+        "#,
+        mockable_imports: true,
+    });
+
+    use std::rc::Rc;
+    use test::mockable::host;
+
+    struct Mock;
+
+    impl host::Imports for Mock {
+        fn add(&self, a: u32, b: u32) -> u32 {
+            a + b
+        }
+
+        fn greet(&self, name: &str) -> String {
+            format!("hello {name}")
+        }
+    }
+
+    #[test]
+    fn calls_installed_imports() {
+        assert!(host::set_imports(Some(Rc::new(Mock))).is_none());
+        assert_eq!(host::add(1, 2), 3);
+        assert_eq!(host::greet("world"), "hello world");
+        assert!(host::set_imports(None).is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "no implementation of `add` was installed with `set_imports`")]
+    fn panics_without_imports() {
+        host::add(1, 2);
+    }
+}
+
+mod serde_option {
+    wit_bindgen::generate!({
+        inline: r#"
+        package test:serde;
+
+        interface types {
+            record point {
+                x: s32,
+                y-coord: s32,
+            }
+
+            variant shape {
+                empty,
+                circle(u32),
+                polygon(list<point>),
+            }
+
+            enum color {
+                red,
+                dark-blue,
+            }
+
+            flags access {
+                read,
+                write,
+                exec-all,
+            }
+
+            record doc {
+                name: string,
+                color: color,
+                access: access,
+                shapes: list<shape>,
+                tags: option<list<string>>,
+                status: result<u8, string>,
+                pair: tuple<char, u64>,
+            }
+
+            resource file {
+                constructor(doc: doc);
+            }
+
+            // Contains a resource, so it doesn't implement the serde traits.
+            record opened {
+                file: file,
+                doc: doc,
+            }
+
+            save: func(doc: doc) -> doc;
+            open: func(opened: opened) -> opened;
+        }
+
+        world test {
+            import types;
+            export types;
+        }
+        "#,
+        serde: true,
+        generate_all,
+    });
+
+    // Borrowed import parameters only implement `Serialize`.
+    mod borrowed {
+        wit_bindgen::generate!({
+            inline: r#"
+            package test:serde-borrowed;
+
+            interface types {
+                record entry {
+                    key: string,
+                    values: list<u32>,
+                }
+
+                insert: func(entry: entry);
+            }
+
+            world test {
+                import types;
+            }
+            "#,
+            serde: true,
+            ownership: Borrowing {
+                duplicate_if_necessary: false
+            },
+        });
+
+        fn serialize(entry: test::serde_borrowed::types::Entry<'_>) -> String {
+            serde_json::to_string(&entry).unwrap()
+        }
+    }
+
+    use serde_json::json;
+    use test::serde::types::{Access, Color, Doc, Point, Shape};
+
+    #[test]
+    fn round_trip() {
+        let doc = Doc {
+            name: "doc".to_string(),
+            color: Color::DarkBlue,
+            access: Access::READ | Access::EXEC_ALL,
+            shapes: vec![
+                Shape::Empty,
+                Shape::Circle(3),
+                Shape::Polygon(vec![Point { x: 1, y_coord: -2 }]),
+            ],
+            tags: None,
+            status: Ok(1),
+            pair: ('x', 5),
+        };
+        let value = serde_json::to_value(&doc).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "name": "doc",
+                "color": "dark-blue",
+                "access": ["read", "exec-all"],
+                "shapes": [
+                    "empty",
+                    { "circle": 3 },
+                    { "polygon": [{ "x": 1, "y-coord": -2 }] },
+                ],
+                "tags": null,
+                "status": { "Ok": 1 },
+                "pair": ["x", 5],
+            })
+        );
+
+        let doc: Doc = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(doc.access, Access::READ | Access::EXEC_ALL);
+        assert_eq!(serde_json::to_value(&doc).unwrap(), value);
+
+        let err = serde_json::from_value::<Access>(json!(["read", "execute"])).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown variant `execute`"),
+            "{err}"
+        );
+    }
+}
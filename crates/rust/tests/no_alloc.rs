@@ -0,0 +1,72 @@
+//! Bindings generated with `no_alloc` build in a `no_std` crate, and don't
+//! refer to `alloc` at all. The test harness links `std` regardless, so the
+//! latter is checked on the generated source.
+
+#![no_std]
+#![allow(dead_code, unused_variables)]
+
+wit_bindgen::generate!({
+    path: "tests/wit/no-alloc.wit",
+    symmetric: true,
+    no_alloc: true,
+    ownership: Borrowing {
+        duplicate_if_necessary: false
+    },
+});
+
+use test::no_alloc::host::{self, Access, Entry, Value};
+
+struct Component;
+
+export!(Component);
+
+impl Guest for Component {
+    fn count(s: &str, bytes: &[u8]) -> u32 {
+        host::log(s, 1);
+        let entry = Entry {
+            key: s,
+            values: &[1, 2],
+        };
+        host::insert(entry, Some(bytes), Access::READ | Access::WRITE);
+        let _ = host::store(s, Value::Bytes(bytes));
+        let digest = host::digest(bytes);
+        host::sum(&[digest[0] as u32]) as u32
+    }
+}
+
+extern crate std;
+
+#[test]
+fn generated_source_doesnt_allocate() {
+    use wit_bindgen_core::wit_parser::Resolve;
+    use wit_bindgen_core::Files;
+    use wit_bindgen_rust::{Opts, Ownership};
+
+    let mut resolve = Resolve::default();
+    let (pkg, _) = resolve.push_path("tests/wit/no-alloc.wit").unwrap();
+    let world = resolve.select_world(pkg, None).unwrap();
+    let opts = Opts {
+        symmetric: true,
+        no_alloc: true,
+        ownership: Ownership::Borrowing {
+            duplicate_if_necessary: false,
+        },
+        ..Opts::default()
+    };
+    let mut files = Files::default();
+    opts.build().generate(&resolve, world, &mut files).unwrap();
+
+    let (_, src) = files.iter().next().unwrap();
+    let src = std::str::from_utf8(src).unwrap();
+    for path in [
+        "alloc::",
+        "alloc_crate",
+        "realloc",
+        "dealloc",
+        "Vec",
+        "String",
+        "Box",
+    ] {
+        assert!(!src.contains(path), "`{path}` found in:\n{src}");
+    }
+}
@@ -0,0 +1,7 @@
+//! Errors reported by `generate!`, along with the spans they point at.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
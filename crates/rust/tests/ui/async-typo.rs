@@ -0,0 +1,14 @@
+wit_bindgen::generate!({
+    inline: "
+        package a:b;
+        interface foo {
+            f: func();
+        }
+        world w {
+            import foo;
+        }
+    ",
+    async: ["a:b/foo#ff"],
+});
+
+fn main() {}
@@ -0,0 +1,10 @@
+wit_bindgen::generate!({
+    inline: "
+        package a:b;
+        world w {
+            import f: func(;
+        }
+    ",
+});
+
+fn main() {}
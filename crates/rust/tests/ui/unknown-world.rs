@@ -0,0 +1,11 @@
+wit_bindgen::generate!({
+    inline: "
+        package a:b;
+        world w {
+            import f: func();
+        }
+    ",
+    world: "x",
+});
+
+fn main() {}
@@ -0,0 +1,16 @@
+wit_bindgen::generate!({
+    inline: "
+        package a:b;
+        interface foo {
+            f: func();
+        }
+        world w {
+            import foo;
+        }
+    ",
+    with: {
+        "a:b/fo": generate,
+    },
+});
+
+fn main() {}
@@ -0,0 +1,74 @@
+//! Runs bindings generated with `--host` against a C guest under WAMR.
+//!
+//! Building and running the module needs `WASI_SDK_PATH` to point at a
+//! wasi-sdk installation and `WAMR_LIB_DIR` at a directory containing
+//! `libiwasm`; without them only the bindings are generated.
+
+use heck::ToSnakeCase;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[test]
+fn runner() {
+    test_helpers::run_world_codegen_test(
+        "rust-wamr",
+        &Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/wamr/runner.wit"),
+        |resolve, world, files| {
+            wit_bindgen_c::Opts::default()
+                .build()
+                .generate(resolve, world, files)
+                .unwrap();
+            let mut opts = wit_bindgen_rust::Opts::default();
+            opts.host = true;
+            opts.additional_derive_attributes = vec!["PartialEq".to_string()];
+            opts.build().generate(resolve, world, files).unwrap();
+        },
+        verify,
+    );
+}
+
+fn verify(dir: &Path, name: &str) {
+    let (Some(sdk), Some(wamr)) = (env::var_os("WASI_SDK_PATH"), env::var_os("WAMR_LIB_DIR"))
+    else {
+        eprintln!("skipping WAMR runtime test: WASI_SDK_PATH or WAMR_LIB_DIR is not set");
+        return;
+    };
+    let sdk = PathBuf::from(sdk);
+    let name = name.to_snake_case();
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/wamr");
+
+    let guest = dir.join("guest.wasm");
+    let mut cmd = Command::new(sdk.join("bin/clang"));
+    cmd.arg("--target=wasm32-wasip1")
+        .arg("--sysroot")
+        .arg(sdk.join("share/wasi-sysroot"))
+        .arg("-mexec-model=reactor")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(dir)
+        .arg(src.join("guest.c"))
+        .arg(dir.join(format!("{name}.c")))
+        .arg(dir.join(format!("{name}_component_type.o")))
+        .arg("-o")
+        .arg(&guest);
+    test_helpers::run_command(&mut cmd);
+
+    let host = dir.join("host");
+    let mut cmd = Command::new("rustc");
+    cmd.arg("--edition=2021")
+        .arg(src.join("host.rs"))
+        .env("BINDINGS", dir.join(format!("{name}.rs")))
+        .arg("-L")
+        .arg(format!("native={}", Path::new(&wamr).display()))
+        .arg("-l")
+        .arg("iwasm")
+        .arg("-o")
+        .arg(&host);
+    test_helpers::run_command(&mut cmd);
+
+    let mut cmd = Command::new(&host);
+    cmd.arg(&guest).env("LD_LIBRARY_PATH", &wamr);
+    test_helpers::run_command(&mut cmd);
+}
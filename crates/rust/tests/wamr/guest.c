@@ -0,0 +1,82 @@
+#include <runner.h>
+#include <stdlib.h>
+#include <string.h>
+
+void exports_test_wamr_guest_greet(runner_string_t *name, runner_string_t *ret) {
+  runner_string_t msg;
+  runner_string_set(&msg, "greet");
+  test_wamr_host_log(&msg);
+
+  static const char prefix[] = "hello, ";
+  size_t prefix_len = strlen(prefix);
+  ret->len = prefix_len + name->len;
+  ret->ptr = malloc(ret->len);
+  memcpy(ret->ptr, prefix, prefix_len);
+  memcpy(ret->ptr + prefix_len, name->ptr, name->len);
+  runner_string_free(name);
+}
+
+uint64_t exports_test_wamr_guest_sum(runner_list_u32_t *values) {
+  uint64_t sum = 0;
+  for (size_t i = 0; i < values->len; i++)
+    sum += values->ptr[i];
+  runner_list_u32_free(values);
+  return sum;
+}
+
+bool exports_test_wamr_guest_centroid(exports_test_wamr_guest_list_point_t *points,
+                                      exports_test_wamr_guest_point_t *ret) {
+  if (points->len == 0) {
+    exports_test_wamr_guest_list_point_free(points);
+    return false;
+  }
+  test_wamr_host_point_t sum = {0, 0};
+  for (size_t i = 0; i < points->len; i++) {
+    sum.x += points->ptr[i].x;
+    sum.y += points->ptr[i].y;
+  }
+  sum.x /= (int32_t)points->len;
+  sum.y /= (int32_t)points->len;
+  test_wamr_host_translate(&sum, 1, ret);
+  exports_test_wamr_guest_list_point_free(points);
+  return true;
+}
+
+runner_callback_code_t exports_test_wamr_guest_async_lookup(runner_list_u32_t *keys) {
+  // Yield once before doing any work so the host has to drive the callback.
+  runner_list_u32_t *state = malloc(sizeof(runner_list_u32_t));
+  *state = *keys;
+  runner_context_set(state);
+  return RUNNER_CALLBACK_CODE_YIELD;
+}
+
+runner_callback_code_t exports_test_wamr_guest_async_lookup_callback(runner_event_t *event) {
+  if (event->event != RUNNER_EVENT_NONE)
+    abort();
+  runner_list_u32_t *keys = runner_context_get();
+  runner_context_set(NULL);
+
+  runner_list_string_t ret;
+  ret.len = keys->len;
+  ret.ptr = malloc(ret.len * sizeof(runner_string_t));
+  for (size_t i = 0; i < keys->len; i++) {
+    runner_subtask_status_t status = test_wamr_host_async_fetch(keys->ptr[i], &ret.ptr[i]);
+    if (RUNNER_SUBTASK_STATE(status) != RUNNER_SUBTASK_RETURNED)
+      abort();
+  }
+  exports_test_wamr_guest_async_lookup_return(ret);
+
+  runner_list_string_free(&ret);
+  runner_list_u32_free(keys);
+  free(keys);
+  return RUNNER_CALLBACK_CODE_EXIT;
+}
+
+runner_callback_code_t exports_test_wamr_guest_async_wait_forever() {
+  return RUNNER_CALLBACK_CODE_WAIT(runner_waitable_set_new());
+}
+
+runner_callback_code_t exports_test_wamr_guest_async_wait_forever_callback(runner_event_t *event) {
+  // Nothing ever joins the waitable set, so no event can be delivered.
+  abort();
+}
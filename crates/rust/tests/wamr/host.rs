@@ -0,0 +1,109 @@
+// Loads the guest module named on the command line into WAMR and checks its
+// exports, serving its imports from `Host`.
+
+include!(env!("BINDINGS"));
+
+use exports::test::wamr::guest;
+use std::ffi::{c_char, c_void, CStr};
+use std::sync::Mutex;
+use test::wamr::host::Point;
+
+unsafe extern "C" {
+    fn wasm_runtime_init() -> bool;
+    fn wasm_runtime_destroy();
+    fn wasm_runtime_load(
+        buf: *mut u8,
+        size: u32,
+        error_buf: *mut c_char,
+        error_buf_size: u32,
+    ) -> *mut c_void;
+    fn wasm_runtime_unload(module: *mut c_void);
+    fn wasm_runtime_instantiate(
+        module: *mut c_void,
+        default_stack_size: u32,
+        host_managed_heap_size: u32,
+        error_buf: *mut c_char,
+        error_buf_size: u32,
+    ) -> *mut c_void;
+    fn wasm_runtime_deinstantiate(module_inst: *mut c_void);
+    fn wasm_runtime_create_exec_env(module_inst: *mut c_void, stack_size: u32) -> *mut c_void;
+    fn wasm_runtime_destroy_exec_env(exec_env: *mut c_void);
+}
+
+static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct Host;
+
+impl test::wamr::host::Host for Host {
+    fn log(&self, msg: String) {
+        LOG.lock().unwrap().push(msg);
+    }
+
+    fn translate(&self, p: Point, dx: i32) -> Point {
+        Point { x: p.x + dx, ..p }
+    }
+
+    fn async_fetch(&self, key: u32) -> String {
+        format!("value{key}")
+    }
+}
+
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: host <module>");
+    let mut wasm = std::fs::read(&path).unwrap();
+    let mut error = [0 as c_char; 128];
+    unsafe {
+        assert!(wasm_runtime_init());
+        let registration = register(Host).unwrap();
+
+        let module = wasm_runtime_load(
+            wasm.as_mut_ptr(),
+            wasm.len() as u32,
+            error.as_mut_ptr(),
+            error.len() as u32,
+        );
+        assert!(!module.is_null(), "{:?}", CStr::from_ptr(error.as_ptr()));
+        let module_inst =
+            wasm_runtime_instantiate(module, 64 * 1024, 0, error.as_mut_ptr(), error.len() as u32);
+        assert!(
+            !module_inst.is_null(),
+            "{:?}",
+            CStr::from_ptr(error.as_ptr())
+        );
+        let exec_env = wasm_runtime_create_exec_env(module_inst, 64 * 1024);
+        assert!(!exec_env.is_null());
+        let instance = Instance::from_raw(exec_env);
+
+        assert_eq!(guest::greet(&instance, "wamr").unwrap(), "hello, wamr");
+        assert_eq!(*LOG.lock().unwrap(), ["greet"]);
+
+        assert_eq!(guest::sum(&instance, &[]).unwrap(), 0);
+        assert_eq!(
+            guest::sum(&instance, &[1, 2, u32::MAX]).unwrap(),
+            u64::from(u32::MAX) + 3
+        );
+
+        assert_eq!(guest::centroid(&instance, &[]).unwrap(), None);
+        assert_eq!(
+            guest::centroid(&instance, &[Point { x: 0, y: 0 }, Point { x: 4, y: 2 }]).unwrap(),
+            Some(Point { x: 3, y: 1 })
+        );
+
+        assert_eq!(
+            guest::async_lookup(&instance, &[7, 1]).unwrap(),
+            ["value7", "value1"]
+        );
+        assert!(guest::async_lookup(&instance, &[]).unwrap().is_empty());
+
+        // The guest waits on an empty waitable set, which can never make
+        // progress; this must trap instead of hanging.
+        let trap = guest::async_wait_forever(&instance).unwrap_err();
+        assert!(trap.message().contains("deadlock"), "{trap}");
+
+        wasm_runtime_destroy_exec_env(exec_env);
+        wasm_runtime_deinstantiate(module_inst);
+        wasm_runtime_unload(module);
+        drop(registration);
+        wasm_runtime_destroy();
+    }
+}
@@ -3,16 +3,17 @@
 #[cfg(feature = "symmetric")]
 pub mod rt {
     pub use dummy_rt::rt::maybe_link_cabi_realloc;
-    pub use wit_bindgen_symmetric_rt::{
-        async_support, bitflags, run, Cleanup, EventGenerator, EventSubscription,
-    };
+    #[cfg(feature = "async")]
+    pub use wit_bindgen_rt::async_support;
+    pub use wit_bindgen_rt::{bitflags, Cleanup};
+    pub use wit_bindgen_symmetric_rt::{run, EventGenerator, EventSubscription};
 }
 
 #[cfg(feature = "canonical")]
 pub use original::rt;
 
-#[cfg(feature = "async")]
-pub use wit_bindgen_symmetric_rt::async_support::{
+#[cfg(all(feature = "symmetric", feature = "async"))]
+pub use wit_bindgen_rt::async_support::{
     block_on, spawn, FutureReader, FutureWriter, StreamReader, StreamResult, StreamWriter,
 };
 
@@ -0,0 +1,3 @@
+//! Tests of the task executor, streams and futures in `wit-bindgen-rt`
+//! running on this executor and `symmetric-stream`, which is what the rt's
+//! `symmetric` feature selects. See `tests/`.
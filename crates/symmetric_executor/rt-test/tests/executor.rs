@@ -0,0 +1,158 @@
+//! Runs `block_on`, `spawn` and `wait_on` from `wit-bindgen-rt` on the
+//! `symmetric-executor`.
+//!
+//! The executor is global, so everything runs from a single test rather than
+//! from tests on parallel threads.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use wit_bindgen_rt::async_support::trace::{self, Event};
+use wit_bindgen_rt::async_support::{block_on, futures, spawn, wait_on};
+
+#[link(name = "symmetric_executor")]
+extern "C" {
+    #[link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BstaticX5Devent_subscriptionX2Efrom_timeout"]
+    fn subscription_from_timeout(nanoseconds: u64) -> *mut u8;
+    #[link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BconstructorX5Devent_generator"]
+    fn generator_new() -> *mut u8;
+    #[link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BmethodX5Devent_generatorX2Esubscribe"]
+    fn generator_subscribe(generator: *mut u8) -> *mut u8;
+    #[link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5BmethodX5Devent_generatorX2Eactivate"]
+    fn generator_activate(generator: *mut u8);
+    #[link_name = "symmetricX3AruntimeX2Fsymmetric_executorX400X2E2X2E1X00X5Bresource_dropX5Devent_generator"]
+    fn generator_drop(generator: *mut u8);
+}
+
+async fn sleep(milliseconds: u64) {
+    unsafe { wait_on(subscription_from_timeout(milliseconds * 1_000_000)).await }
+}
+
+struct Generator(*mut u8);
+
+impl Generator {
+    fn new() -> Generator {
+        Generator(unsafe { generator_new() })
+    }
+
+    async fn wait(&self) {
+        unsafe { wait_on(generator_subscribe(self.0)).await }
+    }
+
+    fn activate(&self) {
+        unsafe { generator_activate(self.0) }
+    }
+}
+
+impl Drop for Generator {
+    fn drop(&mut self) {
+        unsafe { generator_drop(self.0) }
+    }
+}
+
+thread_local! {
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(event: &Event) {
+    EVENTS.with(|events| events.borrow_mut().push(*event));
+}
+
+#[test]
+fn executor() {
+    // A future which never waits doesn't need the executor.
+    assert_eq!(block_on(async { 1 }), 1);
+
+    // `wait_on` suspends the task until the executor sees the event fire.
+    assert_eq!(
+        block_on(async {
+            sleep(1).await;
+            sleep(1).await;
+            2
+        }),
+        2
+    );
+
+    // Spawned tasks run concurrently with the one that spawned them, and
+    // events can be handed from one task to another.
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let log2 = log.clone();
+    let result = block_on(async move {
+        let generator = Rc::new(Generator::new());
+        let generator2 = generator.clone();
+        let log3 = log2.clone();
+        spawn(async move {
+            log3.borrow_mut().push("spawned");
+            sleep(1).await;
+            log3.borrow_mut().push("activate");
+            generator2.activate();
+        });
+        log2.borrow_mut().push("wait");
+        generator.wait().await;
+        log2.borrow_mut().push("woken");
+        3
+    });
+    assert_eq!(result, 3);
+    assert_eq!(*log.borrow(), ["wait", "spawned", "activate", "woken"]);
+
+    // `block_on` doesn't return until tasks spawned from it are done, even
+    // once its own future has completed.
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let log2 = log.clone();
+    block_on(async move {
+        for i in 0..3 {
+            let log = log2.clone();
+            spawn(async move {
+                sleep(3 - i).await;
+                log.borrow_mut().push(i);
+            });
+        }
+    });
+    assert_eq!(*log.borrow(), [2, 1, 0]);
+
+    // Waiting on an event, like an async import call does, registers a waker
+    // which the executor's callback then delivers the event to.
+    assert!(trace::set_hook(Some(record)).is_none());
+    block_on(sleep(1));
+    let events = EVENTS.take();
+    let [Event::TaskStart { task }, Event::WakerRegistered { waitable }, ..] = events[..] else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(
+        events,
+        [
+            Event::TaskStart { task },
+            Event::WakerRegistered { waitable },
+            Event::EventReceived {
+                task,
+                event: 1,
+                waitable,
+                code: 0,
+            },
+            Event::TaskFinish { task },
+        ]
+    );
+
+    // Dropping the wait before the event fires unregisters its waker. The
+    // executor can't cancel the registration though, so the event still has
+    // to fire for it to finish running.
+    let generator = Generator::new();
+    block_on(async move {
+        {
+            let wait = generator.wait();
+            let sleep = sleep(1);
+            futures::pin_mut!(wait, sleep);
+            futures::future::select(wait, sleep).await;
+        }
+        generator.activate();
+    });
+    let events = EVENTS.take();
+    let [Event::TaskStart { task }, Event::WakerRegistered { waitable }, ..] = events[..] else {
+        panic!("unexpected events {events:?}");
+    };
+    assert!(
+        events.contains(&Event::WakerUnregistered { waitable }),
+        "{events:?}"
+    );
+    assert_eq!(events.last(), Some(&Event::TaskFinish { task }));
+    assert!(trace::set_hook(None).is_some());
+}
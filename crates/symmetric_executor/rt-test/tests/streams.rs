@@ -0,0 +1,148 @@
+//! Runs streams and futures from `wit-bindgen-rt` on the `symmetric-stream`
+//! runtime.
+//!
+//! The executor is global, so everything runs from a single test rather than
+//! from tests on parallel threads.
+
+use std::alloc::Layout;
+use std::pin::Pin;
+use wit_bindgen_rt::async_support::{
+    block_on, future_new, futures, spawn, stream_new, FutureVtable, StreamResult, StreamVtable,
+};
+
+static U32: StreamVtable<u32> = StreamVtable {
+    layout: Layout::new::<u32>(),
+    lower: None,
+    lift: None,
+};
+
+static STRING: StreamVtable<String> = StreamVtable {
+    layout: Layout::new::<(*mut u8, usize)>(),
+    lower: Some(lower_string),
+    lift: Some(lift_string),
+};
+
+static FUTURE_STRING: FutureVtable<String> = FutureVtable {
+    layout: Layout::new::<(*mut u8, usize)>(),
+    lower: lower_string,
+    lift: lift_string,
+};
+
+unsafe fn lower_string(value: String, dst: *mut u8) {
+    let bytes = value.into_bytes().into_boxed_slice();
+    let len = bytes.len();
+    let ptr = Box::into_raw(bytes).cast::<u8>();
+    unsafe { dst.cast::<(*mut u8, usize)>().write((ptr, len)) }
+}
+
+unsafe fn lift_string(src: *mut u8) -> String {
+    unsafe {
+        let (ptr, len) = src.cast::<(*mut u8, usize)>().read();
+        String::from_utf8(Vec::from_raw_parts(ptr, len, len)).unwrap()
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn streams_and_futures() {
+    // Values arrive in order across several writes, and dropping the writer
+    // ends the stream.
+    let values = block_on(async {
+        let (mut writer, reader) = stream_new(&U32);
+        spawn(async move {
+            assert!(writer.write_all(vec![1, 2, 3]).await.is_empty());
+            assert_eq!(writer.write_one(4).await, None);
+        });
+        reader.collect().await
+    });
+    assert_eq!(values, [1, 2, 3, 4]);
+
+    // Lists in the values are handed over to the reader.
+    let values = block_on(async {
+        let (mut writer, mut reader) = stream_new(&STRING);
+        spawn(async move {
+            let rest = writer.write_all(strings(&["Hello", "", "World!"])).await;
+            assert!(rest.is_empty());
+        });
+        let mut values = Vec::new();
+        while let Some(value) = reader.next().await {
+            values.push(value);
+        }
+        values
+    });
+    assert_eq!(values, strings(&["Hello", "", "World!"]));
+
+    // Writes to a stream whose reader is gone hand the values back.
+    let rest = block_on(async {
+        let (mut writer, reader) = stream_new(&STRING);
+        drop(reader);
+        writer.write_all(strings(&["lost"])).await
+    });
+    assert_eq!(rest, strings(&["lost"]));
+
+    // A writer waiting for a read sees the reader go away.
+    let rest = block_on(async {
+        let (mut writer, reader) = stream_new(&STRING);
+        let write = spawn_result(async move { writer.write_all(strings(&["a", "b"])).await });
+        spawn(async move { drop(reader) });
+        write.await
+    });
+    assert_eq!(rest, strings(&["a", "b"]));
+
+    // A cancelled read leaves the stream to the next read.
+    let values = block_on(async {
+        let (mut writer, mut reader) = stream_new(&U32);
+        {
+            let mut read = reader.read(Vec::with_capacity(4));
+            assert!(futures::poll!(&mut read).is_pending());
+            let (result, buf) = Pin::new(&mut read).cancel();
+            assert_eq!(result, StreamResult::Cancelled);
+            assert!(buf.is_empty());
+        }
+        spawn(async move {
+            assert!(writer.write_all(vec![5, 6]).await.is_empty());
+        });
+        reader.collect().await
+    });
+    assert_eq!(values, [5, 6]);
+
+    // A future carries a single value.
+    let value = block_on(async {
+        let (writer, reader) = unsafe { future_new(String::new, &FUTURE_STRING) };
+        spawn(async move {
+            writer.write("Hello".to_string()).await.unwrap();
+        });
+        reader.await
+    });
+    assert_eq!(value, "Hello");
+
+    // Dropping a future's writer sends the default value.
+    let value = block_on(async {
+        let (writer, reader) = unsafe { future_new(|| "default".to_string(), &FUTURE_STRING) };
+        drop(writer);
+        reader.await
+    });
+    assert_eq!(value, "default");
+
+    // Writes to a future whose reader is gone hand the value back.
+    let value = block_on(async {
+        let (writer, reader) = unsafe { future_new(String::new, &FUTURE_STRING) };
+        drop(reader);
+        writer.write("lost".to_string()).await.unwrap_err().value
+    });
+    assert_eq!(value, "lost");
+}
+
+/// Runs `future` as a separate task, returning a future for its result.
+fn spawn_result<T: 'static>(
+    future: impl std::future::Future<Output = T> + 'static,
+) -> impl std::future::Future<Output = T> {
+    let (tx, rx) = futures::channel::oneshot::channel();
+    spawn(async move {
+        let _ = tx.send(future.await);
+    });
+    async { rx.await.unwrap() }
+}
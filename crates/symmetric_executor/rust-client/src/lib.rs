@@ -6,7 +6,6 @@ pub use module::symmetric::runtime::symmetric_executor::{
 pub use module::symmetric::runtime::symmetric_stream;
 use std::alloc::{self, Layout};
 
-pub mod async_support;
 mod module;
 
 // Re-export `bitflags` so that we can reference it from macros.
@@ -59,11 +59,12 @@ impl LanguageMethods for C {
 
     fn should_fail_verify(
         &self,
-        _name: &str,
-        config: &crate::config::WitConfig,
+        name: &str,
+        _config: &crate::config::WitConfig,
         _args: &[String],
     ) -> bool {
-        config.error_context
+        // `--arena` rejects async exports.
+        name == "async-trait-function-arena"
     }
 
     fn codegen_test_variants(&self) -> &[(&str, &[&str])] {
@@ -71,6 +72,9 @@ impl LanguageMethods for C {
             ("no-sig-flattening", &["--no-sig-flattening"]),
             ("autodrop", &["--autodrop-borrows=yes"]),
             ("async", &["--async=all"]),
+            ("compact-utf16", &["--string-encoding=compact-utf16"]),
+            ("arena", &["--arena"]),
+            ("blocking-imports", &["--async=all", "--blocking-imports"]),
         ]
     }
 
@@ -72,6 +72,38 @@ impl LanguageMethods for Rust {
             return true;
         }
 
+        // Host bindings for WAMR don't support resources, futures, streams or
+        // error contexts yet.
+        if args.iter().any(|s| s == "--host") {
+            return matches!(
+                name.strip_suffix("-host").unwrap_or(name),
+                "async-trait-function.wit"
+                    | "error-context.wit"
+                    | "futures.wit"
+                    | "import-and-export-resource-alias.wit"
+                    | "import-and-export-resource.wit"
+                    | "issue668.wit"
+                    | "issue929-no-export.wit"
+                    | "issue929-no-import.wit"
+                    | "issue929-only-methods.wit"
+                    | "issue929.wit"
+                    | "resource-alias.wit"
+                    | "resource-borrow-in-record-export.wit"
+                    | "resource-borrow-in-record.wit"
+                    | "resource-local-alias-borrow-import.wit"
+                    | "resource-local-alias-borrow.wit"
+                    | "resource-local-alias.wit"
+                    | "resource-own-in-other-interface.wit"
+                    | "resources-in-aggregates.wit"
+                    | "resources-with-futures.wit"
+                    | "resources-with-lists.wit"
+                    | "resources-with-streams.wit"
+                    | "resources.wit"
+                    | "return-resource-from-export.wit"
+                    | "streams.wit"
+            );
+        }
+
         false
     }
 
@@ -84,6 +116,8 @@ impl LanguageMethods for Rust {
             ),
             ("async", &["--async=all"]),
             ("no-std", &["--std-feature"]),
+            ("mockable-imports", &["--mockable-imports"]),
+            ("host", &["--host"]),
         ]
     }
 
@@ -114,7 +148,7 @@ impl LanguageMethods for Rust {
             ));
             format!("path = {bindgen_path:?}, package = \"mini-bindgen\"")
         } else {
-            match &opts.rust_wit_bindgen_path {
+            let source = match &opts.rust_wit_bindgen_path {
                 Some(path) => format!("path = {:?}", cwd.join(path)),
                 None => {
                     let version = opts
@@ -123,7 +157,8 @@ impl LanguageMethods for Rust {
                         .unwrap_or(env!("CARGO_PKG_VERSION"));
                     format!("version = \"{version}\"")
                 }
-            }
+            };
+            format!("{source}, features = [\"stream-adapters\", \"trace\"]")
         };
 
         super::write_if_different(
@@ -267,6 +302,23 @@ path = 'lib.rs'
         let bindings = verify
             .bindings_dir
             .join(format!("{}.rs", verify.world.to_snake_case()));
+
+        // Host bindings are compiled for the native target and don't depend
+        // on the `wit-bindgen` crate.
+        if verify.args.iter().any(|s| s == "--host") {
+            for edition in ["--edition=2021", "--edition=2024"] {
+                let mut cmd = Command::new("rustc");
+                cmd.arg(edition)
+                    .arg(&bindings)
+                    .arg("--crate-type=rlib")
+                    .arg("-Dwarnings")
+                    .arg("-o")
+                    .arg(verify.artifacts_dir.join("tmp"));
+                runner.run_command(&mut cmd)?;
+            }
+            return Ok(());
+        }
+
         let test_edition = |edition: Edition| -> Result<()> {
             let mut cmd = runner.rustc(edition);
             cmd.arg(&bindings)
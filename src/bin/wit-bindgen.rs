@@ -1,9 +1,15 @@
 use anyhow::{bail, Context, Error, Result};
 use clap::Parser;
-use std::path::PathBuf;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
 use std::str;
-use wit_bindgen_core::{wit_parser, Files, WorldGenerator};
-use wit_parser::Resolve;
+use std::sync::Arc;
+use wit_bindgen_core::abi::{self, AbiVariant, LiftLower};
+use wit_bindgen_core::external::{self, External};
+use wit_bindgen_core::layout::Layouts;
+use wit_bindgen_core::{wit_parser, AsyncFilterSet, Files, WorldGenerator};
+use wit_parser::{PackageId, Resolve, WorldId, WorldItem};
 
 /// Helper for passing VERSION to opt.
 /// If CARGO_VERSION_INFO is set, use it, otherwise use CARGO_PKG_VERSION.
@@ -80,11 +86,95 @@ enum Opt {
         args: Common,
     },
 
+    /// Prints the ABI layout of a world as JSON.
+    ///
+    /// For every function this lists the flattened core signature for both
+    /// the guest-import and guest-export ABI, along with whether parameters
+    /// are passed indirectly and whether a return area is used. The size,
+    /// alignment and field offsets of every named type are included as well.
+    /// All of this is reported for wasm32, wasm64 and the symmetric ABI.
+    ///
+    /// With `--instructions` the sequence of ABI instructions generated for
+    /// every function is printed instead, as recorded by
+    /// `wit_bindgen_core::abi::Recorder`.
+    ///
+    /// Output goes to stdout unless `--out-dir` or `--check` is given, in
+    /// which case `<world>.abi.json` (or `<world>.instructions.json`) is
+    /// written or checked.
+    Abi {
+        /// Print the instruction stream of each function instead of layouts.
+        #[clap(long)]
+        instructions: bool,
+
+        /// Record instructions for the symmetric ABI rather than the
+        /// canonical ABI.
+        #[clap(long, requires = "instructions")]
+        symmetric: bool,
+
+        #[clap(flatten)]
+        args: Common,
+    },
+
+    /// Generates bindings for several targets described in a TOML file.
+    ///
+    /// The WIT package is parsed once and every `[[target]]` in the file is
+    /// generated from it. Paths are relative to the configuration file:
+    ///
+    /// ```toml
+    /// wit = "wit"
+    /// world = "my-world"          # optional if there is only one world
+    /// features = ["unstable-foo"] # optional
+    ///
+    /// [[target]]
+    /// language = "cpp"
+    /// out-dir = "gen/host"
+    /// options = { host = true }
+    ///
+    /// [[target]]
+    /// language = "rust"
+    /// world = "other-world"       # overrides the top-level world
+    /// out-dir = "gen/rust"
+    /// options = { generate-all = true }
+    /// ```
+    ///
+    /// `options` takes the same settings as the language's subcommand, spelled
+    /// in kebab-case.
+    Generate {
+        /// Path to the TOML file describing the targets.
+        #[clap(long, value_name = "PATH")]
+        config: PathBuf,
+
+        #[clap(flatten)]
+        write: WriteOpts,
+    },
+
+    /// Checks that bindings generated from a configuration file are up to
+    /// date.
+    ///
+    /// Every target in the file passed to `--config` (see `generate`) is
+    /// generated in memory and compared with what is on disk. A unified diff
+    /// is printed for each file that differs, and the command fails if any
+    /// file is out of date or missing.
+    Check {
+        /// Path to the TOML file describing the targets.
+        #[clap(long, value_name = "PATH")]
+        config: PathBuf,
+    },
+
     // doc-comments are present on `wit_bindgen_test::Opts` for clap to use.
     Test {
         #[clap(flatten)]
         opts: wit_bindgen_test::Opts,
     },
+
+    /// Any other subcommand `<lang>` runs the external generator
+    /// `wit-bindgen-<lang>` found on `PATH`.
+    ///
+    /// The usual `WIT`, `--out-dir`, `--world` and related options are
+    /// accepted, and any arguments after `--` are passed through to the
+    /// generator.
+    #[command(external_subcommand)]
+    External(Vec<String>),
 }
 
 #[derive(Debug, Parser)]
@@ -114,10 +204,8 @@ struct Common {
     #[clap(short, long)]
     world: Option<String>,
 
-    /// Indicates that no files are written and instead files are checked if
-    /// they're up-to-date with the source files.
-    #[clap(long)]
-    check: bool,
+    #[clap(flatten)]
+    write: WriteOpts,
 
     /// Comma-separated list of features that should be enabled when processing
     /// WIT files.
@@ -133,42 +221,218 @@ struct Common {
     all_features: bool,
 }
 
+#[derive(Debug, Parser)]
+struct WriteOpts {
+    /// Indicates that no files are written and instead files are checked if
+    /// they're up-to-date with the source files.
+    #[clap(long)]
+    check: bool,
+
+    /// Only write files whose contents differ from what is already on disk.
+    ///
+    /// This leaves modification times of unchanged files alone so that
+    /// build systems don't rebuild everything after each regeneration.
+    #[clap(long)]
+    incremental: bool,
+
+    /// Remove files generated by a previous run which this run no longer
+    /// produces.
+    ///
+    /// The list of generated files is tracked in a `.wit-bindgen-manifest`
+    /// file in the output directory, so only files generated by a previous
+    /// run with this flag are ever removed.
+    #[clap(long)]
+    remove_stale: bool,
+}
+
+/// Contents of the file passed to `wit-bindgen generate --config`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Config {
+    wit: PathBuf,
+    #[serde(default)]
+    world: Option<String>,
+    #[serde(default)]
+    features: Vec<String>,
+    #[serde(default)]
+    all_features: bool,
+    #[serde(rename = "target")]
+    targets: Vec<ConfigTarget>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ConfigTarget {
+    language: String,
+    #[serde(default)]
+    world: Option<String>,
+    #[serde(default)]
+    out_dir: Option<PathBuf>,
+    #[serde(default)]
+    options: toml::Table,
+}
+
 fn main() -> Result<()> {
     env_logger::init();
 
     let mut files = Files::default();
+    let mut abi_instructions = None;
     let (generator, opt) = match Opt::parse() {
         #[cfg(feature = "markdown")]
-        Opt::Markdown { opts, args } => (opts.build(), args),
+        Opt::Markdown { opts, args } => (Some(opts.build()), args),
         #[cfg(feature = "moonbit")]
-        Opt::Moonbit { opts, args } => (opts.build(), args),
+        Opt::Moonbit { opts, args } => (Some(opts.build()), args),
         #[cfg(feature = "c")]
-        Opt::C { opts, args } => (opts.build(), args),
+        Opt::C { opts, args } => (Some(opts.build()), args),
         #[cfg(feature = "bridge")]
-        Opt::Bridge { opts, args } => (opts.build(), args),
+        Opt::Bridge { opts, args } => (Some(opts.build()), args),
         #[cfg(feature = "cpp")]
-        Opt::Cpp { opts, args } => (opts.build(args.out_dir.as_ref()), args),
+        Opt::Cpp { opts, args } => (Some(opts.build(args.out_dir.as_ref())), args),
         #[cfg(feature = "rust")]
-        Opt::Rust { opts, args } => (opts.build(), args),
+        Opt::Rust { opts, args } => (Some(opts.build()), args),
         #[cfg(feature = "go")]
         Opt::TinyGo { args: _ } => {
             bail!("Go bindgen has been moved to a separate repository. Please visit https://github.com/bytecodealliance/go-modules for the new Go bindings generator `wit-bindgen-go`.")
         }
         #[cfg(feature = "csharp")]
-        Opt::Csharp { opts, args } => (opts.build(), args),
+        Opt::Csharp { opts, args } => (Some(opts.build()), args),
+        Opt::Abi {
+            instructions,
+            symmetric,
+            args,
+        } => {
+            abi_instructions = instructions.then_some(symmetric);
+            (None, args)
+        }
+        Opt::Generate { config, write } => {
+            for (out_dir, files) in generate_config(&config)? {
+                write_files(&files, Some(&out_dir), &write)?;
+            }
+            return Ok(());
+        }
+        Opt::Check { config } => return check_config(&config),
         Opt::Test { opts } => return opts.run(std::env::args_os().nth(0).unwrap().as_ref()),
+        Opt::External(args) => {
+            let (language, args) = args.split_first().unwrap();
+            let Some(program) = external::find(language) else {
+                bail!(
+                    "unknown subcommand `{language}`: no `{}{language}` found on PATH",
+                    external::PREFIX
+                );
+            };
+            let (args, passthrough) = match args.iter().position(|a| a == "--") {
+                Some(i) => (&args[..i], &args[i + 1..]),
+                None => (args, &[][..]),
+            };
+            let args = Common::try_parse_from(
+                std::iter::once(format!("wit-bindgen {language}")).chain(args.iter().cloned()),
+            )
+            .unwrap_or_else(|e| e.exit());
+            let generator = External::new(program).args(passthrough.to_vec());
+            (Some(generator.build()), args)
+        }
     };
 
-    gen_world(generator, &opt, &mut files).map_err(attach_with_context)?;
+    match generator {
+        Some(generator) => gen_world(generator, &opt, &mut files).map_err(attach_with_context)?,
+        None => {
+            let (resolve, world) = load_world(&opt)?;
+            let name = &resolve.worlds[world].name;
+            let (mut json, file) = match abi_instructions {
+                Some(symmetric) => (
+                    serde_json::to_string_pretty(&world_instructions(&resolve, world, symmetric))?,
+                    format!("{name}.instructions.json"),
+                ),
+                None => (
+                    serde_json::to_string_pretty(&wit_bindgen_core::layout::world_layout(
+                        &resolve, world,
+                    ))?,
+                    format!("{name}.abi.json"),
+                ),
+            };
+            json.push('\n');
+            if opt.out_dir.is_none() && !opt.write.check {
+                print!("{json}");
+                return Ok(());
+            }
+            files.push(&file, json.as_bytes());
+        }
+    }
+
+    write_files(&files, opt.out_dir.as_deref(), &opt.write)
+}
 
+#[derive(serde::Serialize)]
+struct FunctionInstructions {
+    interface: Option<String>,
+    import: bool,
+    #[serde(flatten)]
+    trace: abi::Trace,
+}
+
+/// Records the ABI instructions generated for every function in `world`,
+/// lifting and lowering `async` functions asynchronously.
+fn world_instructions(
+    resolve: &Resolve,
+    world: WorldId,
+    symmetric: bool,
+) -> Vec<FunctionInstructions> {
+    let mut async_ = AsyncFilterSet::default();
+    let mut ret = Vec::new();
+    let world = &resolve.worlds[world];
+    for (import, items) in [(true, &world.imports), (false, &world.exports)] {
+        for (key, item) in items.iter() {
+            let (interface, funcs) = match item {
+                WorldItem::Interface { id, .. } => (
+                    Some(key),
+                    resolve.interfaces[*id].functions.values().collect(),
+                ),
+                WorldItem::Function(func) => (None, vec![func]),
+                WorldItem::Type(_) => continue,
+            };
+            for func in funcs {
+                let is_async = async_.is_async(resolve, interface, func, import);
+                let (variant, lift_lower) = match (import, is_async) {
+                    (true, false) => (AbiVariant::GuestImport, LiftLower::LowerArgsLiftResults),
+                    (true, true) => (
+                        AbiVariant::GuestImportAsync,
+                        LiftLower::LowerArgsLiftResults,
+                    ),
+                    (false, false) => (AbiVariant::GuestExport, LiftLower::LiftArgsLowerResults),
+                    (false, true) => (
+                        AbiVariant::GuestExportAsync,
+                        LiftLower::LiftArgsLowerResults,
+                    ),
+                };
+                let lift_lower = if symmetric {
+                    LiftLower::Symmetric
+                } else {
+                    lift_lower
+                };
+                ret.push(FunctionInstructions {
+                    interface: interface.map(|key| resolve.name_world_key(key)),
+                    import,
+                    trace: abi::record(resolve, variant, lift_lower, func, is_async),
+                });
+            }
+        }
+    }
+    ret
+}
+
+/// Name of the file in the output directory listing everything the last run
+/// generated, used by `--remove-stale`.
+const MANIFEST: &str = ".wit-bindgen-manifest";
+
+fn write_files(files: &Files, out_dir: Option<&Path>, opts: &WriteOpts) -> Result<()> {
     for (name, contents) in files.iter() {
-        let dst = match &opt.out_dir {
+        let dst = match out_dir {
             Some(path) => path.join(name),
             None => name.into(),
         };
         eprintln!("Generating {:?}", dst);
 
-        if opt.check {
+        if opts.check {
             let prev = std::fs::read(&dst).with_context(|| format!("failed to read {:?}", dst))?;
             if prev != contents {
                 // The contents differ. If it looks like textual contents, do a
@@ -192,6 +456,10 @@ fn main() -> Result<()> {
             continue;
         }
 
+        if opts.incremental && std::fs::read(&dst).is_ok_and(|prev| prev == contents) {
+            continue;
+        }
+
         if let Some(parent) = dst.parent() {
             std::fs::create_dir_all(parent)
                 .with_context(|| format!("failed to create {:?}", parent))?;
@@ -199,9 +467,50 @@ fn main() -> Result<()> {
         std::fs::write(&dst, contents).with_context(|| format!("failed to write {:?}", dst))?;
     }
 
+    if opts.remove_stale && !opts.check {
+        remove_stale_files(files, out_dir.unwrap_or(Path::new("")))?;
+    }
+
     Ok(())
 }
 
+fn remove_stale_files(files: &Files, out_dir: &Path) -> Result<()> {
+    let manifest = out_dir.join(MANIFEST);
+    let current = files.iter().map(|(name, _)| name).collect::<HashSet<_>>();
+
+    match std::fs::read_to_string(&manifest) {
+        Ok(prev) => {
+            for name in prev.lines().filter(|name| !current.contains(name)) {
+                // Only ever delete paths that could have come from `Files`.
+                let path = Path::new(name);
+                if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+                    continue;
+                }
+                let dst = out_dir.join(path);
+                eprintln!("Removing stale {:?}", dst);
+                match std::fs::remove_file(&dst) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e).with_context(|| format!("failed to remove {:?}", dst)),
+                }
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).with_context(|| format!("failed to read {:?}", manifest)),
+    }
+
+    let mut contents = String::new();
+    for (name, _) in files.iter() {
+        contents.push_str(name);
+        contents.push('\n');
+    }
+    if let Some(parent) = manifest.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create {:?}", parent))?;
+    }
+    std::fs::write(&manifest, contents).with_context(|| format!("failed to write {:?}", manifest))
+}
+
 fn attach_with_context(err: Error) -> Error {
     #[cfg(feature = "rust")]
     if let Some(e) = err.downcast_ref::<wit_bindgen_rust::MissingWith>() {
@@ -213,14 +522,20 @@ fn attach_with_context(err: Error) -> Error {
     err
 }
 
-fn gen_world(
-    mut generator: Box<dyn WorldGenerator>,
-    opts: &Common,
-    files: &mut Files,
-) -> Result<()> {
+fn load_world(opts: &Common) -> Result<(Resolve, WorldId)> {
+    let (resolve, pkg) = load_resolve(&opts.wit, &opts.features, opts.all_features)?;
+    let world = resolve.select_world(pkg, opts.world.as_deref())?;
+    Ok((resolve, world))
+}
+
+fn load_resolve(
+    wit: &Path,
+    features: &[String],
+    all_features: bool,
+) -> Result<(Resolve, PackageId)> {
     let mut resolve = Resolve::default();
-    resolve.all_features = opts.all_features;
-    for features in opts.features.iter() {
+    resolve.all_features = all_features;
+    for features in features.iter() {
         for feature in features
             .split(',')
             .flat_map(|s| s.split_whitespace())
@@ -229,10 +544,332 @@ fn gen_world(
             resolve.features.insert(feature.to_string());
         }
     }
-    let (pkg, _files) = resolve.push_path(&opts.wit)?;
-    let mut world = resolve.select_world(pkg, opts.world.as_deref())?;
-    generator.apply_resolve_options(&mut resolve, &mut world);
-    generator.generate(&resolve, world, files)?;
+    let (pkg, _files) = resolve.push_path(wit)?;
+    Ok((resolve, pkg))
+}
+
+/// Generates every target listed in the configuration file at `path`,
+/// returning the files produced grouped by output directory.
+fn generate_config(path: &Path) -> Result<Vec<(PathBuf, Files)>> {
+    let contents =
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {:?}", path))?;
+    let config: Config =
+        toml::from_str(&contents).with_context(|| format!("failed to parse {:?}", path))?;
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    let (mut resolve, pkg) = load_resolve(
+        &base.join(&config.wit),
+        &config.features,
+        config.all_features,
+    )?;
+    // Shared by all targets which don't modify the `Resolve`, so that layouts
+    // are computed only once.
+    let layouts = Arc::new(Layouts::default());
+
+    let mut outputs: Vec<(PathBuf, Files)> = Vec::new();
+    let mut seen = HashSet::new();
+    for target in config.targets.iter() {
+        let out_dir = base.join(target.out_dir.as_deref().unwrap_or(Path::new("")));
+        let generator = build_generator(target, Some(&out_dir))?;
+        let world = target.world.as_deref().or(config.world.as_deref());
+        let mut world = resolve.select_world(pkg, world)?;
+        let mut files = Files::default();
+        let result = if generator.modifies_resolve() {
+            let mut resolve = resolve.clone();
+            gen_resolved_world(generator, &mut resolve, &mut world, None, &mut files)
+        } else {
+            gen_resolved_world(
+                generator,
+                &mut resolve,
+                &mut world,
+                Some(layouts.clone()),
+                &mut files,
+            )
+        };
+        result
+            .map_err(attach_with_context)
+            .with_context(|| format!("failed to generate `{}` target", target.language))?;
+
+        let idx = match outputs.iter().position(|(dir, _)| *dir == out_dir) {
+            Some(idx) => idx,
+            None => {
+                outputs.push((out_dir.clone(), Files::default()));
+                outputs.len() - 1
+            }
+        };
+        for (name, contents) in files.iter() {
+            if !seen.insert(out_dir.join(name)) {
+                bail!(
+                    "{:?} is generated by more than one target",
+                    out_dir.join(name)
+                );
+            }
+            outputs[idx].1.push(name, contents);
+        }
+    }
+    Ok(outputs)
+}
+
+/// Compares everything generated from the configuration file at `path` with
+/// the files on disk, printing a unified diff for each one that differs.
+fn check_config(path: &Path) -> Result<()> {
+    let mut stale = 0;
+    for (out_dir, files) in generate_config(path)? {
+        for (name, contents) in files.iter() {
+            let dst = out_dir.join(name);
+            let prev = match std::fs::read(&dst) {
+                Ok(prev) => Some(prev),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e).with_context(|| format!("failed to read {:?}", dst)),
+            };
+            if prev.as_deref() == Some(contents) {
+                continue;
+            }
+            stale += 1;
+            let prev = prev.as_deref().unwrap_or_default();
+            match (str::from_utf8(prev), str::from_utf8(contents)) {
+                (Ok(old), Ok(new)) => {
+                    let diff = unified_diff(&dst, old, new);
+                    if diff.is_empty() {
+                        println!("{} differs only in line endings", dst.display());
+                    } else {
+                        print!("{diff}");
+                    }
+                }
+                _ => println!("Binary file {} differs", dst.display()),
+            }
+        }
+    }
+    if stale > 0 {
+        bail!("{stale} generated file(s) are not up to date");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Edit {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Renders the differences between `old` and `new` in unified diff format,
+/// or returns an empty string if their lines are the same.
+fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    const CONTEXT: usize = 3;
+
+    let a = old.lines().collect::<Vec<_>>();
+    let b = new.lines().collect::<Vec<_>>();
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for edit in diff_lines(&a, &b) {
+        ops.push((edit, i, j));
+        match edit {
+            Edit::Equal => (i, j) = (i + 1, j + 1),
+            Edit::Delete => i += 1,
+            Edit::Insert => j += 1,
+        }
+    }
+
+    // Group changes into hunks, merging those whose context would overlap.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (idx, _) in ops.iter().enumerate().filter(|(_, op)| op.0 != Edit::Equal) {
+        let lo = idx.saturating_sub(CONTEXT);
+        let hi = (idx + 1 + CONTEXT).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if lo <= last.1 => last.1 = hi,
+            _ => hunks.push((lo, hi)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let path = path.display();
+    let mut out = if old.is_empty() {
+        format!("--- /dev/null\n+++ b/{path}\n")
+    } else {
+        format!("--- a/{path}\n+++ b/{path}\n")
+    };
+    let range = |start: usize, len: usize| match len {
+        0 => format!("{start},0"),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{len}", start + 1),
+    };
+    for (lo, hi) in hunks {
+        let ops = &ops[lo..hi];
+        let (_, i, j) = ops[0];
+        let old_len = ops.iter().filter(|op| op.0 != Edit::Insert).count();
+        let new_len = ops.iter().filter(|op| op.0 != Edit::Delete).count();
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(i, old_len),
+            range(j, new_len)
+        ));
+        for (edit, i, j) in ops {
+            let line = match edit {
+                Edit::Equal => format!(" {}", a[*i]),
+                Edit::Delete => format!("-{}", a[*i]),
+                Edit::Insert => format!("+{}", b[*j]),
+            };
+            out.push_str(&line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Computes a shortest edit script turning `a` into `b` using the linear
+/// space variant of Myers' algorithm.
+fn diff_lines(a: &[&str], b: &[&str]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(a.len() + b.len());
+    diff_range(a, b, &mut edits);
+    edits
+}
+
+fn diff_range(a: &[&str], b: &[&str], edits: &mut Vec<Edit>) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    edits.extend(std::iter::repeat_n(Edit::Equal, prefix));
+    if a.is_empty() {
+        edits.extend(std::iter::repeat_n(Edit::Insert, b.len()));
+    } else if b.is_empty() {
+        edits.extend(std::iter::repeat_n(Edit::Delete, a.len()));
+    } else if let Some((x, y)) = middle_snake(a, b) {
+        diff_range(&a[..x], &b[..y], edits);
+        diff_range(&a[x..], &b[y..], edits);
+    } else {
+        // Too different to be worth finding the shortest edit script.
+        edits.extend(std::iter::repeat_n(Edit::Delete, a.len()));
+        edits.extend(std::iter::repeat_n(Edit::Insert, b.len()));
+    }
+    edits.extend(std::iter::repeat_n(Edit::Equal, suffix));
+}
+
+/// Finds a point on a shortest edit path between `a` and `b` which splits it
+/// in two halves with about as many edits each, or returns `None` if that
+/// path has more than `2 * MAX_HALF_EDITS` edits.
+///
+/// `a` and `b` must be non-empty and differ in their first and last lines, so
+/// at least two edits are needed and the point is neither the start nor the
+/// end.
+fn middle_snake(a: &[&str], b: &[&str]) -> Option<(usize, usize)> {
+    // Bounds the time taken, which is proportional to the number of lines
+    // times the number of edits.
+    const MAX_HALF_EDITS: isize = 1000;
+
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = ((n + m + 1) / 2).min(MAX_HALF_EDITS);
+    let offset = max + 1;
+    // The furthest `x` reached on each diagonal `k = x - y` going forwards
+    // from the start, and on each diagonal `k = (n - x) - (m - y)` going
+    // backwards from the end, measured from the end.
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+    let idx = |k: isize| (k + offset) as usize;
+    let furthest = |v: &[isize], k: isize, d: isize| {
+        if k == -d || (k != d && v[idx(k - 1)] < v[idx(k + 1)]) {
+            v[idx(k + 1)]
+        } else {
+            v[idx(k - 1)] + 1
+        }
+    };
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = furthest(&forward, k, d);
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[idx(k)] = x;
+            if odd && (delta - k).abs() < d && x + backward[idx(delta - k)] >= n {
+                return Some((x as usize, y as usize));
+            }
+        }
+        for k in (-d..=d).step_by(2) {
+            let mut x = furthest(&backward, k, d);
+            let mut y = x - k;
+            while x < n && y < m && a[(n - 1 - x) as usize] == b[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[idx(k)] = x;
+            if !odd && (delta - k).abs() <= d && x + forward[idx(delta - k)] >= n {
+                return Some(((n - x) as usize, (m - y) as usize));
+            }
+        }
+    }
+    None
+}
+
+#[cfg_attr(not(feature = "cpp"), allow(unused_variables))]
+fn build_generator(
+    target: &ConfigTarget,
+    out_dir: Option<&PathBuf>,
+) -> Result<Box<dyn WorldGenerator>> {
+    #[allow(dead_code)]
+    fn opts<T: serde::de::DeserializeOwned>(target: &ConfigTarget) -> Result<T> {
+        toml::Value::Table(target.options.clone())
+            .try_into()
+            .with_context(|| format!("invalid options for `{}` target", target.language))
+    }
+
+    Ok(match target.language.as_str() {
+        #[cfg(feature = "markdown")]
+        "markdown" => opts::<wit_bindgen_markdown::Opts>(target)?.build(),
+        #[cfg(feature = "moonbit")]
+        "moonbit" => opts::<wit_bindgen_moonbit::Opts>(target)?.build(),
+        #[cfg(feature = "c")]
+        "c" => opts::<wit_bindgen_c::Opts>(target)?.build(),
+        #[cfg(feature = "bridge")]
+        "bridge" => opts::<wit_bindgen_bridge::Opts>(target)?.build(),
+        #[cfg(feature = "cpp")]
+        "cpp" => opts::<wit_bindgen_cpp::Opts>(target)?.build(out_dir),
+        #[cfg(feature = "rust")]
+        "rust" => opts::<wit_bindgen_rust::Opts>(target)?.build(),
+        #[cfg(feature = "csharp")]
+        "csharp" | "c-sharp" => opts::<wit_bindgen_csharp::Opts>(target)?.build(),
+        other => match external::find(other) {
+            Some(program) => External::new(program)
+                .options(serde_json::to_value(&target.options)?)
+                .build(),
+            None => bail!(
+                "unknown target language `{other}` and no `{}{other}` found on PATH",
+                external::PREFIX
+            ),
+        },
+    })
+}
+
+fn gen_world(generator: Box<dyn WorldGenerator>, opts: &Common, files: &mut Files) -> Result<()> {
+    let (mut resolve, mut world) = load_world(opts)?;
+    gen_resolved_world(generator, &mut resolve, &mut world, None, files)
+}
+
+fn gen_resolved_world(
+    mut generator: Box<dyn WorldGenerator>,
+    resolve: &mut Resolve,
+    world: &mut WorldId,
+    layouts: Option<Arc<Layouts>>,
+    files: &mut Files,
+) -> Result<()> {
+    generator.apply_resolve_options(resolve, world);
+    if let Some(layouts) = layouts {
+        generator.set_layouts(layouts);
+    }
+    generator.generate(resolve, *world, files)?;
 
     Ok(())
 }
@@ -242,3 +879,88 @@ fn verify_cli() {
     use clap::CommandFactory;
     Opt::command().debug_assert()
 }
+
+#[test]
+fn unified_diff_hunks() {
+    let path = Path::new("gen/x.h");
+    assert_eq!(unified_diff(path, "a\nb\n", "a\r\nb\r\n"), "");
+
+    let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n";
+    let new = "1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n";
+    assert_eq!(
+        unified_diff(path, old, new),
+        "--- a/gen/x.h\n+++ b/gen/x.h\n\
+         @@ -1,6 +1,6 @@\n 1\n 2\n-3\n+three\n 4\n 5\n 6\n\
+         @@ -10,3 +10,4 @@\n 10\n 11\n 12\n+13\n"
+    );
+
+    assert_eq!(
+        unified_diff(path, "", "x\n"),
+        "--- /dev/null\n+++ b/gen/x.h\n@@ -0,0 +1 @@\n+x\n"
+    );
+}
+
+#[test]
+fn diff_lines_is_minimal() {
+    // Length of the longest common subsequence, by dynamic programming.
+    fn lcs(a: &[&str], b: &[&str]) -> usize {
+        let mut prev = vec![0; b.len() + 1];
+        for x in a {
+            let mut row = vec![0; b.len() + 1];
+            for (j, y) in b.iter().enumerate() {
+                row[j + 1] = if x == y {
+                    prev[j] + 1
+                } else {
+                    row[j].max(prev[j + 1])
+                };
+            }
+            prev = row;
+        }
+        prev[b.len()]
+    }
+
+    // Pseudo-random files over a small alphabet so that lines repeat.
+    let mut seed = 1u32;
+    let mut file = |len: usize| {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                ["a", "b", "c", "d"][(seed >> 16) as usize % 4]
+            })
+            .collect::<Vec<_>>()
+    };
+    for (n, m) in [(0, 3), (3, 0), (1, 1), (5, 8), (8, 5), (40, 40), (60, 25)] {
+        let (a, b) = (file(n), file(m));
+        let edits = diff_lines(&a, &b);
+
+        // Applying the edits to `a` yields `b`...
+        let (mut i, mut out) = (0, Vec::new());
+        let mut j = 0;
+        for edit in &edits {
+            match edit {
+                Edit::Equal => {
+                    assert_eq!(a[i], b[j]);
+                    out.push(a[i]);
+                    (i, j) = (i + 1, j + 1);
+                }
+                Edit::Delete => i += 1,
+                Edit::Insert => {
+                    out.push(b[j]);
+                    j += 1;
+                }
+            }
+        }
+        assert_eq!((i, out), (a.len(), b.clone()));
+
+        // ... keeping as many lines as possible.
+        let kept = edits.iter().filter(|e| **e == Edit::Equal).count();
+        assert_eq!(kept, lcs(&a, &b), "{a:?} {b:?}");
+    }
+
+    // Large files which are too different are reported as entirely replaced.
+    let a = vec!["a"; 100_000];
+    let b = vec!["b"; 100_000];
+    let edits = diff_lines(&a, &b);
+    assert_eq!(edits.len(), 200_000);
+    assert!(edits[..100_000].iter().all(|e| *e == Edit::Delete));
+}
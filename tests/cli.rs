@@ -0,0 +1,566 @@
+//! Tests of the `wit-bindgen` command line itself, as opposed to the bindings
+//! it generates.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{Duration, SystemTime};
+
+fn wit_bindgen(args: &[&str], cwd: &Path) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_wit-bindgen"))
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "`wit-bindgen {}` failed:\n{}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// Returns an empty directory named `name` for a test to work in.
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn instruction_names(function: &Value) -> Vec<&str> {
+    function["body"]["instructions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| step["instruction"].as_str().unwrap())
+        .collect()
+}
+
+/// `tests/abi/layout.abi.json` is what `wit-bindgen abi` prints for
+/// `tests/abi/layout.wit`. Run with `BLESS=1` to update it after an
+/// intentional change to the layouts.
+#[test]
+fn abi_layout_snapshot() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/abi");
+    let output = wit_bindgen(&["abi", "layout.wit"], &dir);
+    let actual = String::from_utf8(output.stdout).unwrap();
+    let snapshot = dir.join("layout.abi.json");
+    if std::env::var_os("BLESS").is_some() {
+        std::fs::write(&snapshot, &actual).unwrap();
+    }
+    let expected = std::fs::read_to_string(&snapshot).unwrap();
+    assert!(
+        actual.lines().eq(expected.lines()),
+        "{} is out of date, rerun with `BLESS=1` to update it:\n{actual}",
+        snapshot.display()
+    );
+}
+
+#[test]
+fn abi_instructions_async() {
+    let dir = scratch_dir("abi-instructions-async");
+    std::fs::write(
+        dir.join("test.wit"),
+        "
+            package a:b;
+
+            interface i {
+                fetch: async func(key: u32) -> string;
+            }
+
+            world w {
+                import i;
+                export i;
+            }
+        ",
+    )
+    .unwrap();
+
+    for symmetric in [false, true] {
+        let mut args = vec!["abi", "--instructions", "test.wit"];
+        if symmetric {
+            args.push("--symmetric");
+        }
+        let output = wit_bindgen(&args, &dir);
+        let functions: Vec<Value> = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(functions.len(), 2);
+
+        let import = &functions[0];
+        assert_eq!(import["import"], true);
+        assert_eq!(import["variant"], "GuestImportAsync");
+        assert_eq!(import["async"], true);
+        assert_eq!(
+            instruction_names(import),
+            [
+                "ReturnPointer",
+                "GetArg",
+                "I32FromU32",
+                "CallWasm",
+                "PointerLoad",
+                "LengthLoad",
+                "StringLift",
+                "Return",
+            ]
+        );
+        let call = &import["body"]["instructions"][3];
+        assert_eq!(call["fields"]["module_prefix"], "[async-lower]");
+        assert_eq!(call["fields"]["sig"]["results"], serde_json::json!(["i32"]));
+
+        let export = &functions[1];
+        assert_eq!(export["import"], false);
+        assert_eq!(export["variant"], "GuestExportAsync");
+        assert_eq!(export["async"], true);
+        assert_eq!(
+            instruction_names(export),
+            [
+                "GetArg",
+                "U32FromI32",
+                "CallInterface",
+                "StringLower",
+                "AsyncTaskReturn",
+            ]
+        );
+    }
+}
+
+#[test]
+fn config_async_options() {
+    let dir = scratch_dir("config-async-options");
+    std::fs::write(
+        dir.join("test.wit"),
+        "
+            package a:b;
+
+            interface i {
+                f: func();
+                g: async func();
+            }
+
+            world w {
+                import i;
+            }
+        ",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("bindgen.toml"),
+        r#"
+            wit = "test.wit"
+
+            [[target]]
+            language = "rust"
+            out-dir = "rust"
+            options = { async = ["import:a:b/i#f", "-all"] }
+        "#,
+    )
+    .unwrap();
+    wit_bindgen(&["generate", "--config", "bindgen.toml"], &dir);
+
+    let src = std::fs::read_to_string(dir.join("rust/w.rs")).unwrap();
+    assert!(src.contains("//   * async: import:a:b/i#f\n"), "{src}");
+    assert!(src.contains("//   * async: -all\n"), "{src}");
+    assert!(src.contains("pub async fn f()"), "{src}");
+    assert!(src.contains("pub fn g()"), "{src}");
+
+    // The structured form used before is still accepted.
+    std::fs::write(
+        dir.join("bindgen.toml"),
+        r#"
+            wit = "test.wit"
+
+            [[target]]
+            language = "rust"
+            out-dir = "rust"
+            options = { async = [
+                { enabled = true, filter = { Import = "a:b/i#f" } },
+                { enabled = false, filter = "All" },
+            ] }
+        "#,
+    )
+    .unwrap();
+    wit_bindgen(&["generate", "--config", "bindgen.toml"], &dir);
+
+    let src = std::fs::read_to_string(dir.join("rust/w.rs")).unwrap();
+    assert!(src.contains("//   * async: import:a:b/i#f\n"), "{src}");
+    assert!(src.contains("pub async fn f()"), "{src}");
+    assert!(src.contains("pub fn g()"), "{src}");
+
+    std::fs::write(
+        dir.join("bindgen.toml"),
+        r#"
+            wit = "test.wit"
+
+            [[target]]
+            language = "rust"
+            options = { async = ["import:"] }
+        "#,
+    )
+    .unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_wit-bindgen"))
+        .args(["generate", "--config", "bindgen.toml"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("invalid async option `import:`: missing function name"),
+        "{stderr}"
+    );
+}
+
+#[test]
+fn incremental() {
+    let dir = scratch_dir("incremental");
+    std::fs::write(
+        dir.join("test.wit"),
+        "package a:b; world w { import f: func(); }",
+    )
+    .unwrap();
+    let args = ["c", "--incremental", "--out-dir", "out", "test.wit"];
+    wit_bindgen(&args, &dir);
+
+    // Unchanged files are left alone, so their modification time stays put.
+    let header = dir.join("out/w.h");
+    let past = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+    let set_modified = |time| {
+        std::fs::File::options()
+            .write(true)
+            .open(&header)
+            .unwrap()
+            .set_modified(time)
+            .unwrap()
+    };
+    let modified = || std::fs::metadata(&header).unwrap().modified().unwrap();
+    set_modified(past);
+    wit_bindgen(&args, &dir);
+    assert_eq!(modified(), past);
+
+    // Files whose contents change are rewritten.
+    std::fs::write(
+        dir.join("test.wit"),
+        "package a:b; world w { import g: func(); }",
+    )
+    .unwrap();
+    wit_bindgen(&args, &dir);
+    assert_ne!(modified(), past);
+    assert!(std::fs::read_to_string(&header)
+        .unwrap()
+        .contains("w_g(void)"));
+
+    // Without `--incremental` everything is written again.
+    set_modified(past);
+    wit_bindgen(&["c", "--out-dir", "out", "test.wit"], &dir);
+    assert_ne!(modified(), past);
+}
+
+#[test]
+fn remove_stale() {
+    let dir = scratch_dir("remove-stale");
+    let out = dir.join("out");
+    let generate = |world: &str| {
+        std::fs::write(
+            dir.join("test.wit"),
+            format!("package a:b; world {world} {{ import f: func(); }}"),
+        )
+        .unwrap();
+        wit_bindgen(
+            &["c", "--remove-stale", "--out-dir", "out", "test.wit"],
+            &dir,
+        );
+    };
+    let files = || {
+        let mut files = std::fs::read_dir(&out)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    };
+
+    generate("one");
+    std::fs::write(out.join("hand-written.c"), "").unwrap();
+    assert_eq!(
+        files(),
+        [
+            ".wit-bindgen-manifest",
+            "hand-written.c",
+            "one.c",
+            "one.h",
+            "one_component_type.o",
+        ]
+    );
+
+    // Files from the previous run are removed, but not ones it didn't
+    // generate.
+    generate("two");
+    assert_eq!(
+        files(),
+        [
+            ".wit-bindgen-manifest",
+            "hand-written.c",
+            "two.c",
+            "two.h",
+            "two_component_type.o",
+        ]
+    );
+
+    // Paths in the manifest which lead outside of the output directory are
+    // never removed.
+    let outside = dir.join("outside.c");
+    std::fs::write(&outside, "").unwrap();
+    std::fs::write(
+        out.join(".wit-bindgen-manifest"),
+        format!("../outside.c\n{}\nsub/../../outside.c\n", outside.display()),
+    )
+    .unwrap();
+    generate("two");
+    assert!(outside.exists());
+    assert_eq!(
+        std::fs::read_to_string(out.join(".wit-bindgen-manifest")).unwrap(),
+        "two.c\ntwo.h\ntwo_component_type.o\n"
+    );
+}
+
+/// Runs `tests/external/generator.rs` as `wit-bindgen fixture`, through both
+/// the command line and a configuration file.
+#[test]
+fn external_generator() {
+    let dir = scratch_dir("external-generator");
+    let bin = dir.join("bin");
+    std::fs::create_dir(&bin).unwrap();
+    let generator = bin.join(format!(
+        "wit-bindgen-fixture{}",
+        std::env::consts::EXE_SUFFIX
+    ));
+    let status = Command::new(std::env::var_os("RUSTC").unwrap_or("rustc".into()))
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/external/generator.rs"))
+        .arg("-o")
+        .arg(&generator)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // On Unix a file which isn't executable is skipped, so one earlier on
+    // `PATH` doesn't shadow the real generator.
+    let mut dirs = Vec::new();
+    if cfg!(unix) {
+        let shadow = dir.join("shadow");
+        std::fs::create_dir(&shadow).unwrap();
+        std::fs::write(shadow.join("wit-bindgen-fixture"), "not a generator").unwrap();
+        dirs.push(shadow);
+    }
+    dirs.push(bin.clone());
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let path = std::env::join_paths(dirs.into_iter().chain(std::env::split_paths(&path))).unwrap();
+    let run = |args: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_wit-bindgen"))
+            .args(args)
+            .current_dir(&dir)
+            .env("PATH", &path)
+            .output()
+            .unwrap()
+    };
+    let succeeds = |args: &[&str]| {
+        let output = run(args);
+        assert!(
+            output.status.success(),
+            "`wit-bindgen {}` failed:\n{}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let fails = |args: &[&str]| {
+        let output = run(args);
+        assert!(!output.status.success());
+        String::from_utf8(output.stderr).unwrap()
+    };
+    let read_request = |out_dir: &str| -> Value {
+        let request = std::fs::read_to_string(dir.join(out_dir).join("request.json")).unwrap();
+        serde_json::from_str(&request).unwrap()
+    };
+
+    std::fs::write(
+        dir.join("test.wit"),
+        "
+            package a:b;
+
+            world other {}
+
+            world w {
+                import f: func(x: u32);
+            }
+        ",
+    )
+    .unwrap();
+
+    // Arguments after `--` are passed through, and the generator's stderr is
+    // passed through too.
+    let stderr = succeeds(&[
+        "fixture",
+        "--out-dir",
+        "out",
+        "--world",
+        "w",
+        "test.wit",
+        "--",
+        "--flag",
+        "value",
+    ]);
+    assert!(stderr.contains("fixture generator: received"), "{stderr}");
+    let request = read_request("out");
+    assert_eq!(request["version"], 1);
+    assert_eq!(request["args"], serde_json::json!(["--flag", "value"]));
+    assert_eq!(request["options"], serde_json::json!({}));
+    let world = &request["resolve"]["worlds"][request["world"].as_u64().unwrap() as usize];
+    assert_eq!(world["name"], "w");
+    assert_eq!(
+        std::fs::read(dir.join("out/data.bin")).unwrap(),
+        [0, 1, 255]
+    );
+
+    // A configuration file's `options` table is passed through as `options`.
+    std::fs::write(
+        dir.join("bindgen.toml"),
+        r#"
+            wit = "test.wit"
+            world = "w"
+
+            [[target]]
+            language = "fixture"
+            out-dir = "config"
+            options = { some-option = true, names = ["x", "y"] }
+        "#,
+    )
+    .unwrap();
+    succeeds(&["generate", "--config", "bindgen.toml"]);
+    let request = read_request("config");
+    assert_eq!(request["args"], serde_json::json!([]));
+    assert_eq!(
+        request["options"],
+        serde_json::json!({ "some-option": true, "names": ["x", "y"] })
+    );
+
+    // Failures of the generator, and file names which would escape the
+    // output directory, are reported.
+    let stderr = fails(&["fixture", "--world", "w", "test.wit", "--", "--fail"]);
+    assert!(stderr.contains("wit-bindgen-fixture"), "{stderr}");
+    assert!(stderr.contains("failed"), "{stderr}");
+    let stderr = fails(&["fixture", "--world", "w", "test.wit", "--", "--escape"]);
+    assert!(
+        stderr.contains(r#"produced invalid file name "../escape.json""#),
+        "{stderr}"
+    );
+    assert!(!dir.join("escape.json").exists());
+
+    let stderr = fails(&["no-such-language", "test.wit"]);
+    assert!(
+        stderr.contains("no `wit-bindgen-no-such-language` found on PATH"),
+        "{stderr}"
+    );
+}
+
+/// An export which only returns handles, such as an `error-context`, has no
+/// memory to free and so gets no post-return function.
+#[test]
+fn error_context_post_return() {
+    let dir = scratch_dir("error-context-post-return");
+    std::fs::write(
+        dir.join("test.wit"),
+        "
+            package a:b;
+
+            interface i {
+                make: func() -> error-context;
+                describe: func() -> string;
+                both: func() -> tuple<error-context, string>;
+            }
+
+            world w {
+                export i;
+            }
+        ",
+    )
+    .unwrap();
+    for (language, file) in [("rust", "w.rs"), ("c", "w.c")] {
+        wit_bindgen(&[language, "--out-dir", language, "test.wit"], &dir);
+        let src = std::fs::read_to_string(dir.join(language).join(file)).unwrap();
+        assert!(!src.contains("cabi_post_a:b/i#make"), "{src}");
+        assert!(src.contains("cabi_post_a:b/i#describe"), "{src}");
+        assert!(src.contains("cabi_post_a:b/i#both"), "{src}");
+    }
+}
+
+#[test]
+fn check() {
+    let dir = scratch_dir("check");
+    std::fs::write(
+        dir.join("test.wit"),
+        "package a:b; world w { import f: func(); }",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("bindgen.toml"),
+        r#"
+            wit = "test.wit"
+
+            [[target]]
+            language = "c"
+            out-dir = "c"
+        "#,
+    )
+    .unwrap();
+    wit_bindgen(&["generate", "--config", "bindgen.toml"], &dir);
+    let check = || {
+        Command::new(env!("CARGO_BIN_EXE_wit-bindgen"))
+            .args(["check", "--config", "bindgen.toml"])
+            .current_dir(&dir)
+            .output()
+            .unwrap()
+    };
+
+    // Freshly generated bindings are up to date.
+    let output = check();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(output.stdout.is_empty());
+
+    // An edited file is shown as a diff against what would be generated, and
+    // a missing one as a diff against nothing.
+    let header = dir.join("c/w.h");
+    let contents = std::fs::read_to_string(&header).unwrap();
+    let edited = contents.replace("extern void w_f(void);", "extern void w_g(void);");
+    assert_ne!(contents, edited);
+    std::fs::write(&header, edited).unwrap();
+    std::fs::remove_file(dir.join("c/w.c")).unwrap();
+
+    let output = check();
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let header = Path::new("c").join("w.h");
+    let source = Path::new("c").join("w.c");
+    assert!(
+        stdout.contains(&format!("--- a/{0}\n+++ b/{0}\n", header.display())),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("\n-extern void w_g(void);\n+extern void w_f(void);\n"),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!("--- /dev/null\n+++ b/{}\n", source.display())),
+        "{stdout}"
+    );
+    assert!(stdout.contains("\n+void w_f(void) {\n"), "{stdout}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("2 generated file(s) are not up to date"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,36 @@
+//! A trivial external generator for the `external_generator` test in
+//! `tests/cli.rs`, built with plain `rustc` so it has no dependencies.
+//!
+//! It writes the request it received to `request.json` and three bytes to
+//! `data.bin`. Passing `--fail` makes it exit with an error instead, and
+//! `--escape` makes it produce a file outside of the output directory.
+
+use std::io::Read;
+
+fn main() {
+    let mut request = String::new();
+    std::io::stdin().read_to_string(&mut request).unwrap();
+    eprintln!("fixture generator: received {} bytes", request.len());
+
+    if request.contains(r#""--fail""#) {
+        std::process::exit(3);
+    }
+    let name = if request.contains(r#""--escape""#) {
+        "../escape.json"
+    } else {
+        "request.json"
+    };
+
+    let mut contents = String::new();
+    for c in request.chars() {
+        match c {
+            '"' => contents.push_str("\\\""),
+            '\\' => contents.push_str("\\\\"),
+            c if (c as u32) < 0x20 => contents.push_str(&format!("\\u{:04x}", c as u32)),
+            c => contents.push(c),
+        }
+    }
+    print!(
+        r#"{{"files": [{{"name": "{name}", "contents": "{contents}"}}, {{"name": "data.bin", "bytes": [0, 1, 255]}}]}}"#
+    );
+}
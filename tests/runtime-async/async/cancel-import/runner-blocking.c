@@ -0,0 +1,25 @@
+//@ args = '--rename my:test/i=test --blocking-imports'
+
+#include <assert.h>
+#include <runner.h>
+
+// Cancels the import the first time it's polled.
+static bool cancel(void *ctx) {
+  int *polls = ctx;
+  *polls += 1;
+  return false;
+}
+
+int main() {
+  test_future_void_writer_t writer;
+  test_future_void_t reader = test_future_void_new(&writer);
+  int polls = 0;
+  assert(!test_async_pending_import_blocking(reader, cancel, &polls));
+  assert(polls == 1);
+
+  // Cancelling the import dropped the future it was waiting on.
+  runner_waitable_status_t status = test_future_void_write(writer);
+  assert(RUNNER_WAITABLE_STATE(status) == RUNNER_WAITABLE_DROPPED);
+  assert(RUNNER_WAITABLE_COUNT(status) == 0);
+  test_future_void_drop_writable(writer);
+}
@@ -0,0 +1,29 @@
+//@ args = '--rename my:test/i=test'
+
+#include <assert.h>
+#include <string.h>
+#include <runner.h>
+
+static void assert_message(runner_error_context_t ctx, const char *expected) {
+  runner_string_t message;
+  runner_error_context_debug_message(ctx, &message);
+  assert(message.len == strlen(expected));
+  assert(memcmp(message.ptr, expected, message.len) == 0);
+  runner_string_free(&message);
+}
+
+int main() {
+  runner_string_t message;
+  runner_string_set(&message, "hello");
+  runner_error_context_t ctx = test_make(&message);
+  assert_message(ctx, "hello");
+
+  ctx = test_roundtrip(ctx);
+  assert_message(ctx, "hello");
+  runner_error_context_drop(ctx);
+
+  runner_error_context_t local = runner_error_context_new(&message);
+  local = test_roundtrip(local);
+  assert_message(local, "hello");
+  runner_error_context_drop(local);
+}
@@ -0,0 +1,20 @@
+//@ args = '--rename my:test/i=test'
+
+#include <assert.h>
+#include <string.h>
+#include <test.h>
+
+test_error_context_t exports_test_make(test_string_t *message) {
+  test_error_context_t ret = test_error_context_new(message);
+  test_string_free(message);
+  return ret;
+}
+
+test_error_context_t exports_test_roundtrip(test_error_context_t ctx) {
+  test_string_t message;
+  test_error_context_debug_message(ctx, &message);
+  assert(message.len == 5);
+  assert(memcmp(message.ptr, "hello", 5) == 0);
+  test_string_free(&message);
+  return ctx;
+}
@@ -0,0 +1,28 @@
+//@ args = '--rename my:test/i=test --blocking-imports'
+
+#include <assert.h>
+#include <runner.h>
+
+struct poll_state {
+  test_future_void_writer_t writer;
+  int polls;
+};
+
+// Completes the future the import is waiting on the first time it's polled.
+static bool write_future(void *ctx) {
+  struct poll_state *state = ctx;
+  if (state->polls++ == 0) {
+    runner_waitable_status_t status = test_future_void_write(state->writer);
+    assert(RUNNER_WAITABLE_STATE(status) == RUNNER_WAITABLE_COMPLETED);
+    assert(RUNNER_WAITABLE_COUNT(status) == 0);
+    test_future_void_drop_writable(state->writer);
+  }
+  return true;
+}
+
+int main() {
+  struct poll_state state = { 0 };
+  test_future_void_t reader = test_future_void_new(&state.writer);
+  assert(test_async_pending_import_blocking(reader, write_future, &state));
+  assert(state.polls > 0);
+}
@@ -0,0 +1,22 @@
+//@ args = '--rename a:b/i=test --blocking-imports'
+
+#include <assert.h>
+#include <runner.h>
+
+int main() {
+  assert(test_async_one_argument_blocking(1, NULL, NULL));
+
+  uint32_t result = 0xffffffff;
+  assert(test_async_one_result_blocking(&result, NULL, NULL));
+  assert(result == 2);
+
+  result = 0xffffffff;
+  assert(test_async_one_argument_and_result_blocking(3, &result, NULL, NULL));
+  assert(result == 4);
+
+  assert(test_async_two_arguments_blocking(5, 6, NULL, NULL));
+
+  result = 0xffffffff;
+  assert(test_async_two_arguments_and_result_blocking(7, 8, &result, NULL, NULL));
+  assert(result == 9);
+}
@@ -0,0 +1,62 @@
+include!(env!("BINDINGS"));
+
+use crate::my::test::i::*;
+use futures::{AsyncWriteExt, SinkExt};
+use std::io::ErrorKind;
+use wit_bindgen::StreamDroppedError;
+
+fn main() {
+    wit_bindgen::block_on(async {
+        // Values sent to a sink arrive in order at the other end's stream,
+        // which ends once the sink is closed.
+        let (tx, rx) = wit_stream::new();
+        let send = async {
+            let mut tx = tx.into_sink();
+            for i in 0..1000 {
+                tx.send(i).await.unwrap();
+            }
+            tx.close().await.unwrap();
+        };
+        let ((), total) = futures::join!(send, sum(rx));
+        assert_eq!(total, 499500);
+
+        // Once the reader is dropped in the middle of the values the sink
+        // reports an error instead of waiting forever.
+        let (tx, rx) = wit_stream::new();
+        let send = async {
+            let mut tx = tx.into_sink();
+            for i in 0..1000 {
+                if let Err(e) = tx.send(i).await {
+                    return Err(e);
+                }
+            }
+            tx.close().await
+        };
+        let (result, values) = futures::join!(send, take(rx, 10));
+        assert_eq!(result, Err(StreamDroppedError));
+        assert_eq!(values, (0..10).collect::<Vec<_>>());
+
+        // Bytes written with `AsyncWrite` are read back in order with
+        // `AsyncRead`, across more than one read or write.
+        let bytes = (0..100_000).map(|i| i as u8).collect::<Vec<_>>();
+        let (tx, rx) = wit_stream::new();
+        let write = async {
+            let mut tx = tx.into_async_write();
+            tx.write_all(&bytes).await.unwrap();
+            tx.close().await.unwrap();
+        };
+        let ((), read) = futures::join!(write, read_all(rx));
+        assert_eq!(read, bytes);
+
+        // Dropping the reader in the middle of a write breaks the pipe.
+        let (tx, rx) = wit_stream::new();
+        let write = async {
+            let mut tx = tx.into_async_write();
+            tx.write_all(&bytes).await?;
+            tx.close().await
+        };
+        let (result, read) = futures::join!(write, read_exact(rx, 10));
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::BrokenPipe);
+        assert_eq!(read, bytes[..10]);
+    });
+}
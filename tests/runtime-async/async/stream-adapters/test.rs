@@ -0,0 +1,30 @@
+use futures::{AsyncReadExt, StreamExt};
+use wit_bindgen::StreamReader;
+
+include!(env!("BINDINGS"));
+
+struct Component;
+
+export!(Component);
+
+impl crate::exports::my::test::i::Guest for Component {
+    async fn sum(x: StreamReader<u32>) -> u32 {
+        x.into_stream().fold(0, |sum, x| async move { sum + x }).await
+    }
+
+    async fn take(x: StreamReader<u32>, n: u32) -> Vec<u32> {
+        x.into_stream().take(n as usize).collect().await
+    }
+
+    async fn read_all(x: StreamReader<u8>) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        x.into_async_read().read_to_end(&mut bytes).await.unwrap();
+        bytes
+    }
+
+    async fn read_exact(x: StreamReader<u8>, n: u32) -> Vec<u8> {
+        let mut bytes = vec![0; n as usize];
+        x.into_async_read().read_exact(&mut bytes).await.unwrap();
+        bytes
+    }
+}
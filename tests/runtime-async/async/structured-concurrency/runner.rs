@@ -0,0 +1,95 @@
+include!(env!("BINDINGS"));
+
+use crate::my::test::i::*;
+use std::cell::RefCell;
+use std::future::IntoFuture;
+use std::rc::Rc;
+use wit_bindgen::{cancel_on_drop, select, yield_async, JoinSet, Scope, Selected};
+
+fn main() {
+    println!("test fanning out imports with a bounded join set");
+    wit_bindgen::block_on(async {
+        let mut set = JoinSet::bounded(2);
+        for i in 0..5 {
+            set.spawn(double(i));
+        }
+        assert_eq!(set.len(), 5);
+        let mut results = set.join_all().await;
+        results.sort();
+        assert_eq!(results, [0, 2, 4, 6, 8]);
+    });
+
+    println!("test cancelling the losing import of a select");
+    wit_bindgen::block_on(async {
+        let (tx, rx) = wit_future::new(|| unreachable!());
+        let (data_tx, data_rx) = wit_future::new(|| unreachable!());
+        let scope = Scope::new();
+        scope.spawn(async move { data_tx.write(()).await.unwrap() });
+        let selected = scope
+            .run(select(
+                cancel_on_drop(pending_import(rx)),
+                data_rx.into_future(),
+            ))
+            .await;
+        assert!(matches!(selected, Selected::Second((), ())));
+        scope.join().await;
+
+        // The import was cancelled, dropping its end of the future.
+        tx.write(()).await.unwrap_err();
+    });
+
+    println!("test cancelling the losing read of a select");
+    wit_bindgen::block_on(async {
+        let (tx, rx) = wit_future::new(|| unreachable!());
+        match select(cancel_on_drop(double(21)), rx.into_future()).await {
+            // The read was cancelled and handed back its reader.
+            Selected::First(42, Err(rx)) => drop(rx),
+            _ => panic!("unexpected select result"),
+        }
+        tx.write(()).await.unwrap_err();
+    });
+
+    println!("test cancelling a scope's imports when it's dropped");
+    wit_bindgen::block_on(async {
+        let (tx, rx) = wit_future::new(|| unreachable!());
+        let scope = Scope::new();
+        scope.spawn(pending_import(rx));
+        scope.run(yield_async()).await;
+        assert_eq!(scope.len(), 1);
+        drop(scope);
+        tx.write(()).await.unwrap_err();
+    });
+
+    println!("test spawning into a scope from one of its futures");
+    wit_bindgen::block_on(async {
+        let scope = Rc::new(Scope::new());
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let (inner_scope, inner_results) = (scope.clone(), results.clone());
+        scope.spawn(async move {
+            inner_results.borrow_mut().push(double(1).await);
+            inner_scope.spawn(async move {
+                inner_results.borrow_mut().push(double(2).await);
+            });
+        });
+        scope.join().await;
+        assert!(scope.is_empty());
+        assert_eq!(*results.borrow(), [2, 4]);
+    });
+
+    println!("test cancelling a scope from one of its futures");
+    wit_bindgen::block_on(async {
+        let (tx, rx) = wit_future::new(|| unreachable!());
+        let scope = Rc::new(Scope::new());
+        scope.spawn(pending_import(rx));
+        let inner_scope = scope.clone();
+        scope.spawn(async move {
+            assert_eq!(double(1).await, 2);
+            inner_scope.cancel_all();
+            // This future is dropped as soon as it yields.
+            std::future::pending::<()>().await;
+        });
+        scope.join().await;
+        assert!(scope.is_empty());
+        tx.write(()).await.unwrap_err();
+    });
+}
@@ -0,0 +1,17 @@
+use wit_bindgen::FutureReader;
+
+include!(env!("BINDINGS"));
+
+struct Component;
+
+export!(Component);
+
+impl crate::exports::my::test::i::Guest for Component {
+    async fn double(x: u32) -> u32 {
+        x * 2
+    }
+
+    async fn pending_import(x: FutureReader<()>) {
+        x.await
+    }
+}
@@ -0,0 +1,72 @@
+include!(env!("BINDINGS"));
+
+use crate::my::test::i::*;
+use futures::task::noop_waker_ref;
+use std::cell::RefCell;
+use std::future::Future;
+use std::task::Context;
+use wit_bindgen::trace::{self, Event};
+
+const EVENT_SUBTASK: u32 = 1;
+const STATUS_RETURNED: u32 = 2;
+
+thread_local! {
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(event: &Event) {
+    EVENTS.with(|events| events.borrow_mut().push(*event));
+}
+
+fn main() {
+    assert!(trace::set_hook(Some(record)).is_none());
+
+    println!("test the events of an import call");
+    wit_bindgen::block_on(async {
+        yielding().await;
+    });
+    let events = EVENTS.take();
+    let [Event::TaskStart { task }, Event::WakerRegistered { waitable }, ..] = events[..] else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(
+        events,
+        [
+            Event::TaskStart { task },
+            Event::WakerRegistered { waitable },
+            Event::EventReceived {
+                task,
+                event: EVENT_SUBTASK,
+                waitable,
+                code: STATUS_RETURNED,
+            },
+            Event::TaskFinish { task },
+        ]
+    );
+
+    println!("test the events of cancelling an import");
+    wit_bindgen::block_on(async {
+        let (tx, rx) = wit_future::new(|| unreachable!());
+        let mut import = Box::pin(pending(rx));
+        assert!(import
+            .as_mut()
+            .poll(&mut Context::from_waker(noop_waker_ref()))
+            .is_pending());
+        drop(import);
+        tx.write(()).await.unwrap_err();
+    });
+    let events = EVENTS.take();
+    let [Event::TaskStart { task }, Event::WakerRegistered { waitable }, Event::WakerUnregistered { .. }, Event::OperationCancelled { .. }, ..] =
+        events[..]
+    else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(events[2], Event::WakerUnregistered { waitable });
+    assert!(
+        matches!(events[3], Event::OperationCancelled { waitable: w, .. } if w == waitable),
+        "{events:?}"
+    );
+    assert_eq!(events.last(), Some(&Event::TaskFinish { task }));
+
+    assert!(trace::set_hook(None).is_some());
+}
@@ -0,0 +1,19 @@
+use wit_bindgen::{yield_async, FutureReader};
+
+include!(env!("BINDINGS"));
+
+struct Component;
+
+export!(Component);
+
+impl crate::exports::my::test::i::Guest for Component {
+    async fn yielding() {
+        for _ in 0..10 {
+            yield_async().await;
+        }
+    }
+
+    async fn pending(x: FutureReader<()>) {
+        x.await
+    }
+}
@@ -0,0 +1,59 @@
+//@ args = '--arena'
+
+#include <assert.h>
+#include <string.h>
+#include "runner.h"
+
+static void assert_str(runner_string_t *str, const char *expected) {
+  size_t expected_len = strlen(expected);
+  assert(str->len == expected_len);
+  assert(memcmp(str->ptr, expected, expected_len) == 0);
+}
+
+int main() {
+  {
+    runner_string_t strings[3];
+    runner_string_set(&strings[0], "a");
+    runner_string_set(&strings[1], "bc");
+    runner_string_set(&strings[2], "");
+    runner_list_string_t a = { strings, 3 };
+    runner_list_string_t ret;
+    test_arena_to_test_reverse_strings(&a, &ret);
+    assert(ret.len == 3);
+    assert_str(&ret.ptr[0], "");
+    assert_str(&ret.ptr[1], "bc");
+    assert_str(&ret.ptr[2], "a");
+    runner_list_string_free(&ret);
+  }
+
+  {
+    test_arena_to_test_segment_t segments[2] = {
+      { { 0, 0 }, { 1, 2 } },
+      { { 1, 2 }, { -3, 4 } },
+    };
+    runner_string_t tags[2];
+    runner_string_set(&tags[0], "red");
+    runner_string_set(&tags[1], "dashed");
+    test_arena_to_test_path_t p;
+    runner_string_set(&p.name, "path");
+    p.segments.ptr = segments;
+    p.segments.len = 2;
+    p.tags.ptr = tags;
+    p.tags.len = 2;
+    test_arena_to_test_path_t ret;
+    test_arena_to_test_translate(&p, 10, -10, &ret);
+    assert_str(&ret.name, "translated");
+    assert(ret.segments.len == 2);
+    assert(ret.segments.ptr[0].start.x == 10 && ret.segments.ptr[0].start.y == -10);
+    assert(ret.segments.ptr[0].end.x == 11 && ret.segments.ptr[0].end.y == -8);
+    assert(ret.segments.ptr[1].start.x == 11 && ret.segments.ptr[1].start.y == -8);
+    assert(ret.segments.ptr[1].end.x == 7 && ret.segments.ptr[1].end.y == -6);
+    assert(ret.tags.len == 2);
+    assert_str(&ret.tags.ptr[0], "red");
+    assert_str(&ret.tags.ptr[1], "dashed");
+    test_arena_to_test_path_free(&ret);
+  }
+
+  // Nothing is exported, so the runner resets the arena itself
+  cabi_arena_reset();
+}
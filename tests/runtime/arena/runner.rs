@@ -0,0 +1,34 @@
+include!(env!("BINDINGS"));
+
+use crate::test::arena::to_test::*;
+
+fn main() {
+    assert_eq!(
+        reverse_strings(&["a".to_string(), "bc".to_string(), String::new()]),
+        ["", "bc", "a"]
+    );
+
+    let p = Path {
+        name: "path".to_string(),
+        segments: vec![
+            Segment {
+                start: Point { x: 0, y: 0 },
+                end: Point { x: 1, y: 2 },
+            },
+            Segment {
+                start: Point { x: 1, y: 2 },
+                end: Point { x: -3, y: 4 },
+            },
+        ],
+        tags: vec!["red".to_string(), "dashed".to_string()],
+    };
+    let ret = translate(&p, 10, -10);
+    assert_eq!(ret.name, "translated");
+    let segments = ret
+        .segments
+        .iter()
+        .map(|s| (s.start.x, s.start.y, s.end.x, s.end.y))
+        .collect::<Vec<_>>();
+    assert_eq!(segments, [(10, -10, 11, -8), (11, -8, 7, -6)]);
+    assert_eq!(ret.tags, ["red", "dashed"]);
+}
@@ -0,0 +1,36 @@
+//@ args = '--arena'
+
+#include <assert.h>
+#include <stdalign.h>
+#include <string.h>
+#include "test.h"
+
+void exports_test_arena_to_test_reverse_strings(test_list_string_t *a, test_list_string_t *ret) {
+  ret->len = a->len;
+  ret->ptr = cabi_arena_alloc(a->len * sizeof(test_string_t), alignof(test_string_t));
+  for (size_t i = 0; i < a->len; i++) {
+    // The strings stay in the arena until the post-return function resets it
+    ret->ptr[i] = a->ptr[a->len - 1 - i];
+  }
+  // Freeing doesn't release anything, so the strings are still valid
+  test_list_string_free(a);
+}
+
+void exports_test_arena_to_test_translate(exports_test_arena_to_test_path_t *p, int32_t dx, int32_t dy, exports_test_arena_to_test_path_t *ret) {
+  test_string_dup(&ret->name, "translated");
+  ret->segments.len = p->segments.len;
+  ret->segments.ptr = cabi_arena_alloc(
+      p->segments.len * sizeof(exports_test_arena_to_test_segment_t),
+      alignof(exports_test_arena_to_test_segment_t));
+  for (size_t i = 0; i < p->segments.len; i++) {
+    exports_test_arena_to_test_segment_t *from = &p->segments.ptr[i];
+    exports_test_arena_to_test_segment_t *to = &ret->segments.ptr[i];
+    to->start.x = from->start.x + dx;
+    to->start.y = from->start.y + dy;
+    to->end.x = from->end.x + dx;
+    to->end.y = from->end.y + dy;
+  }
+  ret->tags = p->tags;
+  assert(p->name.len == 4 && memcmp(p->name.ptr, "path", 4) == 0);
+  exports_test_arena_to_test_path_free(p);
+}
@@ -0,0 +1,113 @@
+#include <assert.h>
+#include <stdint.h>
+
+#include "runner.h"
+
+int main() {
+  {
+    runner_array4_u32_t a = {{1, 2, 3, 4}};
+    test_fixed_size_lists_to_test_list_param(&a);
+  }
+
+  {
+    runner_array2_array2_u32_t a = {{{{1, 2}}, {{3, 4}}}};
+    test_fixed_size_lists_to_test_list_param2(&a);
+  }
+
+  {
+    runner_array20_s32_t a = {{-1, 2, -3, 4, -5, 6, -7, 8, -9, 10,
+                               -11, 12, -13, 14, -15, 16, -17, 18, -19, 20}};
+    test_fixed_size_lists_to_test_list_param3(&a);
+  }
+
+  {
+    runner_array8_u8_t ret;
+    test_fixed_size_lists_to_test_list_result(&ret);
+    assert(ret.data[0] == '0');
+    assert(ret.data[1] == '1');
+    assert(ret.data[2] == 'A');
+    assert(ret.data[3] == 'B');
+    assert(ret.data[4] == 'a');
+    assert(ret.data[5] == 'b');
+    assert(ret.data[6] == 128);
+    assert(ret.data[7] == 255);
+  }
+
+  {
+    runner_array4_u16_t a = {{0, 1024, 32768, 65535}};
+    runner_array4_s16_t b = {{1, 2048, -32767, -2}};
+    runner_tuple2_array4_u16_array4_s16_t ret;
+    test_fixed_size_lists_to_test_list_minmax16(&a, &b, &ret);
+    for (int i = 0; i < 4; i++) {
+      assert(ret.f0.data[i] == a.data[i]);
+      assert(ret.f1.data[i] == b.data[i]);
+    }
+  }
+
+  {
+    runner_array2_f32_t a = {{2.0, -42.0}};
+    runner_array2_f64_t b = {{0.25, -0.125}};
+    runner_tuple2_array2_f32_array2_f64_t ret;
+    test_fixed_size_lists_to_test_list_minmax_float(&a, &b, &ret);
+    for (int i = 0; i < 2; i++) {
+      assert(ret.f0.data[i] == a.data[i]);
+      assert(ret.f1.data[i] == b.data[i]);
+    }
+  }
+
+  {
+    runner_array12_u8_t a = {{'a', 'b', 'c', 'd', 0, 1, 2, 3, 'A', 'B', 'Y', 'Z'}};
+    runner_array12_u8_t ret;
+    test_fixed_size_lists_to_test_list_roundtrip(&a, &ret);
+    for (int i = 0; i < 12; i++) {
+      assert(ret.data[i] == a.data[i]);
+    }
+  }
+
+  {
+    runner_array2_array2_u32_t a = {{{{1, 5}}, {{42, 1000000}}}};
+    runner_array2_array2_s32_t b = {{{{-1, 3}}, {{-2000000, 4711}}}};
+    runner_tuple2_array2_array2_u32_array2_array2_s32_t ret;
+    test_fixed_size_lists_to_test_nested_roundtrip(&a, &b, &ret);
+    for (int i = 0; i < 2; i++) {
+      for (int j = 0; j < 2; j++) {
+        assert(ret.f0.data[i].data[j] == a.data[i].data[j]);
+        assert(ret.f1.data[i].data[j] == b.data[i].data[j]);
+      }
+    }
+  }
+
+  {
+    runner_array2_array2_u32_t a = {{{{1, 5}}, {{42, 1000000}}}};
+    runner_array4_array4_s32_t b = {{
+        {{-1, 3, -2, 4}},
+        {{-2000000, 4711, 99999, -5}},
+        {{-6, 7, 8, -9}},
+        {{50, -5, 500, -5000}},
+    }};
+    runner_tuple2_array2_array2_u32_array4_array4_s32_t ret;
+    test_fixed_size_lists_to_test_large_roundtrip(&a, &b, &ret);
+    for (int i = 0; i < 2; i++) {
+      for (int j = 0; j < 2; j++) {
+        assert(ret.f0.data[i].data[j] == a.data[i].data[j]);
+      }
+    }
+    for (int i = 0; i < 4; i++) {
+      for (int j = 0; j < 4; j++) {
+        assert(ret.f1.data[i].data[j] == b.data[i].data[j]);
+      }
+    }
+  }
+
+  {
+    test_fixed_size_lists_to_test_array2_nested_t a = {{{{{1, -1}}}, {{{2, -2}}}}};
+    test_fixed_size_lists_to_test_array2_nested_t ret;
+    test_fixed_size_lists_to_test_nightmare_on_cpp(&a, &ret);
+    assert(ret.data[0].l.data[0] == 1);
+    assert(ret.data[0].l.data[1] == -1);
+    assert(ret.data[1].l.data[0] == 2);
+    assert(ret.data[1].l.data[1] == -2);
+  }
+
+  return 0;
+}
@@ -0,0 +1,60 @@
+#include <assert.h>
+#include <runner_cpp.h>
+
+int main()
+{
+    using namespace ::test::fixed_size_lists::to_test;
+
+    ListParam(std::array<uint32_t, 4>{1, 2, 3, 4});
+    ListParam2(std::array<std::array<uint32_t, 2>, 2>{
+        std::array<uint32_t, 2>{1, 2}, std::array<uint32_t, 2>{3, 4}});
+    ListParam3(std::array<int32_t, 20>{-1, 2, -3, 4, -5, 6, -7, 8, -9, 10,
+                                       -11, 12, -13, 14, -15, 16, -17, 18, -19, 20});
+    assert((ListResult() == std::array<uint8_t, 8>{'0', '1', 'A', 'B', 'a', 'b', 128, 255}));
+    {
+        std::array<uint16_t, 4> a{0, 1024, 32768, 65535};
+        std::array<int16_t, 4> b{1, 2048, -32767, -2};
+        auto result = ListMinmax16(a, b);
+        assert(std::get<0>(result) == a);
+        assert(std::get<1>(result) == b);
+    }
+    {
+        std::array<float, 2> a{2.0, -42.0};
+        std::array<double, 2> b{0.25, -0.125};
+        auto result = ListMinmaxFloat(a, b);
+        assert(std::get<0>(result) == a);
+        assert(std::get<1>(result) == b);
+    }
+    {
+        std::array<uint8_t, 12> a{'a', 'b', 'c', 'd', 0, 1, 2, 3, 'A', 'B', 'Y', 'Z'};
+        assert(ListRoundtrip(a) == a);
+    }
+    {
+        std::array<std::array<uint32_t, 2>, 2> a{
+            std::array<uint32_t, 2>{1, 5}, std::array<uint32_t, 2>{42, 1000000}};
+        std::array<std::array<int32_t, 2>, 2> b{
+            std::array<int32_t, 2>{-1, 3}, std::array<int32_t, 2>{-2000000, 4711}};
+        auto result = NestedRoundtrip(a, b);
+        assert(std::get<0>(result) == a);
+        assert(std::get<1>(result) == b);
+    }
+    {
+        std::array<std::array<uint32_t, 2>, 2> a{
+            std::array<uint32_t, 2>{1, 5}, std::array<uint32_t, 2>{42, 1000000}};
+        std::array<std::array<int32_t, 4>, 4> b{
+            std::array<int32_t, 4>{-1, 3, -2, 4},
+            std::array<int32_t, 4>{-2000000, 4711, 99999, -5},
+            std::array<int32_t, 4>{-6, 7, 8, -9},
+            std::array<int32_t, 4>{50, -5, 500, -5000}};
+        auto result = LargeRoundtrip(a, b);
+        assert(std::get<0>(result) == a);
+        assert(std::get<1>(result) == b);
+    }
+    {
+        std::array<Nested, 2> a{Nested{std::array<int32_t, 2>{1, -1}},
+                                Nested{std::array<int32_t, 2>{2, -2}}};
+        auto result = NightmareOnCpp(a);
+        assert((result[0].l == std::array<int32_t, 2>{1, -1}));
+        assert((result[1].l == std::array<int32_t, 2>{2, -2}));
+    }
+}
@@ -0,0 +1,85 @@
+using System.Diagnostics;
+using RunnerWorld.wit.imports.test.fixedSizeLists;
+
+public class Program
+{
+    public static void Main(string[] args)
+    {
+        ToTestInterop.ListParam(new uint[] { 1, 2, 3, 4 });
+        ToTestInterop.ListParam2(new uint[][] { new uint[] { 1, 2 }, new uint[] { 3, 4 } });
+        ToTestInterop.ListParam3(new int[] {
+            -1, 2, -3, 4, -5, 6, -7, 8, -9, 10,
+            -11, 12, -13, 14, -15, 16, -17, 18, -19, 20
+        });
+
+        {
+            byte[] result = ToTestInterop.ListResult();
+            Debug.Assert(result.SequenceEqual(new byte[] {
+                (byte)'0', (byte)'1', (byte)'A', (byte)'B', (byte)'a', (byte)'b', 128, 255
+            }));
+        }
+
+        {
+            var a = new ushort[] { 0, 1024, 32768, 65535 };
+            var b = new short[] { 1, 2048, -32767, -2 };
+            var (u, s) = ToTestInterop.ListMinmax16(a, b);
+            Debug.Assert(u.SequenceEqual(a));
+            Debug.Assert(s.SequenceEqual(b));
+        }
+
+        {
+            var a = new float[] { 2.0f, -42.0f };
+            var b = new double[] { 0.25, -0.125 };
+            var (f, d) = ToTestInterop.ListMinmaxFloat(a, b);
+            Debug.Assert(f.SequenceEqual(a));
+            Debug.Assert(d.SequenceEqual(b));
+        }
+
+        {
+            var a = new byte[] {
+                (byte)'a', (byte)'b', (byte)'c', (byte)'d', 0, 1, 2, 3,
+                (byte)'A', (byte)'B', (byte)'Y', (byte)'Z'
+            };
+            Debug.Assert(ToTestInterop.ListRoundtrip(a).SequenceEqual(a));
+        }
+
+        {
+            var a = new uint[][] { new uint[] { 1, 5 }, new uint[] { 42, 1_000_000 } };
+            var b = new int[][] { new int[] { -1, 3 }, new int[] { -2_000_000, 4711 } };
+            var (ra, rb) = ToTestInterop.NestedRoundtrip(a, b);
+            for (int i = 0; i < 2; i++)
+            {
+                Debug.Assert(ra[i].SequenceEqual(a[i]));
+                Debug.Assert(rb[i].SequenceEqual(b[i]));
+            }
+        }
+
+        {
+            var a = new uint[][] { new uint[] { 1, 5 }, new uint[] { 42, 1_000_000 } };
+            var b = new int[][] {
+                new int[] { -1, 3, -2, 4 },
+                new int[] { -2_000_000, 4711, 99_999, -5 },
+                new int[] { -6, 7, 8, -9 },
+                new int[] { 50, -5, 500, -5000 }
+            };
+            var (ra, rb) = ToTestInterop.LargeRoundtrip(a, b);
+            for (int i = 0; i < 2; i++)
+            {
+                Debug.Assert(ra[i].SequenceEqual(a[i]));
+            }
+            for (int i = 0; i < 4; i++)
+            {
+                Debug.Assert(rb[i].SequenceEqual(b[i]));
+            }
+        }
+
+        {
+            var result = ToTestInterop.NightmareOnCpp(new IToTest.Nested[] {
+                new IToTest.Nested(new int[] { 1, -1 }),
+                new IToTest.Nested(new int[] { 2, -2 })
+            });
+            Debug.Assert(result[0].l.SequenceEqual(new int[] { 1, -1 }));
+            Debug.Assert(result[1].l.SequenceEqual(new int[] { 2, -2 }));
+        }
+    }
+}
@@ -0,0 +1,60 @@
+#include <assert.h>
+#include <stdint.h>
+
+#include "test.h"
+
+void exports_test_fixed_size_lists_to_test_list_param(test_array4_u32_t *a) {
+  assert(a->data[0] == 1);
+  assert(a->data[1] == 2);
+  assert(a->data[2] == 3);
+  assert(a->data[3] == 4);
+}
+
+void exports_test_fixed_size_lists_to_test_list_param2(test_array2_array2_u32_t *a) {
+  assert(a->data[0].data[0] == 1);
+  assert(a->data[0].data[1] == 2);
+  assert(a->data[1].data[0] == 3);
+  assert(a->data[1].data[1] == 4);
+}
+
+void exports_test_fixed_size_lists_to_test_list_param3(test_array20_s32_t *a) {
+  for (int32_t i = 0; i < 20; i++) {
+    int32_t expected = i % 2 == 0 ? -(i + 1) : i + 1;
+    assert(a->data[i] == expected);
+  }
+}
+
+void exports_test_fixed_size_lists_to_test_list_result(test_array8_u8_t *ret) {
+  uint8_t values[8] = {'0', '1', 'A', 'B', 'a', 'b', 128, 255};
+  for (int i = 0; i < 8; i++) {
+    ret->data[i] = values[i];
+  }
+}
+
+void exports_test_fixed_size_lists_to_test_list_minmax16(test_array4_u16_t *a, test_array4_s16_t *b, test_tuple2_array4_u16_array4_s16_t *ret) {
+  ret->f0 = *a;
+  ret->f1 = *b;
+}
+
+void exports_test_fixed_size_lists_to_test_list_minmax_float(test_array2_f32_t *a, test_array2_f64_t *b, test_tuple2_array2_f32_array2_f64_t *ret) {
+  ret->f0 = *a;
+  ret->f1 = *b;
+}
+
+void exports_test_fixed_size_lists_to_test_list_roundtrip(test_array12_u8_t *a, test_array12_u8_t *ret) {
+  *ret = *a;
+}
+
+void exports_test_fixed_size_lists_to_test_nested_roundtrip(test_array2_array2_u32_t *a, test_array2_array2_s32_t *b, test_tuple2_array2_array2_u32_array2_array2_s32_t *ret) {
+  ret->f0 = *a;
+  ret->f1 = *b;
+}
+
+void exports_test_fixed_size_lists_to_test_large_roundtrip(test_array2_array2_u32_t *a, test_array4_array4_s32_t *b, test_tuple2_array2_array2_u32_array4_array4_s32_t *ret) {
+  ret->f0 = *a;
+  ret->f1 = *b;
+}
+
+void exports_test_fixed_size_lists_to_test_nightmare_on_cpp(exports_test_fixed_size_lists_to_test_array2_nested_t *a, exports_test_fixed_size_lists_to_test_array2_nested_t *ret) {
+  *ret = *a;
+}
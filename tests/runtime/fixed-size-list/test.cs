@@ -0,0 +1,61 @@
+using System.Diagnostics;
+
+namespace TestWorld.wit.exports.test.fixedSizeLists
+{
+    public class ToTestImpl : ITestWorld
+    {
+        public static void ListParam(uint[] a)
+        {
+            Debug.Assert(a.SequenceEqual(new uint[] { 1, 2, 3, 4 }));
+        }
+
+        public static void ListParam2(uint[][] a)
+        {
+            Debug.Assert(a[0].SequenceEqual(new uint[] { 1, 2 }));
+            Debug.Assert(a[1].SequenceEqual(new uint[] { 3, 4 }));
+        }
+
+        public static void ListParam3(int[] a)
+        {
+            Debug.Assert(a.SequenceEqual(new int[] {
+                -1, 2, -3, 4, -5, 6, -7, 8, -9, 10,
+                -11, 12, -13, 14, -15, 16, -17, 18, -19, 20
+            }));
+        }
+
+        public static byte[] ListResult()
+        {
+            return new byte[] { (byte)'0', (byte)'1', (byte)'A', (byte)'B', (byte)'a', (byte)'b', 128, 255 };
+        }
+
+        public static (ushort[], short[]) ListMinmax16(ushort[] a, short[] b)
+        {
+            return (a, b);
+        }
+
+        public static (float[], double[]) ListMinmaxFloat(float[] a, double[] b)
+        {
+            return (a, b);
+        }
+
+        public static byte[] ListRoundtrip(byte[] a)
+        {
+            return a;
+        }
+
+        public static (uint[][], int[][]) NestedRoundtrip(uint[][] a, int[][] b)
+        {
+            return (a, b);
+        }
+
+        public static (uint[][], int[][]) LargeRoundtrip(uint[][] a, int[][] b)
+        {
+            return (a, b);
+        }
+
+        public static IToTest.Nested[] NightmareOnCpp(IToTest.Nested[] a)
+        {
+            return a;
+        }
+    }
+}
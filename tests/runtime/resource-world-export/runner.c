@@ -0,0 +1,17 @@
+#include <assert.h>
+#include "runner.h"
+
+int main() {
+  runner_own_thing_t a = runner_constructor_thing(1);
+  runner_own_thing_t b = runner_constructor_thing(2);
+  assert(runner_method_thing_get(runner_borrow_thing(a)) == 1 + 1);
+  assert(runner_method_thing_get(runner_borrow_thing(b)) == 2 + 1);
+
+  runner_own_thing_t c = runner_static_thing_add(runner_borrow_thing(a), runner_borrow_thing(b));
+  assert(runner_method_thing_get(runner_borrow_thing(c)) == 2 + 3);
+  assert(runner_foo(runner_borrow_thing(c)) == 5 + 2);
+
+  runner_thing_drop_own(a);
+  runner_thing_drop_own(b);
+  runner_thing_drop_own(c);
+}
@@ -0,0 +1,12 @@
+include!(env!("BINDINGS"));
+
+fn main() {
+    let a = Thing::new(1);
+    let b = Thing::new(2);
+    assert_eq!(a.get(), 1 + 1);
+    assert_eq!(b.get(), 2 + 1);
+
+    let c = Thing::add(&a, &b);
+    assert_eq!(c.get(), 2 + 3);
+    assert_eq!(foo(&c), 5 + 2);
+}
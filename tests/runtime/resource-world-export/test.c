@@ -0,0 +1,34 @@
+#include <assert.h>
+#include <stdlib.h>
+#include "test.h"
+
+struct test_thing_t {
+  uint32_t my_state;
+};
+
+static test_own_thing_t make_thing(uint32_t state) {
+  test_thing_t *rep = malloc(sizeof(test_thing_t));
+  assert(rep != NULL);
+  rep->my_state = state;
+  return test_thing_new(rep);
+}
+
+test_own_thing_t exports_test_constructor_thing(uint32_t v) {
+  return make_thing(v + 1);
+}
+
+uint32_t exports_test_method_thing_get(test_borrow_thing_t self) {
+  return self->my_state;
+}
+
+test_own_thing_t exports_test_static_thing_add(test_borrow_thing_t a, test_borrow_thing_t b) {
+  return make_thing(a->my_state + b->my_state);
+}
+
+uint32_t exports_test_foo(test_borrow_thing_t v) {
+  return v->my_state + 2;
+}
+
+void test_thing_destructor(test_thing_t *rep) {
+  free(rep);
+}
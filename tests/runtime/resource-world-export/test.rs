@@ -0,0 +1,33 @@
+include!(env!("BINDINGS"));
+
+pub struct Test {}
+
+export!(Test);
+
+pub struct MyThing {
+    val: u32,
+}
+
+impl Guest for Test {
+    type Thing = MyThing;
+
+    fn foo(v: ThingBorrow<'_>) -> u32 {
+        v.get::<MyThing>().val + 2
+    }
+}
+
+impl GuestThing for MyThing {
+    fn new(v: u32) -> Self {
+        Self { val: v + 1 }
+    }
+
+    fn get(&self) -> u32 {
+        self.val
+    }
+
+    fn add(a: ThingBorrow<'_>, b: ThingBorrow<'_>) -> Thing {
+        Thing::new(MyThing {
+            val: a.get::<MyThing>().val + b.get::<MyThing>().val,
+        })
+    }
+}
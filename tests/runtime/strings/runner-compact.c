@@ -0,0 +1,58 @@
+//@ args = '--string-encoding compact-utf16'
+
+#include <assert.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdio.h>
+#include "runner.h"
+
+void assert_str(runner_string_t* str, const char16_t* expected) {
+  size_t expected_len = 0;
+  while (expected[expected_len])
+    expected_len++;
+  assert(str->len == expected_len);
+  if (str->utf16) {
+    assert(memcmp(str->ptr, expected, expected_len * 2) == 0);
+  } else {
+    for (size_t i = 0; i < expected_len; i++)
+      assert(str->ptr[i] == expected[i]);
+  }
+}
+
+// Compact strings must be 2-byte aligned, latin1 ones included.
+static _Alignas(2) const char basic[] = "latin utf16";
+
+int main() {
+  runner_string_t str1;
+  runner_string_set(&str1, basic);
+  test_strings_to_test_take_basic(&str1);
+
+  runner_string_t str2;
+  test_strings_to_test_return_unicode(&str2);
+  assert(str2.utf16);
+  assert_str(&str2, u"🚀🚀🚀 𠈄𓀀");
+  runner_string_free(&str2);
+
+  runner_string_t str3;
+  test_strings_to_test_return_empty(&str3);
+  assert_str(&str3, u"");
+  runner_string_free(&str3);
+
+  runner_string_t str4;
+  runner_string_t str5;
+  runner_string_set_utf16(&str4, u"🚀🚀🚀 𠈄𓀀");
+  test_strings_to_test_roundtrip(&str4, &str5);
+  assert_str(&str5, u"🚀🚀🚀 𠈄𓀀");
+  runner_string_free(&str5);
+
+  // Latin1 strings outside of ASCII stay compact. Copies are aligned even
+  // when the string they're copied from isn't.
+  runner_string_t str6;
+  runner_string_t str7;
+  runner_string_dup(&str6, &"xcaf\xe9"[1]);
+  assert(((uintptr_t) str6.ptr & 1) == 0);
+  test_strings_to_test_roundtrip(&str6, &str7);
+  assert_str(&str7, u"café");
+  runner_string_free(&str6);
+  runner_string_free(&str7);
+}
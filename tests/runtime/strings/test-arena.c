@@ -0,0 +1,33 @@
+//@ args = '--arena'
+
+#include <assert.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdio.h>
+#include "test.h"
+
+void assert_str(test_string_t* str, const char* expected) {
+  size_t expected_len = strlen(expected);
+  assert(str->len == expected_len);
+  assert(memcmp(str->ptr, expected, expected_len) == 0);
+}
+
+void exports_test_strings_to_test_take_basic(test_string_t *str1) {
+  assert_str(str1, "latin utf16");
+  test_string_free(str1);
+}
+
+void exports_test_strings_to_test_return_unicode(test_string_t *ret) {
+  // Nothing is freed individually, so a literal can be returned as-is
+  test_string_set(ret, "🚀🚀🚀 𠈄𓀀");
+}
+
+void exports_test_strings_to_test_return_empty(test_string_t *ret) {
+  test_string_dup(ret, ""); // Exercise cabi_realloc new_size = 0
+}
+
+void exports_test_strings_to_test_roundtrip(test_string_t *str, test_string_t *ret) {
+  assert(str->len > 0);
+  // The argument lives in the arena until the post-return function resets it
+  *ret = *str;
+}
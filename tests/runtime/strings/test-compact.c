@@ -0,0 +1,43 @@
+//@ args = '--string-encoding compact-utf16'
+
+#include <assert.h>
+#include <stdlib.h>
+#include <string.h>
+#include <stdio.h>
+#include "test.h"
+
+void assert_str(test_string_t* str, const char16_t* expected) {
+  size_t expected_len = 0;
+  while (expected[expected_len])
+    expected_len++;
+  assert(str->len == expected_len);
+  if (str->utf16) {
+    assert(memcmp(str->ptr, expected, expected_len * 2) == 0);
+  } else {
+    for (size_t i = 0; i < expected_len; i++)
+      assert(str->ptr[i] == expected[i]);
+  }
+}
+
+void exports_test_strings_to_test_take_basic(test_string_t *str1) {
+  assert_str(str1, u"latin utf16");
+  test_string_free(str1);
+}
+
+void exports_test_strings_to_test_return_unicode(test_string_t *ret) {
+  test_string_dup_utf16(ret, u"🚀🚀🚀 𠈄𓀀");
+}
+
+void exports_test_strings_to_test_return_empty(test_string_t *ret) {
+  test_string_dup(ret, ""); // Exercise cabi_realloc new_size = 0
+}
+
+void exports_test_strings_to_test_roundtrip(test_string_t *str, test_string_t *ret) {
+  assert(str->len > 0);
+  size_t size = str->utf16 ? 2 : 1;
+  ret->len = str->len;
+  ret->utf16 = str->utf16;
+  ret->ptr = (uint8_t *) malloc(ret->len * size);
+  memcpy(ret->ptr, str->ptr, size * ret->len);
+  test_string_free(str);
+}